@@ -0,0 +1,482 @@
+use std::path::{Path, PathBuf};
+
+// Scale qualifiers Windows ships assets in, used when a target size variant is missing
+const SCALES: &[u32] = &[100, 125, 150, 200, 400];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppxApplication {
+    pub id: String,
+    pub aumid: String,
+    pub display_name: String,
+    pub display_name_resource: Option<String>,
+    pub description: Option<String>,
+    pub logo: Option<PathBuf>,
+    pub background_color: Option<String>,
+}
+impl AppxApplication {
+    /// Shell URI that launches the application through its AUMID.
+    pub fn launch_uri(&self) -> String {
+        format!("shell:AppsFolder\\{}", self.aumid)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppxPackage {
+    pub name: String,
+    pub full_name: String,
+    pub family_name: String,
+    pub display_name: Option<String>,
+    pub install_dir: PathBuf,
+    pub applications: Vec<AppxApplication>,
+}
+
+impl AppxPackage {
+    /// Reads `AppxManifest.xml` from a package folder named after its full name
+    /// (`Name_Version_Arch_ResourceId_PublisherId`).
+    pub fn from_dir(install_dir: &Path, icon_size: u32, scale: u32) -> Result<Self, String> {
+        let manifest_path = install_dir.join("AppxManifest.xml");
+        let manifest = std::fs::read_to_string(&manifest_path)
+            .map_err(|e| format!("Error reading {:?}: {}", manifest_path, e))?;
+        let full_name = install_dir.file_name()
+            .and_then(|name| name.to_str())
+            .map(String::from)
+            .unwrap_or_default();
+        Self::parse(&manifest, &full_name, install_dir, icon_size, scale)
+    }
+
+    pub fn parse(manifest: &str, full_name: &str, install_dir: &Path, icon_size: u32, scale: u32) -> Result<Self, String> {
+        let document = roxmltree::Document::parse(manifest)
+            .map_err(|e| format!("Invalid package manifest in {:?}: {}", install_dir, e))?;
+        let root = document.root_element();
+        let identity = child(root, "Identity").ok_or_else(|| format!("Package manifest in {:?} has no Identity", install_dir))?;
+        let name = identity.attribute("Name").unwrap_or_default().to_string();
+        let family_name = family_name(&name, full_name);
+        let properties = child(root, "Properties");
+        // Framework and resource packages never contribute start menu entries
+        let is_framework = properties
+            .and_then(|properties| child(properties, "Framework"))
+            .and_then(|framework| framework.text())
+            .is_some_and(|text| text.trim() == "true");
+        let package_display_name = properties
+            .and_then(|properties| child(properties, "DisplayName"))
+            .and_then(|node| node.text())
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty());
+        let display_name = package_display_name.clone().filter(|text| !text.starts_with("ms-resource:"));
+
+        let mut applications: Vec<AppxApplication> = Vec::new();
+        if !is_framework && let Some(apps) = child(root, "Applications") {
+            for app in apps.children().filter(|node| node.is_element() && node.tag_name().name() == "Application") {
+                let Some(id) = app.attribute("Id") else {
+                    continue;
+                };
+                let Some(visual) = child(app, "VisualElements") else {
+                    continue;
+                };
+                if visual.attribute("AppListEntry").is_some_and(|entry| entry.eq_ignore_ascii_case("none")) {
+                    continue;
+                }
+                let app_display_name = visual.attribute("DisplayName")
+                    .filter(|name| !name.starts_with("ms-resource:"))
+                    .map(String::from)
+                    .or_else(|| display_name.clone())
+                    .unwrap_or_else(|| id.to_string());
+                let logo = ["Square44x44Logo", "Square30x30Logo", "Square150x150Logo", "Logo"].iter()
+                    .find_map(|attribute| visual.attribute(*attribute))
+                    .and_then(|logo| resolve_asset(install_dir, logo, icon_size, scale));
+                applications.push(AppxApplication {
+                    id: id.to_string(),
+                    aumid: format!("{}!{}", family_name, id),
+                    display_name: app_display_name,
+                    display_name_resource: visual.attribute("DisplayName")
+                        .map(String::from)
+                        .or_else(|| package_display_name.clone())
+                        .filter(|name| name.starts_with("ms-resource:")),
+                    description: visual.attribute("Description")
+                        .filter(|text| !text.starts_with("ms-resource:"))
+                        .map(String::from),
+                    logo,
+                    background_color: visual.attribute("BackgroundColor").map(String::from),
+                });
+            }
+        }
+
+        Ok(Self {
+            name,
+            full_name: full_name.to_string(),
+            family_name,
+            display_name,
+            install_dir: install_dir.to_path_buf(),
+            applications,
+        })
+    }
+}
+
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|child| child.is_element() && child.tag_name().name() == name)
+}
+
+/// `Name_PublisherId` from a full name, falling back to the bare name when it is not in full name form.
+fn family_name(name: &str, full_name: &str) -> String {
+    let parts: Vec<&str> = full_name.split('_').collect();
+    if parts.len() >= 5 && parts[0].eq_ignore_ascii_case(name) {
+        format!("{}_{}", parts[0], parts[parts.len() - 1])
+    } else {
+        name.to_string()
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Qualifiers {
+    scale: Option<u32>,
+    target_size: Option<u32>,
+    unplated: bool,
+    other: bool,
+}
+fn parse_qualifiers(text: &str) -> Qualifiers {
+    let mut qualifiers = Qualifiers::default();
+    for part in text.split(['_', '.']) {
+        let lower = part.to_ascii_lowercase();
+        if let Some(value) = lower.strip_prefix("scale-") {
+            qualifiers.scale = value.parse().ok();
+        } else if let Some(value) = lower.strip_prefix("targetsize-") {
+            qualifiers.target_size = value.parse().ok();
+        } else if lower == "altform-unplated" || lower == "altform-lightunplated" {
+            qualifiers.unplated = true;
+        } else if !lower.is_empty() {
+            // contrast-*, theme-* and language qualifiers are not wanted for the normal theme
+            qualifiers.other = true;
+        }
+    }
+    qualifiers
+}
+
+/// Picks the best file for a manifest asset such as `Assets\Square44x44Logo.png`.
+/// Windows stores variants as `Square44x44Logo.targetsize-48_altform-unplated.png`,
+/// `Square44x44Logo.scale-200.png` or `scale-200\Square44x44Logo.png`.
+pub fn resolve_asset(install_dir: &Path, logo: &str, icon_size: u32, scale: u32) -> Option<PathBuf> {
+    let relative: PathBuf = logo.split(['\\', '/']).filter(|part| !part.is_empty()).collect();
+    let asset = install_dir.join(&relative);
+    let dir = asset.parent()?;
+    let stem = asset.file_stem()?.to_str()?.to_ascii_lowercase();
+    let extension = asset.extension()?.to_str()?.to_ascii_lowercase();
+    let wanted = icon_size * scale / 100;
+
+    let mut candidates: Vec<(Qualifiers, PathBuf)> = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()).map(|name| name.to_ascii_lowercase()) else {
+                continue;
+            };
+            if path.is_dir() {
+                // Qualifier folders: scale-200\Square44x44Logo.png
+                let nested = path.join(asset.file_name()?);
+                if nested.is_file() {
+                    candidates.push((parse_qualifiers(&file_name), nested));
+                }
+                continue;
+            }
+            let Some(middle) = file_name.strip_prefix(&format!("{}.", stem)).and_then(|rest| rest.strip_suffix(&format!(".{}", extension))) else {
+                continue;
+            };
+            candidates.push((parse_qualifiers(middle), path));
+        }
+    }
+    candidates.retain(|(qualifiers, _)| !qualifiers.other);
+
+    let by_target_size = candidates.iter()
+        .filter_map(|(qualifiers, path)| qualifiers.target_size.map(|size| (size, qualifiers.unplated, path)))
+        .min_by_key(|(size, unplated, _)| (*size < wanted, size.abs_diff(wanted), !*unplated));
+    if let Some((_, _, path)) = by_target_size {
+        return Some(path.clone());
+    }
+    let wanted_scale = SCALES.iter().copied().find(|candidate| *candidate >= scale).unwrap_or(400);
+    let by_scale = candidates.iter()
+        .filter_map(|(qualifiers, path)| qualifiers.scale.map(|scale| (scale, path)))
+        .min_by_key(|(scale, _)| (*scale < wanted_scale, scale.abs_diff(wanted_scale)));
+    if let Some((_, path)) = by_scale {
+        return Some(path.clone());
+    }
+    if asset.is_file() { Some(asset) } else { None }
+}
+
+/// Whether a package with this `Name_PublisherId` family name is registered for the current user.
+#[cfg(windows)]
+pub fn is_family_installed(family: &str) -> bool {
+    installed_package_dirs().iter().any(|dir| {
+        let full_name = dir.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        let name = full_name.split('_').next().unwrap_or_default();
+        family_name(name, &full_name).eq_ignore_ascii_case(family)
+    })
+}
+
+/// Install folders of every package registered for the current user.
+#[cfg(windows)]
+pub fn installed_package_dirs() -> Vec<PathBuf> {
+    use windows::core::{w, PCWSTR, PWSTR};
+    use windows::Win32::System::Registry::{RegCloseKey, RegEnumKeyExW, RegGetValueW, RegOpenKeyExW, HKEY, HKEY_CURRENT_USER, KEY_READ, RRF_RT_REG_SZ};
+
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    unsafe {
+        let mut packages_key = HKEY::default();
+        if RegOpenKeyExW(
+            HKEY_CURRENT_USER,
+            w!(r"Software\Classes\Local Settings\Software\Microsoft\Windows\CurrentVersion\AppModel\Repository\Packages"),
+            Some(0),
+            KEY_READ,
+            &mut packages_key,
+        ).is_err() {
+            return dirs;
+        }
+        let mut index = 0;
+        loop {
+            let mut name = [0u16; 512];
+            let mut name_len = name.len() as u32;
+            if RegEnumKeyExW(packages_key, index, Some(PWSTR(name.as_mut_ptr())), &mut name_len, None, None, None, None).is_err() {
+                break;
+            }
+            index += 1;
+            let mut root = [0u16; 1024];
+            let mut root_size = (root.len() * 2) as u32;
+            if RegGetValueW(
+                packages_key,
+                PCWSTR(name.as_ptr()),
+                w!("PackageRootFolder"),
+                RRF_RT_REG_SZ,
+                None,
+                Some(root.as_mut_ptr() as *mut _),
+                Some(&mut root_size),
+            ).is_ok() {
+                let len = root.iter().position(|unit| *unit == 0).unwrap_or(root.len());
+                dirs.push(PathBuf::from(String::from_utf16_lossy(&root[..len])));
+            }
+        }
+        let _ = RegCloseKey(packages_key);
+    }
+    dirs
+}
+
+/// Resolves `ms-resource:` display names through the package resource index.
+#[cfg(windows)]
+pub fn load_indirect_string(package: &AppxPackage, resource: &str) -> Option<String> {
+    use windows::core::PCWSTR;
+    use windows::Win32::UI::Shell::SHLoadIndirectString;
+
+    let key = resource.strip_prefix("ms-resource:")?;
+    let uri = if key.starts_with("//") {
+        format!("ms-resource:{}", key)
+    } else if key.contains('/') {
+        format!("ms-resource://{}/{}", package.name, key.trim_start_matches('/'))
+    } else {
+        format!("ms-resource://{}/Resources/{}", package.name, key)
+    };
+    let source = format!("@{{{}?{}}}", package.full_name, uri);
+    let source_wide: Vec<u16> = source.encode_utf16().chain(std::iter::once(0)).collect();
+    let mut output = [0u16; 512];
+    unsafe {
+        SHLoadIndirectString(PCWSTR(source_wide.as_ptr()), &mut output, None).ok()?;
+    }
+    let len = output.iter().position(|unit| *unit == 0).unwrap_or(output.len());
+    let value = String::from_utf16_lossy(&output[..len]);
+    if value.is_empty() { None } else { Some(value) }
+}
+
+/// Every start menu visible application from the user's installed packages.
+#[cfg(windows)]
+pub fn installed_applications(icon_size: u32, scale: u32) -> Vec<AppxApplication> {
+    let mut applications: Vec<AppxApplication> = Vec::new();
+    for dir in installed_package_dirs() {
+        match AppxPackage::from_dir(&dir, icon_size, scale) {
+            Ok(package) => {
+                for mut application in package.applications.clone() {
+                    // Literal names were already taken from the manifest, only resources need the PRI lookup
+                    if let Some(resource) = application.display_name_resource.as_ref()
+                        && let Some(name) = load_indirect_string(&package, resource) {
+                        application.display_name = name;
+                    }
+                    applications.push(application);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error loading package: {}", e);
+            }
+        }
+    }
+    applications
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL_NAME: &str = "Contoso.Notes_1.4.0.0_x64__8wekyb3d8bbwe";
+
+    fn manifest(properties: &str, applications: &str) -> String {
+        format!(r#"<?xml version="1.0" encoding="utf-8"?>
+<Package xmlns="http://schemas.microsoft.com/appx/manifest/foundation/windows10"
+         xmlns:uap="http://schemas.microsoft.com/appx/manifest/uap/windows10">
+  <Identity Name="Contoso.Notes" Publisher="CN=Contoso" Version="1.4.0.0" ProcessorArchitecture="x64"/>
+  <Properties>{}</Properties>
+  <Applications>{}</Applications>
+</Package>"#, properties, applications)
+    }
+
+    fn parse(properties: &str, applications: &str) -> AppxPackage {
+        AppxPackage::parse(&manifest(properties, applications), FULL_NAME, Path::new("/missing"), 48, 100).unwrap()
+    }
+
+    #[test]
+    fn reads_applications() {
+        let package = parse(
+            "<DisplayName>Notes</DisplayName>",
+            r##"<Application Id="App"><uap:VisualElements DisplayName="Contoso Notes" Description="Take notes"
+                BackgroundColor="#336699" Square44x44Logo="Assets\Logo.png"/></Application>
+              <Application Id="Widget"><uap:VisualElements DisplayName="Notes Widget" AppListEntry="None"/></Application>
+              <Application Id="Hidden"><uap:VisualElements DisplayName="Hidden" AppListEntry="none"/></Application>
+              <Application Id="Plain"/>"##,
+        );
+        assert_eq!(package.name, "Contoso.Notes");
+        assert_eq!(package.display_name.as_deref(), Some("Notes"));
+        // Entries kept off the app list and applications without visual elements are skipped
+        assert_eq!(package.applications.len(), 1);
+        let app = &package.applications[0];
+        assert_eq!(app.display_name, "Contoso Notes");
+        assert_eq!(app.description.as_deref(), Some("Take notes"));
+        assert_eq!(app.background_color.as_deref(), Some("#336699"));
+        assert_eq!(app.aumid, "Contoso.Notes_8wekyb3d8bbwe!App");
+        assert_eq!(app.launch_uri(), r"shell:AppsFolder\Contoso.Notes_8wekyb3d8bbwe!App");
+        assert_eq!(app.logo, None);
+    }
+
+    #[test]
+    fn framework_packages_have_no_applications() {
+        let package = parse(
+            "<DisplayName>Runtime</DisplayName><Framework> true </Framework>",
+            r#"<Application Id="App"><uap:VisualElements DisplayName="Runtime"/></Application>"#,
+        );
+        assert!(package.applications.is_empty());
+        let package = parse(
+            "<DisplayName>Runtime</DisplayName><Framework>false</Framework>",
+            r#"<Application Id="App"><uap:VisualElements DisplayName="Runtime"/></Application>"#,
+        );
+        assert_eq!(package.applications.len(), 1);
+    }
+
+    #[test]
+    fn resource_names_fall_back() {
+        // To the package's literal name, keeping the resource for the PRI lookup
+        let package = parse(
+            "<DisplayName>Notes</DisplayName>",
+            r#"<Application Id="App"><uap:VisualElements DisplayName="ms-resource:AppName" Description="ms-resource:AppDescription"/></Application>"#,
+        );
+        let app = &package.applications[0];
+        assert_eq!(app.display_name, "Notes");
+        assert_eq!(app.display_name_resource.as_deref(), Some("ms-resource:AppName"));
+        assert_eq!(app.description, None);
+
+        // To the application ID when the package name is a resource too
+        let package = parse(
+            "<DisplayName>ms-resource:PackageName</DisplayName>",
+            r#"<Application Id="App"><uap:VisualElements/></Application>"#,
+        );
+        assert_eq!(package.display_name, None);
+        let app = &package.applications[0];
+        assert_eq!(app.display_name, "App");
+        assert_eq!(app.display_name_resource.as_deref(), Some("ms-resource:PackageName"));
+
+        let package = parse("<DisplayName>Notes</DisplayName>", r#"<Application Id="App"><uap:VisualElements DisplayName="Notes"/></Application>"#);
+        assert_eq!(package.applications[0].display_name_resource, None);
+    }
+
+    #[test]
+    fn rejects_broken_manifests() {
+        assert!(AppxPackage::parse("<Package", FULL_NAME, Path::new("/missing"), 48, 100).is_err());
+        assert!(AppxPackage::parse("<Package/>", FULL_NAME, Path::new("/missing"), 48, 100).is_err());
+    }
+
+    #[test]
+    fn derives_family_names() {
+        let cases = [
+            ("Contoso.Notes", FULL_NAME, "Contoso.Notes_8wekyb3d8bbwe"),
+            ("contoso.notes", FULL_NAME, "Contoso.Notes_8wekyb3d8bbwe"),
+            ("Microsoft.VCLibs.140.00", "Microsoft.VCLibs.140.00_14.0.33519.0_x64__8wekyb3d8bbwe", "Microsoft.VCLibs.140.00_8wekyb3d8bbwe"),
+            // Not in full name form, or the full name of another package
+            ("Contoso.Notes", "Contoso.Notes", "Contoso.Notes"),
+            ("Contoso.Notes", "Contoso.Paint_1.0.0.0_x64__8wekyb3d8bbwe", "Contoso.Notes"),
+            ("Contoso.Notes", "", "Contoso.Notes"),
+        ];
+        for (name, full_name, expected) in cases {
+            assert_eq!(family_name(name, full_name), expected, "{}", full_name);
+        }
+    }
+
+    fn assets(files: &[&str]) -> tempfile::TempDir {
+        let temp = tempfile::tempdir().unwrap();
+        for file in files {
+            let path = temp.path().join("Assets").join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "").unwrap();
+        }
+        temp
+    }
+
+    fn resolved(install_dir: &tempfile::TempDir, icon_size: u32, scale: u32) -> Option<String> {
+        resolve_asset(install_dir.path(), r"Assets\Logo.png", icon_size, scale)
+            .map(|path| path.strip_prefix(install_dir.path().join("Assets")).unwrap().to_string_lossy().replace('\\', "/"))
+    }
+
+    #[test]
+    fn ranks_target_size_variants() {
+        let install_dir = assets(&[
+            "Logo.png",
+            "Logo.scale-100.png",
+            "Logo.scale-200.png",
+            "Logo.targetsize-32.png",
+            "Logo.targetsize-48.png",
+            "Logo.targetsize-48_altform-unplated.png",
+            "Logo.targetsize-256_altform-unplated.png",
+            "Logo.targetsize-64_contrast-black.png",
+            "Logo.targetsize-64_altform-lightunplated_theme-light.png",
+        ]);
+        let cases = [
+            // Unplated wins between variants of the same size
+            (48, 100, "Logo.targetsize-48_altform-unplated.png"),
+            // The smallest variant at least as big as wanted, scaled down rather than up
+            (40, 100, "Logo.targetsize-48_altform-unplated.png"),
+            (24, 100, "Logo.targetsize-32.png"),
+            (48, 200, "Logo.targetsize-256_altform-unplated.png"),
+            // Only bigger than every variant leaves the largest one
+            (512, 100, "Logo.targetsize-256_altform-unplated.png"),
+        ];
+        for (icon_size, scale, expected) in cases {
+            assert_eq!(resolved(&install_dir, icon_size, scale).as_deref(), Some(expected), "{}@{}", icon_size, scale);
+        }
+    }
+
+    #[test]
+    fn ranks_scale_variants() {
+        let install_dir = assets(&["Logo.png", "Logo.scale-100.png", "Logo.Scale-200.png", "scale-400/Logo.png", "Logo.scale-200_contrast-white.png"]);
+        let cases = [
+            (100, "Logo.scale-100.png"),
+            // 150 is not shipped, the next bigger scale is
+            (125, "Logo.Scale-200.png"),
+            (150, "Logo.Scale-200.png"),
+            (200, "Logo.Scale-200.png"),
+            // Qualifier folders count the same as qualified names
+            (300, "scale-400/Logo.png"),
+            (800, "scale-400/Logo.png"),
+        ];
+        for (scale, expected) in cases {
+            assert_eq!(resolved(&install_dir, 48, scale).as_deref(), Some(expected), "{}", scale);
+        }
+    }
+
+    #[test]
+    fn falls_back_to_the_plain_asset() {
+        let install_dir = assets(&["Logo.png", "Logo.targetsize-48_contrast-black.png", "Other.scale-100.png"]);
+        assert_eq!(resolved(&install_dir, 48, 100).as_deref(), Some("Logo.png"));
+        let install_dir = assets(&["Other.png"]);
+        assert_eq!(resolved(&install_dir, 48, 100), None);
+    }
+}
@@ -0,0 +1,136 @@
+#[cfg(target_os = "linux")]
+use crate::pulse;
+#[cfg(windows)]
+use crate::sys_util;
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, Stream, StreamExt};
+use iced::Subscription;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeviceDirection {
+    Output,
+    Input,
+}
+
+/// A speaker, headset or microphone the system can route audio through.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioDevice {
+    /// The endpoint ID on Windows, the sink or source name on Linux.
+    pub id: String,
+    pub name: String,
+    pub direction: DeviceDirection,
+    pub is_default: bool,
+}
+
+pub fn devices(direction: DeviceDirection) -> Result<Vec<AudioDevice>, String> {
+    #[cfg(windows)]
+    return sys_util::get_audio_devices(direction).map_err(|e| e.to_string());
+    #[cfg(target_os = "linux")]
+    return pulse::devices(direction);
+}
+
+/// Makes the device the default for its direction, moving what is playing or recording over to it.
+pub fn set_default(device: &AudioDevice) -> Result<(), String> {
+    #[cfg(windows)]
+    return sys_util::set_default_audio_device(&device.id).map_err(|e| e.to_string());
+    #[cfg(target_os = "linux")]
+    return pulse::set_default_device(device);
+}
+
+/// Volume and mute of the default microphone.
+pub fn input_state() -> Result<(f32, bool), String> {
+    #[cfg(windows)]
+    return sys_util::get_microphone_state().map_err(|e| e.to_string());
+    #[cfg(target_os = "linux")]
+    return pulse::default_source_state();
+}
+
+pub fn set_input_state(volume: f32, muted: bool) -> Result<(), String> {
+    let volume = volume.clamp(0.0, 1.0);
+    #[cfg(windows)]
+    return sys_util::set_microphone_state(volume, muted).map_err(|e| e.to_string());
+    #[cfg(target_os = "linux")]
+    return pulse::set_default_source_state(volume, muted);
+}
+
+/// Fires with whether another application is recording from a microphone, once at the start and then on every change.
+pub fn watch_microphone() -> Subscription<bool> {
+    Subscription::run(microphone_changes)
+}
+
+fn microphone_changes() -> impl Stream<Item = bool> {
+    iced::stream::channel(10, async |mut output: mpsc::Sender<bool>| {
+        let (sender, mut receiver) = mpsc::unbounded::<bool>();
+        std::thread::spawn(move || {
+            let mut last = None;
+            let mut report = move || {
+                let in_use = match microphone_in_use() {
+                    Ok(in_use) => in_use,
+                    Err(e) => {
+                        eprintln!("Error checking microphone use: {}", e);
+                        false
+                    }
+                };
+                if last == Some(in_use) {
+                    return true;
+                }
+                last = Some(in_use);
+                sender.unbounded_send(in_use).is_ok()
+            };
+            // Like the mixer, sessions are polled on Windows as notifications would need a COM callback object
+            #[cfg(windows)]
+            while report() {
+                std::thread::sleep(std::time::Duration::from_secs(1));
+            }
+            #[cfg(target_os = "linux")]
+            if report()
+                && let Err(e) = pulse::subscribe(|kind| !matches!(kind, "source-output" | "source") || report()) {
+                    eprintln!("Error watching microphone use: {}", e);
+                }
+        });
+        while let Some(in_use) = receiver.next().await {
+            if output.send(in_use).await.is_err() {
+                return;
+            }
+        }
+    })
+}
+
+fn microphone_in_use() -> Result<bool, String> {
+    #[cfg(windows)]
+    return sys_util::microphone_in_use().map_err(|e| e.to_string());
+    #[cfg(target_os = "linux")]
+    return pulse::recording_from_source();
+}
+
+/// Peak level of a microphone from 0 to 1, about twenty times a second. Reading the level opens a recording
+/// stream of its own, so this is only subscribed to while the level is on screen.
+pub fn level_meter(device: &AudioDevice) -> Subscription<f32> {
+    Subscription::run_with(device.id.clone(), |id: &String| levels(id))
+}
+
+fn levels(id: &str) -> impl Stream<Item = f32> + use<> {
+    let id = id.to_string();
+    iced::stream::channel(10, async move |mut output: mpsc::Sender<f32>| {
+        let (sender, mut receiver) = mpsc::unbounded::<f32>();
+        std::thread::spawn(move || {
+            #[cfg(windows)]
+            let result = sys_util::MicrophoneMeter::open(&id).and_then(|meter| {
+                while sender.unbounded_send(meter.peak()?).is_ok() {
+                    std::thread::sleep(std::time::Duration::from_millis(50));
+                }
+                Ok(())
+            }).map_err(|e| e.to_string());
+            #[cfg(target_os = "linux")]
+            let result = pulse::record_peaks(&id, |peak| sender.unbounded_send(peak).is_ok());
+            if let Err(e) = result {
+                eprintln!("Error reading microphone level: {}", e);
+            }
+        });
+        while let Some(level) = receiver.next().await {
+            if output.send(level).await.is_err() {
+                return;
+            }
+        }
+    })
+}
@@ -0,0 +1,130 @@
+#[cfg(target_os = "linux")]
+use crate::bluez::BlueZ;
+#[cfg(windows)]
+use crate::sys_util;
+use iced::futures::channel::{mpsc, oneshot};
+use iced::futures::{SinkExt, Stream, StreamExt};
+use iced::{Subscription, Task};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BluetoothDevice {
+    /// The D-Bus object path on Linux, the device address on Windows.
+    pub id: String,
+    pub name: String,
+    pub paired: bool,
+    pub connected: bool,
+    /// Battery level from 0 to 100, for devices that report one.
+    pub battery: Option<u8>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BluetoothState {
+    /// Whether there is an adapter at all.
+    pub available: bool,
+    pub powered: bool,
+    /// Paired devices, and unpaired ones seen while discovering.
+    pub devices: Vec<BluetoothDevice>,
+}
+
+/// Lists the known devices and whether the adapter is on.
+pub fn state() -> Result<BluetoothState, String> {
+    #[cfg(windows)]
+    return sys_util::get_bluetooth_state(false).map_err(|e| e.to_string());
+    #[cfg(target_os = "linux")]
+    return BlueZ::open()?.state().map_err(|e| e.to_string());
+}
+
+pub fn set_powered(powered: bool) -> Result<(), String> {
+    #[cfg(windows)]
+    return sys_util::set_bluetooth_power(powered);
+    #[cfg(target_os = "linux")]
+    return BlueZ::open()?.set_powered(powered).map_err(|e| e.to_string());
+}
+
+pub fn connect(id: &str) -> Result<(), String> {
+    #[cfg(windows)]
+    return sys_util::set_bluetooth_connected(id, true).map_err(|e| e.to_string());
+    #[cfg(target_os = "linux")]
+    return BlueZ::open()?.connect(id).map_err(|e| e.to_string());
+}
+
+pub fn disconnect(id: &str) -> Result<(), String> {
+    #[cfg(windows)]
+    return sys_util::set_bluetooth_connected(id, false).map_err(|e| e.to_string());
+    #[cfg(target_os = "linux")]
+    return BlueZ::open()?.disconnect(id).map_err(|e| e.to_string());
+}
+
+/// Pairs with a device found while discovering and connects to it.
+pub fn pair(id: &str) -> Result<(), String> {
+    #[cfg(windows)]
+    return sys_util::pair_bluetooth_device(id).map_err(|e| e.to_string());
+    #[cfg(target_os = "linux")]
+    return BlueZ::open()?.pair(id).map_err(|e| e.to_string());
+}
+
+/// Removes the pairing with a device.
+pub fn forget(id: &str) -> Result<(), String> {
+    #[cfg(windows)]
+    return sys_util::forget_bluetooth_device(id).map_err(|e| e.to_string());
+    #[cfg(target_os = "linux")]
+    return BlueZ::open()?.forget(id).map_err(|e| e.to_string());
+}
+
+/// Runs one of the calls above on a thread of its own, as pairing and connecting can take seconds.
+pub fn in_background<T: Send + 'static>(work: impl FnOnce() -> Result<T, String> + Send + 'static) -> Task<Result<T, String>> {
+    Task::perform(async move {
+        let (sender, receiver) = oneshot::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(work());
+        });
+        receiver.await.unwrap_or_else(|_| Err("The Bluetooth request was cancelled".to_string()))
+    }, |result| result)
+}
+
+/// Connected devices first, then paired ones, then by name.
+pub fn sort_devices(devices: &mut [BluetoothDevice]) {
+    devices.sort_by(|a, b| b.connected.cmp(&a.connected).then(b.paired.cmp(&a.paired)).then(a.name.cmp(&b.name)));
+}
+
+/// Looks for devices to pair with for as long as it is subscribed to, firing with the devices found every few seconds.
+pub fn discover() -> Subscription<BluetoothState> {
+    Subscription::run(discoveries)
+}
+
+fn discoveries() -> impl Stream<Item = BluetoothState> {
+    iced::stream::channel(10, async |mut output: mpsc::Sender<BluetoothState>| {
+        let (sender, mut receiver) = mpsc::unbounded::<BluetoothState>();
+        std::thread::spawn(move || {
+            // Each search blocks while it inquires, there is nothing to start or stop
+            #[cfg(windows)]
+            let result = loop {
+                match sys_util::get_bluetooth_state(true) {
+                    Ok(state) => {
+                        if sender.unbounded_send(state).is_err() {
+                            break Ok(());
+                        }
+                    }
+                    Err(e) => break Err(e.to_string()),
+                }
+            };
+            // BlueZ stops discovering once the connection that started it closes, so it is held until unsubscribed
+            #[cfg(target_os = "linux")]
+            let result = BlueZ::open().and_then(|bluez| {
+                bluez.start_discovery().map_err(|e| e.to_string())?;
+                while sender.unbounded_send(bluez.state().map_err(|e| e.to_string())?).is_ok() {
+                    std::thread::sleep(std::time::Duration::from_secs(2));
+                }
+                bluez.stop_discovery().map_err(|e| e.to_string())
+            });
+            if let Err(e) = result {
+                eprintln!("Error discovering Bluetooth devices: {}", e);
+            }
+        });
+        while let Some(state) = receiver.next().await {
+            if output.send(state).await.is_err() {
+                return;
+            }
+        }
+    })
+}
@@ -0,0 +1,390 @@
+use crate::bluetooth::{sort_devices, BluetoothDevice, BluetoothState};
+use std::collections::HashMap;
+use zbus::blocking::{proxy, Connection, Proxy};
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue};
+
+const SERVICE: &str = "org.bluez";
+const OBJECT_MANAGER: &str = "org.freedesktop.DBus.ObjectManager";
+const ADAPTER: &str = "org.bluez.Adapter1";
+const DEVICE: &str = "org.bluez.Device1";
+const BATTERY: &str = "org.bluez.Battery1";
+
+/// Every object BlueZ exports, with the properties of each of its interfaces.
+type ManagedObjects = HashMap<OwnedObjectPath, HashMap<String, HashMap<String, OwnedValue>>>;
+
+/// Bluetooth through BlueZ's D-Bus API.
+pub struct BlueZ {
+    connection: Connection,
+}
+impl BlueZ {
+    /// Connects to BlueZ on the system bus. `FROSTWIN_BLUEZ_BUS_ADDRESS` points it at another bus instead,
+    /// so a stub object tree on a private bus can stand in for the real one.
+    pub fn open() -> Result<Self, String> {
+        let connection = match std::env::var("FROSTWIN_BLUEZ_BUS_ADDRESS") {
+            Ok(address) => zbus::blocking::connection::Builder::address(address.as_str()).and_then(|builder| builder.build()),
+            Err(_) => Connection::system(),
+        };
+        match connection {
+            Ok(connection) => Ok(Self::with_connection(connection)),
+            Err(e) => Err(format!("Could not reach BlueZ: {}", e)),
+        }
+    }
+
+    pub fn with_connection(connection: Connection) -> Self {
+        Self { connection }
+    }
+
+    fn proxy(&self, path: &str, interface: &'static str) -> zbus::Result<Proxy<'static>> {
+        // Properties are read once per call, a cache would only add signal subscriptions
+        proxy::Builder::new(&self.connection)
+            .destination(SERVICE)?
+            .path(path.to_string())?
+            .interface(interface)?
+            .cache_properties(CacheProperties::No)
+            .build()
+    }
+
+    fn objects(&self) -> zbus::Result<ManagedObjects> {
+        self.proxy("/", OBJECT_MANAGER)?.call("GetManagedObjects", &())
+    }
+
+    /// The first adapter by path, `None` on machines without one.
+    fn adapter(objects: &ManagedObjects) -> Option<OwnedObjectPath> {
+        objects.iter()
+            .filter(|(_, interfaces)| interfaces.contains_key(ADAPTER))
+            .map(|(path, _)| path.clone())
+            .min_by(|a, b| a.as_str().cmp(b.as_str()))
+    }
+
+    fn require_adapter(&self) -> zbus::Result<OwnedObjectPath> {
+        Self::adapter(&self.objects()?).ok_or_else(|| zbus::Error::Failure("No Bluetooth adapter found".to_string()))
+    }
+
+    pub fn state(&self) -> zbus::Result<BluetoothState> {
+        let objects = self.objects()?;
+        let Some(adapter) = Self::adapter(&objects) else {
+            return Ok(BluetoothState::default());
+        };
+        let powered = objects[&adapter].get(ADAPTER).and_then(|adapter| flag(adapter, "Powered")).unwrap_or(false);
+        let mut devices: Vec<BluetoothDevice> = objects.iter()
+            .filter_map(|(path, interfaces)| device(path, interfaces, &adapter))
+            .collect();
+        sort_devices(&mut devices);
+        Ok(BluetoothState { available: true, powered, devices })
+    }
+
+    pub fn set_powered(&self, powered: bool) -> zbus::Result<()> {
+        let adapter = self.require_adapter()?;
+        self.proxy(adapter.as_str(), ADAPTER)?.set_property("Powered", powered)?;
+        Ok(())
+    }
+
+    /// BlueZ keeps discovering until this connection stops it or goes away.
+    pub fn start_discovery(&self) -> zbus::Result<()> {
+        let adapter = self.require_adapter()?;
+        self.proxy(adapter.as_str(), ADAPTER)?.call("StartDiscovery", &())
+    }
+
+    pub fn stop_discovery(&self) -> zbus::Result<()> {
+        let adapter = self.require_adapter()?;
+        self.proxy(adapter.as_str(), ADAPTER)?.call("StopDiscovery", &())
+    }
+
+    pub fn connect(&self, device: &str) -> zbus::Result<()> {
+        self.proxy(device, DEVICE)?.call("Connect", &())
+    }
+
+    pub fn disconnect(&self, device: &str) -> zbus::Result<()> {
+        self.proxy(device, DEVICE)?.call("Disconnect", &())
+    }
+
+    /// Pairs with a device, trusts it so it may reconnect by itself later and connects to it. Without an agent
+    /// registered, BlueZ pairs the way devices without a display or keyboard do, which is what headphones expect.
+    pub fn pair(&self, device: &str) -> zbus::Result<()> {
+        let proxy = self.proxy(device, DEVICE)?;
+        proxy.call::<_, _, ()>("Pair", &())?;
+        proxy.set_property("Trusted", true)?;
+        proxy.call("Connect", &())
+    }
+
+    /// Removes the pairing and BlueZ's record of the device.
+    pub fn forget(&self, device: &str) -> zbus::Result<()> {
+        let adapter: OwnedObjectPath = self.proxy(device, DEVICE)?.get_property("Adapter")?;
+        self.proxy(adapter.as_str(), ADAPTER)?.call("RemoveDevice", &(ObjectPath::try_from(device)?,))
+    }
+}
+
+/// Reads a device of `adapter` from its interfaces, `None` for other objects and for unpaired devices without a name.
+fn device(path: &OwnedObjectPath, interfaces: &HashMap<String, HashMap<String, OwnedValue>>, adapter: &OwnedObjectPath) -> Option<BluetoothDevice> {
+    let device = interfaces.get(DEVICE)?;
+    if device.get("Adapter").and_then(|value| value.downcast_ref::<ObjectPath>().ok()).is_none_or(|path| path.as_str() != adapter.as_str()) {
+        return None;
+    }
+    let paired = flag(device, "Paired").unwrap_or(false);
+    // Discovery turns up beacons and other nameless devices, which would only show as addresses
+    if !paired && !device.contains_key("Name") {
+        return None;
+    }
+    Some(BluetoothDevice {
+        id: path.to_string(),
+        name: text(device, "Alias").or_else(|| text(device, "Address")).unwrap_or_default(),
+        paired,
+        connected: flag(device, "Connected").unwrap_or(false),
+        battery: interfaces.get(BATTERY)
+            .and_then(|battery| battery.get("Percentage"))
+            .and_then(|percentage| percentage.downcast_ref::<u8>().ok()),
+    })
+}
+
+fn flag(properties: &HashMap<String, OwnedValue>, name: &str) -> Option<bool> {
+    properties.get(name).and_then(|value| value.downcast_ref::<bool>().ok())
+}
+
+fn text(properties: &HashMap<String, OwnedValue>, name: &str) -> Option<String> {
+    properties.get(name).and_then(|value| value.downcast_ref::<&str>().ok()).map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_bus::TestBus;
+    use std::sync::{Arc, Mutex};
+    use zbus::interface;
+    use zbus::zvariant::Value;
+
+    const ADAPTER_PATH: &str = "/org/bluez/hci0";
+    const HEADPHONES: &str = "/org/bluez/hci0/dev_00_11_22_33_44_55";
+    const MOUSE: &str = "/org/bluez/hci0/dev_66_77_88_99_AA_BB";
+    const SPEAKER: &str = "/org/bluez/hci0/dev_CC_DD_EE_FF_00_11";
+    const BEACON: &str = "/org/bluez/hci0/dev_22_22_22_22_22_22";
+    const OTHER_ADAPTER_DEVICE: &str = "/org/bluez/hci1/dev_33_33_33_33_33_33";
+
+    fn path(path: &str) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(path).unwrap()
+    }
+
+    fn properties(properties: Vec<(&str, Value)>) -> HashMap<String, OwnedValue> {
+        properties.into_iter().map(|(name, value)| (name.to_string(), value.try_into().unwrap())).collect()
+    }
+
+    #[test]
+    fn reads_device_state() {
+        let adapter = path(ADAPTER_PATH);
+        let device_of = |adapter_path: &str, extra: Vec<(&str, Value)>, battery: Option<u8>| {
+            let mut device = vec![("Adapter", Value::from(ObjectPath::try_from(adapter_path).unwrap())), ("Address", Value::from("00:11:22:33:44:55"))];
+            device.extend(extra);
+            let mut interfaces = HashMap::from([(DEVICE.to_string(), properties(device))]);
+            if let Some(battery) = battery {
+                interfaces.insert(BATTERY.to_string(), properties(vec![("Percentage", Value::from(battery))]));
+            }
+            interfaces
+        };
+        let read = |interfaces| device(&path(HEADPHONES), &interfaces, &adapter);
+
+        let headphones = read(device_of(ADAPTER_PATH, vec![("Name", Value::from("WH-1000")), ("Alias", Value::from("My headphones")), ("Paired", Value::from(true)), ("Connected", Value::from(true))], Some(70)));
+        assert_eq!(headphones, Some(BluetoothDevice { id: HEADPHONES.to_string(), name: "My headphones".to_string(), paired: true, connected: true, battery: Some(70) }));
+        // Paired devices are listed by address when they have no name
+        let paired = read(device_of(ADAPTER_PATH, vec![("Paired", Value::from(true))], None));
+        assert_eq!(paired, Some(BluetoothDevice { id: HEADPHONES.to_string(), name: "00:11:22:33:44:55".to_string(), paired: true, connected: false, battery: None }));
+        let found = read(device_of(ADAPTER_PATH, vec![("Name", Value::from("Speaker")), ("Alias", Value::from("Speaker"))], None));
+        assert_eq!(found.map(|found| (found.name, found.paired, found.connected)), Some(("Speaker".to_string(), false, false)));
+
+        assert_eq!(read(device_of(ADAPTER_PATH, vec![("Paired", Value::from(false))], None)), None);
+        assert_eq!(read(device_of("/org/bluez/hci1", vec![("Name", Value::from("Other")), ("Paired", Value::from(true))], None)), None);
+        assert_eq!(read(HashMap::from([(ADAPTER.to_string(), properties(vec![("Powered", Value::from(true))]))])), None);
+    }
+
+    struct StubAdapter {
+        powered: bool,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+    #[interface(name = "org.bluez.Adapter1")]
+    impl StubAdapter {
+        fn start_discovery(&self) {
+            self.calls.lock().unwrap().push("StartDiscovery".to_string());
+        }
+        fn stop_discovery(&self) {
+            self.calls.lock().unwrap().push("StopDiscovery".to_string());
+        }
+        fn remove_device(&self, device: OwnedObjectPath) {
+            self.calls.lock().unwrap().push(format!("RemoveDevice {}", device.as_str()));
+        }
+        #[zbus(property)]
+        fn powered(&self) -> bool {
+            self.powered
+        }
+        #[zbus(property)]
+        fn set_powered(&mut self, powered: bool) {
+            self.powered = powered;
+        }
+    }
+
+    struct StubDevice {
+        adapter: &'static str,
+        alias: &'static str,
+        paired: bool,
+        connected: bool,
+        trusted: bool,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+    impl StubDevice {
+        fn call(&self, method: &str) {
+            self.calls.lock().unwrap().push(format!("{} {}", method, self.alias));
+        }
+    }
+    #[interface(name = "org.bluez.Device1")]
+    impl StubDevice {
+        fn connect(&mut self) {
+            self.call("Connect");
+            self.connected = true;
+        }
+        fn disconnect(&mut self) {
+            self.call("Disconnect");
+            self.connected = false;
+        }
+        fn pair(&mut self) -> zbus::fdo::Result<()> {
+            if self.trusted {
+                return Err(zbus::fdo::Error::Failed("Already paired".to_string()));
+            }
+            self.call("Pair");
+            self.paired = true;
+            Ok(())
+        }
+        #[zbus(property)]
+        fn adapter(&self) -> OwnedObjectPath {
+            path(self.adapter)
+        }
+        #[zbus(property)]
+        fn name(&self) -> String {
+            self.alias.to_string()
+        }
+        #[zbus(property)]
+        fn alias(&self) -> String {
+            self.alias.to_string()
+        }
+        #[zbus(property)]
+        fn paired(&self) -> bool {
+            self.paired
+        }
+        #[zbus(property)]
+        fn connected(&self) -> bool {
+            self.connected
+        }
+        #[zbus(property)]
+        fn trusted(&self) -> bool {
+            self.trusted
+        }
+        #[zbus(property)]
+        fn set_trusted(&mut self, trusted: bool) {
+            self.call(&format!("Trusted {}", trusted));
+            self.trusted = trusted;
+        }
+    }
+
+    /// A device only known by its address, as beacons are.
+    struct StubBeacon;
+    #[interface(name = "org.bluez.Device1")]
+    impl StubBeacon {
+        #[zbus(property)]
+        fn adapter(&self) -> OwnedObjectPath {
+            path(ADAPTER_PATH)
+        }
+        #[zbus(property)]
+        fn address(&self) -> String {
+            "22:22:22:22:22:22".to_string()
+        }
+    }
+
+    struct StubBattery(u8);
+    #[interface(name = "org.bluez.Battery1")]
+    impl StubBattery {
+        #[zbus(property)]
+        fn percentage(&self) -> u8 {
+            self.0
+        }
+    }
+
+    /// Serves a stub BlueZ object tree with two adapters on a private bus.
+    fn start() -> (TestBus, Connection, Arc<Mutex<Vec<String>>>) {
+        let bus = TestBus::start();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let service = bus.connect();
+        {
+            let objects = service.object_server();
+            objects.at("/", zbus::fdo::ObjectManager).unwrap();
+            objects.at(ADAPTER_PATH, StubAdapter { powered: true, calls: calls.clone() }).unwrap();
+            objects.at("/org/bluez/hci1", StubAdapter { powered: false, calls: calls.clone() }).unwrap();
+            let device = |adapter, alias, paired, connected| StubDevice { adapter, alias, paired, connected, trusted: paired, calls: calls.clone() };
+            objects.at(HEADPHONES, device(ADAPTER_PATH, "Headphones", true, true)).unwrap();
+            objects.at(HEADPHONES, StubBattery(55)).unwrap();
+            objects.at(MOUSE, device(ADAPTER_PATH, "Mouse", true, false)).unwrap();
+            objects.at(SPEAKER, device(ADAPTER_PATH, "Speaker", false, false)).unwrap();
+            objects.at(BEACON, StubBeacon).unwrap();
+            objects.at(OTHER_ADAPTER_DEVICE, device("/org/bluez/hci1", "Elsewhere", true, true)).unwrap();
+        }
+        service.request_name(SERVICE).unwrap();
+        (bus, service, calls)
+    }
+
+    fn listed(bluez: &BlueZ) -> Vec<(String, bool, bool, Option<u8>)> {
+        bluez.state().unwrap().devices.into_iter().map(|device| (device.name, device.paired, device.connected, device.battery)).collect()
+    }
+
+    #[test]
+    fn lists_the_first_adapters_devices() {
+        let (bus, _service, _calls) = start();
+        let bluez = BlueZ::with_connection(bus.connect());
+        let state = bluez.state().unwrap();
+        assert!(state.available && state.powered);
+        assert_eq!(state.devices.iter().map(|device| device.id.as_str()).collect::<Vec<_>>(), [HEADPHONES, MOUSE, SPEAKER]);
+        assert_eq!(listed(&bluez), [
+            ("Headphones".to_string(), true, true, Some(55)),
+            ("Mouse".to_string(), true, false, None),
+            ("Speaker".to_string(), false, false, None),
+        ]);
+
+        bluez.set_powered(false).unwrap();
+        assert!(!bluez.state().unwrap().powered);
+    }
+
+    #[test]
+    fn pairs_connects_and_forgets() {
+        let (bus, _service, calls) = start();
+        let bluez = BlueZ::with_connection(bus.connect());
+        bluez.start_discovery().unwrap();
+        bluez.pair(SPEAKER).unwrap();
+        bluez.stop_discovery().unwrap();
+        bluez.disconnect(HEADPHONES).unwrap();
+        bluez.connect(MOUSE).unwrap();
+        bluez.forget(MOUSE).unwrap();
+        assert!(bluez.pair(HEADPHONES).is_err());
+        assert_eq!(*calls.lock().unwrap(), [
+            "StartDiscovery",
+            "Pair Speaker",
+            "Trusted true Speaker",
+            "Connect Speaker",
+            "StopDiscovery",
+            "Disconnect Headphones",
+            "Connect Mouse",
+            &format!("RemoveDevice {}", MOUSE),
+        ]);
+        assert_eq!(listed(&bluez), [
+            ("Mouse".to_string(), true, true, None),
+            ("Speaker".to_string(), true, true, None),
+            ("Headphones".to_string(), true, false, Some(55)),
+        ]);
+    }
+
+    #[test]
+    fn reports_no_adapter() {
+        let bus = TestBus::start();
+        let service = bus.connect();
+        service.object_server().at("/", zbus::fdo::ObjectManager).unwrap();
+        service.request_name(SERVICE).unwrap();
+        let bluez = BlueZ::with_connection(bus.connect());
+        let state = bluez.state().unwrap();
+        assert!(!state.available && state.devices.is_empty());
+        assert!(bluez.set_powered(true).is_err());
+    }
+}
@@ -0,0 +1,613 @@
+use iced::futures::channel::oneshot;
+use iced::Task;
+#[cfg(target_os = "linux")]
+use std::path::PathBuf;
+
+/// How a display's brightness is reached.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisplayControl {
+    /// A built-in panel, by its device name in `/sys/class/backlight`.
+    #[cfg(target_os = "linux")]
+    Backlight(String),
+    /// An external monitor speaking DDC/CI on an I2C bus like `/dev/i2c-5`.
+    #[cfg(target_os = "linux")]
+    I2c(PathBuf),
+    /// A built-in panel, by its WMI instance name.
+    #[cfg(windows)]
+    Wmi(String),
+    /// A monitor reached through the monitor configuration API (DDC/CI), by the display device it is on,
+    /// like `\\.\DISPLAY1`, and its index there.
+    #[cfg(windows)]
+    Physical(String, usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Display {
+    pub name: String,
+    /// Brightness from 0 to 1.
+    pub brightness: f32,
+    pub control: DisplayControl,
+}
+
+/// The displays whose brightness can be changed, built-in panel first.
+pub fn displays() -> Vec<Display> {
+    #[cfg(windows)]
+    return monitor_config::displays();
+    #[cfg(target_os = "linux")]
+    return sysfs::Sysfs::open().displays();
+}
+
+pub fn set_brightness(control: &DisplayControl, brightness: f32) -> Result<(), String> {
+    let brightness = brightness.clamp(0.0, 1.0);
+    #[cfg(windows)]
+    return monitor_config::set_brightness(control, brightness);
+    #[cfg(target_os = "linux")]
+    return sysfs::Sysfs::open().set_brightness(control, brightness);
+}
+
+/// Runs `work` on a thread of its own, DDC/CI takes tens of milliseconds for every read and write.
+pub fn in_background<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Task<Option<T>> {
+    Task::perform(async move {
+        let (sender, receiver) = oneshot::channel();
+        std::thread::spawn(move || {
+            #[cfg(windows)]
+            unsafe {
+                // WMI is reached over COM
+                let _ = windows::Win32::System::Com::CoInitializeEx(None, windows::Win32::System::Com::COINIT_APARTMENTTHREADED);
+            }
+            let _ = sender.send(work());
+        });
+        receiver.await.ok()
+    }, |result| result)
+}
+
+#[cfg(target_os = "linux")]
+mod sysfs {
+    use super::{ddc, Display, DisplayControl};
+    use std::fs;
+    use std::io::ErrorKind;
+    use std::path::{Path, PathBuf};
+
+    /// Backlights and monitor connectors under a sysfs root, `/sys` unless `FROSTWIN_SYSFS_ROOT` says otherwise,
+    /// which lets a fixture directory stand in for the real one.
+    pub struct Sysfs {
+        root: PathBuf,
+    }
+    impl Sysfs {
+        pub fn open() -> Self {
+            Self::new(std::env::var_os("FROSTWIN_SYSFS_ROOT").map(PathBuf::from).unwrap_or_else(|| PathBuf::from("/sys")))
+        }
+
+        pub fn new(root: impl Into<PathBuf>) -> Self {
+            Self { root: root.into() }
+        }
+
+        fn backlight_dir(&self) -> PathBuf {
+            self.root.join("class/backlight")
+        }
+
+        pub fn displays(&self) -> Vec<Display> {
+            let mut displays = Vec::new();
+            if let Some((device, brightness)) = self.backlight() {
+                displays.push(Display {
+                    name: "Built-in display".to_string(),
+                    brightness,
+                    control: DisplayControl::Backlight(device),
+                });
+            }
+            for (name, bus) in self.ddc_buses() {
+                // Monitors without DDC/CI, or with it turned off in their menu, don't answer
+                if let Ok((current, maximum)) = ddc::get_vcp(&bus, ddc::VCP_BRIGHTNESS) && maximum > 0 {
+                    displays.push(Display {
+                        name,
+                        brightness: current as f32 / maximum as f32,
+                        control: DisplayControl::I2c(bus),
+                    });
+                }
+            }
+            displays
+        }
+
+        /// The built-in panel's backlight and its brightness. Kernels can offer several interfaces to the same panel,
+        /// firmware ones are preferred over platform and raw ones as the kernel documentation suggests.
+        pub fn backlight(&self) -> Option<(String, f32)> {
+            let mut best: Option<(u8, String, f32)> = None;
+            for entry in fs::read_dir(self.backlight_dir()).ok()?.flatten() {
+                let device = entry.path();
+                let name = entry.file_name().to_string_lossy().to_string();
+                let Some(maximum) = read_number(&device.join("max_brightness")).filter(|maximum| *maximum > 0) else {
+                    continue;
+                };
+                let Some(current) = read_number(&device.join("actual_brightness")).or_else(|| read_number(&device.join("brightness"))) else {
+                    continue;
+                };
+                let rank = match fs::read_to_string(device.join("type")).unwrap_or_default().trim() {
+                    "firmware" => 0,
+                    "platform" => 1,
+                    _ => 2,
+                };
+                if best.as_ref().is_none_or(|(best_rank, best_name, _)| (rank, &name) < (*best_rank, best_name)) {
+                    best = Some((rank, name, current as f32 / maximum as f32));
+                }
+            }
+            best.map(|(_, name, brightness)| (name, brightness))
+        }
+
+        pub fn set_brightness(&self, control: &DisplayControl, brightness: f32) -> Result<(), String> {
+            match control {
+                DisplayControl::Backlight(device) => self.set_backlight(device, brightness),
+                DisplayControl::I2c(bus) => {
+                    let (_, maximum) = ddc::get_vcp(bus, ddc::VCP_BRIGHTNESS).map_err(|e| format!("Error reading brightness from {}: {}", bus.display(), e))?;
+                    let value = (brightness * maximum as f32).round() as u16;
+                    ddc::set_vcp(bus, ddc::VCP_BRIGHTNESS, value).map_err(|e| format!("Error setting brightness on {}: {}", bus.display(), e))
+                }
+            }
+        }
+
+        /// Writes the backlight level directly when allowed to, and through logind otherwise,
+        /// which lets the user of the active session change it without a udev rule.
+        pub fn set_backlight(&self, device: &str, brightness: f32) -> Result<(), String> {
+            let path = self.backlight_dir().join(device);
+            let maximum = read_number(&path.join("max_brightness")).ok_or_else(|| format!("No backlight named {}", device))?;
+            // Level 0 turns some panels off completely, leaving no way to see the slider
+            let level = ((brightness * maximum as f32).round() as u32).max(1);
+            match fs::write(path.join("brightness"), level.to_string()) {
+                Err(e) if e.kind() == ErrorKind::PermissionDenied => logind_set_brightness(device, level),
+                Err(e) => Err(format!("Error writing brightness of {}: {}", device, e)),
+                Ok(()) => Ok(()),
+            }
+        }
+
+        /// Connected external monitors with the I2C bus device their DDC/CI runs on, found through the `ddc` link of
+        /// DRM connectors like `card0-HDMI-A-1`.
+        pub fn ddc_buses(&self) -> Vec<(String, PathBuf)> {
+            let Ok(entries) = fs::read_dir(self.root.join("class/drm")) else {
+                return Vec::new();
+            };
+            let mut buses = Vec::new();
+            for entry in entries.flatten() {
+                let connector_path = entry.path();
+                let connector = entry.file_name().to_string_lossy().to_string();
+                let Some((_, connector_name)) = connector.split_once('-') else {
+                    continue;
+                };
+                // Built-in panels are driven by their backlight instead
+                if ["eDP", "LVDS", "DSI"].iter().any(|internal| connector_name.starts_with(internal)) {
+                    continue;
+                }
+                if fs::read_to_string(connector_path.join("status")).unwrap_or_default().trim() != "connected" {
+                    continue;
+                }
+                let Some(bus) = fs::read_link(connector_path.join("ddc")).ok().and_then(|link| link.file_name().map(|name| name.to_owned())) else {
+                    continue;
+                };
+                let name = fs::read(connector_path.join("edid")).ok()
+                    .and_then(|edid| edid_monitor_name(&edid))
+                    .unwrap_or_else(|| connector_name.to_string());
+                buses.push((name, Path::new("/dev").join(bus)));
+            }
+            buses.sort();
+            buses
+        }
+    }
+
+    fn read_number(path: &Path) -> Option<u32> {
+        fs::read_to_string(path).ok()?.trim().parse().ok()
+    }
+
+    fn logind_set_brightness(device: &str, level: u32) -> Result<(), String> {
+        let connection = zbus::blocking::Connection::system().map_err(|e| format!("Error connecting to the system bus: {}", e))?;
+        connection.call_method(
+            Some("org.freedesktop.login1"),
+            "/org/freedesktop/login1/session/auto",
+            Some("org.freedesktop.login1.Session"),
+            "SetBrightness",
+            &("backlight", device, level),
+        ).map(|_| ()).map_err(|e| format!("Error setting brightness through logind: {}", e))
+    }
+
+    /// The monitor name from the display descriptors of an EDID block.
+    fn edid_monitor_name(edid: &[u8]) -> Option<String> {
+        (0..4).map(|index| 54 + index * 18).find_map(|offset| {
+            let descriptor = edid.get(offset..offset + 18)?;
+            if descriptor[0..3] != [0, 0, 0] || descriptor[3] != 0xFC {
+                return None;
+            }
+            let name = String::from_utf8_lossy(&descriptor[5..]);
+            let name = name.split('\n').next().unwrap_or_default().trim();
+            (!name.is_empty()).then(|| name.to_string())
+        })
+    }
+}
+
+/// VCP feature reads and writes over DDC/CI on a Linux I2C bus device.
+#[cfg(target_os = "linux")]
+mod ddc {
+    use std::fs::{File, OpenOptions};
+    use std::io::{self, ErrorKind, Read, Write};
+    use std::os::fd::AsRawFd;
+    use std::path::Path;
+    use std::thread::sleep;
+    use std::time::Duration;
+
+    pub const VCP_BRIGHTNESS: u8 = 0x10;
+    // I2C_SLAVE from linux/i2c-dev.h
+    const I2C_SLAVE: libc::c_ulong = 0x0703;
+    const DDC_CI_ADDRESS: libc::c_ulong = 0x37;
+    const HOST_ADDRESS: u8 = 0x51;
+    const DISPLAY_ADDRESS: u8 = 0x6E;
+    // Replies are checked against the address the host reads from
+    const REPLY_CHECK_ADDRESS: u8 = 0x50;
+
+    fn open(bus: &Path) -> io::Result<File> {
+        let file = OpenOptions::new().read(true).write(true).open(bus)?;
+        if unsafe { libc::ioctl(file.as_raw_fd(), I2C_SLAVE, DDC_CI_ADDRESS) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(file)
+    }
+
+    fn checksum(start: u8, bytes: &[u8]) -> u8 {
+        bytes.iter().fold(start, |checksum, byte| checksum ^ byte)
+    }
+
+    /// Returns the current and maximum value of a VCP feature.
+    pub fn get_vcp(bus: &Path, code: u8) -> io::Result<(u16, u16)> {
+        let mut file = open(bus)?;
+        let mut request = vec![HOST_ADDRESS, 0x82, 0x01, code];
+        request.push(checksum(DISPLAY_ADDRESS, &request));
+        file.write_all(&request)?;
+        // Displays need 40 ms before the reply can be read
+        sleep(Duration::from_millis(40));
+        // Source, length, opcode, result, feature, type, maximum and current value, checksum
+        let mut reply = [0u8; 11];
+        file.read_exact(&mut reply)?;
+        if checksum(REPLY_CHECK_ADDRESS, &reply[..10]) != reply[10] {
+            return Err(io::Error::new(ErrorKind::InvalidData, "DDC/CI reply failed its checksum"));
+        }
+        if reply[2] != 0x02 || reply[4] != code {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Unexpected DDC/CI reply"));
+        }
+        if reply[3] != 0 {
+            return Err(io::Error::new(ErrorKind::Unsupported, "The display does not support this setting"));
+        }
+        Ok((u16::from_be_bytes([reply[8], reply[9]]), u16::from_be_bytes([reply[6], reply[7]])))
+    }
+
+    pub fn set_vcp(bus: &Path, code: u8, value: u16) -> io::Result<()> {
+        let mut file = open(bus)?;
+        let [high, low] = value.to_be_bytes();
+        let mut request = vec![HOST_ADDRESS, 0x84, 0x03, code, high, low];
+        request.push(checksum(DISPLAY_ADDRESS, &request));
+        file.write_all(&request)?;
+        // Leave the display time to apply it before the next command
+        sleep(Duration::from_millis(50));
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+mod monitor_config {
+    use super::{Display, DisplayControl};
+    use windows::core::{BOOL, BSTR, PCWSTR};
+    use windows::Win32::Devices::Display::{DestroyPhysicalMonitors, GetMonitorBrightness, GetNumberOfPhysicalMonitorsFromHMONITOR, GetPhysicalMonitorsFromHMONITOR, SetMonitorBrightness, PHYSICAL_MONITOR};
+    use windows::Win32::Foundation::{HANDLE, LPARAM, RECT};
+    use windows::Win32::Graphics::Gdi::{EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITORINFO, MONITORINFOEXW};
+    use windows::Win32::System::Com::{CoCreateInstance, CoSetProxyBlanket, CLSCTX_INPROC_SERVER, EOAC_NONE, RPC_C_AUTHN_LEVEL_CALL, RPC_C_IMP_LEVEL_IMPERSONATE};
+    use windows::Win32::System::Rpc::{RPC_C_AUTHN_WINNT, RPC_C_AUTHZ_NONE};
+    use windows::Win32::System::Variant::{VariantClear, VARIANT, VT_BSTR, VT_UI1, VT_UI4};
+    use windows::Win32::System::Wmi::{IWbemClassObject, IWbemLocator, IWbemServices, WbemLocator, WBEM_FLAG_FORWARD_ONLY, WBEM_FLAG_RETURN_IMMEDIATELY, WBEM_FLAG_RETURN_WBEM_COMPLETE, WBEM_INFINITE};
+
+    pub fn displays() -> Vec<Display> {
+        let mut displays = match wmi_panels() {
+            Ok(panels) => panels,
+            Err(e) => {
+                // Desktops have no panel WMI knows about
+                eprintln!("Error reading built-in display brightness: {}", e);
+                Vec::new()
+            }
+        };
+        for_each_physical_monitor(|device, index, monitor| {
+            let (minimum, current, maximum) = monitor_brightness(monitor.hPhysicalMonitor)?;
+            let description = monitor.szPhysicalMonitorDescription;
+            let length = description.iter().position(|c| *c == 0).unwrap_or(description.len());
+            displays.push(Display {
+                name: String::from_utf16_lossy(&description[..length]),
+                brightness: (current - minimum) as f32 / (maximum - minimum) as f32,
+                control: DisplayControl::Physical(device.to_string(), index),
+            });
+            Some(())
+        });
+        displays
+    }
+
+    pub fn set_brightness(control: &DisplayControl, brightness: f32) -> Result<(), String> {
+        match control {
+            DisplayControl::Wmi(instance) => set_wmi_brightness(instance, (brightness * 100.0).round() as u8).map_err(|e| format!("Error setting brightness through WMI: {}", e)),
+            DisplayControl::Physical(device, index) => {
+                let mut set = false;
+                for_each_physical_monitor(|monitor_device, monitor_index, monitor| {
+                    if monitor_device != device || monitor_index != *index {
+                        return None;
+                    }
+                    let (minimum, _, maximum) = monitor_brightness(monitor.hPhysicalMonitor)?;
+                    let value = minimum + (brightness * (maximum - minimum) as f32).round() as u32;
+                    set = unsafe { SetMonitorBrightness(monitor.hPhysicalMonitor, value) } != 0;
+                    Some(())
+                });
+                if set {
+                    Ok(())
+                } else {
+                    Err(format!("Error setting brightness on {}", device))
+                }
+            }
+        }
+    }
+
+    /// Minimum, current and maximum brightness over DDC/CI, `None` for monitors that don't support it.
+    fn monitor_brightness(monitor: HANDLE) -> Option<(u32, u32, u32)> {
+        let (mut minimum, mut current, mut maximum) = (0, 0, 0);
+        let supported = unsafe { GetMonitorBrightness(monitor, &mut minimum, &mut current, &mut maximum) } != 0;
+        (supported && maximum > minimum).then_some((minimum, current, maximum))
+    }
+
+    /// Calls `f` with every physical monitor and the display device it belongs to. The monitor handles are only
+    /// valid during the call.
+    fn for_each_physical_monitor(mut f: impl FnMut(&str, usize, &PHYSICAL_MONITOR) -> Option<()>) {
+        let mut monitors: Vec<HMONITOR> = Vec::new();
+        unsafe {
+            let _ = EnumDisplayMonitors(None, None, Some(collect_monitor), LPARAM(&mut monitors as *mut _ as isize));
+        }
+        for monitor in monitors {
+            unsafe {
+                let mut info = MONITORINFOEXW::default();
+                info.monitorInfo.cbSize = size_of::<MONITORINFOEXW>() as u32;
+                if !GetMonitorInfoW(monitor, &mut info as *mut MONITORINFOEXW as *mut MONITORINFO).as_bool() {
+                    continue;
+                }
+                let length = info.szDevice.iter().position(|c| *c == 0).unwrap_or(info.szDevice.len());
+                let device = String::from_utf16_lossy(&info.szDevice[..length]);
+                let mut count = 0;
+                if GetNumberOfPhysicalMonitorsFromHMONITOR(monitor, &mut count).is_err() || count == 0 {
+                    continue;
+                }
+                let mut physical_monitors = vec![PHYSICAL_MONITOR::default(); count as usize];
+                if GetPhysicalMonitorsFromHMONITOR(monitor, &mut physical_monitors).is_err() {
+                    continue;
+                }
+                for (index, physical_monitor) in physical_monitors.iter().enumerate() {
+                    f(&device, index, physical_monitor);
+                }
+                let _ = DestroyPhysicalMonitors(&physical_monitors);
+            }
+        }
+    }
+
+    unsafe extern "system" fn collect_monitor(monitor: HMONITOR, _: HDC, _: *mut RECT, data: LPARAM) -> BOOL {
+        let monitors = unsafe { &mut *(data.0 as *mut Vec<HMONITOR>) };
+        monitors.push(monitor);
+        true.into()
+    }
+
+    fn wmi_services() -> windows::core::Result<IWbemServices> {
+        unsafe {
+            let locator: IWbemLocator = CoCreateInstance(&WbemLocator, None, CLSCTX_INPROC_SERVER)?;
+            let services = locator.ConnectServer(&BSTR::from("ROOT\\WMI"), &BSTR::new(), &BSTR::new(), &BSTR::new(), 0, &BSTR::new(), None)?;
+            CoSetProxyBlanket(&services, RPC_C_AUTHN_WINNT, RPC_C_AUTHZ_NONE, PCWSTR::null(), RPC_C_AUTHN_LEVEL_CALL, RPC_C_IMP_LEVEL_IMPERSONATE, None, EOAC_NONE)?;
+            Ok(services)
+        }
+    }
+
+    fn wmi_query(services: &IWbemServices, query: &str) -> windows::core::Result<Vec<IWbemClassObject>> {
+        unsafe {
+            let enumerator = services.ExecQuery(&BSTR::from("WQL"), &BSTR::from(query), WBEM_FLAG_FORWARD_ONLY | WBEM_FLAG_RETURN_IMMEDIATELY, None)?;
+            let mut objects = Vec::new();
+            loop {
+                let mut row = [None];
+                let mut returned = 0;
+                enumerator.Next(WBEM_INFINITE, &mut row, &mut returned).ok()?;
+                match row[0].take() {
+                    Some(object) if returned > 0 => objects.push(object),
+                    _ => break,
+                }
+            }
+            Ok(objects)
+        }
+    }
+
+    /// Reads a string property, like an instance name or object path.
+    fn wmi_string(object: &IWbemClassObject, name: PCWSTR) -> windows::core::Result<String> {
+        unsafe {
+            let mut value = VARIANT::default();
+            object.Get(name, 0, &mut value, None, None)?;
+            let text = if value.Anonymous.Anonymous.vt == VT_BSTR {
+                value.Anonymous.Anonymous.Anonymous.bstrVal.to_string()
+            } else {
+                String::new()
+            };
+            VariantClear(&mut value)?;
+            Ok(text)
+        }
+    }
+
+    fn wmi_panels() -> windows::core::Result<Vec<Display>> {
+        let services = wmi_services()?;
+        let mut panels = Vec::new();
+        for object in wmi_query(&services, "SELECT InstanceName, CurrentBrightness FROM WmiMonitorBrightness WHERE Active = TRUE")? {
+            let instance = wmi_string(&object, windows::core::w!("InstanceName"))?;
+            let brightness = unsafe {
+                let mut value = VARIANT::default();
+                object.Get(windows::core::w!("CurrentBrightness"), 0, &mut value, None, None)?;
+                value.Anonymous.Anonymous.Anonymous.bVal
+            };
+            panels.push(Display {
+                name: "Built-in display".to_string(),
+                brightness: brightness as f32 / 100.0,
+                control: DisplayControl::Wmi(instance),
+            });
+        }
+        Ok(panels)
+    }
+
+    fn set_wmi_brightness(instance: &str, percent: u8) -> windows::core::Result<()> {
+        let services = wmi_services()?;
+        let methods = wmi_query(&services, "SELECT * FROM WmiMonitorBrightnessMethods WHERE Active = TRUE")?;
+        for object in methods {
+            if wmi_string(&object, windows::core::w!("InstanceName"))? != instance {
+                continue;
+            }
+            let path = wmi_string(&object, windows::core::w!("__PATH"))?;
+            unsafe {
+                let mut class = None;
+                services.GetObject(&BSTR::from("WmiMonitorBrightnessMethods"), WBEM_FLAG_RETURN_WBEM_COMPLETE, None, Some(&mut class), None)?;
+                let Some(class) = class else {
+                    return Ok(());
+                };
+                let mut signature = None;
+                class.GetMethod(windows::core::w!("WmiSetBrightness"), 0, &mut signature, std::ptr::null_mut())?;
+                let Some(signature) = signature else {
+                    return Ok(());
+                };
+                let parameters = signature.SpawnInstance(0)?;
+                let mut timeout = VARIANT::default();
+                (*timeout.Anonymous.Anonymous).vt = VT_UI4;
+                (*timeout.Anonymous.Anonymous).Anonymous.ulVal = 0;
+                parameters.Put(windows::core::w!("Timeout"), 0, &timeout, 0)?;
+                let mut brightness = VARIANT::default();
+                (*brightness.Anonymous.Anonymous).vt = VT_UI1;
+                (*brightness.Anonymous.Anonymous).Anonymous.bVal = percent.min(100);
+                parameters.Put(windows::core::w!("Brightness"), 0, &brightness, 0)?;
+                services.ExecMethod(&BSTR::from(path), &BSTR::from("WmiSetBrightness"), WBEM_FLAG_RETURN_WBEM_COMPLETE, None, &parameters, None, None)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::sysfs::Sysfs;
+    use super::*;
+    use std::path::Path;
+
+    fn write(root: &Path, relative: &str, content: &[u8]) {
+        let path = root.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    fn backlight(root: &Path, device: &str, kind: &str, maximum: &str, brightness: &str) {
+        write(root, &format!("class/backlight/{}/type", device), kind.as_bytes());
+        write(root, &format!("class/backlight/{}/max_brightness", device), maximum.as_bytes());
+        write(root, &format!("class/backlight/{}/brightness", device), brightness.as_bytes());
+    }
+
+    /// An EDID block with the monitor name in its second display descriptor.
+    fn edid(name: &str) -> Vec<u8> {
+        let mut edid = vec![0u8; 128];
+        edid[72..77].copy_from_slice(&[0, 0, 0, 0xFC, 0]);
+        let mut text = format!("{}\n", name).into_bytes();
+        text.resize(13, b' ');
+        edid[77..90].copy_from_slice(&text);
+        edid
+    }
+
+    fn connector(root: &Path, name: &str, status: &str, bus: Option<&str>, monitor: Option<&str>) {
+        let connector = root.join("class/drm").join(name);
+        write(root, &format!("class/drm/{}/status", name), format!("{}\n", status).as_bytes());
+        if let Some(bus) = bus {
+            std::os::unix::fs::symlink(format!("../../../devices/i2c/{}", bus), connector.join("ddc")).unwrap();
+        }
+        if let Some(monitor) = monitor {
+            std::fs::write(connector.join("edid"), edid(monitor)).unwrap();
+        }
+    }
+
+    #[test]
+    fn scales_brightness_by_the_maximum() {
+        let root = tempfile::tempdir().unwrap();
+        backlight(root.path(), "intel_backlight", "raw", "19393\n", "4848\n");
+        let sysfs = Sysfs::new(root.path());
+        let (device, brightness) = sysfs.backlight().unwrap();
+        assert_eq!(device, "intel_backlight");
+        assert!((brightness - 0.25).abs() < 0.001, "{}", brightness);
+
+        // The level the hardware settled on wins over the one last asked for
+        write(root.path(), "class/backlight/intel_backlight/actual_brightness", b"19393");
+        assert_eq!(sysfs.backlight(), Some(("intel_backlight".to_string(), 1.0)));
+    }
+
+    #[test]
+    fn prefers_firmware_backlights() {
+        let root = tempfile::tempdir().unwrap();
+        backlight(root.path(), "intel_backlight", "raw", "1000", "250");
+        backlight(root.path(), "acpi_video0", "firmware", "10", "5");
+        backlight(root.path(), "dell_backlight", "platform", "15", "15");
+        assert_eq!(Sysfs::new(root.path()).backlight(), Some(("acpi_video0".to_string(), 0.5)));
+    }
+
+    #[test]
+    fn skips_backlights_with_missing_files() {
+        let root = tempfile::tempdir().unwrap();
+        let sysfs = Sysfs::new(root.path());
+        assert_eq!(sysfs.backlight(), None);
+        assert!(sysfs.displays().is_empty());
+
+        // No maximum, a zero maximum, an unreadable level, and no level at all
+        write(root.path(), "class/backlight/acpi_video0/brightness", b"5");
+        backlight(root.path(), "acpi_video1", "firmware", "0", "0");
+        backlight(root.path(), "acpi_video2", "firmware", "10", "bright");
+        write(root.path(), "class/backlight/acpi_video3/max_brightness", b"10");
+        assert_eq!(sysfs.backlight(), None);
+
+        // A missing type counts as a raw interface
+        write(root.path(), "class/backlight/radeon_bl0/max_brightness", b"255");
+        write(root.path(), "class/backlight/radeon_bl0/brightness", b"51");
+        assert_eq!(sysfs.backlight(), Some(("radeon_bl0".to_string(), 0.2)));
+        assert_eq!(sysfs.displays(), [Display {
+            name: "Built-in display".to_string(),
+            brightness: 0.2,
+            control: DisplayControl::Backlight("radeon_bl0".to_string()),
+        }]);
+    }
+
+    #[test]
+    fn finds_external_monitor_buses() {
+        let root = tempfile::tempdir().unwrap();
+        connector(root.path(), "card0-eDP-1", "connected", Some("i2c-3"), Some("Panel"));
+        connector(root.path(), "card0-HDMI-A-1", "connected", Some("i2c-5"), Some("DELL U2720Q"));
+        connector(root.path(), "card0-DP-1", "connected", Some("i2c-7"), None);
+        connector(root.path(), "card0-DP-2", "disconnected", Some("i2c-8"), Some("Unplugged"));
+        connector(root.path(), "card0-DP-3", "connected", None, Some("No DDC"));
+        write(root.path(), "class/drm/version", b"drm 1.1.0");
+        let sysfs = Sysfs::new(root.path());
+        assert_eq!(sysfs.ddc_buses(), [
+            ("DELL U2720Q".to_string(), PathBuf::from("/dev/i2c-5")),
+            ("DP-1".to_string(), PathBuf::from("/dev/i2c-7")),
+        ]);
+        // Neither bus exists here, so neither monitor answers
+        assert!(sysfs.displays().is_empty());
+    }
+
+    #[test]
+    fn writes_the_scaled_level() {
+        let root = tempfile::tempdir().unwrap();
+        backlight(root.path(), "acpi_video0", "firmware", "10", "5");
+        let sysfs = Sysfs::new(root.path());
+        let level = || std::fs::read_to_string(root.path().join("class/backlight/acpi_video0/brightness")).unwrap();
+
+        sysfs.set_backlight("acpi_video0", 0.74).unwrap();
+        assert_eq!(level(), "7");
+        sysfs.set_brightness(&DisplayControl::Backlight("acpi_video0".to_string()), 1.0).unwrap();
+        assert_eq!(level(), "10");
+        // Never all the way off
+        sysfs.set_backlight("acpi_video0", 0.0).unwrap();
+        assert_eq!(level(), "1");
+
+        assert!(sysfs.set_backlight("acpi_video9", 0.5).unwrap_err().contains("No backlight named acpi_video9"));
+        // Errors other than a missing permission are not retried through logind
+        std::fs::remove_file(root.path().join("class/backlight/acpi_video0/brightness")).unwrap();
+        std::fs::create_dir(root.path().join("class/backlight/acpi_video0/brightness")).unwrap();
+        assert!(sysfs.set_backlight("acpi_video0", 0.5).unwrap_err().starts_with("Error writing brightness of acpi_video0"));
+    }
+}
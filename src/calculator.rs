@@ -0,0 +1,547 @@
+// Arithmetic and unit conversion typed into the start menu search, like "12*(3+4)", "5 km in mi" or "0x1F to dec"
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Operator(char),
+    LeftParen,
+    RightParen,
+    Comma,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dimension {
+    Length,
+    Mass,
+    Time,
+    Volume,
+    Data,
+    Temperature,
+    Speed,
+    Area,
+}
+
+struct Unit {
+    names: &'static [&'static str],
+    dimension: Dimension,
+    // Value in the base unit of the dimension is `value * factor + offset`
+    factor: f64,
+    offset: f64,
+}
+
+const fn unit(names: &'static [&'static str], dimension: Dimension, factor: f64) -> Unit {
+    Unit { names, dimension, factor, offset: 0.0 }
+}
+
+const UNITS: &[Unit] = &[
+    unit(&["m", "meter", "meters", "metre", "metres"], Dimension::Length, 1.0),
+    unit(&["km", "kilometer", "kilometers", "kilometre", "kilometres"], Dimension::Length, 1000.0),
+    unit(&["cm", "centimeter", "centimeters"], Dimension::Length, 0.01),
+    unit(&["mm", "millimeter", "millimeters"], Dimension::Length, 0.001),
+    unit(&["mi", "mile", "miles"], Dimension::Length, 1609.344),
+    unit(&["yd", "yard", "yards"], Dimension::Length, 0.9144),
+    unit(&["ft", "foot", "feet"], Dimension::Length, 0.3048),
+    unit(&["in", "inch", "inches"], Dimension::Length, 0.0254),
+    unit(&["nmi"], Dimension::Length, 1852.0),
+    unit(&["g", "gram", "grams"], Dimension::Mass, 1.0),
+    unit(&["kg", "kilogram", "kilograms"], Dimension::Mass, 1000.0),
+    unit(&["mg", "milligram", "milligrams"], Dimension::Mass, 0.001),
+    unit(&["t", "tonne", "tonnes"], Dimension::Mass, 1_000_000.0),
+    unit(&["lb", "lbs", "pound", "pounds"], Dimension::Mass, 453.59237),
+    unit(&["oz", "ounce", "ounces"], Dimension::Mass, 28.349523125),
+    unit(&["st", "stone"], Dimension::Mass, 6350.29318),
+    unit(&["s", "sec", "second", "seconds"], Dimension::Time, 1.0),
+    unit(&["ms", "millisecond", "milliseconds"], Dimension::Time, 0.001),
+    unit(&["min", "minute", "minutes"], Dimension::Time, 60.0),
+    unit(&["h", "hr", "hour", "hours"], Dimension::Time, 3600.0),
+    unit(&["d", "day", "days"], Dimension::Time, 86400.0),
+    unit(&["wk", "week", "weeks"], Dimension::Time, 604800.0),
+    unit(&["l", "liter", "liters", "litre", "litres"], Dimension::Volume, 1.0),
+    unit(&["ml", "milliliter", "milliliters"], Dimension::Volume, 0.001),
+    unit(&["gal", "gallon", "gallons"], Dimension::Volume, 3.785411784),
+    unit(&["qt", "quart", "quarts"], Dimension::Volume, 0.946352946),
+    unit(&["pt", "pint", "pints"], Dimension::Volume, 0.473176473),
+    unit(&["cup", "cups"], Dimension::Volume, 0.2365882365),
+    unit(&["floz"], Dimension::Volume, 0.0295735295625),
+    unit(&["b", "byte", "bytes"], Dimension::Data, 1.0),
+    unit(&["kb", "kilobyte", "kilobytes"], Dimension::Data, 1e3),
+    unit(&["mb", "megabyte", "megabytes"], Dimension::Data, 1e6),
+    unit(&["gb", "gigabyte", "gigabytes"], Dimension::Data, 1e9),
+    unit(&["tb", "terabyte", "terabytes"], Dimension::Data, 1e12),
+    unit(&["kib", "kibibyte", "kibibytes"], Dimension::Data, 1024.0),
+    unit(&["mib", "mebibyte", "mebibytes"], Dimension::Data, 1048576.0),
+    unit(&["gib", "gibibyte", "gibibytes"], Dimension::Data, 1073741824.0),
+    unit(&["tib", "tebibyte", "tebibytes"], Dimension::Data, 1099511627776.0),
+    Unit { names: &["k", "kelvin"], dimension: Dimension::Temperature, factor: 1.0, offset: 0.0 },
+    Unit { names: &["c", "celsius"], dimension: Dimension::Temperature, factor: 1.0, offset: 273.15 },
+    Unit { names: &["f", "fahrenheit"], dimension: Dimension::Temperature, factor: 5.0 / 9.0, offset: 459.67 * 5.0 / 9.0 },
+    unit(&["mps"], Dimension::Speed, 1.0),
+    unit(&["kph", "kmh"], Dimension::Speed, 1.0 / 3.6),
+    unit(&["mph"], Dimension::Speed, 0.44704),
+    unit(&["kn", "knot", "knots"], Dimension::Speed, 1852.0 / 3600.0),
+    unit(&["m2", "sqm"], Dimension::Area, 1.0),
+    unit(&["km2", "sqkm"], Dimension::Area, 1e6),
+    unit(&["ha", "hectare", "hectares"], Dimension::Area, 1e4),
+    unit(&["acre", "acres"], Dimension::Area, 4046.8564224),
+    unit(&["ft2", "sqft"], Dimension::Area, 0.09290304),
+];
+
+fn find_unit(name: &str) -> Option<&'static Unit> {
+    let name = name.to_lowercase();
+    UNITS.iter().find(|unit| unit.names.contains(&name.as_str()))
+}
+
+/// A computed search result, `result` being what gets copied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calculation {
+    pub result: String,
+}
+
+/// Evaluates `input` when it reads as a calculation. Plain words and lone numbers are left to the
+/// program search, so typing "e" or "2048" does not turn into a result row.
+pub fn evaluate(input: &str) -> Option<Calculation> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return None;
+    }
+    // "<expression> <unit> in <unit>" or "<expression> to hex", the keyword being next to last
+    // so "5 in in cm" and "3 in in in" still read the other "in"s as inches
+    let keyword = tokens.len().checked_sub(2)
+        .filter(|index| matches!(&tokens[*index], Token::Ident(word) if word == "in" || word == "to"));
+    if let Some(index) = keyword && index > 0 && let Token::Ident(target) = &tokens[index + 1] {
+        let left = &tokens[..index];
+        if let Some(base) = Base::from_name(target) {
+            let (value, _) = parse_expression(left)?;
+            return Some(Calculation { result: base.format(value)? });
+        }
+        if let Some(Token::Ident(source)) = left.last() && let (Some(from), Some(to)) = (find_unit(source), find_unit(target)) {
+            if from.dimension != to.dimension {
+                return None;
+            }
+            let (value, _) = parse_expression(&left[..left.len() - 1])?;
+            let converted = ((value * from.factor + from.offset) - to.offset) / to.factor;
+            return Some(Calculation { result: format!("{} {}", format_number(converted)?, target) });
+        }
+        return None;
+    }
+    let (value, operations) = parse_expression(&tokens)?;
+    if operations == 0 {
+        return None;
+    }
+    Some(Calculation { result: format_number(value)? })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Base {
+    Binary,
+    Octal,
+    Decimal,
+    Hexadecimal,
+}
+impl Base {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "bin" | "binary" => Some(Base::Binary),
+            "oct" | "octal" => Some(Base::Octal),
+            "dec" | "decimal" => Some(Base::Decimal),
+            "hex" | "hexadecimal" => Some(Base::Hexadecimal),
+            _ => None,
+        }
+    }
+    fn format(&self, value: f64) -> Option<String> {
+        if *self == Base::Decimal {
+            return format_number(value);
+        }
+        // Other bases only make sense for whole numbers that fit an integer
+        if value.fract() != 0.0 || value.abs() >= i64::MAX as f64 {
+            return None;
+        }
+        let integer = value as i64;
+        let sign = if integer < 0 { "-" } else { "" };
+        let magnitude = integer.unsigned_abs();
+        Some(match self {
+            Base::Binary => format!("{}0b{:b}", sign, magnitude),
+            Base::Octal => format!("{}0o{:o}", sign, magnitude),
+            Base::Hexadecimal => format!("{}0x{:X}", sign, magnitude),
+            Base::Decimal => unreachable!(),
+        })
+    }
+}
+
+/// Whole numbers print without a fraction, others with up to 12 significant digits.
+fn format_number(value: f64) -> Option<String> {
+    if !value.is_finite() {
+        return None;
+    }
+    if value == 0.0 {
+        return Some("0".to_string());
+    }
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return Some(format!("{}", value as i64));
+    }
+    let magnitude = value.abs().log10().floor() as i32;
+    if !(-6..15).contains(&magnitude) {
+        return Some(format!("{:.6e}", value));
+    }
+    let decimals = (11 - magnitude).max(0) as usize;
+    let formatted = format!("{:.*}", decimals, value);
+    let trimmed = if formatted.contains('.') {
+        formatted.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        formatted.as_str()
+    };
+    Some(trimmed.to_string())
+}
+
+fn tokenize(input: &str) -> Option<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut index = 0;
+    while index < chars.len() {
+        let c = chars[index];
+        if c.is_whitespace() {
+            index += 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(index + 1).is_some_and(|next| next.is_ascii_digit())) {
+            let radix = match (c, chars.get(index + 1).map(|next| next.to_ascii_lowercase())) {
+                ('0', Some('x')) => 16,
+                ('0', Some('b')) => 2,
+                ('0', Some('o')) => 8,
+                _ => 10,
+            };
+            if radix != 10 {
+                let start = index + 2;
+                let mut end = start;
+                while end < chars.len() && (chars[end].is_digit(radix) || chars[end] == '_') {
+                    end += 1;
+                }
+                let digits: String = chars[start..end].iter().filter(|c| **c != '_').collect();
+                tokens.push(Token::Number(i64::from_str_radix(&digits, radix).ok()? as f64));
+                index = end;
+                continue;
+            }
+            let start = index;
+            while index < chars.len() && (chars[index].is_ascii_digit() || chars[index] == '.' || chars[index] == '_') {
+                index += 1;
+            }
+            // An exponent needs digits after it, otherwise "2e" is two followed by the constant e
+            if index < chars.len() && chars[index].eq_ignore_ascii_case(&'e') {
+                let mut end = index + 1;
+                if end < chars.len() && (chars[end] == '+' || chars[end] == '-') {
+                    end += 1;
+                }
+                if end < chars.len() && chars[end].is_ascii_digit() {
+                    while end < chars.len() && chars[end].is_ascii_digit() {
+                        end += 1;
+                    }
+                    index = end;
+                }
+            }
+            let number: String = chars[start..index].iter().filter(|c| **c != '_').collect();
+            tokens.push(Token::Number(number.parse().ok()?));
+        } else if c.is_alphabetic() {
+            let start = index;
+            while index < chars.len() && (chars[index].is_alphanumeric() || chars[index] == '_') {
+                index += 1;
+            }
+            tokens.push(Token::Ident(chars[start..index].iter().collect::<String>().to_lowercase()));
+        } else {
+            let token = match c {
+                '+' | '-' | '/' | '%' | '^' => Token::Operator(c),
+                '*' | '×' => {
+                    // "**" is the power operator of many languages
+                    if chars.get(index + 1) == Some(&'*') {
+                        index += 1;
+                        Token::Operator('^')
+                    } else {
+                        Token::Operator('*')
+                    }
+                }
+                '÷' => Token::Operator('/'),
+                '(' => Token::LeftParen,
+                ')' => Token::RightParen,
+                ',' => Token::Comma,
+                _ => return None,
+            };
+            tokens.push(token);
+            index += 1;
+        }
+    }
+    Some(tokens)
+}
+
+/// Parses and evaluates the whole token list, returning the value and how many operators
+/// and function calls it took.
+fn parse_expression(tokens: &[Token]) -> Option<(f64, usize)> {
+    let mut parser = Parser { tokens, position: 0, operations: 0 };
+    let value = parser.sum()?;
+    if parser.position != tokens.len() {
+        return None;
+    }
+    Some((value, parser.operations))
+}
+
+/// Recursive descent over sum, product, unary minus, power and atoms, from lowest to highest precedence.
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+    operations: usize,
+}
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    fn sum(&mut self) -> Option<f64> {
+        let mut value = self.product()?;
+        while let Some(Token::Operator(operator @ ('+' | '-'))) = self.peek() {
+            let operator = *operator;
+            self.position += 1;
+            self.operations += 1;
+            let right = self.product()?;
+            value = if operator == '+' { value + right } else { value - right };
+        }
+        Some(value)
+    }
+
+    fn product(&mut self) -> Option<f64> {
+        let mut value = self.unary()?;
+        loop {
+            match self.peek() {
+                Some(Token::Operator(operator @ ('*' | '/' | '%'))) => {
+                    let operator = *operator;
+                    self.position += 1;
+                    self.operations += 1;
+                    let right = self.unary()?;
+                    value = match operator {
+                        '*' => value * right,
+                        '/' => value / right,
+                        _ => value % right,
+                    };
+                }
+                // Implicit multiplication, as in "2pi" or "3(4+5)"
+                Some(Token::LeftParen | Token::Ident(_)) => {
+                    self.operations += 1;
+                    value *= self.unary()?;
+                }
+                _ => return Some(value),
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Option<f64> {
+        match self.peek() {
+            Some(Token::Operator('-')) => {
+                self.position += 1;
+                Some(-self.unary()?)
+            }
+            Some(Token::Operator('+')) => {
+                self.position += 1;
+                self.unary()
+            }
+            _ => self.power(),
+        }
+    }
+
+    /// Right associative, and binding tighter than unary minus so "-2^2" is -4.
+    fn power(&mut self) -> Option<f64> {
+        let base = self.atom()?;
+        if let Some(Token::Operator('^')) = self.peek() {
+            self.position += 1;
+            self.operations += 1;
+            let exponent = self.unary()?;
+            return Some(base.powf(exponent));
+        }
+        Some(base)
+    }
+
+    fn atom(&mut self) -> Option<f64> {
+        match self.next()?.clone() {
+            Token::Number(value) => Some(value),
+            Token::LeftParen => {
+                let value = self.sum()?;
+                match self.next()? {
+                    Token::RightParen => Some(value),
+                    _ => None,
+                }
+            }
+            Token::Ident(name) => {
+                if let Some(Token::LeftParen) = self.peek() {
+                    self.position += 1;
+                    let mut arguments: Vec<f64> = Vec::new();
+                    if let Some(Token::RightParen) = self.peek() {
+                        self.position += 1;
+                    } else {
+                        loop {
+                            arguments.push(self.sum()?);
+                            match self.next()? {
+                                Token::Comma => {}
+                                Token::RightParen => break,
+                                _ => return None,
+                            }
+                        }
+                    }
+                    self.operations += 1;
+                    return call_function(&name, &arguments);
+                }
+                match name.as_str() {
+                    "pi" => Some(std::f64::consts::PI),
+                    "e" => Some(std::f64::consts::E),
+                    "tau" => Some(std::f64::consts::TAU),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+fn call_function(name: &str, arguments: &[f64]) -> Option<f64> {
+    match (name, arguments) {
+        ("sqrt", [x]) => Some(x.sqrt()),
+        ("cbrt", [x]) => Some(x.cbrt()),
+        ("abs", [x]) => Some(x.abs()),
+        ("floor", [x]) => Some(x.floor()),
+        ("ceil", [x]) => Some(x.ceil()),
+        ("round", [x]) => Some(x.round()),
+        ("exp", [x]) => Some(x.exp()),
+        ("ln", [x]) => Some(x.ln()),
+        ("log", [x]) | ("log10", [x]) => Some(x.log10()),
+        ("log2", [x]) => Some(x.log2()),
+        ("log", [x, base]) => Some(x.log(*base)),
+        ("sin", [x]) => Some(x.sin()),
+        ("cos", [x]) => Some(x.cos()),
+        ("tan", [x]) => Some(x.tan()),
+        ("asin", [x]) => Some(x.asin()),
+        ("acos", [x]) => Some(x.acos()),
+        ("atan", [x]) => Some(x.atan()),
+        ("pow", [x, y]) => Some(x.powf(*y)),
+        ("min", [first, rest @ ..]) => Some(rest.iter().fold(*first, |min, x| min.min(*x))),
+        ("max", [first, rest @ ..]) => Some(rest.iter().fold(*first, |max, x| max.max(*x))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(input: &str) -> Option<String> {
+        evaluate(input).map(|calculation| calculation.result)
+    }
+
+    #[test]
+    fn evaluates_arithmetic() {
+        let cases = [
+            ("1+2*3", "7"),
+            ("(1+2)*3", "9"),
+            ("12*(3+4)", "84"),
+            ("10-4-3", "3"),
+            ("100/10/5", "2"),
+            ("7 % 4", "3"),
+            ("-3+5", "2"),
+            ("2*-3", "-6"),
+            ("--2+1", "3"),
+            ("-2^2", "-4"),
+            ("(-2)^2", "4"),
+            ("2^3^2", "512"),
+            ("2**10", "1024"),
+            ("2^-1", "0.5"),
+            ("3 × 4 ÷ 6", "2"),
+            ("2pi", "6.28318530718"),
+            ("3(4+5)", "27"),
+            ("sqrt(16)+1", "5"),
+            ("max(3, 9, 4)", "9"),
+            ("log(8, 2)", "3"),
+            ("1_000 * 3", "3000"),
+            ("1.5e3 + 1", "1501"),
+            ("1/3", "0.333333333333"),
+            ("1e20 * 3", "3.000000e20"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(result(input).as_deref(), Some(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn rejects_non_results() {
+        let cases = ["1/0", "-1/0", "0/0", "sqrt(-1)", "firefox", "e", "pi", "2048", "0x1F", "", "   ",
+            "2+", "(1+2", "1+2)", "foo(2)", "2 $ 3", "visual studio code"];
+        for input in cases {
+            assert_eq!(result(input), None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn converts_bases() {
+        let cases = [
+            ("255 to hex", "0xFF"),
+            ("0x1F to dec", "31"),
+            ("0b1010 in decimal", "10"),
+            ("0o17 + 1 to dec", "16"),
+            ("10 to bin", "0b1010"),
+            ("64 to oct", "0o100"),
+            ("-255 to hex", "-0xFF"),
+            ("0xff_ff to binary", "0b1111111111111111"),
+            ("1/4 to dec", "0.25"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(result(input).as_deref(), Some(expected), "{}", input);
+        }
+        // Fractions have no place in other bases
+        assert_eq!(result("1/4 to hex"), None);
+        assert_eq!(result("1/0 to hex"), None);
+    }
+
+    #[test]
+    fn converts_units() {
+        let cases = [
+            ("5 km in mi", "3.10685596119 mi"),
+            ("1 mi to km", "1.609344 km"),
+            ("12 in in cm", "30.48 cm"),
+            ("100 c in f", "212 f"),
+            ("32 fahrenheit to celsius", "0 celsius"),
+            ("0 k in c", "-273.15 c"),
+            ("1 gib in mb", "1073.741824 mb"),
+            ("2 h in min", "120 min"),
+            ("1 lb in kg", "0.45359237 kg"),
+            ("1 gal in l", "3.785411784 l"),
+            ("100 kph in mph", "62.1371192237 mph"),
+            ("1 ha in acres", "2.47105381467 acres"),
+            ("(1+1) km in m", "2000 m"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(result(input).as_deref(), Some(expected), "{}", input);
+        }
+    }
+
+    #[test]
+    fn every_unit_name_converts_to_itself() {
+        for unit in UNITS {
+            let canonical = unit.names[0];
+            for name in unit.names {
+                assert_eq!(result(&format!("3 {} in {}", name, canonical)), Some(format!("3 {}", canonical)), "{}", name);
+            }
+            // And through the base unit of its dimension and back
+            let base = UNITS.iter().find(|other| other.dimension == unit.dimension).unwrap();
+            let there = result(&format!("3 {} in {}", canonical, base.names[0])).unwrap();
+            let value = there.strip_suffix(&format!(" {}", base.names[0])).unwrap();
+            let back = result(&format!("{} {} in {}", value, base.names[0], canonical)).unwrap();
+            let back: f64 = back.strip_suffix(&format!(" {}", canonical)).unwrap().parse().unwrap();
+            assert!((back - 3.0).abs() < 1e-9, "{} came back as {}", canonical, back);
+        }
+    }
+
+    #[test]
+    fn rejects_mismatched_units() {
+        let cases = ["5 km in kg", "1 h to gb", "20 c in mph", "3 acres in l", "5 km in parsecs", "5 apples in km", "5 km in"];
+        for input in cases {
+            assert_eq!(result(input), None, "{}", input);
+        }
+    }
+}
@@ -1,8 +1,11 @@
 use crate::Message;
 use iced::widget::row;
 use iced::{window, Element, Task};
+#[cfg(windows)]
 use std::ffi::c_void;
+#[cfg(windows)]
 use windows::Win32::Foundation::HWND;
+#[cfg(windows)]
 use windows::Win32::UI::WindowsAndMessaging::{GetWindowLongPtrW, SetWindowLongPtrW, SetWindowPos, GWL_EXSTYLE, HWND_BOTTOM, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE, WS_EX_NOACTIVATE};
 
 #[derive(Debug, Clone)]
@@ -15,17 +18,19 @@ pub struct Desktop {
 }
 impl Desktop {
     pub fn new() -> (Self,Task<window::Id>) {
-        let mut settings = window::Settings::default();
-        settings.decorations = false;
-        settings.resizable = false;
-        settings.min_size = None;
-        settings.max_size = None;
-        settings.icon = None;
-        settings.transparent = false;
-        settings.closeable = false;
-        settings.minimizable = false;
-        settings.fullscreen = true;
-        settings.level = window::Level::AlwaysOnBottom;
+        let settings = window::Settings {
+            decorations: false,
+            resizable: false,
+            min_size: None,
+            max_size: None,
+            icon: None,
+            transparent: false,
+            closeable: false,
+            minimizable: false,
+            fullscreen: true,
+            level: window::Level::AlwaysOnBottom,
+            ..window::Settings::default()
+        };
         let (id,open_task) = window::open(settings);
         (Self {
             id
@@ -34,8 +39,12 @@ impl Desktop {
     }
     pub fn update(&mut self, message: DesktopMessage) -> Task<Message> {
         match message {
+            // The window's level keeps it below other windows on Linux, Windows needs it pushed down itself
             DesktopMessage::Init => {
-                window::run(self.id, |window| {
+                #[cfg(target_os = "linux")]
+                return Task::none();
+                #[cfg(windows)]
+                return window::run(self.id, |window| {
                     match window.window_handle() {
                         Ok(window_handle) => {
                             let raw_handle = window_handle.as_raw();
@@ -69,10 +78,13 @@ impl Desktop {
                         }
                     }
                     Message::None
-                })
+                });
             }
             DesktopMessage::KeepAtBottom => {
-                window::run(self.id, |window| {
+                #[cfg(target_os = "linux")]
+                return Task::none();
+                #[cfg(windows)]
+                return window::run(self.id, |window| {
                     match window.window_handle() {
                         Ok(window_handle) => {
                             let raw_handle = window_handle.as_raw();
@@ -101,7 +113,7 @@ impl Desktop {
                         }
                     }
                     Message::None
-                })
+                });
             }
         }
     }
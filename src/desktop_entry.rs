@@ -0,0 +1,613 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+// Desktop Entry Specification: https://specifications.freedesktop.org/desktop-entry-spec/latest/
+const MAIN_CATEGORIES: &[(&str, &str)] = &[
+    ("AudioVideo", "Sound & Video"),
+    ("Audio", "Sound & Video"),
+    ("Video", "Sound & Video"),
+    ("Development", "Programming"),
+    ("Education", "Education"),
+    ("Game", "Games"),
+    ("Graphics", "Graphics"),
+    ("Network", "Internet"),
+    ("Office", "Office"),
+    ("Science", "Science"),
+    ("Settings", "Settings"),
+    ("System", "System Tools"),
+    ("Utility", "Accessories"),
+];
+pub const OTHER_CATEGORY: &str = "Other";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DesktopEntry {
+    pub id: String,
+    pub path: PathBuf,
+    pub name: String,
+    pub generic_name: Option<String>,
+    pub comment: Option<String>,
+    pub icon: Option<String>,
+    pub exec: Option<String>,
+    pub try_exec: Option<String>,
+    pub working_dir: Option<String>,
+    pub terminal: bool,
+    pub categories: Vec<String>,
+    pub keywords: Vec<String>,
+    pub no_display: bool,
+    pub hidden: bool,
+    pub only_show_in: Vec<String>,
+    pub not_show_in: Vec<String>,
+    pub entry_type: String,
+}
+
+impl DesktopEntry {
+    pub fn from_path(path: &Path, id: String, locale: Option<&str>) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Self::parse(&content, path.to_path_buf(), id, locale),
+            Err(e) => Err(format!("Error reading desktop entry {:?}: {}", path, e)),
+        }
+    }
+
+    pub fn parse(content: &str, path: PathBuf, id: String, locale: Option<&str>) -> Result<Self, String> {
+        let group = parse_group(content, "Desktop Entry")
+            .ok_or_else(|| format!("Missing [Desktop Entry] group in {:?}", path))?;
+        let locale_keys = locale.map(locale_variants).unwrap_or_default();
+        let localized = |key: &str| -> Option<String> {
+            locale_keys.iter()
+                .find_map(|suffix| group.get(&format!("{}[{}]", key, suffix)))
+                .or_else(|| group.get(key))
+                .map(|value| unescape_value(value))
+        };
+        let plain = |key: &str| group.get(key).map(|value| unescape_value(value));
+        let boolean = |key: &str| group.get(key).map(|value| value.trim() == "true").unwrap_or(false);
+        let list = |key: &str| group.get(key).map(|value| split_list(value)).unwrap_or_default();
+
+        let name = localized("Name").ok_or_else(|| format!("Missing Name in {:?}", path))?;
+        Ok(Self {
+            id,
+            path,
+            name,
+            generic_name: localized("GenericName"),
+            comment: localized("Comment"),
+            icon: localized("Icon").filter(|icon| !icon.is_empty()),
+            exec: plain("Exec").filter(|exec| !exec.is_empty()),
+            try_exec: plain("TryExec").filter(|exec| !exec.is_empty()),
+            working_dir: plain("Path").filter(|dir| !dir.is_empty()),
+            terminal: boolean("Terminal"),
+            categories: list("Categories"),
+            keywords: locale_keys.iter()
+                .find_map(|suffix| group.get(&format!("Keywords[{}]", suffix)))
+                .or_else(|| group.get("Keywords"))
+                .map(|value| split_list(value))
+                .unwrap_or_default(),
+            no_display: boolean("NoDisplay"),
+            hidden: boolean("Hidden"),
+            only_show_in: list("OnlyShowIn"),
+            not_show_in: list("NotShowIn"),
+            entry_type: plain("Type").unwrap_or_default(),
+        })
+    }
+
+    /// Whether the entry belongs in a menu for the given `XDG_CURRENT_DESKTOP` names.
+    pub fn should_show(&self, current_desktops: &[String]) -> bool {
+        if self.hidden || self.no_display || self.entry_type != "Application" || self.exec.is_none() {
+            return false;
+        }
+        if !self.only_show_in.is_empty() && !self.only_show_in.iter().any(|name| current_desktops.contains(name)) {
+            return false;
+        }
+        if self.not_show_in.iter().any(|name| current_desktops.contains(name)) {
+            return false;
+        }
+        if let Some(try_exec) = self.try_exec.as_ref() && find_executable(try_exec).is_none() {
+            return false;
+        }
+        true
+    }
+
+    /// Display name of the menu folder this entry is grouped into.
+    pub fn main_category(&self) -> &'static str {
+        for category in self.categories.iter() {
+            if let Some((_, label)) = MAIN_CATEGORIES.iter().find(|(name, _)| name == category) {
+                return label;
+            }
+        }
+        OTHER_CATEGORY
+    }
+
+    /// Expands the Exec line into an argument vector, wrapping it in a terminal when `Terminal=true`.
+    pub fn command(&self, files: &[String]) -> Result<Vec<String>, String> {
+        let exec = self.exec.as_ref().ok_or_else(|| format!("{} has no Exec line", self.name))?;
+        let mut command = expand_exec(exec, files, self.icon.as_deref(), &self.name, &self.path)?;
+        if self.terminal {
+            let mut wrapped = terminal_command();
+            wrapped.append(&mut command);
+            command = wrapped;
+        }
+        Ok(command)
+    }
+}
+
+/// Splits an Exec value into words and substitutes the field codes.
+pub fn expand_exec(exec: &str, files: &[String], icon: Option<&str>, name: &str, desktop_file: &Path) -> Result<Vec<String>, String> {
+    let mut command: Vec<String> = Vec::new();
+    for (word, quoted) in split_exec(exec)? {
+        if !quoted {
+            match word.as_str() {
+                "%f" | "%u" => {
+                    if let Some(file) = files.first() {
+                        command.push(file.clone());
+                    }
+                    continue;
+                }
+                "%F" | "%U" => {
+                    command.extend(files.iter().cloned());
+                    continue;
+                }
+                "%i" => {
+                    if let Some(icon) = icon {
+                        command.push("--icon".to_string());
+                        command.push(icon.to_string());
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+        }
+        let mut expanded = String::with_capacity(word.len());
+        let mut chars = word.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                expanded.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('%') => expanded.push('%'),
+                Some('c') => expanded.push_str(name),
+                Some('k') => expanded.push_str(&desktop_file.to_string_lossy()),
+                Some('f') | Some('u') => {
+                    if let Some(file) = files.first() {
+                        expanded.push_str(file);
+                    }
+                }
+                // Deprecated codes are dropped, %F %U %i are only valid as standalone words
+                Some('d') | Some('D') | Some('n') | Some('N') | Some('v') | Some('m') => {}
+                Some(other) => return Err(format!("Invalid field code %{} in Exec line: {}", other, exec)),
+                None => return Err(format!("Dangling % in Exec line: {}", exec)),
+            }
+        }
+        command.push(expanded);
+    }
+    if command.is_empty() {
+        Err(format!("Empty Exec line for {}", name))
+    } else {
+        Ok(command)
+    }
+}
+
+/// Splits an Exec value on spaces honoring the spec's double quoting rules.
+fn split_exec(exec: &str) -> Result<Vec<(String, bool)>, String> {
+    let mut words: Vec<(String, bool)> = Vec::new();
+    let mut chars = exec.chars().peekable();
+    loop {
+        while chars.peek().is_some_and(|c| c.is_whitespace()) {
+            chars.next();
+        }
+        let Some(first) = chars.next() else {
+            break;
+        };
+        let mut word = String::new();
+        if first == '"' {
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some(escaped @ ('"' | '`' | '$' | '\\')) => word.push(escaped),
+                        Some(other) => {
+                            word.push('\\');
+                            word.push(other);
+                        }
+                        None => return Err(format!("Unterminated quote in Exec line: {}", exec)),
+                    },
+                    Some(c) => word.push(c),
+                    None => return Err(format!("Unterminated quote in Exec line: {}", exec)),
+                }
+            }
+            words.push((word, true));
+        } else {
+            word.push(first);
+            while let Some(c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                word.push(*c);
+                chars.next();
+            }
+            words.push((word, false));
+        }
+    }
+    Ok(words)
+}
+
+/// Command prefix that runs a program in the user's terminal emulator.
+pub fn terminal_command() -> Vec<String> {
+    if let Ok(terminal) = std::env::var("TERMINAL") && !terminal.is_empty() {
+        return vec![terminal, "-e".to_string()];
+    }
+    for candidate in ["x-terminal-emulator", "xdg-terminal-exec", "konsole", "gnome-terminal", "xfce4-terminal", "xterm"] {
+        if find_executable(candidate).is_some() {
+            return match candidate {
+                "xdg-terminal-exec" => vec![candidate.to_string()],
+                "gnome-terminal" => vec![candidate.to_string(), "--".to_string()],
+                _ => vec![candidate.to_string(), "-e".to_string()],
+            };
+        }
+    }
+    vec!["xterm".to_string(), "-e".to_string()]
+}
+
+pub fn find_executable(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    if path.is_absolute() {
+        return if is_executable(path) { Some(path.to_path_buf()) } else { None };
+    }
+    let search_path = std::env::var_os("PATH")?;
+    std::env::split_paths(&search_path)
+        .map(|dir| dir.join(name))
+        .find(|candidate| is_executable(candidate))
+}
+
+/// A file anyone may run, like the shell requires before starting it.
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata().is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+fn parse_group(content: &str, wanted: &str) -> Option<BTreeMap<String, String>> {
+    let mut group: Option<BTreeMap<String, String>> = None;
+    let mut in_wanted = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            if group.is_some() && in_wanted {
+                break;
+            }
+            in_wanted = &line[1..line.len() - 1] == wanted;
+            if in_wanted {
+                group = Some(BTreeMap::new());
+            }
+            continue;
+        }
+        if in_wanted && let Some((key, value)) = line.split_once('=') && let Some(group) = group.as_mut() {
+            group.entry(key.trim().to_string()).or_insert_with(|| value.trim().to_string());
+        }
+    }
+    group
+}
+
+fn unescape_value(value: &str) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            output.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => output.push(' '),
+            Some('n') => output.push('\n'),
+            Some('t') => output.push('\t'),
+            Some('r') => output.push('\r'),
+            Some('\\') => output.push('\\'),
+            Some(other) => {
+                output.push('\\');
+                output.push(other);
+            }
+            None => output.push('\\'),
+        }
+    }
+    output
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    let mut items: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(';') => current.push(';'),
+                Some(other) => {
+                    current.push('\\');
+                    current.push(other);
+                }
+                None => current.push('\\'),
+            },
+            ';' => {
+                if !current.is_empty() {
+                    items.push(unescape_value(&current));
+                }
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        items.push(unescape_value(&current));
+    }
+    items
+}
+
+/// Locale suffixes to try for `Key[locale]`, most specific first.
+/// `de_DE.UTF-8@euro` gives `de_DE@euro`, `de_DE`, `de@euro`, `de`.
+pub fn locale_variants(locale: &str) -> Vec<String> {
+    let (without_modifier, modifier) = match locale.split_once('@') {
+        Some((rest, modifier)) => (rest, Some(modifier)),
+        None => (locale, None),
+    };
+    let without_encoding = without_modifier.split('.').next().unwrap_or(without_modifier);
+    let (lang, country) = match without_encoding.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (without_encoding, None),
+    };
+    let mut variants: Vec<String> = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        variants.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        variants.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        variants.push(format!("{}@{}", lang, modifier));
+    }
+    if !lang.is_empty() && lang != "C" && lang != "POSIX" {
+        variants.push(lang.to_string());
+    }
+    variants
+}
+
+pub fn current_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"].iter()
+        .find_map(|key| std::env::var(key).ok().filter(|value| !value.is_empty()))
+}
+
+pub fn current_desktops() -> Vec<String> {
+    std::env::var("XDG_CURRENT_DESKTOP")
+        .map(|value| value.split(':').filter(|name| !name.is_empty()).map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// `applications` folders in precedence order: `$XDG_DATA_HOME` first, then each of `$XDG_DATA_DIRS`.
+pub fn application_dirs() -> Vec<PathBuf> {
+    let mut folders: Vec<PathBuf> = Vec::new();
+    match std::env::var_os("XDG_DATA_HOME").filter(|value| !value.is_empty()) {
+        Some(data_home) => folders.push(PathBuf::from(data_home).join("applications")),
+        None => {
+            if let Some(home) = dirs::home_dir() {
+                folders.push(home.join(".local/share/applications"));
+            }
+        }
+    }
+    let data_dirs = std::env::var_os("XDG_DATA_DIRS")
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    for dir in std::env::split_paths(&data_dirs) {
+        folders.push(dir.join("applications"));
+    }
+    folders
+}
+
+/// Collects every visible application keyed by desktop file ID.
+/// An ID found in an earlier directory shadows the same ID further down, even when the earlier one is hidden.
+pub fn load_applications(dirs: &[PathBuf], locale: Option<&str>, current_desktops: &[String]) -> BTreeMap<String, DesktopEntry> {
+    let mut seen: BTreeMap<String, Option<DesktopEntry>> = BTreeMap::new();
+    for dir in dirs {
+        let mut files: Vec<(String, PathBuf)> = Vec::new();
+        collect_desktop_files(dir, "", &mut files);
+        for (id, path) in files {
+            if seen.contains_key(&id) {
+                continue;
+            }
+            match DesktopEntry::from_path(&path, id.clone(), locale) {
+                Ok(entry) => {
+                    let visible = entry.should_show(current_desktops);
+                    seen.insert(id, if visible { Some(entry) } else { None });
+                }
+                Err(e) => {
+                    eprintln!("Error loading desktop entry: {}", e);
+                }
+            }
+        }
+    }
+    seen.into_iter().filter_map(|(id, entry)| entry.map(|entry| (id, entry))).collect()
+}
+
+/// Desktop file ID of a file relative to its applications folder, `kde4/app.desktop` becoming `kde4-app.desktop`.
+pub fn desktop_file_id(relative: &Path) -> Option<String> {
+    let parts: Vec<&str> = relative.iter().map(|part| part.to_str()).collect::<Option<Vec<&str>>>()?;
+    let id = parts.join("-");
+    if id.ends_with(".desktop") { Some(id) } else { None }
+}
+
+/// Loads the entry that wins for one desktop file ID, following the same shadowing as `load_applications`.
+/// Returns `None` when no folder has the file anymore or the winning file is not shown.
+pub fn load_application(relative: &Path, dirs: &[PathBuf], locale: Option<&str>, current_desktops: &[String]) -> Option<DesktopEntry> {
+    let id = desktop_file_id(relative)?;
+    let path = dirs.iter().map(|dir| dir.join(relative)).find(|path| path.is_file())?;
+    match DesktopEntry::from_path(&path, id, locale) {
+        Ok(entry) => if entry.should_show(current_desktops) { Some(entry) } else { None },
+        Err(e) => {
+            eprintln!("Error loading desktop entry: {}", e);
+            None
+        }
+    }
+}
+
+/// Every desktop file below `dir`, for folders that appear after the initial scan.
+pub fn desktop_files_below(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<(String, PathBuf)> = Vec::new();
+    collect_desktop_files(dir, "", &mut files);
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+fn collect_desktop_files(dir: &Path, prefix: &str, files: &mut Vec<(String, PathBuf)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()).map(String::from) else {
+            continue;
+        };
+        if path.is_dir() {
+            collect_desktop_files(&path, &format!("{}{}-", prefix, file_name), files);
+        } else if file_name.ends_with(".desktop") {
+            files.push((format!("{}{}", prefix, file_name), path));
+        }
+    }
+}
+
+/// Groups applications into menu folders by their main category.
+pub fn group_by_category(entries: BTreeMap<String, DesktopEntry>) -> BTreeMap<String, Vec<DesktopEntry>> {
+    let mut groups: BTreeMap<String, Vec<DesktopEntry>> = BTreeMap::new();
+    for (_, entry) in entries {
+        groups.entry(entry.main_category().to_string()).or_default().push(entry);
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn entry(content: &str) -> DesktopEntry {
+        DesktopEntry::parse(content, PathBuf::from("/usr/share/applications/app.desktop"), "app.desktop".to_string(), None).unwrap()
+    }
+
+    fn write_entry(dir: &Path, file_name: &str, name: &str, extra: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        std::fs::write(dir.join(file_name), format!("[Desktop Entry]\nType=Application\nName={}\nExec=app\n{}", name, extra)).unwrap();
+    }
+
+    #[test]
+    fn expands_field_codes() {
+        let files = ["/tmp/a b.txt".to_string(), "/tmp/c.txt".to_string()];
+        let desktop_file = Path::new("/usr/share/applications/editor.desktop");
+        let expand = |exec: &str, files: &[String]| expand_exec(exec, files, Some("editor"), "Editor", desktop_file);
+        assert_eq!(expand("editor %f", &files).unwrap(), ["editor", "/tmp/a b.txt"]);
+        assert_eq!(expand("editor %u", &files).unwrap(), ["editor", "/tmp/a b.txt"]);
+        assert_eq!(expand("editor %F", &files).unwrap(), ["editor", "/tmp/a b.txt", "/tmp/c.txt"]);
+        assert_eq!(expand("editor %U --new", &files).unwrap(), ["editor", "/tmp/a b.txt", "/tmp/c.txt", "--new"]);
+        // Codes without a value leave nothing behind
+        assert_eq!(expand("editor %f %U", &[]).unwrap(), ["editor"]);
+        assert_eq!(expand("editor %i", &[]).unwrap(), ["editor", "--icon", "editor"]);
+        assert_eq!(expand_exec("editor %i", &[], None, "Editor", desktop_file).unwrap(), ["editor"]);
+        assert_eq!(expand("editor --name=%c --from=%k", &[]).unwrap(), ["editor", "--name=Editor", "--from=/usr/share/applications/editor.desktop"]);
+        assert_eq!(expand("editor --file=%f", &files).unwrap(), ["editor", "--file=/tmp/a b.txt"]);
+        assert_eq!(expand("printf 100%%", &[]).unwrap(), ["printf", "100%"]);
+        // Deprecated codes are dropped
+        assert_eq!(expand("editor %d%D %n %N %v %m", &[]).unwrap(), ["editor", "", "", "", "", ""]);
+        // %F and %U only stand for several files as words of their own
+        assert!(expand("editor \"%F\"", &files).is_err());
+        assert!(expand("editor --files=%U", &files).is_err());
+        assert!(expand("editor %x", &[]).is_err());
+        assert!(expand("editor 100%", &[]).is_err());
+        assert!(expand("  ", &[]).is_err());
+    }
+
+    #[test]
+    fn splits_exec_lines() {
+        let words = |exec: &str| split_exec(exec).unwrap();
+        assert_eq!(words("app  --flag\targ"), [("app".to_string(), false), ("--flag".to_string(), false), ("arg".to_string(), false)]);
+        assert_eq!(words(r#""/opt/My App/app" "a \"quoted\" word""#), [
+            ("/opt/My App/app".to_string(), true),
+            ("a \"quoted\" word".to_string(), true),
+        ]);
+        assert_eq!(words(r#"sh -c "echo \$HOME \`date\` \\ \x""#)[2], (r"echo $HOME `date` \ \x".to_string(), true));
+        assert_eq!(words(r#""""#), [(String::new(), true)]);
+        assert!(words("").is_empty());
+        assert!(split_exec(r#"app "unterminated"#).is_err());
+        assert!(split_exec(r#"app "escape at end\"#).is_err());
+    }
+
+    #[test]
+    fn tries_locale_variants_most_specific_first() {
+        assert_eq!(locale_variants("de_DE.UTF-8@euro"), ["de_DE@euro", "de_DE", "de@euro", "de"]);
+        assert_eq!(locale_variants("pt_BR.UTF-8"), ["pt_BR", "pt"]);
+        assert_eq!(locale_variants("sr@latin"), ["sr@latin", "sr"]);
+        assert_eq!(locale_variants("fr"), ["fr"]);
+        assert!(locale_variants("C").is_empty());
+        assert!(locale_variants("POSIX").is_empty());
+
+        let content = "[Desktop Entry]\nName=Files\nName[de]=Dateien\nName[de_AT]=Dateien (AT)\nComment=Browse\nComment[de@euro]=Durchsuchen\n";
+        let localized = |locale: &str| DesktopEntry::parse(content, PathBuf::new(), String::new(), Some(locale)).unwrap();
+        assert_eq!(localized("de_AT.UTF-8").name, "Dateien (AT)");
+        assert_eq!(localized("de_DE.UTF-8").name, "Dateien");
+        assert_eq!(localized("de_DE@euro").comment.as_deref(), Some("Durchsuchen"));
+        assert_eq!(localized("de_DE").comment.as_deref(), Some("Browse"));
+        assert_eq!(localized("en_US").name, "Files");
+    }
+
+    #[test]
+    fn decides_what_to_show() {
+        let desktops = ["XFCE".to_string()];
+        let app = "[Desktop Entry]\nType=Application\nName=App\nExec=app\n";
+        assert!(entry(app).should_show(&desktops));
+        for extra in ["NoDisplay=true", "Hidden=true", "OnlyShowIn=GNOME;KDE;", "NotShowIn=KDE;XFCE;", "TryExec=/nonexistent/frostwin-app"] {
+            assert!(!entry(&format!("{}{}\n", app, extra)).should_show(&desktops), "{}", extra);
+        }
+        assert!(entry(&format!("{}OnlyShowIn=GNOME;XFCE;\nNotShowIn=KDE;\nNoDisplay=false\n", app)).should_show(&desktops));
+        assert!(!entry("[Desktop Entry]\nType=Link\nName=Site\nURL=https://example.com\n").should_show(&desktops));
+        assert!(!entry("[Desktop Entry]\nType=Application\nName=App\n").should_show(&desktops));
+        // Entries limited to some desktops stay out when none is known
+        assert!(!entry(&format!("{}OnlyShowIn=XFCE;\n", app)).should_show(&[]));
+
+        let dir = tempfile::tempdir().unwrap();
+        let program = dir.path().join("app");
+        std::fs::write(&program, "#!/bin/sh\n").unwrap();
+        let try_exec = entry(&format!("{}TryExec={}\n", app, program.display()));
+        assert!(!try_exec.should_show(&desktops));
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(try_exec.should_show(&desktops));
+    }
+
+    #[test]
+    fn finds_only_executable_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let program = dir.path().join("program");
+        std::fs::write(&program, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert_eq!(find_executable(&program.to_string_lossy()), None);
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o744)).unwrap();
+        assert_eq!(find_executable(&program.to_string_lossy()), Some(program));
+        assert_eq!(find_executable(&dir.path().to_string_lossy()), None);
+        assert_eq!(find_executable("/nonexistent/frostwin-program"), None);
+    }
+
+    #[test]
+    fn earlier_application_dirs_shadow_later_ones() {
+        let root = tempfile::tempdir().unwrap();
+        let (home, system) = (root.path().join("home"), root.path().join("system"));
+        write_entry(&home, "editor.desktop", "My Editor", "");
+        write_entry(&system, "editor.desktop", "Editor", "");
+        // A hidden entry hides the system one with the same ID too
+        write_entry(&home, "player.desktop", "Player", "Hidden=true\n");
+        write_entry(&system, "player.desktop", "Player", "");
+        // Subfolders become part of the ID
+        write_entry(&system.join("kde"), "viewer.desktop", "Viewer", "Categories=Graphics;\n");
+        write_entry(&system, "kde-viewer.desktop", "Other Viewer", "");
+        std::fs::write(system.join("broken.desktop"), "[Desktop Entry]\nType=Application\n").unwrap();
+
+        let applications = load_applications(&[home.clone(), system.clone()], None, &[]);
+        let names: Vec<(&str, &str)> = applications.iter().map(|(id, entry)| (id.as_str(), entry.name.as_str())).collect();
+        assert_eq!(names, [("editor.desktop", "My Editor"), ("kde-viewer.desktop", "Viewer")]);
+        assert_eq!(applications["editor.desktop"].path, home.join("editor.desktop"));
+
+        let groups = group_by_category(applications);
+        assert_eq!(groups.keys().collect::<Vec<_>>(), ["Graphics", OTHER_CATEGORY]);
+    }
+}
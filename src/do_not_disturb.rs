@@ -0,0 +1,289 @@
+use chrono::{Datelike, NaiveDateTime, Timelike};
+
+const DAY_NAMES: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// A daily period Do Not Disturb turns on for, in minutes since midnight.
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone, PartialEq)]
+pub struct QuietHours {
+    pub start: u32,
+    pub end: u32,
+    // Days the period starts on, Monday first
+    pub days: [bool; 7],
+}
+impl QuietHours {
+    /// Whether the time falls in the period. A period ending before it starts runs past midnight and belongs
+    /// to the day it starts on. One ending when it starts is empty, the panel menu does not add those.
+    pub fn contains(&self, now: NaiveDateTime) -> bool {
+        let minute = now.hour() * 60 + now.minute();
+        let today = now.weekday().num_days_from_monday() as usize;
+        let yesterday = (today + 6) % 7;
+        if self.start <= self.end {
+            self.days[today] && minute >= self.start && minute < self.end
+        } else {
+            (self.days[today] && minute >= self.start) || (self.days[yesterday] && minute < self.end)
+        }
+    }
+
+    /// Describes the period like "22:00 - 07:00, Weekdays".
+    pub fn label(&self) -> String {
+        let days = match self.days {
+            [true, true, true, true, true, true, true] => "Every day".to_string(),
+            [true, true, true, true, true, false, false] => "Weekdays".to_string(),
+            [false, false, false, false, false, true, true] => "Weekends".to_string(),
+            days => days.iter().zip(DAY_NAMES).filter(|(on, _)| **on).map(|(_, name)| name).collect::<Vec<&str>>().join(" "),
+        };
+        format!("{} - {}, {}", format_minutes(self.start), format_minutes(self.end), days)
+    }
+}
+
+/// The user's Do Not Disturb choices, saved with the shell settings.
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone, Default)]
+pub struct DoNotDisturbSettings {
+    // Turned on from the panel menu, stays on until turned off there
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub schedules: Vec<QuietHours>,
+    // Turns on while another application's window covers the whole screen, like games and presentations
+    #[serde(default)]
+    pub when_full_screen: bool,
+    // Applications that still get through, by name or executable
+    #[serde(default)]
+    pub allowed_apps: Vec<String>,
+}
+impl DoNotDisturbSettings {
+    pub fn apply(&mut self, change: DoNotDisturbChange) {
+        match change {
+            DoNotDisturbChange::Enabled(enabled) => self.enabled = enabled,
+            DoNotDisturbChange::WhenFullScreen(enabled) => self.when_full_screen = enabled,
+            DoNotDisturbChange::AddSchedule(schedule) => self.schedules.push(schedule),
+            DoNotDisturbChange::RemoveSchedule(index) => {
+                if index < self.schedules.len() {
+                    self.schedules.remove(index);
+                }
+            }
+            DoNotDisturbChange::AllowApp(app) => {
+                if !self.allowed_apps.iter().any(|allowed| allowed.eq_ignore_ascii_case(&app)) {
+                    self.allowed_apps.push(app);
+                }
+            }
+            DoNotDisturbChange::RemoveAllowedApp(index) => {
+                if index < self.allowed_apps.len() {
+                    self.allowed_apps.remove(index);
+                }
+            }
+        }
+    }
+}
+
+/// An edit to the settings from the panel menu.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DoNotDisturbChange {
+    Enabled(bool),
+    WhenFullScreen(bool),
+    AddSchedule(QuietHours),
+    RemoveSchedule(usize),
+    AllowApp(String),
+    RemoveAllowedApp(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DoNotDisturbReason {
+    Manual,
+    Schedule,
+    FullScreen,
+}
+impl DoNotDisturbReason {
+    pub fn label(self) -> &'static str {
+        match self {
+            DoNotDisturbReason::Manual => "On until turned off",
+            DoNotDisturbReason::Schedule => "On for quiet hours",
+            DoNotDisturbReason::FullScreen => "On while a window is fullscreen",
+        }
+    }
+}
+
+/// Whether Do Not Disturb is on right now, which notifications and sounds check before getting in the way.
+#[derive(Debug, Clone, Default)]
+pub struct DoNotDisturb {
+    reason: Option<DoNotDisturbReason>,
+    allowed_apps: Vec<String>,
+}
+impl DoNotDisturb {
+    pub fn evaluate(settings: &DoNotDisturbSettings, now: NaiveDateTime, full_screen: bool) -> Self {
+        let reason = if settings.enabled {
+            Some(DoNotDisturbReason::Manual)
+        } else if settings.schedules.iter().any(|schedule| schedule.contains(now)) {
+            Some(DoNotDisturbReason::Schedule)
+        } else if settings.when_full_screen && full_screen {
+            Some(DoNotDisturbReason::FullScreen)
+        } else {
+            None
+        };
+        Self { reason, allowed_apps: settings.allowed_apps.clone() }
+    }
+
+    pub fn reason(&self) -> Option<DoNotDisturbReason> {
+        self.reason
+    }
+
+    /// Whether a notification or sound from the application should still get through. Matches the name an
+    /// application gives itself or its executable, ignoring case.
+    pub fn allows(&self, app: &str) -> bool {
+        let app = without_exe(app);
+        self.reason.is_none() || self.allowed_apps.iter().any(|allowed| without_exe(allowed).eq_ignore_ascii_case(app))
+    }
+}
+
+fn without_exe(app: &str) -> &str {
+    match app.len().checked_sub(4) {
+        Some(end) if app.get(end..).is_some_and(|extension| extension.eq_ignore_ascii_case(".exe")) => &app[..end],
+        _ => app,
+    }
+}
+
+/// Reads a time like "22:00" or "7" into minutes since midnight.
+pub fn parse_minutes(text: &str) -> Option<u32> {
+    let (hours, minutes) = text.trim().split_once(':').unwrap_or((text.trim(), "0"));
+    let (hours, minutes) = (hours.parse::<u32>().ok()?, minutes.parse::<u32>().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+pub fn format_minutes(minutes: u32) -> String {
+    format!("{:02}:{:02}", minutes / 60, minutes % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    const WEEKDAYS: [bool; 7] = [true, true, true, true, true, false, false];
+
+    // 2026-10-16 is a Friday
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn quiet_hours(start: &str, end: &str, days: [bool; 7]) -> QuietHours {
+        QuietHours { start: parse_minutes(start).unwrap(), end: parse_minutes(end).unwrap(), days }
+    }
+
+    #[test]
+    fn overnight_period_belongs_to_its_start_day() {
+        let night = quiet_hours("22:00", "07:00", WEEKDAYS);
+        let cases = [
+            // Friday night into Saturday morning, started on a weekday
+            (at(16, 21, 59), false),
+            (at(16, 22, 0), true),
+            (at(16, 23, 59), true),
+            (at(17, 0, 0), true),
+            (at(17, 6, 59), true),
+            (at(17, 7, 0), false),
+            // Saturday night starts on the weekend, so nothing carries into Sunday or Monday morning
+            (at(17, 22, 0), false),
+            (at(18, 3, 0), false),
+            (at(19, 3, 0), false),
+            // Sunday is not a start day, Monday night is
+            (at(18, 23, 0), false),
+            (at(19, 23, 0), true),
+            (at(20, 6, 0), true),
+        ];
+        for (now, expected) in cases {
+            assert_eq!(night.contains(now), expected, "{}", now);
+        }
+    }
+
+    #[test]
+    fn daytime_period_ends_before_its_end() {
+        let work = quiet_hours("9", "17:30", WEEKDAYS);
+        let cases = [(at(16, 8, 59), false), (at(16, 9, 0), true), (at(16, 17, 29), true), (at(16, 17, 30), false), (at(17, 12, 0), false)];
+        for (now, expected) in cases {
+            assert_eq!(work.contains(now), expected, "{}", now);
+        }
+    }
+
+    #[test]
+    fn period_ending_when_it_starts_is_empty() {
+        let empty = quiet_hours("08:00", "08:00", [true; 7]);
+        assert!((0..24).all(|hour| !empty.contains(at(16, hour, 0)) && !empty.contains(at(17, hour, 30))));
+        let midnight = quiet_hours("0", "0", [true; 7]);
+        assert!(!midnight.contains(at(18, 0, 0)));
+    }
+
+    #[test]
+    fn labels_periods() {
+        assert_eq!(quiet_hours("22:00", "7", WEEKDAYS).label(), "22:00 - 07:00, Weekdays");
+        assert_eq!(quiet_hours("0", "23:59", [true; 7]).label(), "00:00 - 23:59, Every day");
+        assert_eq!(quiet_hours("13:05", "14", [false, false, false, false, false, true, true]).label(), "13:05 - 14:00, Weekends");
+        assert_eq!(quiet_hours("9", "17", [true, false, true, false, false, false, true]).label(), "09:00 - 17:00, Mon Wed Sun");
+    }
+
+    #[test]
+    fn evaluates_reasons_in_priority_order() {
+        let night = quiet_hours("22:00", "07:00", [true; 7]);
+        let cases = [
+            // enabled, schedule covers now, when_full_screen, a window is fullscreen
+            ((false, false, false, false), None),
+            ((false, false, false, true), None),
+            ((false, false, true, false), None),
+            ((false, false, true, true), Some(DoNotDisturbReason::FullScreen)),
+            ((false, true, false, false), Some(DoNotDisturbReason::Schedule)),
+            ((false, true, true, true), Some(DoNotDisturbReason::Schedule)),
+            ((true, false, false, false), Some(DoNotDisturbReason::Manual)),
+            ((true, true, true, true), Some(DoNotDisturbReason::Manual)),
+        ];
+        for ((enabled, scheduled, when_full_screen, full_screen), expected) in cases {
+            let settings = DoNotDisturbSettings {
+                enabled,
+                schedules: vec![night.clone()],
+                when_full_screen,
+                allowed_apps: Vec::new(),
+            };
+            let now = if scheduled { at(16, 23, 0) } else { at(16, 12, 0) };
+            assert_eq!(DoNotDisturb::evaluate(&settings, now, full_screen).reason(), expected, "{:?}", (enabled, scheduled, when_full_screen, full_screen));
+        }
+    }
+
+    #[test]
+    fn allows_listed_applications() {
+        let settings = DoNotDisturbSettings {
+            enabled: true,
+            allowed_apps: vec!["Teams.EXE".to_string(), "thunderbird".to_string()],
+            ..Default::default()
+        };
+        let quiet = DoNotDisturb::evaluate(&settings, at(16, 12, 0), false);
+        for app in ["teams", "Teams.exe", "TEAMS.Exe", "Thunderbird", "thunderbird.exe"] {
+            assert!(quiet.allows(app), "{}", app);
+        }
+        for app in ["Slack", "slack.exe", "exe", ".exe", "teams.exe.bak", ""] {
+            assert!(!quiet.allows(app), "{}", app);
+        }
+        let off = DoNotDisturb::evaluate(&DoNotDisturbSettings { enabled: false, ..settings }, at(16, 12, 0), false);
+        assert!(off.allows("Slack"));
+    }
+
+    #[test]
+    fn parses_times() {
+        let cases = [
+            ("22:00", Some(1320)),
+            ("7", Some(420)),
+            ("07:05", Some(425)),
+            (" 7:5 ", Some(425)),
+            ("0", Some(0)),
+            ("23:59", Some(1439)),
+            ("24:00", None),
+            ("12:60", None),
+            ("", None),
+            ("7:", None),
+            (":30", None),
+            ("-1", None),
+            ("7:30:00", None),
+            ("7pm", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_minutes(text), expected, "{:?}", text);
+        }
+        assert_eq!(format_minutes(425), "07:05");
+    }
+}
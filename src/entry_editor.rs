@@ -0,0 +1,159 @@
+use crate::start_menu::StartMessage;
+use crate::start_settings::CustomEntry;
+use crate::styles::{colored_button, transparent_button};
+use crate::Message;
+use iced::widget::{button, column, row, space, text, text_input, Column};
+use iced::{Alignment, Color, Element, Length};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EntryField {
+    Name,
+    Command,
+    Arguments,
+    WorkingDir,
+    Icon,
+    Folder,
+}
+
+/// The form behind "Edit entry" and "New entry". Custom entries can change everything,
+/// other entries only the name they are shown with.
+#[derive(Debug, Clone)]
+pub struct EntryEditor {
+    /// Entry being edited, `None` for a new custom entry.
+    pub path: Option<PathBuf>,
+    /// Name of the entry without an override, shown while the name is empty.
+    pub original_name: String,
+    /// ID of the custom entry being edited, 0 for a new one.
+    pub custom_id: Option<u32>,
+    pub name: String,
+    pub command: String,
+    pub arguments: String,
+    pub working_dir: String,
+    pub icon: String,
+    pub folder: String,
+}
+impl EntryEditor {
+    pub fn new_entry() -> Self {
+        Self {
+            path: None,
+            original_name: String::new(),
+            custom_id: Some(0),
+            name: String::new(),
+            command: String::new(),
+            arguments: String::new(),
+            working_dir: String::new(),
+            icon: String::new(),
+            folder: String::new(),
+        }
+    }
+
+    pub fn for_custom(entry: &CustomEntry) -> Self {
+        let path_text = |path: &Option<PathBuf>| path.as_ref().map(|path| path.to_string_lossy().to_string()).unwrap_or_default();
+        Self {
+            path: Some(entry.path()),
+            original_name: entry.name.clone(),
+            custom_id: Some(entry.id),
+            name: entry.name.clone(),
+            command: entry.command.clone(),
+            arguments: entry.arguments.clone(),
+            working_dir: path_text(&entry.working_dir),
+            icon: path_text(&entry.icon),
+            folder: entry.folder.clone(),
+        }
+    }
+
+    pub fn for_entry(path: PathBuf, original_name: String, name_override: Option<&String>) -> Self {
+        Self {
+            path: Some(path),
+            original_name,
+            custom_id: None,
+            name: name_override.cloned().unwrap_or_default(),
+            ..Self::new_entry()
+        }
+    }
+
+    pub fn set(&mut self, field: EntryField, value: String) {
+        match field {
+            EntryField::Name => self.name = value,
+            EntryField::Command => self.command = value,
+            EntryField::Arguments => self.arguments = value,
+            EntryField::WorkingDir => self.working_dir = value,
+            EntryField::Icon => self.icon = value,
+            EntryField::Folder => self.folder = value,
+        }
+    }
+
+    /// The custom entry the form describes.
+    pub fn custom_entry(&self) -> Result<CustomEntry, String> {
+        let id = self.custom_id.ok_or_else(|| "Only names can be changed for this entry".to_string())?;
+        let optional_path = |text: &str| if text.trim().is_empty() { None } else { Some(PathBuf::from(text.trim())) };
+        if self.name.trim().is_empty() {
+            return Err("The entry needs a name".to_string());
+        }
+        if self.command.trim().is_empty() {
+            return Err("The entry needs a command".to_string());
+        }
+        Ok(CustomEntry {
+            id,
+            name: self.name.trim().to_string(),
+            command: self.command.trim().to_string(),
+            arguments: self.arguments.trim().to_string(),
+            working_dir: optional_path(&self.working_dir),
+            icon: optional_path(&self.icon),
+            folder: self.folder.trim().to_string(),
+        })
+    }
+
+    pub fn view(&self, text_height: f32, spacing: f32) -> Element<'_, Message> {
+        let field = |label, placeholder, value, entry_field| labelled_input(label, placeholder, value, entry_field, text_height, spacing);
+        let mut form: Column<Message> = Column::new().spacing(spacing * 2.0);
+        match self.custom_id {
+            Some(_) => {
+                form = form
+                    .push(field("Name", "Name", &self.name, EntryField::Name))
+                    .push(field("Command", "Program, file or address", &self.command, EntryField::Command))
+                    .push(field("Arguments", "", &self.arguments, EntryField::Arguments))
+                    .push(field("Start in", "Working folder", &self.working_dir, EntryField::WorkingDir))
+                    .push(field("Icon", "Path to a PNG or JPEG image", &self.icon, EntryField::Icon))
+                    .push(field("Folder", "Folder in Applications, like Tools/Scripts", &self.folder, EntryField::Folder));
+            }
+            None => {
+                form = form
+                    .push(field("Name", &self.original_name, &self.name, EntryField::Name))
+                    .push(text!("Leave the name empty to use the original one.").size(text_height * 0.4));
+            }
+        }
+        let mut buttons = row![space().width(Length::Fill)].spacing(spacing);
+        if let Some(id) = self.custom_id && id != 0 {
+            buttons = buttons.push(
+                button(text!("Delete").size(text_height * 0.5))
+                    .style(|theme, status| colored_button(theme, status, Color::from_rgb(0.7, 0.2, 0.2)))
+                    .on_press(Message::StartMenu(StartMessage::DeleteEntry(id))),
+            );
+        }
+        buttons = buttons
+            .push(
+                button(text!("Save").size(text_height * 0.5))
+                    .style(|theme, status| colored_button(theme, status, Color::from_rgb(0.2, 0.2, 0.7)))
+                    .on_press(Message::StartMenu(StartMessage::SaveEntry)),
+            )
+            .push(
+                button(text!("Cancel").size(text_height * 0.5))
+                    .style(transparent_button)
+                    .on_press(Message::StartMenu(StartMessage::CancelEdit)),
+            );
+        form.push(buttons.align_y(Alignment::Center)).into()
+    }
+}
+
+fn labelled_input<'a>(label: &'a str, placeholder: &'a str, value: &'a str, field: EntryField, text_height: f32, spacing: f32) -> Column<'a, Message> {
+    column![
+        text!("{}", label).size(text_height * 0.4),
+        text_input(placeholder, value)
+            .on_input(move |value| Message::StartMenu(StartMessage::EntryInput(field, value)))
+            .on_submit(Message::StartMenu(StartMessage::SaveEntry))
+            .size(text_height * 0.5)
+            .width(Length::Fill),
+    ].spacing(spacing)
+}
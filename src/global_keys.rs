@@ -0,0 +1,339 @@
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, Stream, StreamExt};
+use iced::Subscription;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// Keys handled by the shell no matter which window has focus.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GlobalKey {
+    /// The Meta (Windows/Super) key was tapped, without any other key or button in between.
+    Meta,
+    /// Meta+R, which opens the Run dialog.
+    Run,
+    /// Hardware media keys, sent to the most recently active player.
+    MediaPlayPause,
+    MediaNext,
+    MediaPrevious,
+}
+
+// Whether a media player is there to take the media keys, read by the keyboard hook
+static MEDIA_SESSION: AtomicBool = AtomicBool::new(false);
+
+/// Tells the keyboard hook whether to take the media keys. Without a player they are passed on to the system.
+pub fn set_media_session(active: bool) {
+    MEDIA_SESSION.store(active, Ordering::Relaxed);
+}
+
+pub fn listen() -> Subscription<GlobalKey> {
+    Subscription::run(global_keys)
+}
+
+fn global_keys() -> impl Stream<Item = GlobalKey> {
+    iced::stream::channel(10, async |mut output: mpsc::Sender<GlobalKey>| {
+        let (sender, mut receiver) = mpsc::unbounded::<GlobalKey>();
+        // Both the hook and the X connection block, so they get a thread of their own
+        std::thread::spawn(move || {
+            #[cfg(windows)]
+            keyboard_hook::run(sender);
+            #[cfg(target_os = "linux")]
+            if let Err(e) = x11_keys::run(sender) {
+                eprintln!("Error listening for global keys: {}", e);
+            }
+        });
+        while let Some(key) = receiver.next().await {
+            if output.send(key).await.is_err() {
+                return;
+            }
+        }
+    })
+}
+
+// Holding Meta longer than this means the user changed their mind, so the release is not a tap
+const TAP_TIMEOUT: Duration = Duration::from_millis(800);
+
+/// Tracks a lone tap of the Meta key, so shortcuts like Meta+E are left alone.
+#[derive(Debug, Default)]
+struct MetaTap {
+    pressed_at: Option<Instant>,
+    armed: bool,
+}
+impl MetaTap {
+    fn meta_down(&mut self, now: Instant) {
+        // Auto-repeat sends more presses while the key is held
+        if self.pressed_at.is_none() {
+            self.pressed_at = Some(now);
+            self.armed = true;
+        }
+    }
+    fn other_input(&mut self) {
+        self.armed = false;
+    }
+    fn meta_held(&self) -> bool {
+        self.pressed_at.is_some()
+    }
+    /// Returns whether the release completes a tap.
+    fn meta_up(&mut self, now: Instant) -> bool {
+        let tapped = self.armed && self.pressed_at.is_some_and(|pressed_at| now.duration_since(pressed_at) < TAP_TIMEOUT);
+        self.pressed_at = None;
+        self.armed = false;
+        tapped
+    }
+}
+
+#[cfg(windows)]
+mod keyboard_hook {
+    use super::{GlobalKey, MetaTap, MEDIA_SESSION};
+    use std::time::Instant;
+    use iced::futures::channel::mpsc::UnboundedSender;
+    use std::cell::RefCell;
+    use std::sync::atomic::Ordering;
+    use windows::Win32::Foundation::{LPARAM, LRESULT, WPARAM};
+    use windows::Win32::UI::Input::KeyboardAndMouse::{SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP, VIRTUAL_KEY, VK_LWIN, VK_MEDIA_NEXT_TRACK, VK_MEDIA_PLAY_PAUSE, VK_MEDIA_PREV_TRACK, VK_R, VK_RWIN};
+    use windows::Win32::UI::WindowsAndMessaging::{CallNextHookEx, DispatchMessageW, GetMessageW, SetWindowsHookExW, TranslateMessage, UnhookWindowsHookEx, HC_ACTION, KBDLLHOOKSTRUCT, LLKHF_INJECTED, MSG, WH_KEYBOARD_LL, WM_KEYDOWN, WM_KEYUP, WM_SYSKEYDOWN, WM_SYSKEYUP};
+
+    // An unassigned virtual key, pressed before Meta is released so Windows does not open its own start menu
+    const MASK_KEY: VIRTUAL_KEY = VIRTUAL_KEY(0xE8);
+
+    // The hook procedure runs on the thread that installed it, so its state can stay thread local
+    thread_local! {
+        static HOOK_STATE: RefCell<Option<(UnboundedSender<GlobalKey>, MetaTap)>> = const { RefCell::new(None) };
+    }
+
+    pub fn run(sender: UnboundedSender<GlobalKey>) {
+        HOOK_STATE.with(|state| *state.borrow_mut() = Some((sender, MetaTap::default())));
+        unsafe {
+            let hook = match SetWindowsHookExW(WH_KEYBOARD_LL, Some(hook_proc), None, 0) {
+                Ok(hook) => hook,
+                Err(e) => {
+                    eprintln!("Error installing keyboard hook: {}", e);
+                    return;
+                }
+            };
+            // Low level hooks are only called while the installing thread pumps messages
+            let mut message = MSG::default();
+            while GetMessageW(&mut message, None, 0, 0).as_bool() {
+                let _ = TranslateMessage(&message);
+                DispatchMessageW(&message);
+            }
+            let _ = UnhookWindowsHookEx(hook);
+        }
+    }
+
+    unsafe extern "system" fn hook_proc(code: i32, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
+        if code == HC_ACTION as i32 {
+            let info = unsafe { &*(lparam.0 as *const KBDLLHOOKSTRUCT) };
+            // Our own replayed keys come back through the hook and are let through untouched
+            if info.flags.0 & LLKHF_INJECTED.0 == 0 {
+                let key = VIRTUAL_KEY(info.vkCode as u16);
+                // Explorer is what normally hands media keys to the playing application, so the shell takes them over
+                // while it knows of a player
+                if let Some(media_key) = media_key(key).filter(|_| MEDIA_SESSION.load(Ordering::Relaxed)) {
+                    if matches!(wparam.0 as u32, WM_KEYDOWN | WM_SYSKEYDOWN) {
+                        HOOK_STATE.with(|state| {
+                            if let Some((sender, _)) = state.borrow().as_ref() {
+                                let _ = sender.unbounded_send(media_key);
+                            }
+                        });
+                    }
+                    return LRESULT(1);
+                }
+                let is_meta = key == VK_LWIN || key == VK_RWIN;
+                let swallow = HOOK_STATE.with(|state| {
+                    let mut state = state.borrow_mut();
+                    let Some((sender, tap)) = state.as_mut() else {
+                        return false;
+                    };
+                    match wparam.0 as u32 {
+                        WM_KEYDOWN | WM_SYSKEYDOWN => {
+                            if is_meta {
+                                tap.meta_down(Instant::now());
+                                return false;
+                            }
+                            let chord = key == VK_R && tap.meta_held();
+                            tap.other_input();
+                            if chord {
+                                let _ = sender.unbounded_send(GlobalKey::Run);
+                            }
+                            chord
+                        }
+                        WM_KEYUP | WM_SYSKEYUP if is_meta => {
+                            if tap.meta_up(Instant::now()) {
+                                let _ = sender.unbounded_send(GlobalKey::Meta);
+                                true
+                            } else {
+                                false
+                            }
+                        }
+                        _ => false,
+                    }
+                });
+                if swallow {
+                    // Windows opens its own start menu when Meta is released with only swallowed keys in between
+                    let mut keys = vec![(MASK_KEY, KEYBD_EVENT_FLAGS(0)), (MASK_KEY, KEYEVENTF_KEYUP)];
+                    if is_meta {
+                        keys.push((key, KEYEVENTF_KEYUP));
+                    }
+                    send_keys(&keys);
+                    return LRESULT(1);
+                }
+            }
+        }
+        unsafe { CallNextHookEx(None, code, wparam, lparam) }
+    }
+
+    fn media_key(key: VIRTUAL_KEY) -> Option<GlobalKey> {
+        match key {
+            VK_MEDIA_PLAY_PAUSE => Some(GlobalKey::MediaPlayPause),
+            VK_MEDIA_NEXT_TRACK => Some(GlobalKey::MediaNext),
+            VK_MEDIA_PREV_TRACK => Some(GlobalKey::MediaPrevious),
+            _ => None,
+        }
+    }
+
+    /// Injects key presses and releases, used to replay a swallowed Meta release behind a tap of the mask key.
+    fn send_keys(keys: &[(VIRTUAL_KEY, KEYBD_EVENT_FLAGS)]) {
+        let key_input = |key: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS| INPUT {
+            r#type: INPUT_KEYBOARD,
+            Anonymous: INPUT_0 {
+                ki: KEYBDINPUT {
+                    wVk: key,
+                    wScan: 0,
+                    dwFlags: flags,
+                    time: 0,
+                    dwExtraInfo: 0,
+                },
+            },
+        };
+        let inputs: Vec<INPUT> = keys.iter().map(|(key, flags)| key_input(*key, *flags)).collect();
+        unsafe {
+            SendInput(&inputs, std::mem::size_of::<INPUT>() as i32);
+        }
+    }
+}
+
+/// Listens to XInput2 raw key events on the root window. Raw events reach every client
+/// without grabbing the key, so Super shortcuts of other programs keep working.
+#[cfg(target_os = "linux")]
+mod x11_keys {
+    use super::{GlobalKey, MetaTap};
+    use std::time::Instant;
+    use iced::futures::channel::mpsc::UnboundedSender;
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xinput::{self, ConnectionExt as _};
+    use x11rb::protocol::xproto::ConnectionExt as _;
+    use x11rb::protocol::Event;
+
+    const XK_SUPER_L: u32 = 0xffeb;
+    const XK_SUPER_R: u32 = 0xffec;
+    const XK_LOWER_R: u32 = 0x0072;
+    const XK_UPPER_R: u32 = 0x0052;
+    const XF86_AUDIO_PLAY: u32 = 0x1008ff14;
+    const XF86_AUDIO_PAUSE: u32 = 0x1008ff31;
+    const XF86_AUDIO_NEXT: u32 = 0x1008ff17;
+    const XF86_AUDIO_PREV: u32 = 0x1008ff16;
+
+    pub fn run(sender: UnboundedSender<GlobalKey>) -> Result<(), String> {
+        if std::env::var_os("DISPLAY").is_none() {
+            return Err("no X11 display, global keys are not available".to_string());
+        }
+        let (connection, screen_num) = x11rb::connect(None).map_err(|e| e.to_string())?;
+        let root = connection.setup().roots[screen_num].root;
+        connection.xinput_xi_query_version(2, 0)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| format!("XInput2 is not available: {}", e))?;
+        let meta_keycodes = keycodes_for(&connection, &[XK_SUPER_L, XK_SUPER_R])?;
+        let run_keycodes = keycodes_for(&connection, &[XK_LOWER_R, XK_UPPER_R])?;
+        let media_keycodes = [
+            (keycodes_for(&connection, &[XF86_AUDIO_PLAY, XF86_AUDIO_PAUSE])?, GlobalKey::MediaPlayPause),
+            (keycodes_for(&connection, &[XF86_AUDIO_NEXT])?, GlobalKey::MediaNext),
+            (keycodes_for(&connection, &[XF86_AUDIO_PREV])?, GlobalKey::MediaPrevious),
+        ];
+        if meta_keycodes.is_empty() {
+            return Err("no key is mapped to Super".to_string());
+        }
+        let mask = xinput::XIEventMask::RAW_KEY_PRESS | xinput::XIEventMask::RAW_KEY_RELEASE | xinput::XIEventMask::RAW_BUTTON_PRESS;
+        connection.xinput_xi_select_events(root, &[xinput::EventMask {
+            deviceid: xinput::Device::ALL_MASTER.into(),
+            mask: vec![mask],
+        }]).map_err(|e| e.to_string())?;
+        connection.flush().map_err(|e| e.to_string())?;
+
+        let mut tap = MetaTap::default();
+        loop {
+            match connection.wait_for_event().map_err(|e| e.to_string())? {
+                Event::XinputRawKeyPress(event) => {
+                    if meta_keycodes.contains(&event.detail) {
+                        tap.meta_down(Instant::now());
+                    } else if let Some((_, media_key)) = media_keycodes.iter().find(|(keycodes, _)| keycodes.contains(&event.detail)) {
+                        if sender.unbounded_send(*media_key).is_err() {
+                            return Ok(());
+                        }
+                        tap.other_input();
+                    } else {
+                        // Raw events cannot be swallowed, so the focused window sees the R as well
+                        if run_keycodes.contains(&event.detail) && tap.meta_held() && sender.unbounded_send(GlobalKey::Run).is_err() {
+                            return Ok(());
+                        }
+                        tap.other_input();
+                    }
+                }
+                Event::XinputRawKeyRelease(event) if meta_keycodes.contains(&event.detail)
+                    && tap.meta_up(Instant::now()) && sender.unbounded_send(GlobalKey::Meta).is_err() => {
+                        return Ok(());
+                    }
+                Event::XinputRawButtonPress(_) => tap.other_input(),
+                _ => {}
+            }
+        }
+    }
+
+    fn keycodes_for(connection: &impl Connection, wanted: &[u32]) -> Result<Vec<u32>, String> {
+        let setup = connection.setup();
+        let (min, max) = (setup.min_keycode, setup.max_keycode);
+        let mapping = connection.get_keyboard_mapping(min, max - min + 1)
+            .map_err(|e| e.to_string())?
+            .reply()
+            .map_err(|e| e.to_string())?;
+        let per_keycode = mapping.keysyms_per_keycode.max(1) as usize;
+        Ok(mapping.keysyms.chunks(per_keycode)
+            .enumerate()
+            .filter(|(_, keysyms)| keysyms.iter().any(|keysym| wanted.contains(keysym)))
+            .map(|(index, _)| min as u32 + index as u32)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_lone_tap_opens_the_menu() {
+        let start = Instant::now();
+        let mut tap = MetaTap::default();
+        tap.meta_down(start);
+        // Auto-repeat while held
+        tap.meta_down(start + Duration::from_millis(50));
+        assert!(tap.meta_up(start + Duration::from_millis(100)));
+        // Each release ends the tap
+        assert!(!tap.meta_up(start + Duration::from_millis(150)));
+    }
+
+    #[test]
+    fn shortcuts_and_long_holds_do_not() {
+        let start = Instant::now();
+        let mut tap = MetaTap::default();
+        tap.meta_down(start);
+        tap.other_input();
+        assert!(!tap.meta_up(start + Duration::from_millis(100)));
+
+        tap.meta_down(start);
+        assert!(!tap.meta_up(start + TAP_TIMEOUT));
+
+        // A shortcut does not spoil the next tap
+        tap.meta_down(start);
+        assert!(tap.meta_up(start + Duration::from_millis(100)));
+    }
+}
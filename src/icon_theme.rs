@@ -0,0 +1,487 @@
+use iced::widget::image::Handle;
+use resvg::{tiny_skia, usvg};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+// Icon Theme Specification: https://specifications.freedesktop.org/icon-theme-spec/latest/
+// XPM icons are left out, nothing here decodes them
+pub const ICON_EXTENSIONS: &[&str] = &["png", "svg"];
+const FALLBACK_THEME: &str = "hicolor";
+
+#[derive(Debug, Clone, PartialEq)]
+enum DirectoryType {
+    Fixed,
+    Scalable { min_size: u32, max_size: u32 },
+    Threshold(u32),
+}
+
+#[derive(Debug, Clone)]
+struct IconDirectory {
+    path: String,
+    size: u32,
+    scale: u32,
+    kind: DirectoryType,
+}
+impl IconDirectory {
+    fn matches_size(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+        match self.kind {
+            DirectoryType::Fixed => self.size == size,
+            DirectoryType::Scalable { min_size, max_size } => min_size <= size && size <= max_size,
+            DirectoryType::Threshold(threshold) => {
+                self.size.saturating_sub(threshold) <= size && size <= self.size + threshold
+            }
+        }
+    }
+    fn size_distance(&self, size: u32, scale: u32) -> u32 {
+        let wanted = size * scale;
+        match self.kind {
+            DirectoryType::Fixed => (self.size * self.scale).abs_diff(wanted),
+            DirectoryType::Scalable { min_size, max_size } => {
+                // Zero inside the range, otherwise how far outside it
+                (min_size * self.scale).saturating_sub(wanted) + wanted.saturating_sub(max_size * self.scale)
+            }
+            DirectoryType::Threshold(threshold) => {
+                let low = self.size.saturating_sub(threshold) * self.scale;
+                let high = (self.size + threshold) * self.scale;
+                low.saturating_sub(wanted) + wanted.saturating_sub(high)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct IconTheme {
+    roots: Vec<PathBuf>,
+    inherits: Vec<String>,
+    directories: Vec<IconDirectory>,
+}
+impl IconTheme {
+    /// Loads `name` from the first base directory holding an `index.theme` for it.
+    /// Every base directory containing the theme folder contributes icons.
+    fn load(name: &str, base_dirs: &[PathBuf]) -> Option<Self> {
+        let roots: Vec<PathBuf> = base_dirs.iter()
+            .map(|base| base.join(name))
+            .filter(|root| root.is_dir())
+            .collect();
+        let index = roots.iter()
+            .find_map(|root| std::fs::read_to_string(root.join("index.theme")).ok())?;
+        Some(Self::parse(&index, roots))
+    }
+
+    fn parse(index: &str, roots: Vec<PathBuf>) -> Self {
+        let groups = parse_ini(index);
+        let theme_group = groups.get("Icon Theme").cloned().unwrap_or_default();
+        let inherits: Vec<String> = theme_group.get("Inherits")
+            .map(|value| value.split(',').map(|name| name.trim().to_string()).filter(|name| !name.is_empty()).collect())
+            .unwrap_or_default();
+        let mut directories: Vec<IconDirectory> = Vec::new();
+        let listed = [theme_group.get("Directories"), theme_group.get("ScaledDirectories")];
+        for list in listed.into_iter().flatten() {
+            for path in list.split(',').map(str::trim).filter(|path| !path.is_empty()) {
+                let Some(group) = groups.get(path) else {
+                    continue;
+                };
+                let number = |key: &str| group.get(key).and_then(|value| value.trim().parse::<u32>().ok());
+                let Some(size) = number("Size") else {
+                    continue;
+                };
+                let kind = match group.get("Type").map(|value| value.trim()) {
+                    Some("Fixed") => DirectoryType::Fixed,
+                    Some("Scalable") => DirectoryType::Scalable {
+                        min_size: number("MinSize").unwrap_or(size),
+                        max_size: number("MaxSize").unwrap_or(size),
+                    },
+                    _ => DirectoryType::Threshold(number("Threshold").unwrap_or(2)),
+                };
+                directories.push(IconDirectory {
+                    path: path.to_string(),
+                    size,
+                    scale: number("Scale").unwrap_or(1),
+                    kind,
+                });
+            }
+        }
+        Self { roots, inherits, directories }
+    }
+
+    fn lookup(&self, icon: &str, size: u32, scale: u32, extensions: &[&str]) -> Option<PathBuf> {
+        for directory in self.directories.iter().filter(|directory| directory.matches_size(size, scale)) {
+            if let Some(found) = self.find_in(&directory.path, icon, extensions) {
+                return Some(found);
+            }
+        }
+        let mut best: Option<(u32, PathBuf)> = None;
+        for directory in self.directories.iter() {
+            let distance = directory.size_distance(size, scale);
+            if best.as_ref().is_some_and(|(best_distance, _)| *best_distance <= distance) {
+                continue;
+            }
+            if let Some(found) = self.find_in(&directory.path, icon, extensions) {
+                best = Some((distance, found));
+            }
+        }
+        best.map(|(_, path)| path)
+    }
+
+    fn find_in(&self, directory: &str, icon: &str, extensions: &[&str]) -> Option<PathBuf> {
+        for root in self.roots.iter() {
+            for extension in extensions {
+                let candidate = root.join(directory).join(format!("{}.{}", icon, extension));
+                if candidate.is_file() {
+                    return Some(candidate);
+                }
+            }
+        }
+        None
+    }
+}
+
+pub struct IconLookup {
+    theme: String,
+    base_dirs: Vec<PathBuf>,
+    pixmap_dirs: Vec<PathBuf>,
+    themes: Mutex<BTreeMap<String, Option<IconTheme>>>,
+    cache: Mutex<BTreeMap<(String, u32, u32), Option<PathBuf>>>,
+}
+impl IconLookup {
+    pub fn new(theme: String, base_dirs: Vec<PathBuf>, pixmap_dirs: Vec<PathBuf>) -> Self {
+        Self {
+            theme,
+            base_dirs,
+            pixmap_dirs,
+            themes: Mutex::new(BTreeMap::new()),
+            cache: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Lookup using the user's configured theme and the standard XDG icon folders.
+    pub fn system() -> Self {
+        let mut base_dirs: Vec<PathBuf> = Vec::new();
+        if let Some(home) = dirs::home_dir() {
+            base_dirs.push(home.join(".icons"));
+        }
+        if let Some(data_home) = dirs::data_dir() {
+            base_dirs.push(data_home.join("icons"));
+        }
+        let data_dirs = std::env::var_os("XDG_DATA_DIRS")
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+        for dir in std::env::split_paths(&data_dirs) {
+            base_dirs.push(dir.join("icons"));
+        }
+        Self::new(configured_theme().unwrap_or_else(|| FALLBACK_THEME.to_string()), base_dirs, vec![PathBuf::from("/usr/share/pixmaps")])
+    }
+
+    pub fn find_icon(&self, icon: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        self.find_icon_with_extensions(icon, size, scale, ICON_EXTENSIONS)
+    }
+
+    /// Resolves an icon name, or an absolute path, to a file.
+    /// `extensions` lists the formats the caller can decode in order of preference.
+    pub fn find_icon_with_extensions(&self, icon: &str, size: u32, scale: u32, extensions: &[&str]) -> Option<PathBuf> {
+        let icon_path = Path::new(icon);
+        if icon_path.is_absolute() {
+            return if icon_path.is_file() { Some(icon_path.to_path_buf()) } else { None };
+        }
+        let key = (format!("{}|{}", icon, extensions.join(",")), size, scale);
+        if let Ok(cache) = self.cache.lock() && let Some(found) = cache.get(&key) {
+            return found.clone();
+        }
+        let found = self.find_uncached(icon, size, scale, extensions);
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.insert(key, found.clone());
+        }
+        found
+    }
+
+    fn find_uncached(&self, icon: &str, size: u32, scale: u32, extensions: &[&str]) -> Option<PathBuf> {
+        let mut visited: Vec<String> = Vec::new();
+        if let Some(found) = self.find_in_theme(&self.theme.clone(), icon, size, scale, extensions, &mut visited) {
+            return Some(found);
+        }
+        if !visited.iter().any(|name| name == FALLBACK_THEME)
+            && let Some(found) = self.find_in_theme(FALLBACK_THEME, icon, size, scale, extensions, &mut visited) {
+            return Some(found);
+        }
+        for dir in self.base_dirs.iter().chain(self.pixmap_dirs.iter()) {
+            for extension in extensions {
+                let candidate = dir.join(format!("{}.{}", icon, extension));
+                if candidate.is_file() {
+                    return Some(candidate);
+                }
+            }
+        }
+        None
+    }
+
+    fn find_in_theme(&self, theme: &str, icon: &str, size: u32, scale: u32, extensions: &[&str], visited: &mut Vec<String>) -> Option<PathBuf> {
+        if visited.iter().any(|name| name == theme) {
+            return None;
+        }
+        visited.push(theme.to_string());
+        let loaded = match self.themes.lock() {
+            Ok(mut themes) => themes.entry(theme.to_string())
+                .or_insert_with(|| IconTheme::load(theme, &self.base_dirs))
+                .clone(),
+            Err(e) => {
+                eprintln!("Error accessing icon theme cache: {}", e);
+                None
+            }
+        }?;
+        if let Some(found) = loaded.lookup(icon, size, scale, extensions) {
+            return Some(found);
+        }
+        for parent in loaded.inherits.iter() {
+            if let Some(found) = self.find_in_theme(parent, icon, size, scale, extensions, visited) {
+                return Some(found);
+            }
+        }
+        None
+    }
+}
+
+/// Loads an icon file for display at `size` pixels. iced images only decode raster formats,
+/// so SVG icons are rendered here, centered in a square.
+pub fn load_icon(path: &Path, size: u32) -> Option<Handle> {
+    if !path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("svg")) {
+        return Some(Handle::from_path(path));
+    }
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Error reading icon {:?}: {}", path, e);
+            return None;
+        }
+    };
+    let tree = match usvg::Tree::from_data(&data, &usvg::Options::default()) {
+        Ok(tree) => tree,
+        Err(e) => {
+            eprintln!("Error parsing icon {:?}: {}", path, e);
+            return None;
+        }
+    };
+    let mut pixmap = tiny_skia::Pixmap::new(size, size)?;
+    let (width, height) = (tree.size().width(), tree.size().height());
+    let scale = (size as f32 / width).min(size as f32 / height);
+    let transform = tiny_skia::Transform::from_scale(scale, scale)
+        .post_translate((size as f32 - width * scale) / 2.0, (size as f32 - height * scale) / 2.0);
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+    // The pixmap holds premultiplied colors, iced wants them straight
+    let pixels: Vec<u8> = pixmap.pixels().iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    Some(Handle::from_rgba(size, size, pixels))
+}
+
+/// Shared lookup used by the start menu and tray, built on first use.
+pub fn system_icon_lookup() -> &'static IconLookup {
+    static LOOKUP: OnceLock<IconLookup> = OnceLock::new();
+    LOOKUP.get_or_init(IconLookup::system)
+}
+
+/// Icon theme named in the GTK settings, which most desktops keep in sync with their own.
+fn configured_theme() -> Option<String> {
+    if let Ok(theme) = std::env::var("FROSTWIN_ICON_THEME") && !theme.is_empty() {
+        return Some(theme);
+    }
+    let config_dir = dirs::config_dir()?;
+    for file in ["gtk-4.0/settings.ini", "gtk-3.0/settings.ini"] {
+        if let Ok(content) = std::fs::read_to_string(config_dir.join(file))
+            && let Some(settings) = parse_ini(&content).get("Settings")
+            && let Some(theme) = settings.get("gtk-icon-theme-name") {
+            let theme = theme.trim().trim_matches('"').to_string();
+            if !theme.is_empty() {
+                return Some(theme);
+            }
+        }
+    }
+    None
+}
+
+fn parse_ini(content: &str) -> BTreeMap<String, BTreeMap<String, String>> {
+    let mut groups: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    let mut current: Option<String> = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            let name = line[1..line.len() - 1].to_string();
+            groups.entry(name.clone()).or_default();
+            current = Some(name);
+        } else if let Some(group) = current.as_ref() && let Some((key, value)) = line.split_once('=') {
+            groups.entry(group.clone()).or_default()
+                .entry(key.trim().to_string())
+                .or_insert_with(|| value.trim().to_string());
+        }
+    }
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: &str = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="20"><rect width="10" height="20" fill="red"/></svg>"#;
+
+    fn write(root: &Path, relative: &str, content: &str) {
+        let path = root.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    /// An `icons` folder with a theme inheriting from two others, hicolor and a pixmaps folder next to it.
+    fn icons_tree() -> (tempfile::TempDir, IconLookup) {
+        let temp = tempfile::tempdir().unwrap();
+        let icons = temp.path().join("icons");
+        write(&icons, "Custom/index.theme", "\
+[Icon Theme]
+Name=Custom
+Inherits=Parent, Other
+Directories=16x16/apps,32x32/apps,scalable/apps,32x32@2/apps
+
+[16x16/apps]
+Size=16
+Type=Fixed
+
+[32x32/apps]
+Size=32
+Type=Threshold
+Threshold=4
+
+[scalable/apps]
+Size=128
+Type=Scalable
+MinSize=64
+MaxSize=256
+
+[32x32@2/apps]
+Size=32
+Scale=2
+Type=Fixed
+");
+        write(&icons, "Custom/16x16/apps/app.png", "");
+        write(&icons, "Custom/32x32/apps/app.png", "");
+        write(&icons, "Custom/32x32/apps/app.svg", "");
+        write(&icons, "Custom/scalable/apps/app.svg", "");
+        write(&icons, "Custom/32x32@2/apps/app.png", "");
+        write(&icons, "Custom/16x16/apps/small-only.png", "");
+        write(&icons, "Parent/index.theme", "[Icon Theme]\nDirectories=48x48/apps\n\n[48x48/apps]\nSize=48\nType=Fixed\n");
+        write(&icons, "Parent/48x48/apps/small-only.png", "");
+        write(&icons, "Parent/48x48/apps/inherited.png", "");
+        write(&icons, "Other/index.theme", "[Icon Theme]\nInherits=Parent\nDirectories=48x48/apps\n\n[48x48/apps]\nSize=48\nType=Fixed\n");
+        write(&icons, "Other/48x48/apps/inherited.png", "");
+        write(&icons, "Other/48x48/apps/other-only.png", "");
+        write(&icons, "hicolor/index.theme", "[Icon Theme]\nDirectories=48x48/apps\n\n[48x48/apps]\nSize=48\nType=Threshold\n");
+        write(&icons, "hicolor/48x48/apps/fallback.png", "");
+        write(&icons, "hicolor/48x48/apps/inherited.png", "");
+        write(&icons, "loose.png", "");
+        let pixmaps = temp.path().join("pixmaps");
+        write(&pixmaps, "legacy.png", "");
+        write(&pixmaps, "legacy.xpm", "");
+        let lookup = IconLookup::new("Custom".to_string(), vec![icons], vec![pixmaps]);
+        (temp, lookup)
+    }
+
+    fn found(lookup: &IconLookup, icon: &str, size: u32, scale: u32) -> Option<String> {
+        let root = lookup.base_dirs[0].parent().unwrap().to_path_buf();
+        lookup.find_icon(icon, size, scale)
+            .map(|path| path.strip_prefix(&root).unwrap().to_string_lossy().to_string())
+    }
+
+    #[test]
+    fn picks_the_directory_closest_in_size() {
+        let (_temp, lookup) = icons_tree();
+        let cases = [
+            // Exact matches, by type
+            (16, 1, "icons/Custom/16x16/apps/app.png"),
+            (30, 1, "icons/Custom/32x32/apps/app.png"),
+            (36, 1, "icons/Custom/32x32/apps/app.png"),
+            (200, 1, "icons/Custom/scalable/apps/app.svg"),
+            (32, 2, "icons/Custom/32x32@2/apps/app.png"),
+            // Nearest by distance: 20 is 4 from the fixed 16 and 8 from the threshold's 28
+            (20, 1, "icons/Custom/16x16/apps/app.png"),
+            // 26 is 2 from the threshold's 28
+            (26, 1, "icons/Custom/32x32/apps/app.png"),
+            // 48 is 8 from the threshold's 40 and 16 from the scalable 64
+            (48, 1, "icons/Custom/32x32/apps/app.png"),
+            (512, 1, "icons/Custom/scalable/apps/app.svg"),
+            (4, 1, "icons/Custom/16x16/apps/app.png"),
+        ];
+        for (size, scale, expected) in cases {
+            assert_eq!(found(&lookup, "app", size, scale).as_deref(), Some(expected), "{}@{}", size, scale);
+        }
+    }
+
+    #[test]
+    fn prefers_formats_in_the_given_order() {
+        let (_temp, lookup) = icons_tree();
+        assert_eq!(found(&lookup, "app", 32, 1).as_deref(), Some("icons/Custom/32x32/apps/app.png"));
+        let svg_first = lookup.find_icon_with_extensions("app", 32, 1, &["svg", "png"]).unwrap();
+        assert!(svg_first.ends_with("Custom/32x32/apps/app.svg"));
+        // XPM is not shown, so the PNG next to it is found
+        assert_eq!(found(&lookup, "legacy", 48, 1).as_deref(), Some("pixmaps/legacy.png"));
+        assert_eq!(lookup.find_icon_with_extensions("legacy", 48, 1, &["xpm"]).map(|path| path.ends_with("legacy.xpm")), Some(true));
+    }
+
+    #[test]
+    fn searches_inherited_themes_in_order() {
+        let (_temp, lookup) = icons_tree();
+        let cases = [
+            // A theme's own icon at any size beats an exact size further up
+            ("small-only", 48, "icons/Custom/16x16/apps/small-only.png"),
+            // Parent is listed before Other, and both before hicolor
+            ("inherited", 48, "icons/Parent/48x48/apps/inherited.png"),
+            ("other-only", 48, "icons/Other/48x48/apps/other-only.png"),
+            // hicolor is searched even though nothing inherits from it
+            ("fallback", 48, "icons/hicolor/48x48/apps/fallback.png"),
+            // Then loose files in the base and pixmaps folders
+            ("loose", 48, "icons/loose.png"),
+            ("legacy", 48, "pixmaps/legacy.png"),
+        ];
+        for (icon, size, expected) in cases {
+            assert_eq!(found(&lookup, icon, size, 1).as_deref(), Some(expected), "{}", icon);
+        }
+        assert_eq!(found(&lookup, "missing", 48, 1), None);
+    }
+
+    #[test]
+    fn falls_back_to_hicolor_without_the_configured_theme() {
+        let (_temp, lookup) = icons_tree();
+        let missing_theme = IconLookup::new("Missing".to_string(), lookup.base_dirs.clone(), lookup.pixmap_dirs.clone());
+        assert_eq!(found(&missing_theme, "inherited", 48, 1).as_deref(), Some("icons/hicolor/48x48/apps/inherited.png"));
+        assert_eq!(found(&missing_theme, "app", 16, 1), None);
+    }
+
+    #[test]
+    fn renders_svg_icons() {
+        let temp = tempfile::tempdir().unwrap();
+        let svg = temp.path().join("square.svg");
+        std::fs::write(&svg, SQUARE).unwrap();
+        let Some(handle) = load_icon(&svg, 8) else {
+            panic!("SVG was not rendered");
+        };
+        let Handle::Rgba { width, height, pixels, .. } = handle else {
+            panic!("SVG was not rendered to pixels");
+        };
+        assert_eq!((width, height), (8, 8));
+        let pixel = |x: usize, y: usize| &pixels[(y * 8 + x) * 4..(y * 8 + x) * 4 + 4];
+        // The tall image is centered, leaving the sides clear
+        assert_eq!(pixel(0, 4), [0, 0, 0, 0]);
+        assert_eq!(pixel(4, 4), [255, 0, 0, 255]);
+        assert_eq!(pixel(7, 4), [0, 0, 0, 0]);
+
+        let broken = temp.path().join("broken.svg");
+        std::fs::write(&broken, "<svg").unwrap();
+        assert!(load_icon(&broken, 8).is_none());
+        assert!(matches!(load_icon(Path::new("/icons/app.png"), 8), Some(Handle::Path(..))));
+    }
+}
@@ -0,0 +1,366 @@
+use std::path::Path;
+#[cfg(windows)]
+use std::path::PathBuf;
+use std::process::Command;
+
+/// How an installed application gets removed, shown to the user before it runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Uninstaller {
+    /// `UninstallString` from the registry, which is a full command line rather than an argument list.
+    #[cfg(windows)]
+    CommandLine { display_name: String, command_line: String },
+    Command { display_name: String, program: String, args: Vec<String> },
+}
+impl Uninstaller {
+    pub fn display_name(&self) -> &str {
+        match self {
+            #[cfg(windows)]
+            Uninstaller::CommandLine { display_name, .. } => display_name,
+            Uninstaller::Command { display_name, .. } => display_name,
+        }
+    }
+
+    pub fn command_text(&self) -> String {
+        match self {
+            #[cfg(windows)]
+            Uninstaller::CommandLine { command_line, .. } => command_line.clone(),
+            Uninstaller::Command { program, args, .. } => {
+                let mut words = vec![program.clone()];
+                words.extend(args.iter().map(|arg| {
+                    if arg.contains(' ') { format!("\"{}\"", arg) } else { arg.clone() }
+                }));
+                words.join(" ")
+            }
+        }
+    }
+
+    pub fn run(&self) -> Result<(), String> {
+        match self {
+            #[cfg(windows)]
+            Uninstaller::CommandLine { command_line, .. } => {
+                use std::os::windows::process::CommandExt;
+                // /S makes cmd strip exactly the outer quotes, keeping any quoted executable path intact
+                Command::new("cmd")
+                    .raw_arg(format!("/S /C \"{}\"", command_line))
+                    .spawn()
+                    .map(|_| ())
+                    .map_err(|e| format!("Failed to start uninstaller: {}", e))
+            }
+            Uninstaller::Command { program, args, .. } => {
+                Command::new(program)
+                    .args(args)
+                    .spawn()
+                    .map(|_| ())
+                    .map_err(|e| format!("Failed to start {}: {}", program, e))
+            }
+        }
+    }
+}
+
+/// Opens the folder holding `path` with the item selected where the file manager supports it.
+pub fn show_in_folder(path: &Path) -> Result<(), String> {
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        // explorer does its own parsing of /select, so the argument is passed through untouched
+        Command::new("explorer")
+            .raw_arg(format!("/select,\"{}\"", path.display()))
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| format!("explorer: {}", e))
+    }
+    #[cfg(not(windows))]
+    {
+        let uri = format!("file://{}", path.display());
+        let shown = Command::new("dbus-send")
+            .args([
+                "--session",
+                "--type=method_call",
+                "--dest=org.freedesktop.FileManager1",
+                "/org/freedesktop/FileManager1",
+                "org.freedesktop.FileManager1.ShowItems",
+                &format!("array:string:{}", uri),
+                "string:",
+            ])
+            .status()
+            .is_ok_and(|status| status.success());
+        if shown {
+            return Ok(());
+        }
+        let folder = path.parent().ok_or_else(|| format!("{:?} has no parent folder", path))?;
+        Command::new("xdg-open")
+            .arg(folder)
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| format!("xdg-open: {}", e))
+    }
+}
+
+/// Starts `file` through the `runas` verb, which brings up the UAC prompt.
+#[cfg(windows)]
+pub fn run_as_administrator(file: &Path) -> Result<(), String> {
+    use windows::core::{w, HSTRING, PCWSTR};
+    use windows::Win32::UI::Shell::ShellExecuteW;
+    use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    let file_name = HSTRING::from(file.as_os_str());
+    let result = unsafe { ShellExecuteW(None, w!("runas"), &file_name, PCWSTR::null(), PCWSTR::null(), SW_SHOWNORMAL) };
+    // Values above 32 mean success, 5 is returned when the UAC prompt is declined
+    match result.0 as isize {
+        code if code > 32 => Ok(()),
+        5 => Err(format!("Running {} as administrator was cancelled", file.display())),
+        code => Err(format!("Failed to run {} as administrator (error {})", file.display(), code)),
+    }
+}
+
+/// Runs a command as root through polkit, passing on the variables a graphical program needs.
+#[cfg(target_os = "linux")]
+pub fn run_with_pkexec(command: &[String], working_dir: Option<&Path>) -> Result<(), String> {
+    if command.is_empty() {
+        return Err("Nothing to run".to_string());
+    }
+    Command::new("pkexec")
+        .args(pkexec_args(command, working_dir, |variable| std::env::var(variable).ok()))
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("pkexec: {}", e))
+}
+
+/// Arguments for pkexec, env only takes options before the first `NAME=VALUE`.
+#[cfg(target_os = "linux")]
+fn pkexec_args(command: &[String], working_dir: Option<&Path>, var: impl Fn(&str) -> Option<String>) -> Vec<String> {
+    let mut args = vec!["env".to_string()];
+    if let Some(working_dir) = working_dir {
+        args.push(format!("--chdir={}", working_dir.display()));
+    }
+    for variable in ["DISPLAY", "XAUTHORITY", "WAYLAND_DISPLAY", "XDG_RUNTIME_DIR"] {
+        if let Some(value) = var(variable) {
+            args.push(format!("{}={}", variable, value));
+        }
+    }
+    args.extend(command.iter().cloned());
+    args
+}
+
+#[cfg(windows)]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UninstallEntry {
+    pub display_name: String,
+    pub install_location: Option<PathBuf>,
+    pub display_icon: Option<PathBuf>,
+    pub uninstall_string: String,
+}
+
+/// Picks the registered program that most likely installed the shortcut.
+/// A target inside the install folder or matching the display icon beats a matching name.
+#[cfg(windows)]
+pub fn match_uninstall_entry<'a>(entries: &'a [UninstallEntry], name: &str, target: Option<&Path>) -> Option<&'a UninstallEntry> {
+    let name = name.to_lowercase();
+    let target = target.map(|target| normalize_path(target));
+    let mut best: Option<(u32, &UninstallEntry)> = None;
+    for entry in entries {
+        let display_name = entry.display_name.to_lowercase();
+        let mut score = 0;
+        if let Some(target) = target.as_ref() {
+            if let Some(location) = entry.install_location.as_ref().map(|location| normalize_path(location))
+                && !location.is_empty()
+                && target.starts_with(&format!("{}\\", location)) {
+                score = score.max(3);
+            }
+            if entry.display_icon.as_ref().is_some_and(|icon| normalize_path(icon) == *target) {
+                score = score.max(3);
+            }
+        }
+        if display_name == name {
+            score = score.max(2);
+        } else if name.len() >= 3 && (display_name.contains(&name) || name.contains(&display_name)) {
+            score = score.max(1);
+        }
+        if score > 0 && best.is_none_or(|(best_score, _)| score > best_score) {
+            best = Some((score, entry));
+        }
+    }
+    best.map(|(_, entry)| entry)
+}
+
+// Registry paths mix slash styles and casing, so compare them in one lower-case backslash form
+#[cfg(windows)]
+fn normalize_path(path: &Path) -> String {
+    path.to_string_lossy().replace('/', "\\").trim_end_matches('\\').to_lowercase()
+}
+
+/// `DisplayIcon` values look like `"C:\Program Files\App\app.exe",0`.
+#[cfg(windows)]
+pub fn parse_display_icon(value: &str) -> Option<PathBuf> {
+    let value = value.trim();
+    let path = match value.rsplit_once(',') {
+        Some((path, index)) if index.trim().parse::<i32>().is_ok() => path,
+        _ => value,
+    };
+    let path = path.trim().trim_matches('"');
+    if path.is_empty() { None } else { Some(PathBuf::from(path)) }
+}
+
+/// Programs listed under the machine, 32-bit and per-user Uninstall keys.
+#[cfg(windows)]
+pub fn uninstall_entries() -> Vec<UninstallEntry> {
+    use windows::core::{w, PCWSTR, PWSTR};
+    use windows::Win32::System::Registry::{RegCloseKey, RegEnumKeyExW, RegGetValueW, RegOpenKeyExW, HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, KEY_READ, RRF_RT_REG_DWORD, RRF_RT_REG_SZ};
+
+    let read_string = |key: HKEY, subkey: &[u16], value: PCWSTR| -> Option<String> {
+        let mut buffer = [0u16; 2048];
+        let mut size = (buffer.len() * 2) as u32;
+        unsafe {
+            RegGetValueW(key, PCWSTR(subkey.as_ptr()), value, RRF_RT_REG_SZ, None, Some(buffer.as_mut_ptr() as *mut _), Some(&mut size)).ok().ok()?;
+        }
+        let len = buffer.iter().position(|unit| *unit == 0).unwrap_or(buffer.len());
+        let text = String::from_utf16_lossy(&buffer[..len]);
+        if text.trim().is_empty() { None } else { Some(text) }
+    };
+
+    let mut entries: Vec<UninstallEntry> = Vec::new();
+    let roots = [
+        (HKEY_LOCAL_MACHINE, w!(r"Software\Microsoft\Windows\CurrentVersion\Uninstall")),
+        (HKEY_LOCAL_MACHINE, w!(r"Software\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall")),
+        (HKEY_CURRENT_USER, w!(r"Software\Microsoft\Windows\CurrentVersion\Uninstall")),
+    ];
+    for (root, path) in roots {
+        unsafe {
+            let mut uninstall_key = HKEY::default();
+            if RegOpenKeyExW(root, path, Some(0), KEY_READ, &mut uninstall_key).is_err() {
+                continue;
+            }
+            let mut index = 0;
+            loop {
+                let mut name = [0u16; 512];
+                let mut name_len = name.len() as u32;
+                if RegEnumKeyExW(uninstall_key, index, Some(PWSTR(name.as_mut_ptr())), &mut name_len, None, None, None, None).is_err() {
+                    break;
+                }
+                index += 1;
+                // Updates and runtime components are hidden from the Programs list, so they are skipped here too
+                let mut system_component = 0u32;
+                let mut size = 4u32;
+                if RegGetValueW(uninstall_key, PCWSTR(name.as_ptr()), w!("SystemComponent"), RRF_RT_REG_DWORD, None, Some(&mut system_component as *mut u32 as *mut _), Some(&mut size)).is_ok()
+                    && system_component == 1 {
+                    continue;
+                }
+                let (Some(display_name), Some(uninstall_string)) = (
+                    read_string(uninstall_key, &name, w!("DisplayName")),
+                    read_string(uninstall_key, &name, w!("UninstallString")),
+                ) else {
+                    continue;
+                };
+                entries.push(UninstallEntry {
+                    display_name,
+                    install_location: read_string(uninstall_key, &name, w!("InstallLocation"))
+                        .map(|location| PathBuf::from(location.trim().trim_matches('"'))),
+                    display_icon: read_string(uninstall_key, &name, w!("DisplayIcon"))
+                        .and_then(|icon| parse_display_icon(&icon)),
+                    uninstall_string,
+                });
+            }
+            let _ = RegCloseKey(uninstall_key);
+        }
+    }
+    entries
+}
+
+#[cfg(windows)]
+pub fn find_registered_uninstaller(name: &str, target: Option<&Path>) -> Result<Uninstaller, String> {
+    let entries = uninstall_entries();
+    match match_uninstall_entry(&entries, name, target) {
+        Some(entry) => Ok(Uninstaller::CommandLine {
+            display_name: entry.display_name.clone(),
+            command_line: entry.uninstall_string.clone(),
+        }),
+        None => Err(format!("No uninstaller is registered for {}", name)),
+    }
+}
+
+/// Works out which package installed a desktop entry and the command removing it.
+/// Flatpak and Snap exports are recognised by their folder, everything else is asked of the distribution's package manager.
+#[cfg(target_os = "linux")]
+pub fn find_package_uninstaller(desktop_file: &Path, name: &str) -> Result<Uninstaller, String> {
+    use crate::desktop_entry::find_executable;
+
+    let path_text = desktop_file.to_string_lossy();
+    let stem = desktop_file.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default().to_string();
+    if path_text.contains("/flatpak/exports/share/applications/") {
+        let installation = if dirs::home_dir().is_some_and(|home| desktop_file.starts_with(home)) { "--user" } else { "--system" };
+        return Ok(Uninstaller::Command {
+            display_name: name.to_string(),
+            program: "flatpak".to_string(),
+            args: vec!["uninstall".to_string(), "--noninteractive".to_string(), installation.to_string(), stem],
+        });
+    }
+    if desktop_file.starts_with("/var/lib/snapd/desktop/applications") {
+        // Snap desktop files are named <snap>_<app>.desktop
+        let snap = stem.split('_').next().unwrap_or_default().to_string();
+        return Ok(Uninstaller::Command {
+            display_name: name.to_string(),
+            program: "snap".to_string(),
+            args: vec!["remove".to_string(), snap],
+        });
+    }
+
+    let query = |program: &str, args: &[&str]| -> Option<String> {
+        find_executable(program)?;
+        let output = Command::new(program).args(args).arg(desktop_file).output().ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if text.is_empty() { None } else { Some(text) }
+    };
+    let pkexec = |args: Vec<&str>, package: String| Uninstaller::Command {
+        display_name: name.to_string(),
+        program: "pkexec".to_string(),
+        args: args.into_iter().map(String::from).chain(std::iter::once(package)).collect(),
+    };
+
+    if let Some(package) = query("pacman", &["-Qoq"]) {
+        return Ok(pkexec(vec!["pacman", "-R", "--noconfirm"], package));
+    }
+    if let Some(output) = query("dpkg-query", &["-S"]) {
+        // "package:arch, other: /usr/share/applications/app.desktop"
+        let package = output.lines().next().unwrap_or_default()
+            .split(": ").next().unwrap_or_default()
+            .split(", ").next().unwrap_or_default()
+            .to_string();
+        if !package.is_empty() {
+            return Ok(pkexec(vec!["apt-get", "remove", "-y"], package));
+        }
+    }
+    if let Some(package) = query("rpm", &["-qf", "--queryformat", "%{NAME}"]) {
+        let remove = if find_executable("dnf").is_some() {
+            vec!["dnf", "remove", "-y"]
+        } else if find_executable("zypper").is_some() {
+            vec!["zypper", "--non-interactive", "remove"]
+        } else {
+            vec!["rpm", "-e"]
+        };
+        return Ok(pkexec(remove, package));
+    }
+    Err(format!("{} was not installed by a package manager", name))
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pkexec_changes_directory_before_setting_variables() {
+        let command = vec!["gparted".to_string(), "/dev/sda".to_string()];
+        let var = |variable: &str| match variable {
+            "DISPLAY" => Some(":0".to_string()),
+            "XDG_RUNTIME_DIR" => Some("/run/user/1000".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            pkexec_args(&command, Some(Path::new("/tmp/work dir")), var),
+            vec!["env", "--chdir=/tmp/work dir", "DISPLAY=:0", "XDG_RUNTIME_DIR=/run/user/1000", "gparted", "/dev/sda"],
+        );
+        assert_eq!(pkexec_args(&command, None, |_| None), vec!["env", "gparted", "/dev/sda"]);
+    }
+}
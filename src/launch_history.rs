@@ -0,0 +1,216 @@
+use crate::launcher::is_uri;
+use dirs::data_dir;
+use serde_json::{from_str, to_string_pretty};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+// Only the newest launches are kept per entry, older ones just count towards the total
+const MAX_TIMESTAMPS: usize = 10;
+const DAY: i64 = 24 * 60 * 60;
+
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone, Default)]
+pub struct HistoryEntry {
+    pub count: u32,
+    pub launches: Vec<i64>,
+}
+impl HistoryEntry {
+    pub fn last_launch(&self) -> i64 {
+        self.launches.last().copied().unwrap_or(0)
+    }
+}
+
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone, Default)]
+pub struct LaunchHistory {
+    entries: BTreeMap<String, HistoryEntry>,
+}
+impl LaunchHistory {
+    pub fn history_file() -> Option<PathBuf> {
+        data_dir().map(|data_dir| data_dir.join("Frostwin").join("Launch_History.json"))
+    }
+
+    pub fn load() -> Self {
+        match Self::history_file() {
+            Some(history_file) => match std::fs::read_to_string(&history_file) {
+                Ok(content) => match from_str::<LaunchHistory>(&content) {
+                    Ok(history) => history,
+                    Err(e) => {
+                        eprintln!("Error loading launch history: {:?}", e);
+                        Self::default()
+                    }
+                },
+                Err(e) => {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        eprintln!("Error opening launch history: {:?}", e);
+                    }
+                    Self::default()
+                }
+            },
+            None => Self::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        if let Some(history_file) = Self::history_file() {
+            if let Some(parent) = history_file.parent()
+                && let Err(e) = std::fs::create_dir_all(parent) {
+                eprintln!("Error creating the Frostwin data folder: {:?}", e);
+                return;
+            }
+            match to_string_pretty(self) {
+                Ok(history) => {
+                    if let Err(e) = std::fs::write(&history_file, history) {
+                        eprintln!("Error writing launch history: {:?}", e);
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error serializing launch history: {:?}", e);
+                }
+            }
+        }
+    }
+
+    pub fn record(&mut self, target: &str, now: i64) {
+        let entry = self.entries.entry(target.to_string()).or_default();
+        entry.count = entry.count.saturating_add(1);
+        entry.launches.push(now);
+        if entry.launches.len() > MAX_TIMESTAMPS {
+            let excess = entry.launches.len() - MAX_TIMESTAMPS;
+            entry.launches.drain(..excess);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Drops entries whose file is gone. URIs such as `ms-settings:` are kept.
+    pub fn prune(&mut self) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|target, _| is_uri(target) || PathBuf::from(target).exists());
+        before != self.entries.len()
+    }
+
+    /// Frecency in the style of Firefox: launch count times the average recency weight of the kept launches.
+    pub fn frecency(&self, target: &str, now: i64) -> f64 {
+        match self.entries.get(target) {
+            Some(entry) if !entry.launches.is_empty() => {
+                let total: f64 = entry.launches.iter().map(|launch| recency_weight(now - launch)).sum();
+                entry.count as f64 * total / entry.launches.len() as f64
+            }
+            _ => 0.0,
+        }
+    }
+
+    pub fn recent(&self, limit: usize) -> Vec<String> {
+        let mut entries: Vec<(&String, &HistoryEntry)> = self.entries.iter().collect();
+        entries.sort_by_key(|(_, entry)| std::cmp::Reverse(entry.last_launch()));
+        entries.into_iter().take(limit).map(|(target, _)| target.clone()).collect()
+    }
+
+    pub fn most_used(&self, limit: usize, now: i64) -> Vec<String> {
+        let mut entries: Vec<(&String, f64)> = self.entries.keys()
+            .map(|target| (target, self.frecency(target, now)))
+            .collect();
+        entries.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        entries.into_iter().take(limit).map(|(target, _)| target.clone()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn recency_weight(age: i64) -> f64 {
+    match age / DAY {
+        ..4 => 100.0,
+        4..14 => 70.0,
+        14..31 => 50.0,
+        31..90 => 30.0,
+        _ => 10.0,
+    }
+}
+
+pub fn now_timestamp() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn history(launches: &[(&str, &[i64])]) -> LaunchHistory {
+        let mut history = LaunchHistory::default();
+        for (target, times) in launches {
+            for time in times.iter() {
+                history.record(target, *time);
+            }
+        }
+        history
+    }
+
+    #[test]
+    fn weighs_launches_by_age() {
+        let cases = [
+            (0, 100.0), (4 * DAY - 1, 100.0), (4 * DAY, 70.0), (14 * DAY - 1, 70.0), (14 * DAY, 50.0),
+            (31 * DAY - 1, 50.0), (31 * DAY, 30.0), (90 * DAY - 1, 30.0), (90 * DAY, 10.0), (1000 * DAY, 10.0),
+        ];
+        for (age, weight) in cases {
+            assert_eq!(recency_weight(age), weight, "{}", age);
+        }
+    }
+
+    #[test]
+    fn scores_count_times_average_weight() {
+        let history = history(&[("/apps/editor", &[NOW - 20 * DAY, NOW - DAY]), ("/apps/old", &[NOW - 200 * DAY])]);
+        assert_eq!(history.frecency("/apps/editor", NOW), 2.0 * (50.0 + 100.0) / 2.0);
+        assert_eq!(history.frecency("/apps/old", NOW), 10.0);
+        assert_eq!(history.frecency("/apps/never", NOW), 0.0);
+        // The same launches count for less as they age
+        assert_eq!(history.frecency("/apps/editor", NOW + 20 * DAY), 2.0 * (30.0 + 50.0) / 2.0);
+    }
+
+    #[test]
+    fn keeps_only_the_newest_timestamps() {
+        let times: Vec<i64> = (0..15).map(|day| NOW - (15 - day) * DAY).collect();
+        let history = history(&[("/apps/editor", &times)]);
+        let entry = &history.entries["/apps/editor"];
+        assert_eq!(entry.count, 15);
+        assert_eq!(entry.launches, times[5..]);
+        assert_eq!(entry.last_launch(), NOW - DAY);
+    }
+
+    #[test]
+    fn lists_recent_and_most_used() {
+        let history = history(&[
+            ("/apps/b", &[NOW - 2 * DAY]),
+            ("/apps/a", &[NOW - 2 * DAY]),
+            ("/apps/c", &[NOW - 60 * DAY, NOW - 50 * DAY, NOW - 40 * DAY, NOW - 35 * DAY]),
+            ("/apps/d", &[NOW - 10]),
+        ]);
+        // Launches at the same time keep the targets' order
+        assert_eq!(history.recent(3), ["/apps/d", "/apps/a", "/apps/b"]);
+        // c has 4 * 30, the others 100 each, equal scores go alphabetically
+        assert_eq!(history.most_used(10, NOW), ["/apps/c", "/apps/a", "/apps/b", "/apps/d"]);
+        assert_eq!(history.most_used(1, NOW), ["/apps/c"]);
+        assert!(LaunchHistory::default().recent(5).is_empty());
+    }
+
+    #[test]
+    fn prunes_missing_files_but_not_uris() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("editor.desktop");
+        std::fs::write(&existing, "").unwrap();
+        let missing = dir.path().join("removed.desktop");
+        let (existing, missing) = (existing.to_string_lossy().to_string(), missing.to_string_lossy().to_string());
+        let mut history = history(&[(&existing, &[NOW]), (&missing, &[NOW]), ("ms-settings:home", &[NOW])]);
+        assert!(history.prune());
+        let mut targets: Vec<&String> = history.entries.keys().collect();
+        targets.sort();
+        assert_eq!(targets, [&existing, &"ms-settings:home".to_string()]);
+        assert!(!history.prune());
+        history.clear();
+        assert!(history.is_empty());
+    }
+}
@@ -0,0 +1,518 @@
+#[cfg(target_os = "linux")]
+use crate::desktop_entry::{application_dirs, current_desktops, current_locale, load_application, DesktopEntry};
+#[cfg(windows)]
+use crate::shell_link::ShellLink;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+// Children failing within this long of starting are reported, as they likely never showed a window
+const EARLY_EXIT: Duration = Duration::from_secs(5);
+
+/// Something the shell can start, from a start menu entry, a tile or the Run dialog.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LaunchTarget {
+    /// A `.lnk` file, started from its resolved target, arguments and working folder.
+    #[cfg(windows)]
+    Shortcut(PathBuf),
+    /// A `.desktop` file, started from its Exec line.
+    #[cfg(target_os = "linux")]
+    DesktopEntry(PathBuf),
+    Executable {
+        program: PathBuf,
+        args: Vec<String>,
+        working_dir: Option<PathBuf>,
+    },
+    /// A program and its arguments as one line, the way a shortcut or a custom start menu entry holds them.
+    CommandLine {
+        program: String,
+        arguments: String,
+        working_dir: Option<PathBuf>,
+    },
+    /// An address like `https:` or `ms-settings:`, opened by whatever handles its scheme.
+    Uri(String),
+    /// A file or folder, opened with its default program.
+    Document(PathBuf),
+}
+impl LaunchTarget {
+    /// Tells the kind of target from a path or address.
+    pub fn classify(target: &OsStr) -> Self {
+        let text = target.to_string_lossy();
+        if is_uri(&text) {
+            return LaunchTarget::Uri(text.to_string());
+        }
+        let path = PathBuf::from(target);
+        let extension = path.extension().map(|extension| extension.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            #[cfg(windows)]
+            Some("lnk") => LaunchTarget::Shortcut(path),
+            #[cfg(target_os = "linux")]
+            Some("desktop") => LaunchTarget::DesktopEntry(path),
+            _ if is_program(&path) => LaunchTarget::Executable { program: path, args: Vec::new(), working_dir: None },
+            _ => LaunchTarget::Document(path),
+        }
+    }
+}
+
+/// A started target. The process ID is missing when another program was asked to do the start,
+/// like the shell for a URI whose handler was already running.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Launched {
+    pub pid: Option<u32>,
+}
+
+/// Starts targets without going through Explorer or a shell.
+#[derive(Debug, Clone, Copy)]
+pub struct Launcher {
+    detach: bool,
+}
+impl Default for Launcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Launcher {
+    /// Children are detached by default, so they keep running when the shell is restarted.
+    pub fn new() -> Self {
+        Self { detach: true }
+    }
+
+    /// Whether children get a process group of their own instead of sharing the shell's.
+    pub fn detach(mut self, detach: bool) -> Self {
+        self.detach = detach;
+        self
+    }
+
+    pub fn launch(&self, target: &LaunchTarget) -> Result<Launched, String> {
+        match target {
+            #[cfg(windows)]
+            LaunchTarget::Shortcut(path) => self.launch_shortcut(path),
+            #[cfg(target_os = "linux")]
+            LaunchTarget::DesktopEntry(path) => {
+                let entry = DesktopEntry::from_path(path, String::new(), current_locale().as_deref())?;
+                self.launch_desktop_entry(&entry, &[])
+            }
+            LaunchTarget::Executable { program, args, working_dir } => {
+                let mut command = Command::new(program);
+                command.args(args);
+                if let Some(working_dir) = working_dir {
+                    command.current_dir(working_dir);
+                }
+                self.spawn(&mut command)
+            }
+            LaunchTarget::CommandLine { program, arguments, working_dir } => {
+                let program_path = Path::new(program);
+                // Without arguments this may just as well be an address, a document or another shortcut
+                if arguments.trim().is_empty() && (is_uri(program) || (program_path.exists() && !is_program(program_path))) {
+                    return self.launch(&LaunchTarget::classify(OsStr::new(program)));
+                }
+                self.launch_command_line(program, arguments, working_dir.as_deref())
+            }
+            LaunchTarget::Uri(uri) => self.open(uri),
+            LaunchTarget::Document(path) => {
+                if !path.exists() {
+                    return Err(format!("{} does not exist", path.display()));
+                }
+                self.open(&path.to_string_lossy())
+            }
+        }
+    }
+
+    /// Starts `command` with its own standard input, reaping it in the background once it exits.
+    pub fn spawn(&self, command: &mut Command) -> Result<Launched, String> {
+        let program = command.get_program().to_string_lossy().to_string();
+        command.stdin(Stdio::null());
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+            const CREATE_BREAKAWAY_FROM_JOB: u32 = 0x0100_0000;
+            if self.detach {
+                command.creation_flags(CREATE_NEW_PROCESS_GROUP | CREATE_BREAKAWAY_FROM_JOB);
+            }
+            let child = match command.spawn() {
+                // Leaving the job fails when the job does not allow it, the process group is still worth having
+                Err(e) if self.detach && e.raw_os_error() == Some(5) => command.creation_flags(CREATE_NEW_PROCESS_GROUP).spawn(),
+                result => result,
+            };
+            return child.map(|child| reap(program.clone(), child)).map_err(|e| format!("{}: {}", program, e));
+        }
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::unix::process::CommandExt;
+            if self.detach {
+                command.process_group(0);
+            }
+            command.spawn().map(|child| reap(program.clone(), child)).map_err(|e| format!("{}: {}", program, e))
+        }
+    }
+
+    /// Follows a shortcut to its program when it names one, so arguments and working folder apply.
+    /// Shortcuts to documents, folders and shell items are opened like documents.
+    #[cfg(windows)]
+    fn launch_shortcut(&self, path: &Path) -> Result<Launched, String> {
+        let link = ShellLink::from_path(path)?;
+        match link.resolved_target(path) {
+            Some(target) if is_program(&target) => {
+                use std::os::windows::process::CommandExt;
+                let mut command = Command::new(&target);
+                // Windows programs parse their own command line, so the arguments are passed as written
+                if let Some(arguments) = link.arguments.as_ref() && !arguments.is_empty() {
+                    command.raw_arg(arguments);
+                }
+                if let Some(working_dir) = link.resolved_working_dir() && working_dir.is_dir() {
+                    command.current_dir(working_dir);
+                }
+                self.spawn(&mut command)
+            }
+            _ => self.open(&path.to_string_lossy()),
+        }
+    }
+
+    /// Windows programs parse their own command line, so the arguments are passed as written.
+    /// Programs registered under App Paths and scripts are only found by the shell.
+    #[cfg(windows)]
+    fn launch_command_line(&self, program: &str, arguments: &str, working_dir: Option<&Path>) -> Result<Launched, String> {
+        use std::os::windows::process::CommandExt;
+        let mut command = Command::new(program);
+        if !arguments.is_empty() {
+            command.raw_arg(arguments);
+        }
+        if let Some(working_dir) = working_dir {
+            command.current_dir(working_dir);
+        }
+        self.spawn(&mut command).or_else(|_| self.shell_execute(program, Some(arguments), working_dir))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn launch_command_line(&self, program: &str, arguments: &str, working_dir: Option<&Path>) -> Result<Launched, String> {
+        let mut command = Command::new(program);
+        command.args(split_shell_words(arguments)?.into_iter().map(|word| word.text));
+        if let Some(working_dir) = working_dir {
+            command.current_dir(working_dir);
+        }
+        self.spawn(&mut command)
+    }
+
+    #[cfg(target_os = "linux")]
+    fn launch_desktop_entry(&self, entry: &DesktopEntry, files: &[String]) -> Result<Launched, String> {
+        let words = entry.command(files)?;
+        let mut command = Command::new(&words[0]);
+        command.args(&words[1..]);
+        if let Some(working_dir) = entry.working_dir.as_ref() {
+            command.current_dir(working_dir);
+        }
+        self.spawn(&mut command)
+    }
+
+    /// Opens a file, folder or URI with the program registered for it.
+    #[cfg(windows)]
+    fn open(&self, target: &str) -> Result<Launched, String> {
+        self.shell_execute(target, None, None)
+    }
+
+    /// Opens a file, folder or URI with the program registered for it, found through `xdg-mime`.
+    /// Falls back to `xdg-open` when no handler can be read.
+    #[cfg(target_os = "linux")]
+    fn open(&self, target: &str) -> Result<Launched, String> {
+        if let Some(entry) = default_handler(target) {
+            return self.launch_desktop_entry(&entry, &[target.to_string()]);
+        }
+        self.spawn(Command::new("xdg-open").arg(target))
+    }
+
+    /// Hands `file` to the shell, which knows about URI schemes, App Paths and file associations.
+    #[cfg(windows)]
+    pub fn shell_execute(&self, file: &str, parameters: Option<&str>, working_dir: Option<&Path>) -> Result<Launched, String> {
+        use windows::core::{w, HSTRING, PCWSTR};
+        use windows::Win32::Foundation::CloseHandle;
+        use windows::Win32::System::Threading::GetProcessId;
+        use windows::Win32::UI::Shell::{ShellExecuteExW, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW};
+        use windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+        let file_name = HSTRING::from(file);
+        let parameters = parameters.filter(|parameters| !parameters.is_empty()).map(HSTRING::from);
+        let directory = working_dir.map(|dir| HSTRING::from(dir.as_os_str()));
+        let mut info = SHELLEXECUTEINFOW {
+            cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+            fMask: SEE_MASK_NOCLOSEPROCESS,
+            lpVerb: w!("open"),
+            lpFile: PCWSTR(file_name.as_ptr()),
+            lpParameters: parameters.as_ref().map_or(PCWSTR::null(), |parameters| PCWSTR(parameters.as_ptr())),
+            lpDirectory: directory.as_ref().map_or(PCWSTR::null(), |directory| PCWSTR(directory.as_ptr())),
+            nShow: SW_SHOWNORMAL.0,
+            ..Default::default()
+        };
+        unsafe { ShellExecuteExW(&mut info) }.map_err(|e| format!("{}: {}", file, e.message()))?;
+        // No process handle comes back when an already running program took the request
+        if info.hProcess.is_invalid() {
+            return Ok(Launched { pid: None });
+        }
+        let pid = unsafe { GetProcessId(info.hProcess) };
+        unsafe {
+            let _ = CloseHandle(info.hProcess);
+        }
+        Ok(Launched { pid: if pid == 0 { None } else { Some(pid) } })
+    }
+}
+
+/// Waits for the child on a thread of its own, so it does not linger as a zombie after exiting.
+/// A child failing right after it started is reported with its process ID.
+fn reap(program: String, mut child: Child) -> Launched {
+    let pid = child.id();
+    let started = Instant::now();
+    std::thread::spawn(move || {
+        match child.wait() {
+            Ok(status) if !status.success() && started.elapsed() < EARLY_EXIT => {
+                eprintln!("Error: {} (process {}) exited right after starting with {}", program, pid, status);
+            }
+            Err(e) => {
+                eprintln!("Error waiting for {} (process {}): {}", program, pid, e);
+            }
+            _ => {}
+        }
+    });
+    Launched { pid: Some(pid) }
+}
+
+/// The system settings, opened by the start menu's settings button.
+#[cfg(windows)]
+pub fn settings_app() -> Option<OsString> {
+    Some("ms-settings:home".into())
+}
+
+/// The settings application of the running desktop, or of any other desktop that has one installed.
+#[cfg(target_os = "linux")]
+pub fn settings_app() -> Option<OsString> {
+    const SETTINGS_APPS: [(&str, &str); 6] = [
+        ("GNOME", "org.gnome.Settings.desktop"),
+        ("GNOME", "gnome-control-center.desktop"),
+        ("KDE", "systemsettings.desktop"),
+        ("XFCE", "xfce-settings-manager.desktop"),
+        ("X-Cinnamon", "cinnamon-settings.desktop"),
+        ("MATE", "mate-control-center.desktop"),
+    ];
+    let desktops = current_desktops();
+    let mut apps = SETTINGS_APPS.to_vec();
+    apps.sort_by_key(|(desktop, _)| !desktops.iter().any(|current| current == desktop));
+    let dirs = application_dirs();
+    apps.iter()
+        .find_map(|(_, id)| dirs.iter().map(|dir| dir.join(id)).find(|path| path.is_file()))
+        .map(PathBuf::into_os_string)
+}
+
+/// A `scheme:` prefix, which a drive letter like `C:` is not.
+pub fn is_uri(text: &str) -> bool {
+    match text.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.len() > 1
+                && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.')
+        }
+        None => false,
+    }
+}
+
+/// Whether `path` is a file that runs on its own rather than being opened by another program.
+pub fn is_program(path: &Path) -> bool {
+    #[cfg(windows)]
+    return path.is_file() && path.extension().is_some_and(|extension| {
+        ["exe", "com", "bat", "cmd"].contains(&extension.to_string_lossy().to_lowercase().as_str())
+    });
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::metadata(path).is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+    }
+}
+
+/// The application registered for a file's MIME type or a URI's scheme.
+#[cfg(target_os = "linux")]
+fn default_handler(target: &str) -> Option<DesktopEntry> {
+    let mime_type = if is_uri(target) && !target.starts_with("file:") {
+        format!("x-scheme-handler/{}", target.split(':').next()?.to_lowercase())
+    } else if Path::new(target).is_dir() {
+        "inode/directory".to_string()
+    } else {
+        xdg_mime(&["query", "filetype", target])?
+    };
+    let desktop_id = xdg_mime(&["query", "default", &mime_type])?;
+    let locale = current_locale();
+    load_application(Path::new(&desktop_id), &application_dirs(), locale.as_deref(), &current_desktops())
+}
+
+#[cfg(target_os = "linux")]
+fn xdg_mime(args: &[&str]) -> Option<String> {
+    let output = Command::new("xdg-mime").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    // Several handlers may be listed, the first one is the default
+    let answer = String::from_utf8_lossy(&output.stdout).split(';').next()?.trim().to_string();
+    if answer.is_empty() { None } else { Some(answer) }
+}
+
+/// A word of a command line, `quoted` when it starts inside quotes or with an escape.
+#[cfg(target_os = "linux")]
+#[derive(Debug, PartialEq)]
+pub struct ShellWord {
+    pub text: String,
+    pub quoted: bool,
+}
+
+/// Splits a command line into words like a POSIX shell: whitespace separates words, single quotes
+/// keep everything literal, double quotes allow `\"`, `\\`, `\$` and `` \` `` escapes, and a backslash
+/// outside quotes escapes the next character. Variables and globs are not expanded.
+#[cfg(target_os = "linux")]
+pub fn split_shell_words(command_line: &str) -> Result<Vec<ShellWord>, String> {
+    let mut words: Vec<ShellWord> = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    let mut chars = command_line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(ShellWord { text: std::mem::take(&mut word), quoted });
+                    in_word = false;
+                    quoted = false;
+                }
+            }
+            '\'' => {
+                // Only quoting at the start of a word keeps a `~` there literal
+                quoted |= !in_word;
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err("Missing closing single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                quoted |= !in_word;
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err("Missing closing double quote".to_string()),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err("Missing closing double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                quoted |= !in_word;
+                in_word = true;
+                match chars.next() {
+                    Some('\n') => {}
+                    Some(c) => word.push(c),
+                    None => word.push('\\'),
+                }
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        words.push(ShellWord { text: word, quoted });
+    }
+    Ok(words)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_addresses_from_drive_letters() {
+        assert!(is_uri("https://example.com"));
+        assert!(is_uri("ms-settings:home"));
+        assert!(is_uri("svn+ssh://host/repo"));
+        assert!(!is_uri("C:\\foo"));
+        assert!(!is_uri("c:/foo"));
+        assert!(!is_uri("1http://example.com"));
+        assert!(!is_uri("/usr/bin/vim"));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn programs_need_the_execute_bit() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let script = dir.path().join("tool");
+        std::fs::write(&script, "#!/bin/sh\n").unwrap();
+        assert!(!is_program(&script));
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(is_program(&script));
+        assert!(!is_program(dir.path()));
+        assert!(!is_program(&dir.path().join("missing")));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn classifies_targets() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let program = dir.path().join("tool");
+        std::fs::write(&program, "#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let document = dir.path().join("notes.txt");
+        std::fs::write(&document, "").unwrap();
+
+        assert_eq!(LaunchTarget::classify(OsStr::new("https://example.com")), LaunchTarget::Uri("https://example.com".to_string()));
+        assert_eq!(LaunchTarget::classify(OsStr::new("/usr/share/applications/firefox.desktop")),
+            LaunchTarget::DesktopEntry(PathBuf::from("/usr/share/applications/firefox.desktop")));
+        assert_eq!(LaunchTarget::classify(program.as_os_str()),
+            LaunchTarget::Executable { program: program.clone(), args: Vec::new(), working_dir: None });
+        assert_eq!(LaunchTarget::classify(document.as_os_str()), LaunchTarget::Document(document.clone()));
+        assert_eq!(LaunchTarget::classify(dir.path().as_os_str()), LaunchTarget::Document(dir.path().to_path_buf()));
+    }
+
+    #[cfg(target_os = "linux")]
+    fn words(command_line: &str) -> Vec<(String, bool)> {
+        split_shell_words(command_line).unwrap().into_iter().map(|word| (word.text, word.quoted)).collect()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn splits_like_a_shell() {
+        assert_eq!(words("  vim   notes.txt "), [("vim".to_string(), false), ("notes.txt".to_string(), false)]);
+        assert_eq!(words(r#"echo 'a  b' "c \"d\" \$e \x" f\ g"#), [
+            ("echo".to_string(), false),
+            ("a  b".to_string(), true),
+            (r#"c "d" $e \x"#.to_string(), true),
+            ("f g".to_string(), false),
+        ]);
+        // Quotes in the middle of a word join it, and only a leading quote marks the word as quoted
+        assert_eq!(words(r#"pre'fix'"ed" \~ '~'"#), [
+            ("prefixed".to_string(), false),
+            ("~".to_string(), true),
+            ("~".to_string(), true),
+        ]);
+        assert_eq!(words("''"), [(String::new(), true)]);
+        assert_eq!(words("trailing\\"), [("trailing\\".to_string(), false)]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn reports_unclosed_quotes() {
+        assert!(split_shell_words("echo 'open").is_err());
+        assert!(split_shell_words("echo \"open").is_err());
+        assert!(split_shell_words("echo \"open\\").is_err());
+    }
+}
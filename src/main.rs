@@ -7,20 +7,81 @@ mod panel_menu;
 mod styles;
 mod sys_util;
 mod power_window;
+#[cfg(windows)]
 mod windows_icons;
 mod raw_icons;
+// Only Windows start menu folders hold shortcuts, the parser builds everywhere so its tests run on any platform
+#[cfg_attr(not(windows), allow(dead_code))]
+mod shell_link;
+mod launch_history;
+mod start_settings;
+// Packaged apps only exist on Windows, the manifest parser builds everywhere so its tests run on any platform
+#[cfg_attr(not(windows), allow(dead_code))]
+mod appx;
+mod item_actions;
+mod programs_watcher;
+mod global_keys;
+mod places;
+mod entry_editor;
+mod calculator;
+mod run_dialog;
+mod launcher;
+mod start_layout;
+mod wifi;
+mod brightness;
+mod mixer;
+mod audio_devices;
+mod bluetooth;
+mod media;
+mod shell_settings;
+mod do_not_disturb;
+mod notifications;
+mod toasts;
+mod tray;
+mod tray_menu;
+#[cfg(target_os = "linux")]
+mod desktop_entry;
+#[cfg(target_os = "linux")]
+mod icon_theme;
+#[cfg(target_os = "linux")]
+mod network_manager;
+#[cfg(target_os = "linux")]
+mod pulse;
+#[cfg(target_os = "linux")]
+mod mpris;
+#[cfg(target_os = "linux")]
+mod bluez;
+#[cfg(target_os = "linux")]
+mod notification_server;
+#[cfg(all(test, target_os = "linux"))]
+mod test_bus;
+#[cfg(target_os = "linux")]
+mod status_notifier;
 
 use crate::desktop::{Desktop, DesktopMessage};
+use crate::do_not_disturb::{DoNotDisturb, DoNotDisturbChange};
+use crate::global_keys::GlobalKey;
+use crate::media::{MediaCommand, MediaState, NowPlaying};
+use crate::notifications::{CloseReason, Notification, NotificationEvent, NotificationReplies, NotificationReply, Urgency};
 use crate::panel_menu::{PanelMenu, PanelMessage};
 use crate::power_window::{PowerMenuMessage, PowerOptions, PowerWindow};
-use crate::start_menu::{StartMenu, StartMessage};
-use crate::sys_util::{get_battery_info, get_sound_state, get_wifi_status, set_sound_state, WifiStatus};
+use crate::programs_watcher::ProgramsChange;
+use crate::run_dialog::{run_key, RunDialog, RunMessage};
+use crate::shell_settings::ShellSettings;
+use crate::start_menu::{navigation_key, StartCatalog, StartMenu, StartMessage};
+use crate::sys_util::{get_battery_info, SystemStatus, WifiStatus};
+#[cfg(windows)]
+use crate::sys_util::{get_sound_state, get_wifi_status, set_sound_state};
 use crate::taskbar::{Taskbar, TaskbarMessage};
+use crate::toasts::Toasts;
+use crate::tray::{TrayCommand, TrayItem};
+use crate::tray_menu::{TrayMenu, TrayMenuMessage};
+use chrono::Local;
 use dirs::data_dir;
-use iced::time::{self, milliseconds};
+use iced::time::{self, milliseconds, seconds};
 use iced::widget::column;
 use iced::widget::image::Handle;
-use iced::{window, Size, Subscription, Task};
+use iced::{event, window, Size, Subscription, Task};
 use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
@@ -28,6 +89,10 @@ use std::time::Instant;
 use crate::raw_icons::{load_frostwin_icons, unpack_missing_icons};
 
 fn main() -> iced::Result {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = start_layout::handle_arguments(&args) {
+        std::process::exit(code);
+    }
     iced::daemon::daemon(AppMain::new, AppMain::update, AppMain::view)
         .subscription(AppMain::subscription)
         .title(AppMain::title)
@@ -46,14 +111,43 @@ pub enum Message {
     MetaPressed,
     OpenPanelMenu,
     OpenPowerWindow(PowerOptions),
+    OpenRunDialog,
     Taskbar(TaskbarMessage),
     Desktop(DesktopMessage),
     StartMenu(StartMessage),
     PanelMenu(PanelMessage),
     PowerMenu(PowerMenuMessage),
+    RunDialog(RunMessage),
     VolumeChange(f32),
     VolumeMute,
+    MicrophoneInUse(bool),
+    MediaChanged(Option<MediaState>),
+    MediaControl(MediaCommand),
+    MediaDone(Result<(), String>),
+    TaskbarMedia(bool),
+    DoNotDisturb(DoNotDisturbChange),
+    Notification(NotificationEvent),
+    NotificationAction(u32, String),
+    NotificationDismiss(u32),
+    /// Removes a notification from the history.
+    NotificationForget(u32),
+    NotificationsClear,
+    ToastScreen(Option<Size>),
+    TrayChanged(Vec<TrayItem>),
+    TrayActivate(String),
+    TrayCommand(String, TrayCommand),
+    OpenTrayMenu(String),
+    TrayMenu(TrayMenuMessage),
+    TrayDone(Result<(), String>),
     ResizeContext(Size),
+    /// The catalog read or updated in the background, `None` when the changes left it as it was.
+    StartCatalogLoaded(Option<StartCatalog>),
+    ProgramsChanged(ProgramsChange),
+    #[cfg(target_os = "linux")]
+    WifiStatus(WifiStatus),
+    /// Volume and mute of the default output.
+    #[cfg(target_os = "linux")]
+    SoundState((f32, bool)),
     Tick(Instant),
     None,
 }
@@ -62,13 +156,31 @@ struct AppMain {
     taskbar: Taskbar,
     desktop: Desktop,
     start_menu: Option<StartMenu>,
+    start_catalog: Arc<StartCatalog>,
+    // Whether the catalog is being read or updated in the background, changes arriving meanwhile wait for it
+    start_catalog_busy: bool,
+    programs_changes: Vec<ProgramsChange>,
     panel_menu: Option<PanelMenu>,
     power_window: Option<PowerWindow>,
+    run_dialog: Option<RunDialog>,
     battery: Option<(f32,bool)>,
     base_size: f32,
     wifi_status: WifiStatus,
     system_volume: f32,
     volume_muted: bool,
+    // Whether another application is recording, shown on the taskbar
+    microphone_in_use: bool,
+    now_playing: Option<NowPlaying>,
+    shell_settings: ShellSettings,
+    // Worked out again on every tick, as schedules and fullscreen windows turn it on and off
+    do_not_disturb: DoNotDisturb,
+    // Set once the notification server is running
+    notification_replies: Option<NotificationReplies>,
+    toasts: Toasts,
+    // Dismissed, expired and held back notifications, newest first
+    notification_history: Vec<Notification>,
+    tray_items: Vec<TrayItem>,
+    tray_menu: Option<TrayMenu>,
 }
 impl AppMain {
     pub fn new() -> (Self,Task<Message>) {
@@ -88,40 +200,49 @@ impl AppMain {
                     }
                 }
             }
-            match unpack_missing_icons(data_folder.clone()) {
-                Err(e) => {
-                    eprintln!("Error: Unpacking default icons: {}", e);
-                }
-                _ => {}
+            if let Err(e) = unpack_missing_icons(data_folder.clone()) {
+                eprintln!("Error: Unpacking default icons: {}", e);
             };
-            match load_frostwin_icons(&data_folder, app_image_cache.clone()) {
-                Err(e) => {
-                    eprintln!("Error: Loading system images: {}", e);
-                }
-                _ => {}
+            if let Err(e) = load_frostwin_icons(&data_folder, app_image_cache.clone()) {
+                eprintln!("Error: Loading system images: {}", e);
             };
         } else {
             eprintln!("Error: Could not determine the system data directory.");
         }
         let (desktop,open_desktop) = Desktop::new();
         let (taskbar,open_taskbar) = Taskbar::new();
+        let load_start_catalog = StartCatalog::load_in_background(app_image_cache.clone());
         (
             Self {
                 app_image_cache,
                 taskbar,
                 desktop,
                 start_menu: None,
+                start_catalog: Arc::default(),
+                start_catalog_busy: true,
+                programs_changes: Vec::new(),
                 panel_menu: None,
                 power_window: None,
+                run_dialog: None,
                 battery: None,
                 base_size: 1.0,
                 wifi_status: WifiStatus::Disconnected,
                 system_volume: 0.0,
                 volume_muted: false,
+                microphone_in_use: false,
+                now_playing: None,
+                shell_settings: ShellSettings::load(),
+                do_not_disturb: DoNotDisturb::default(),
+                notification_replies: None,
+                toasts: Toasts::default(),
+                notification_history: Vec::new(),
+                tray_items: Vec::new(),
+                tray_menu: None,
             },
             Task::batch([
                 open_taskbar.map(Message::WindowOpened),
-                open_desktop.map(Message::WindowOpened)
+                open_desktop.map(Message::WindowOpened),
+                load_start_catalog.map(|catalog| Message::StartCatalogLoaded(Some(catalog))),
             ])
         )
     }
@@ -133,11 +254,17 @@ impl AppMain {
                 } else if id == self.desktop.id {
                     self.desktop.update(DesktopMessage::Init)
                 } else if let Some(start_menu) = self.start_menu.as_mut() && id == start_menu.id {
-                    start_menu.update(StartMessage::Init(self.app_image_cache.clone()))
+                    start_menu.update(StartMessage::Init(self.start_catalog.clone()))
                 } else if let Some(panel_menu) = self.panel_menu.as_mut() && id == panel_menu.id {
                     panel_menu.update(PanelMessage::Init)
                 } else if let Some(power_window) = self.power_window.as_mut() && id == power_window.id {
                     power_window.update(PowerMenuMessage::Init)
+                } else if let Some(run_dialog) = self.run_dialog.as_mut() && id == run_dialog.id {
+                    run_dialog.update(RunMessage::Init)
+                } else if let Some(tray_menu) = self.tray_menu.as_mut() && id == tray_menu.id {
+                    tray_menu.update(TrayMenuMessage::Init)
+                } else if self.toasts.contains(id) {
+                    self.toasts.opened(id)
                 } else {
                     Task::none()
                 }
@@ -160,6 +287,12 @@ impl AppMain {
                 } else if let Some(power_window) = self.power_window.as_ref() && id == power_window.id {
                     self.power_window = None;
                     Task::none()
+                } else if let Some(run_dialog) = self.run_dialog.as_ref() && id == run_dialog.id {
+                    self.run_dialog = None;
+                    Task::none()
+                } else if let Some(tray_menu) = self.tray_menu.as_ref() && id == tray_menu.id {
+                    self.tray_menu = None;
+                    Task::none()
                 } else {
                     Task::none()
                 }
@@ -179,6 +312,10 @@ impl AppMain {
                     window::close(panel_menu.id).map(Message::WindowClosed)
                 } else if let Some(power_window) = self.power_window.as_mut() && id == power_window.id {
                     power_window.update(PowerMenuMessage::Cancel)
+                } else if let Some(run_dialog) = self.run_dialog.as_ref() && id == run_dialog.id {
+                    window::close(run_dialog.id).map(Message::WindowClosed)
+                } else if let Some(tray_menu) = self.tray_menu.as_ref() && id == tray_menu.id {
+                    window::close(tray_menu.id).map(Message::WindowClosed)
                 } else {
                     Task::none()
                 }
@@ -197,6 +334,13 @@ impl AppMain {
                 self.start_menu = Some(start_menu);
                 open_start_menu.map(Message::WindowOpened)
             }
+            Message::MetaPressed => {
+                if let Some(start_menu) = self.start_menu.as_ref() {
+                    window::close(start_menu.id).map(Message::WindowClosed)
+                } else {
+                    self.update(Message::OpenStartMenu)
+                }
+            }
             Message::OpenPanelMenu => {
                 let (panel_menu,open_panel_menu) = PanelMenu::new();
                 self.panel_menu = Some(panel_menu);
@@ -207,6 +351,22 @@ impl AppMain {
                 self.power_window = Some(power_window);
                 open_power_window.map(Message::WindowOpened)
             }
+            Message::OpenRunDialog => {
+                if let Some(run_dialog) = self.run_dialog.as_ref() {
+                    window::gain_focus(run_dialog.id)
+                } else {
+                    let (run_dialog,open_run_dialog) = RunDialog::new();
+                    self.run_dialog = Some(run_dialog);
+                    open_run_dialog.map(Message::WindowOpened)
+                }
+            }
+            Message::RunDialog(message) => {
+                if let Some(run_dialog) = self.run_dialog.as_mut() {
+                    run_dialog.update(message)
+                } else {
+                    Task::none()
+                }
+            }
             Message::StartMenu(message) => {
                 if let Some(start_menu) = self.start_menu.as_mut() {
                     start_menu.update(message)
@@ -228,12 +388,170 @@ impl AppMain {
                     Task::none()
                 }
             }
+            Message::StartCatalogLoaded(catalog) => {
+                self.start_catalog_busy = false;
+                let mut tasks = Vec::new();
+                if let Some(catalog) = catalog {
+                    self.start_catalog = Arc::new(catalog);
+                    if let Some(start_menu) = self.start_menu.as_mut() {
+                        tasks.push(start_menu.update(StartMessage::CatalogUpdated(self.start_catalog.clone())));
+                    }
+                }
+                tasks.push(self.update_start_catalog());
+                Task::batch(tasks)
+            }
+            Message::ProgramsChanged(change) => {
+                self.programs_changes.push(change);
+                self.update_start_catalog()
+            }
             Message::VolumeChange(value) => {
-                set_sound_state(value, self.volume_muted).unwrap_or_default();
-                Task::none()
+                self.set_sound(value, self.volume_muted)
             }
             Message::VolumeMute => {
-                set_sound_state(self.system_volume, !self.volume_muted).unwrap_or_default();
+                self.set_sound(self.system_volume, !self.volume_muted)
+            }
+            #[cfg(target_os = "linux")]
+            Message::WifiStatus(status) => {
+                self.wifi_status = status;
+                Task::none()
+            }
+            #[cfg(target_os = "linux")]
+            Message::SoundState((volume, muted)) => {
+                (self.system_volume, self.volume_muted) = (volume, muted);
+                Task::none()
+            }
+            Message::MicrophoneInUse(in_use) => {
+                self.microphone_in_use = in_use;
+                Task::none()
+            }
+            Message::MediaChanged(state) => {
+                self.now_playing = state.map(NowPlaying::new);
+                global_keys::set_media_session(self.now_playing.is_some());
+                Task::none()
+            }
+            Message::MediaControl(command) => {
+                let Some(now_playing) = self.now_playing.as_mut() else {
+                    return Task::none();
+                };
+                // Shown right away, the player reports back once it has caught up
+                match command {
+                    MediaCommand::PlayPause => {
+                        now_playing.state.position = now_playing.position();
+                        now_playing.state.playing = !now_playing.state.playing;
+                        now_playing.since = Instant::now();
+                    }
+                    MediaCommand::SetPosition(position) => {
+                        now_playing.state.position = position;
+                        now_playing.since = Instant::now();
+                    }
+                    _ => {}
+                }
+                media::control(&now_playing.state.player, command).map(Message::MediaDone)
+            }
+            Message::MediaDone(result) => {
+                if let Err(e) = result {
+                    eprintln!("Error controlling media player: {}", e);
+                }
+                Task::none()
+            }
+            Message::TaskbarMedia(enabled) => {
+                self.shell_settings.taskbar_media = enabled;
+                self.shell_settings.save();
+                Task::none()
+            }
+            Message::DoNotDisturb(change) => {
+                self.shell_settings.do_not_disturb.apply(change);
+                self.shell_settings.save();
+                self.update_do_not_disturb();
+                Task::none()
+            }
+            Message::Notification(NotificationEvent::Started(replies)) => {
+                self.notification_replies = Some(replies);
+                Task::none()
+            }
+            Message::Notification(NotificationEvent::Notify(notification)) => {
+                self.notification_history.retain(|old| old.id != notification.id);
+                // Held back notifications go straight to the history, where they can be read once it is quiet again
+                let allowed = notification.urgency == Urgency::Critical
+                    || self.do_not_disturb.allows(&notification.app_name)
+                    || notification.desktop_entry.as_ref().is_some_and(|entry| self.do_not_disturb.allows(entry));
+                if !allowed {
+                    self.add_to_history(*notification);
+                    return Task::none();
+                }
+                let (task, overflow) = self.toasts.show(*notification);
+                if let Some(overflow) = overflow {
+                    self.add_to_history(overflow);
+                }
+                task
+            }
+            Message::Notification(NotificationEvent::Close(id)) => {
+                let (task, toast) = self.toasts.remove(id);
+                let in_history = self.notification_history.iter().any(|notification| notification.id == id);
+                self.notification_history.retain(|notification| notification.id != id);
+                if toast.is_some() || in_history {
+                    self.reply_notification(NotificationReply::Closed(id, CloseReason::ClosedByCall));
+                }
+                task
+            }
+            Message::NotificationAction(id, action) => {
+                // The application takes it from here, so there is nothing left to keep
+                let (task, _) = self.toasts.remove(id);
+                self.reply_notification(NotificationReply::ActionInvoked(id, action));
+                self.reply_notification(NotificationReply::Closed(id, CloseReason::Dismissed));
+                task
+            }
+            Message::NotificationDismiss(id) => {
+                self.close_toast(id, CloseReason::Dismissed)
+            }
+            Message::NotificationForget(id) => {
+                self.notification_history.retain(|notification| notification.id != id);
+                Task::none()
+            }
+            Message::NotificationsClear => {
+                self.notification_history.clear();
+                Task::none()
+            }
+            Message::ToastScreen(size) => {
+                self.toasts.set_screen(size)
+            }
+            Message::TrayChanged(items) => {
+                self.tray_items = items;
+                Task::none()
+            }
+            Message::TrayActivate(id) => {
+                // Items that are only a menu want it opened on a left click too
+                match self.tray_items.iter().find(|item| item.id == id) {
+                    Some(item) if item.item_is_menu && item.menu.is_some() => self.update(Message::OpenTrayMenu(id)),
+                    _ => self.update(Message::TrayCommand(id, TrayCommand::Activate)),
+                }
+            }
+            Message::TrayCommand(id, command) => {
+                tray::command(&id, command).map(Message::TrayDone)
+            }
+            Message::OpenTrayMenu(id) => {
+                let Some(menu) = self.tray_items.iter().find(|item| item.id == id).and_then(|item| item.menu.clone()) else {
+                    return self.update(Message::TrayCommand(id, TrayCommand::ContextMenu));
+                };
+                let close_task = match self.tray_menu.as_ref() {
+                    Some(tray_menu) => window::close(tray_menu.id).map(Message::WindowClosed),
+                    None => Task::none(),
+                };
+                let (tray_menu, open_tray_menu) = TrayMenu::new(&id, &menu);
+                self.tray_menu = Some(tray_menu);
+                Task::batch([close_task, open_tray_menu.map(Message::WindowOpened)])
+            }
+            Message::TrayMenu(message) => {
+                if let Some(tray_menu) = self.tray_menu.as_mut() {
+                    tray_menu.update(message)
+                } else {
+                    Task::none()
+                }
+            }
+            Message::TrayDone(result) => {
+                if let Err(e) = result {
+                    eprintln!("Error using tray item: {}", e);
+                }
                 Task::none()
             }
             Message::Tick(_) => {
@@ -246,25 +564,92 @@ impl AppMain {
                         None
                     }
                 };
-                self.wifi_status = get_wifi_status();
-                (self.system_volume, self.volume_muted) = match get_sound_state() {
-                    Ok(data) => {
-                        data
-                    }
-                    Err(e) => {
-                        eprintln!("Error getting volume data: {}", e);
-                        (0.0, false)
-                    }
-                };
+                self.update_do_not_disturb();
+                // Linux follows the Wi-Fi status and the volume through subscriptions
+                #[cfg(windows)]
+                {
+                    self.wifi_status = get_wifi_status();
+                    (self.system_volume, self.volume_muted) = match get_sound_state() {
+                        Ok(data) => {
+                            data
+                        }
+                        Err(e) => {
+                            eprintln!("Error getting volume data: {}", e);
+                            (0.0, false)
+                        }
+                    };
+                }
                 let power_task = if let Some(power_window) = self.power_window.as_mut() {
                     power_window.update(PowerMenuMessage::Tick)
                 } else {Task::none()};
                 let taskbar_task = self.taskbar.update(TaskbarMessage::Tick);
-                Task::batch(vec![power_task,taskbar_task])
+                let mut tasks = vec![power_task,taskbar_task];
+                for id in self.toasts.expired() {
+                    tasks.push(self.close_toast(id, CloseReason::Expired));
+                }
+                Task::batch(tasks)
             }
             _ => Task::none()
         }
     }
+    /// Takes a toast down, keeping its notification in the history and telling the application why it went.
+    fn close_toast(&mut self, id: u32, reason: CloseReason) -> Task<Message> {
+        let (task, toast) = self.toasts.remove(id);
+        if let Some(notification) = toast {
+            self.add_to_history(notification);
+            self.reply_notification(NotificationReply::Closed(id, reason));
+        }
+        task
+    }
+    fn add_to_history(&mut self, notification: Notification) {
+        self.notification_history.insert(0, notification);
+        self.notification_history.truncate(notifications::HISTORY_LIMIT);
+    }
+    fn reply_notification(&self, reply: NotificationReply) {
+        if let Some(replies) = self.notification_replies.as_ref() {
+            replies.send(reply);
+        }
+    }
+    fn update_do_not_disturb(&mut self) {
+        self.do_not_disturb = DoNotDisturb::evaluate(&self.shell_settings.do_not_disturb, Local::now().naive_local(), self.taskbar.full_screen_window());
+    }
+    /// Applies the program folder changes gathered so far in the background. Only one batch is worked on at a time,
+    /// so none is applied to a catalog that is about to be replaced.
+    fn update_start_catalog(&mut self) -> Task<Message> {
+        if self.start_catalog_busy || self.programs_changes.is_empty() {
+            return Task::none();
+        }
+        self.start_catalog_busy = true;
+        let changes = std::mem::take(&mut self.programs_changes);
+        StartCatalog::apply_in_background(self.start_catalog.clone(), changes, self.app_image_cache.clone()).map(Message::StartCatalogLoaded)
+    }
+    /// Sets the default output's volume. pactl takes a moment to answer, so on Linux it is asked in the background.
+    fn set_sound(&mut self, level: f32, mute: bool) -> Task<Message> {
+        // Shown right away, the next read confirms it
+        (self.system_volume, self.volume_muted) = (level.clamp(0.0, 1.0), mute);
+        #[cfg(windows)]
+        set_sound_state(level, mute).unwrap_or_default();
+        #[cfg(windows)]
+        return Task::none();
+        #[cfg(target_os = "linux")]
+        return mixer::set_output(level, mute).map(|result| {
+            if let Err(e) = result {
+                eprintln!("Error setting the volume: {}", e);
+            }
+            Message::None
+        });
+    }
+    fn system_status(&self) -> SystemStatus<'_> {
+        SystemStatus {
+            battery: self.battery,
+            wifi_status: self.wifi_status.clone(),
+            system_volume: self.system_volume,
+            volume_muted: self.volume_muted,
+            microphone_in_use: self.microphone_in_use,
+            now_playing: self.now_playing.as_ref(),
+            do_not_disturb: self.do_not_disturb.reason(),
+        }
+    }
     pub fn view(&self, window_id: window::Id) -> iced::Element<'_, Message> {
         if window_id == self.taskbar.id {
             let start_state = self.start_menu.is_some();
@@ -274,18 +659,25 @@ impl AppMain {
                 start_state,
                 panel_state,
                 self.base_size,
-                self.battery,
-                self.wifi_status.clone(),
-                self.system_volume,
-                self.volume_muted)
+                SystemStatus {
+                    now_playing: self.now_playing.as_ref().filter(|_| self.shell_settings.taskbar_media),
+                    ..self.system_status()
+                },
+                &self.tray_items)
         } else if window_id == self.desktop.id {
             self.desktop.view()
         } else if let Some(start_menu) = self.start_menu.as_ref() && window_id == start_menu.id {
             start_menu.view(self.app_image_cache.clone(),self.base_size)
         } else if let Some(panel) = self.panel_menu.as_ref() && window_id == panel.id {
-            panel.view(self.app_image_cache.clone(),self.base_size,self.battery,self.wifi_status.clone(),self.system_volume,self.volume_muted)
+            panel.view(self.app_image_cache.clone(),self.taskbar.process_icons(),self.base_size,self.system_status(),&self.shell_settings,&self.notification_history)
         } else if let Some(power_window) = self.power_window.as_ref() && window_id == power_window.id {
             power_window.view(self.app_image_cache.clone())
+        } else if let Some(run_dialog) = self.run_dialog.as_ref() && window_id == run_dialog.id {
+            run_dialog.view(self.base_size)
+        } else if let Some(tray_menu) = self.tray_menu.as_ref() && window_id == tray_menu.id {
+            tray_menu.view(self.base_size)
+        } else if let Some(toast) = self.toasts.view(window_id, self.base_size) {
+            toast
         } else {
             column![].into()
         }
@@ -305,6 +697,72 @@ impl AppMain {
         subscriptions.push(
             time::every(milliseconds(100)).map(Message::Tick)
         );
+        subscriptions.push(
+            programs_watcher::watch().map(Message::ProgramsChanged)
+        );
+        subscriptions.push(
+            global_keys::listen().map(|key| match key {
+                GlobalKey::Meta => Message::MetaPressed,
+                GlobalKey::Run => Message::OpenRunDialog,
+                GlobalKey::MediaPlayPause => Message::MediaControl(MediaCommand::PlayPause),
+                GlobalKey::MediaNext => Message::MediaControl(MediaCommand::Next),
+                GlobalKey::MediaPrevious => Message::MediaControl(MediaCommand::Previous),
+            })
+        );
+        #[cfg(target_os = "linux")]
+        subscriptions.push(
+            wifi::status_updates().map(Message::WifiStatus)
+        );
+        #[cfg(target_os = "linux")]
+        subscriptions.push(
+            mixer::watch_output().map(Message::SoundState)
+        );
+        subscriptions.push(
+            audio_devices::watch_microphone().map(Message::MicrophoneInUse)
+        );
+        subscriptions.push(
+            media::watch().map(Message::MediaChanged)
+        );
+        subscriptions.push(
+            notifications::serve().map(Message::Notification)
+        );
+        subscriptions.push(
+            tray::watch().map(Message::TrayChanged)
+        );
+        if self.start_menu.is_some() {
+            subscriptions.push(
+                event::listen_with(navigation_key)
+            );
+        }
+        if self.run_dialog.is_some() {
+            subscriptions.push(
+                event::listen_with(run_key)
+            );
+        }
+        if let Some(panel_menu) = self.panel_menu.as_ref() {
+            subscriptions.push(
+                time::every(seconds(10)).map(|_| Message::PanelMenu(PanelMessage::WifiRefresh))
+            );
+            if let Some(microphone) = panel_menu.microphone() {
+                subscriptions.push(
+                    audio_devices::level_meter(microphone).map(|level| Message::PanelMenu(PanelMessage::InputLevel(level)))
+                );
+            }
+            if panel_menu.bluetooth_scanning {
+                subscriptions.push(
+                    bluetooth::discover().map(|state| Message::PanelMenu(PanelMessage::BluetoothLoaded(Ok(state))))
+                );
+            } else if panel_menu.bluetooth_open {
+                subscriptions.push(
+                    time::every(seconds(10)).map(|_| Message::PanelMenu(PanelMessage::BluetoothRefresh))
+                );
+            }
+            if panel_menu.mixer_open {
+                subscriptions.push(
+                    mixer::watch().map(|_| Message::PanelMenu(PanelMessage::StreamsChanged))
+                );
+            }
+        }
         Subscription::batch(subscriptions)
     }
     pub fn title(&self, window_id: window::Id) -> String {
@@ -316,6 +774,12 @@ impl AppMain {
             "FrostWin StartMenu".to_string()
         } else if let Some(panel) = self.panel_menu.as_ref() && window_id == panel.id {
             "FrostWin PanelMenu".to_string()
+        } else if let Some(run_dialog) = self.run_dialog.as_ref() && window_id == run_dialog.id {
+            "FrostWin Run".to_string()
+        } else if let Some(tray_menu) = self.tray_menu.as_ref() && window_id == tray_menu.id {
+            "FrostWin TrayMenu".to_string()
+        } else if self.toasts.contains(window_id) {
+            "FrostWin Notification".to_string()
         } else {
             "FrostWin Unknown".to_string()
         }
@@ -0,0 +1,260 @@
+#[cfg(target_os = "linux")]
+use crate::mpris::Mpris;
+use iced::futures::channel::{mpsc, oneshot};
+use iced::futures::{SinkExt, Stream, StreamExt};
+use iced::widget::image::Handle;
+use iced::{Subscription, Task};
+use std::time::{Duration, Instant};
+
+/// What the most recently active media player is playing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaState {
+    /// The MPRIS bus name on Linux, the app user model ID of the media session on Windows.
+    pub player: String,
+    /// Name of the application playing, shown when the track has no title.
+    pub app: String,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub art: Option<Handle>,
+    pub playing: bool,
+    pub can_go_next: bool,
+    pub can_go_previous: bool,
+    pub can_seek: bool,
+    pub position: Duration,
+    /// Unknown for streams and some players.
+    pub length: Option<Duration>,
+}
+
+impl MediaState {
+    /// The track title, or the player's name for tracks without one.
+    pub fn display_title(&self) -> &str {
+        if self.title.is_empty() { &self.app } else { &self.title }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaCommand {
+    PlayPause,
+    Next,
+    Previous,
+    SetPosition(Duration),
+}
+
+/// The last reported state and when it arrived, so the position can move on between reports.
+#[derive(Debug, Clone)]
+pub struct NowPlaying {
+    pub state: MediaState,
+    pub since: Instant,
+}
+impl NowPlaying {
+    pub fn new(state: MediaState) -> Self {
+        Self { state, since: Instant::now() }
+    }
+
+    pub fn position(&self) -> Duration {
+        let mut position = self.state.position;
+        if self.state.playing {
+            position += self.since.elapsed();
+        }
+        match self.state.length {
+            Some(length) => position.min(length),
+            None => position,
+        }
+    }
+
+    /// Progress through the track from 0 to 1, 0 when the length is unknown.
+    pub fn progress(&self) -> f32 {
+        match self.state.length {
+            Some(length) if !length.is_zero() => (self.position().as_secs_f32() / length.as_secs_f32()).clamp(0.0, 1.0),
+            _ => 0.0,
+        }
+    }
+}
+
+/// Formats a position like 3:07, or 1:02:03 past an hour.
+pub fn format_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// Sends a command to a player on a thread of its own, as players can be slow to answer.
+pub fn control(player: &str, command: MediaCommand) -> Task<Result<(), String>> {
+    let player = player.to_string();
+    Task::perform(async move {
+        let (sender, receiver) = oneshot::channel();
+        std::thread::spawn(move || {
+            #[cfg(windows)]
+            let result = smtc::control(&player, command).map_err(|e| e.to_string());
+            #[cfg(target_os = "linux")]
+            let result = Mpris::open().and_then(|mpris| mpris.control(&player, command).map_err(|e| e.to_string()));
+            let _ = sender.send(result);
+        });
+        receiver.await.unwrap_or_else(|_| Err("The media command was cancelled".to_string()))
+    }, |result| result)
+}
+
+/// Fires with the state of the most recently active player whenever it changes, `None` while nothing is playing.
+pub fn watch() -> Subscription<Option<MediaState>> {
+    Subscription::run(media_changes)
+}
+
+fn media_changes() -> impl Stream<Item = Option<MediaState>> {
+    iced::stream::channel(10, async |mut output: mpsc::Sender<Option<MediaState>>| {
+        let (sender, mut receiver) = mpsc::unbounded::<Option<MediaState>>();
+        std::thread::spawn(move || {
+            let mut last = None;
+            let report = move |state: Option<MediaState>| {
+                if last.as_ref() == Some(&state) {
+                    return true;
+                }
+                last = Some(state.clone());
+                sender.unbounded_send(state).is_ok()
+            };
+            #[cfg(windows)]
+            let result = smtc::watch(report).map_err(|e| e.to_string());
+            #[cfg(target_os = "linux")]
+            let result = Mpris::open().and_then(|mpris| mpris.watch(report));
+            if let Err(e) = result {
+                eprintln!("Error following media players: {}", e);
+            }
+        });
+        while let Some(state) = receiver.next().await {
+            if output.send(state).await.is_err() {
+                return;
+            }
+        }
+    })
+}
+
+/// Media sessions through the Global System Media Transport Controls, the API behind the volume flyout's media card.
+#[cfg(windows)]
+mod smtc {
+    use super::{MediaCommand, MediaState};
+    use iced::widget::image::Handle;
+    use std::time::Duration;
+    use windows::core::Result;
+    use windows::Media::Control::{
+        GlobalSystemMediaTransportControlsSession as Session,
+        GlobalSystemMediaTransportControlsSessionManager as SessionManager,
+        GlobalSystemMediaTransportControlsSessionPlaybackStatus as PlaybackStatus,
+    };
+    use windows::Storage::Streams::{DataReader, IRandomAccessStreamReference};
+
+    // Difference between the FILETIME epoch of 1601 and the Unix epoch, in 100 nanosecond ticks
+    const UNIX_EPOCH_TICKS: i64 = 116_444_736_000_000_000;
+
+    fn ticks(duration: i64) -> Duration {
+        Duration::from_nanos(duration.max(0) as u64 * 100)
+    }
+
+    fn session(manager: &SessionManager, player: &str) -> Result<Option<Session>> {
+        let sessions = manager.GetSessions()?;
+        for index in 0..sessions.Size()? {
+            let session = sessions.GetAt(index)?;
+            if session.SourceAppUserModelId()? == player {
+                return Ok(Some(session));
+            }
+        }
+        Ok(None)
+    }
+
+    fn read_thumbnail(thumbnail: &IRandomAccessStreamReference) -> Result<Vec<u8>> {
+        let stream = thumbnail.OpenReadAsync()?.join()?;
+        let size = stream.Size()? as u32;
+        let reader = DataReader::CreateDataReader(&stream)?;
+        reader.LoadAsync(size)?.join()?;
+        let mut bytes = vec![0u8; size as usize];
+        reader.ReadBytes(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// The session Windows considers current. Thumbnails are only read again when the track changes.
+    fn current(manager: &SessionManager, art_cache: &mut Option<(String, Handle)>) -> Result<Option<MediaState>> {
+        // Fails while there are no sessions
+        let Ok(session) = manager.GetCurrentSession() else {
+            return Ok(None);
+        };
+        let player = session.SourceAppUserModelId()?.to_string();
+        let properties = session.TryGetMediaPropertiesAsync()?.join()?;
+        let playback = session.GetPlaybackInfo()?;
+        let controls = playback.Controls()?;
+        let timeline = session.GetTimelineProperties()?;
+        let playing = playback.PlaybackStatus()? == PlaybackStatus::Playing;
+        let title = properties.Title()?.to_string();
+        let album = properties.AlbumTitle()?.to_string();
+        let art_key = format!("{}\n{}\n{}", player, title, album);
+        if art_cache.as_ref().is_none_or(|(key, _)| *key != art_key) {
+            *art_cache = properties.Thumbnail().and_then(|thumbnail| read_thumbnail(&thumbnail))
+                .ok()
+                .filter(|bytes| !bytes.is_empty())
+                .map(|bytes| (art_key, Handle::from_bytes(bytes)));
+        }
+        let start = timeline.StartTime()?.Duration;
+        let end = timeline.EndTime()?.Duration;
+        let mut position = timeline.Position()?.Duration - start;
+        // Players only report the position now and then, a playing track has moved on since
+        if playing {
+            let now = chrono::Utc::now().timestamp_micros() * 10 + UNIX_EPOCH_TICKS;
+            position += (now - timeline.LastUpdatedTime()?.UniversalTime).max(0);
+        }
+        // Session IDs of desktop apps end in the executable name
+        let app = player.rsplit(['!', '\\']).next().unwrap_or(&player).trim_end_matches(".exe").to_string();
+        Ok(Some(MediaState {
+            app,
+            title,
+            artist: properties.Artist()?.to_string(),
+            album,
+            art: art_cache.as_ref().map(|(_, art)| art.clone()),
+            playing,
+            can_go_next: controls.IsNextEnabled()?,
+            can_go_previous: controls.IsPreviousEnabled()?,
+            can_seek: controls.IsPlaybackPositionEnabled()?,
+            position: ticks(position),
+            length: (end > start).then(|| ticks(end - start)),
+            player,
+        }))
+    }
+
+    /// Checks the current session once a second, session events would need delegates running on a COM apartment.
+    pub fn watch(mut report: impl FnMut(Option<MediaState>) -> bool) -> Result<()> {
+        let manager = SessionManager::RequestAsync()?.join()?;
+        let mut art_cache = None;
+        loop {
+            let state = match current(&manager, &mut art_cache) {
+                Ok(state) => state,
+                Err(e) => {
+                    eprintln!("Error reading media session: {}", e);
+                    None
+                }
+            };
+            if !report(state) {
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_secs(1));
+        }
+    }
+
+    pub fn control(player: &str, command: MediaCommand) -> Result<()> {
+        let manager = SessionManager::RequestAsync()?.join()?;
+        let Some(session) = session(&manager, player)? else {
+            return Ok(());
+        };
+        let operation = match command {
+            MediaCommand::PlayPause => session.TryTogglePlayPauseAsync()?,
+            MediaCommand::Next => session.TrySkipNextAsync()?,
+            MediaCommand::Previous => session.TrySkipPreviousAsync()?,
+            MediaCommand::SetPosition(position) => {
+                let start = session.GetTimelineProperties()?.StartTime()?.Duration;
+                session.TryChangePlaybackPositionAsync(start + (position.as_nanos() / 100) as i64)?
+            }
+        };
+        // The player may refuse, which is not worth an error
+        operation.join()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+#[cfg(target_os = "linux")]
+use crate::pulse;
+#[cfg(windows)]
+use crate::sys_util;
+use iced::futures::channel::mpsc;
+#[cfg(target_os = "linux")]
+use iced::futures::channel::oneshot;
+use iced::futures::{SinkExt, Stream, StreamExt};
+use iced::Subscription;
+#[cfg(target_os = "linux")]
+use iced::Task;
+
+/// An application playing audio, as listed in the volume mixer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioStream {
+    /// The audio session's instance ID on Windows, the sink input index on Linux.
+    pub id: String,
+    pub pid: Option<u32>,
+    pub name: String,
+    /// Executable name, for finding the application's window when the audio comes from a helper process.
+    pub executable: String,
+    pub volume: f32,
+    pub muted: bool,
+}
+
+pub fn streams() -> Result<Vec<AudioStream>, String> {
+    #[cfg(windows)]
+    return sys_util::get_audio_sessions().map_err(|e| e.to_string());
+    #[cfg(target_os = "linux")]
+    return pulse::sink_inputs();
+}
+
+pub fn set_volume(stream: &AudioStream, volume: f32) -> Result<(), String> {
+    let volume = volume.clamp(0.0, 1.0);
+    #[cfg(windows)]
+    return sys_util::set_audio_session(&stream.id, volume, stream.muted).map_err(|e| e.to_string());
+    #[cfg(target_os = "linux")]
+    return pulse::set_sink_input_volume(&stream.id, volume);
+}
+
+pub fn set_mute(stream: &AudioStream, muted: bool) -> Result<(), String> {
+    #[cfg(windows)]
+    return sys_util::set_audio_session(&stream.id, stream.volume, muted).map_err(|e| e.to_string());
+    #[cfg(target_os = "linux")]
+    return pulse::set_sink_input_mute(&stream.id, muted);
+}
+
+/// Fires whenever streams may have started, stopped or changed.
+pub fn watch() -> Subscription<()> {
+    Subscription::run(stream_changes)
+}
+
+fn stream_changes() -> impl Stream<Item = ()> {
+    iced::stream::channel(10, async |mut output: mpsc::Sender<()>| {
+        let (sender, mut receiver) = mpsc::unbounded::<()>();
+        std::thread::spawn(move || {
+            // Session notifications would need a COM callback object, checking once a second is plenty for a list
+            #[cfg(windows)]
+            while sender.unbounded_send(()).is_ok() {
+                std::thread::sleep(std::time::Duration::from_secs(1));
+            }
+            #[cfg(target_os = "linux")]
+            if let Err(e) = pulse::subscribe(|kind| kind != "sink-input" || sender.unbounded_send(()).is_ok()) {
+                eprintln!("Error watching audio streams: {}", e);
+            }
+        });
+        while receiver.next().await.is_some() {
+            if output.send(()).await.is_err() {
+                return;
+            }
+        }
+    })
+}
+
+/// Volume and mute of the default output, sent at the start and whenever the sound server reports a change.
+#[cfg(target_os = "linux")]
+pub fn watch_output() -> Subscription<(f32, bool)> {
+    Subscription::run(output_changes)
+}
+
+#[cfg(target_os = "linux")]
+fn output_changes() -> impl Stream<Item = (f32, bool)> {
+    iced::stream::channel(10, async |mut output: mpsc::Sender<(f32, bool)>| {
+        let (sender, mut receiver) = mpsc::unbounded::<(f32, bool)>();
+        std::thread::spawn(move || {
+            let mut last_state = None;
+            // Errors are only logged, the taskbar keeps showing the last state it got
+            let mut read = || match pulse::default_sink_state() {
+                Ok(state) if last_state != Some(state) => {
+                    last_state = Some(state);
+                    sender.unbounded_send(state).is_ok()
+                }
+                Ok(_) => true,
+                Err(e) => {
+                    eprintln!("Error getting volume data: {}", e);
+                    true
+                }
+            };
+            if !read() {
+                return;
+            }
+            // A new default output shows up as a server change
+            if let Err(e) = pulse::subscribe(|kind| !matches!(kind, "sink" | "server") || read()) {
+                eprintln!("Error watching the output volume: {}", e);
+            }
+        });
+        while let Some(state) = receiver.next().await {
+            if output.send(state).await.is_err() {
+                return;
+            }
+        }
+    })
+}
+
+/// Sets volume and mute of the default output on a thread of its own, as pactl takes a moment to answer.
+#[cfg(target_os = "linux")]
+pub fn set_output(volume: f32, muted: bool) -> Task<Result<(), String>> {
+    Task::perform(async move {
+        let (sender, receiver) = oneshot::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(pulse::set_default_sink_state(volume.clamp(0.0, 1.0), muted));
+        });
+        receiver.await.unwrap_or_else(|_| Err("Setting the volume was cancelled".to_string()))
+    }, |result| result)
+}
@@ -0,0 +1,487 @@
+use crate::media::{MediaCommand, MediaState};
+use iced::widget::image::Handle;
+use std::collections::HashMap;
+use std::time::Duration;
+use zbus::blocking::fdo::DBusProxy;
+use zbus::blocking::{proxy, Connection, MessageIterator, Proxy};
+use zbus::message::Type;
+use zbus::names::BusName;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::MatchRule;
+
+const NAME_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const PATH: &str = "/org/mpris/MediaPlayer2";
+const ROOT: &str = "org.mpris.MediaPlayer2";
+const PLAYER: &str = "org.mpris.MediaPlayer2.Player";
+
+const RULES: [&str; 3] = [
+    "type='signal',sender='org.freedesktop.DBus',interface='org.freedesktop.DBus',member='NameOwnerChanged',arg0namespace='org.mpris.MediaPlayer2'",
+    "type='signal',interface='org.freedesktop.DBus.Properties',member='PropertiesChanged',path='/org/mpris/MediaPlayer2'",
+    "type='signal',interface='org.mpris.MediaPlayer2.Player',member='Seeked',path='/org/mpris/MediaPlayer2'",
+];
+
+/// A client for media players implementing MPRIS on the session bus.
+pub struct Mpris {
+    connection: Connection,
+    // Players by bus name with their unique name, which their signals come from. The most recently active is last.
+    players: Vec<(String, String)>,
+}
+impl Mpris {
+    pub fn open() -> Result<Self, String> {
+        match Connection::session() {
+            Ok(connection) => Ok(Self::with_connection(connection)),
+            Err(e) => Err(format!("Could not reach the session bus: {}", e)),
+        }
+    }
+
+    pub fn with_connection(connection: Connection) -> Self {
+        Self { connection, players: Vec::new() }
+    }
+
+    fn proxy(&self, name: &str, interface: &'static str) -> zbus::Result<Proxy<'static>> {
+        // Position is never signalled, so a property cache would go stale
+        proxy::Builder::new(&self.connection)
+            .destination(name.to_string())?
+            .path(PATH)?
+            .interface(interface)?
+            .cache_properties(CacheProperties::No)
+            .build()
+    }
+
+    fn playing(&self, name: &str) -> bool {
+        self.proxy(name, PLAYER)
+            .and_then(|player| player.get_property::<String>("PlaybackStatus"))
+            .is_ok_and(|status| status == "Playing")
+    }
+
+    /// Picks up the players already on the bus, putting those that are playing last.
+    fn list_players(&mut self) -> zbus::Result<()> {
+        let dbus = DBusProxy::new(&self.connection)?;
+        let mut players = Vec::new();
+        for name in dbus.list_names()? {
+            if name.starts_with(NAME_PREFIX) {
+                let owner = dbus.get_name_owner(BusName::try_from(name.as_str())?)?;
+                players.push((name.to_string(), owner.to_string()));
+            }
+        }
+        players.sort_by_key(|(name, _)| self.playing(name));
+        self.players = players;
+        Ok(())
+    }
+
+    /// Moves the player with the unique name to the end, as the most recently active.
+    fn touch(&mut self, owner: &str) {
+        if let Some(index) = self.players.iter().position(|(_, player_owner)| player_owner == owner) {
+            let player = self.players.remove(index);
+            self.players.push(player);
+        }
+    }
+
+    pub fn state(&self, name: &str) -> zbus::Result<MediaState> {
+        let player = self.proxy(name, PLAYER)?;
+        let status: String = player.get_property("PlaybackStatus")?;
+        let metadata: HashMap<String, OwnedValue> = player.get_property("Metadata")?;
+        let app: String = self.proxy(name, ROOT)?.get_property("Identity").unwrap_or_else(|_| name.trim_start_matches(NAME_PREFIX).to_string());
+        // Optional for players, missing ones read as not supported
+        let flag = |property: &str| player.get_property::<bool>(property).unwrap_or(false);
+        Ok(MediaState {
+            player: name.to_string(),
+            app,
+            title: text(&metadata, "xesam:title"),
+            artist: text(&metadata, "xesam:artist"),
+            album: text(&metadata, "xesam:album"),
+            art: art(&text(&metadata, "mpris:artUrl")),
+            playing: status == "Playing",
+            can_go_next: flag("CanGoNext"),
+            can_go_previous: flag("CanGoPrevious"),
+            can_seek: flag("CanSeek"),
+            position: microseconds(player.get_property::<i64>("Position").unwrap_or(0)),
+            length: metadata.get("mpris:length").and_then(|length| number(length)).filter(|length| *length > 0).map(microseconds),
+        })
+    }
+
+    fn current(&self) -> Option<MediaState> {
+        let (name, _) = self.players.last()?;
+        match self.state(name) {
+            Ok(state) => Some(state),
+            Err(e) => {
+                eprintln!("Error reading media player {}: {}", name, e);
+                None
+            }
+        }
+    }
+
+    pub fn control(&self, name: &str, command: MediaCommand) -> zbus::Result<()> {
+        let player = self.proxy(name, PLAYER)?;
+        match command {
+            MediaCommand::PlayPause => player.call("PlayPause", &()),
+            MediaCommand::Next => player.call("Next", &()),
+            MediaCommand::Previous => player.call("Previous", &()),
+            MediaCommand::SetPosition(position) => {
+                let position = position.as_micros() as i64;
+                let metadata: HashMap<String, OwnedValue> = player.get_property("Metadata")?;
+                match metadata.get("mpris:trackid").and_then(|track| OwnedObjectPath::try_from(track.clone()).ok()) {
+                    Some(track) => player.call("SetPosition", &(track, position)),
+                    // SetPosition needs the track ID, without one the player can only be asked to seek relative to now
+                    None => {
+                        let current: i64 = player.get_property("Position")?;
+                        player.call("Seek", &(position - current))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Calls `report` with the most recently active player's state, then again after every change to any player,
+    /// until it returns false. A player becomes the most recently active when it appears or starts playing.
+    pub fn watch(mut self, mut report: impl FnMut(Option<MediaState>) -> bool) -> Result<(), String> {
+        let dbus = DBusProxy::new(&self.connection).map_err(|e| e.to_string())?;
+        for rule in RULES {
+            let rule = MatchRule::try_from(rule).map_err(|e| e.to_string())?;
+            dbus.add_match_rule(rule).map_err(|e| e.to_string())?;
+        }
+        let messages = MessageIterator::from(&self.connection);
+        self.list_players().map_err(|e| e.to_string())?;
+        if !report(self.current()) {
+            return Ok(());
+        }
+        for message in messages {
+            let message = message.map_err(|e| e.to_string())?;
+            if message.message_type() != Type::Signal {
+                continue;
+            }
+            let header = message.header();
+            let sender = header.sender().map(|sender| sender.to_string()).unwrap_or_default();
+            match header.member().map(|member| member.as_str()) {
+                Some("NameOwnerChanged") => {
+                    let Ok((name, _, new_owner)) = message.body().deserialize::<(String, String, String)>() else {
+                        continue;
+                    };
+                    if !name.starts_with(NAME_PREFIX) {
+                        continue;
+                    }
+                    self.players.retain(|(player, _)| *player != name);
+                    if !new_owner.is_empty() {
+                        self.players.push((name, new_owner));
+                    }
+                }
+                Some("PropertiesChanged") => {
+                    let Ok((interface, changed, _)) = message.body().deserialize::<(String, HashMap<String, OwnedValue>, Vec<String>)>() else {
+                        continue;
+                    };
+                    if interface != PLAYER {
+                        continue;
+                    }
+                    if changed.get("PlaybackStatus").and_then(|status| status.downcast_ref::<&str>().ok()) == Some("Playing") {
+                        self.touch(&sender);
+                    }
+                }
+                Some("Seeked") => {}
+                _ => continue,
+            }
+            if !report(self.current()) {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn microseconds(value: i64) -> Duration {
+    Duration::from_micros(value.max(0) as u64)
+}
+
+/// Reads a metadata entry as text, joining lists like `xesam:artist`.
+fn text(metadata: &HashMap<String, OwnedValue>, key: &str) -> String {
+    match metadata.get(key).map(|value| &**value) {
+        Some(Value::Str(text)) => text.to_string(),
+        Some(Value::Array(values)) => values.iter()
+            .filter_map(|value| match value {
+                Value::Str(text) => Some(text.to_string()),
+                _ => None,
+            })
+            .collect::<Vec<String>>()
+            .join(", "),
+        _ => String::new(),
+    }
+}
+
+/// Reads an integer of any width, as players disagree on the type of `mpris:length`.
+fn number(value: &Value) -> Option<i64> {
+    match value {
+        Value::I64(number) => Some(*number),
+        Value::U64(number) => i64::try_from(*number).ok(),
+        Value::I32(number) => Some(*number as i64),
+        Value::U32(number) => Some(*number as i64),
+        Value::F64(number) => Some(*number as i64),
+        _ => None,
+    }
+}
+
+/// Album art from a `file://` URL. Players serving art over the network are left without, as there is no HTTP
+/// client to fetch it with.
+fn art(url: &str) -> Option<Handle> {
+    let path = url.strip_prefix("file://")?;
+    Some(Handle::from_path(percent_decode(path)))
+}
+
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%'
+            && let Some(byte) = text.get(index + 1..index + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            decoded.push(byte);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_bus::{wait_for, TestBus};
+    use std::sync::{mpsc, Arc, Mutex};
+    use zbus::interface;
+    use zbus::zvariant::ObjectPath;
+
+    fn metadata(entries: Vec<(&str, Value)>) -> HashMap<String, OwnedValue> {
+        entries.into_iter().map(|(key, value)| (key.to_string(), value.try_into().unwrap())).collect()
+    }
+
+    #[test]
+    fn reads_metadata_text() {
+        let metadata = metadata(vec![
+            ("xesam:title", Value::from("Song")),
+            ("xesam:artist", Value::from(vec!["First", "Second"])),
+            ("xesam:album", Value::from(7u32)),
+            ("xesam:genre", Value::from(Vec::<&str>::new())),
+        ]);
+        assert_eq!(text(&metadata, "xesam:title"), "Song");
+        assert_eq!(text(&metadata, "xesam:artist"), "First, Second");
+        assert_eq!(text(&metadata, "xesam:album"), "");
+        assert_eq!(text(&metadata, "xesam:genre"), "");
+        assert_eq!(text(&metadata, "xesam:comment"), "");
+    }
+
+    #[test]
+    fn reads_numbers_of_any_width() {
+        let cases = [
+            (Value::from(180_000_000i64), Some(180_000_000)),
+            (Value::from(180_000_000u64), Some(180_000_000)),
+            (Value::from(u64::MAX), None),
+            (Value::from(-5i32), Some(-5)),
+            (Value::from(7u32), Some(7)),
+            (Value::from(2.9f64), Some(2)),
+            (Value::from("180000000"), None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(number(&value), expected, "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn decodes_percent_escapes() {
+        let cases = [
+            ("/home/user/My%20Music/cover.jpg", "/home/user/My Music/cover.jpg"),
+            ("/caf%C3%A9.png", "/café.png"),
+            ("/100%", "/100%"),
+            ("/50%2", "/50%2"),
+            ("/%zz%41", "/%zzA"),
+            ("/%E2%82", "/\u{FFFD}"),
+            ("", ""),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(percent_decode(text), expected, "{}", text);
+        }
+    }
+
+    struct StubRoot;
+    #[interface(name = "org.mpris.MediaPlayer2")]
+    impl StubRoot {
+        #[zbus(property)]
+        fn identity(&self) -> String {
+            "Stub Player".to_string()
+        }
+    }
+
+    /// A player with every optional property, recording the calls it gets.
+    struct StubPlayer {
+        status: &'static str,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+    impl StubPlayer {
+        fn call(&self, call: String) {
+            self.calls.lock().unwrap().push(call);
+        }
+    }
+    #[interface(name = "org.mpris.MediaPlayer2.Player")]
+    impl StubPlayer {
+        fn play_pause(&self) {
+            self.call("PlayPause".to_string());
+        }
+        fn next(&self) {
+            self.call("Next".to_string());
+        }
+        fn previous(&self) {
+            self.call("Previous".to_string());
+        }
+        fn set_position(&self, track: OwnedObjectPath, position: i64) {
+            self.call(format!("SetPosition {} {}", track.as_str(), position));
+        }
+        #[zbus(property)]
+        fn playback_status(&self) -> String {
+            self.status.to_string()
+        }
+        #[zbus(property)]
+        fn metadata(&self) -> HashMap<String, OwnedValue> {
+            metadata(vec![
+                ("mpris:trackid", Value::from(ObjectPath::from_static_str_unchecked("/org/example/track/1"))),
+                ("mpris:length", Value::from(180_000_000u64)),
+                ("mpris:artUrl", Value::from("file:///tmp/My%20Art.png")),
+                ("xesam:title", Value::from("Song")),
+                ("xesam:artist", Value::from(vec!["First", "Second"])),
+                ("xesam:album", Value::from("Album")),
+            ])
+        }
+        #[zbus(property)]
+        fn position(&self) -> i64 {
+            2_000_000
+        }
+        #[zbus(property)]
+        fn can_go_next(&self) -> bool {
+            true
+        }
+        #[zbus(property)]
+        fn can_go_previous(&self) -> bool {
+            false
+        }
+        #[zbus(property)]
+        fn can_seek(&self) -> bool {
+            true
+        }
+    }
+
+    /// A player with only the required properties and no track ID.
+    struct MinimalPlayer {
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+    #[interface(name = "org.mpris.MediaPlayer2.Player")]
+    impl MinimalPlayer {
+        fn seek(&self, offset: i64) {
+            self.calls.lock().unwrap().push(format!("Seek {}", offset));
+        }
+        #[zbus(property)]
+        fn playback_status(&self) -> String {
+            "Stopped".to_string()
+        }
+        #[zbus(property)]
+        fn metadata(&self) -> HashMap<String, OwnedValue> {
+            metadata(vec![("mpris:length", Value::from(0i32)), ("mpris:artUrl", Value::from("https://example.org/art.png"))])
+        }
+        #[zbus(property)]
+        fn position(&self) -> i64 {
+            -1
+        }
+    }
+
+    fn player(bus: &TestBus, name: &str, status: &'static str, calls: &Arc<Mutex<Vec<String>>>) -> Connection {
+        let connection = bus.connect();
+        connection.object_server().at(PATH, StubRoot).unwrap();
+        connection.object_server().at(PATH, StubPlayer { status, calls: calls.clone() }).unwrap();
+        connection.request_name(format!("{}{}", NAME_PREFIX, name)).unwrap();
+        connection
+    }
+
+    fn minimal_player(bus: &TestBus, calls: &Arc<Mutex<Vec<String>>>) -> Connection {
+        let connection = bus.connect();
+        connection.object_server().at(PATH, MinimalPlayer { calls: calls.clone() }).unwrap();
+        connection.request_name(format!("{}minimal", NAME_PREFIX)).unwrap();
+        connection
+    }
+
+    #[test]
+    fn reads_player_state() {
+        let bus = TestBus::start();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let _full = player(&bus, "full", "Playing", &calls);
+        let _minimal = minimal_player(&bus, &calls);
+        let mpris = Mpris::with_connection(bus.connect());
+
+        let state = mpris.state("org.mpris.MediaPlayer2.full").unwrap();
+        assert_eq!((state.app.as_str(), state.title.as_str(), state.artist.as_str(), state.album.as_str()), ("Stub Player", "Song", "First, Second", "Album"));
+        assert!(state.playing && state.can_go_next && !state.can_go_previous && state.can_seek);
+        assert_eq!((state.position, state.length), (Duration::from_secs(2), Some(Duration::from_secs(180))));
+        match state.art {
+            Some(Handle::Path(_, path)) => assert_eq!(path, std::path::Path::new("/tmp/My Art.png")),
+            art => panic!("Expected art from a file, got {:?}", art),
+        }
+
+        // Players without the optional properties, an identity or a usable length
+        let state = mpris.state("org.mpris.MediaPlayer2.minimal").unwrap();
+        assert_eq!((state.app.as_str(), state.display_title()), ("minimal", "minimal"));
+        assert!(!state.playing && !state.can_go_next && !state.can_go_previous && !state.can_seek);
+        assert_eq!((state.position, state.length, state.art), (Duration::ZERO, None, None));
+    }
+
+    #[test]
+    fn sends_commands() {
+        let bus = TestBus::start();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let _full = player(&bus, "full", "Playing", &calls);
+        let _minimal = minimal_player(&bus, &calls);
+        let mpris = Mpris::with_connection(bus.connect());
+        for command in [MediaCommand::PlayPause, MediaCommand::Next, MediaCommand::Previous, MediaCommand::SetPosition(Duration::from_secs(5))] {
+            mpris.control("org.mpris.MediaPlayer2.full", command).unwrap();
+        }
+        // Without a track ID the player is asked to seek from where it is
+        mpris.control("org.mpris.MediaPlayer2.minimal", MediaCommand::SetPosition(Duration::from_secs(5))).unwrap();
+        assert_eq!(*calls.lock().unwrap(), ["PlayPause", "Next", "Previous", "SetPosition /org/example/track/1 5000000", "Seek 5000001"]);
+        assert!(mpris.control("org.mpris.MediaPlayer2.minimal", MediaCommand::Next).is_err());
+    }
+
+    /// Waits for a report matching `check`, skipping the ones before it.
+    fn until(reports: &mpsc::Receiver<Option<MediaState>>, what: &str, check: impl Fn(&Option<MediaState>) -> bool) {
+        wait_for(what, || reports.try_iter().find(|report| check(report)).map(|_| ()));
+    }
+
+    fn current_player(report: &Option<MediaState>) -> Option<&str> {
+        report.as_ref().map(|state| state.player.as_str())
+    }
+
+    #[test]
+    fn follows_the_most_recently_active_player() {
+        let bus = TestBus::start();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let playing = player(&bus, "playing", "Playing", &calls);
+        let paused = player(&bus, "paused", "Paused", &calls);
+        let mpris = Mpris::with_connection(bus.connect());
+        let (sender, reports) = mpsc::channel();
+        std::thread::spawn(move || mpris.watch(|report| sender.send(report).is_ok()));
+
+        // Players already playing come first, whatever their names
+        until(&reports, "the playing player", |report| current_player(report) == Some("org.mpris.MediaPlayer2.playing"));
+        let newest = player(&bus, "newest", "Paused", &calls);
+        until(&reports, "the new player", |report| current_player(report) == Some("org.mpris.MediaPlayer2.newest"));
+
+        // Starting to play makes a player the current one
+        let interface = paused.object_server().interface::<_, StubPlayer>(PATH).unwrap();
+        interface.get_mut().status = "Playing";
+        zbus::block_on(interface.get().playback_status_changed(interface.signal_emitter())).unwrap();
+        until(&reports, "the player that started", |report| current_player(report) == Some("org.mpris.MediaPlayer2.paused") && report.as_ref().is_some_and(|state| state.playing));
+
+        // Players leave when their connection closes, which the interface reference holds on to
+        drop(interface);
+        drop(paused);
+        until(&reports, "the newest player again", |report| current_player(report) == Some("org.mpris.MediaPlayer2.newest"));
+        drop(newest);
+        drop(playing);
+        until(&reports, "no player", Option::is_none);
+    }
+}
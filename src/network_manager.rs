@@ -0,0 +1,549 @@
+use crate::sys_util::WifiStatus;
+use crate::wifi::{merge_networks, WifiNetwork, WifiSecurity, WifiState};
+use std::collections::HashMap;
+use zbus::blocking::{proxy, Connection, Proxy};
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value};
+
+const SERVICE: &str = "org.freedesktop.NetworkManager";
+const MANAGER_PATH: &str = "/org/freedesktop/NetworkManager";
+const SETTINGS_PATH: &str = "/org/freedesktop/NetworkManager/Settings";
+const MANAGER: &str = "org.freedesktop.NetworkManager";
+const DEVICE: &str = "org.freedesktop.NetworkManager.Device";
+const WIRELESS: &str = "org.freedesktop.NetworkManager.Device.Wireless";
+const ACCESS_POINT: &str = "org.freedesktop.NetworkManager.AccessPoint";
+const ACTIVE_CONNECTION: &str = "org.freedesktop.NetworkManager.Connection.Active";
+const SETTINGS: &str = "org.freedesktop.NetworkManager.Settings";
+const SETTINGS_CONNECTION: &str = "org.freedesktop.NetworkManager.Settings.Connection";
+
+// NMDeviceType
+const DEVICE_TYPE_WIFI: u32 = 2;
+// NM80211ApFlags and NM80211ApSecurityFlags
+const AP_FLAGS_PRIVACY: u32 = 0x1;
+const KEY_MGMT_PSK: u32 = 0x100;
+const KEY_MGMT_802_1X: u32 = 0x200;
+const KEY_MGMT_SAE: u32 = 0x400;
+
+/// Connection settings as NetworkManager hands them out, setting name to property map.
+type ConnectionSettings = HashMap<String, HashMap<String, OwnedValue>>;
+
+/// Wi-Fi through NetworkManager's D-Bus API.
+pub struct NetworkManager {
+    connection: Connection,
+}
+impl NetworkManager {
+    /// Connects to NetworkManager on the system bus. `FROSTWIN_NM_BUS_ADDRESS` points it at another bus instead,
+    /// so a stub service on a private bus can stand in for the real one.
+    pub fn open() -> Result<Self, String> {
+        let connection = match std::env::var("FROSTWIN_NM_BUS_ADDRESS") {
+            Ok(address) => zbus::blocking::connection::Builder::address(address.as_str()).and_then(|builder| builder.build()),
+            Err(_) => Connection::system(),
+        };
+        match connection {
+            Ok(connection) => Ok(Self::with_connection(connection)),
+            Err(e) => Err(format!("Could not reach NetworkManager: {}", e)),
+        }
+    }
+
+    pub fn with_connection(connection: Connection) -> Self {
+        Self { connection }
+    }
+
+    fn proxy(&self, path: &str, interface: &'static str) -> zbus::Result<Proxy<'static>> {
+        // Properties are read once per call, a cache would only add signal subscriptions
+        proxy::Builder::new(&self.connection)
+            .destination(SERVICE)?
+            .path(path.to_string())?
+            .interface(interface)?
+            .cache_properties(CacheProperties::No)
+            .build()
+    }
+
+    /// The first wireless device, `None` on machines without one.
+    fn wifi_device(&self) -> zbus::Result<Option<OwnedObjectPath>> {
+        let devices: Vec<OwnedObjectPath> = self.proxy(MANAGER_PATH, MANAGER)?.call("GetDevices", &())?;
+        for device in devices {
+            let device_type: u32 = self.proxy(device.as_str(), DEVICE)?.get_property("DeviceType")?;
+            if device_type == DEVICE_TYPE_WIFI {
+                return Ok(Some(device));
+            }
+        }
+        Ok(None)
+    }
+
+    fn require_wifi_device(&self) -> zbus::Result<OwnedObjectPath> {
+        self.wifi_device()?.ok_or_else(|| zbus::Error::Failure("No Wi-Fi adapter found".to_string()))
+    }
+
+    /// Saved Wi-Fi connections with the SSID they are for.
+    fn saved_connections(&self) -> zbus::Result<Vec<(OwnedObjectPath, Vec<u8>)>> {
+        let paths: Vec<OwnedObjectPath> = self.proxy(SETTINGS_PATH, SETTINGS)?.call("ListConnections", &())?;
+        let mut saved = Vec::new();
+        for path in paths {
+            let settings: ConnectionSettings = self.proxy(path.as_str(), SETTINGS_CONNECTION)?.call("GetSettings", &())?;
+            if let Some(ssid) = settings.get("802-11-wireless").and_then(|wireless| wireless.get("ssid")) {
+                saved.push((path, bytes(ssid)));
+            }
+        }
+        Ok(saved)
+    }
+
+    fn access_point(&self, path: &OwnedObjectPath) -> zbus::Result<(Vec<u8>, u32, WifiSecurity)> {
+        let access_point = self.proxy(path.as_str(), ACCESS_POINT)?;
+        let ssid: Vec<u8> = access_point.get_property("Ssid")?;
+        let strength: u8 = access_point.get_property("Strength")?;
+        let flags: u32 = access_point.get_property("Flags")?;
+        let wpa_flags: u32 = access_point.get_property("WpaFlags")?;
+        let rsn_flags: u32 = access_point.get_property("RsnFlags")?;
+        Ok((ssid, strength as u32, security(flags, wpa_flags, rsn_flags)))
+    }
+
+    pub fn scan(&self) -> zbus::Result<WifiState> {
+        let radio_enabled: bool = self.proxy(MANAGER_PATH, MANAGER)?.get_property("WirelessEnabled")?;
+        let Some(device) = self.wifi_device()? else {
+            return Ok(WifiState { radio_enabled, networks: Vec::new() });
+        };
+        let wireless = self.proxy(device.as_str(), WIRELESS)?;
+        // Refused while a scan is running or the radio is off, the access points from the last one are listed either way
+        let _: zbus::Result<()> = wireless.call("RequestScan", &(HashMap::<&str, Value>::new(),));
+        let access_points: Vec<OwnedObjectPath> = wireless.call("GetAllAccessPoints", &())?;
+        let active: OwnedObjectPath = wireless.get_property("ActiveAccessPoint")?;
+        let saved = self.saved_connections()?;
+        let mut networks = Vec::new();
+        for path in access_points {
+            // Access points can vanish between listing and reading them
+            if let Ok((ssid, signal, security)) = self.access_point(&path) {
+                networks.push(WifiNetwork {
+                    known: saved.iter().any(|(_, saved_ssid)| *saved_ssid == ssid),
+                    ssid: String::from_utf8_lossy(&ssid).to_string(),
+                    signal,
+                    security,
+                    connected: path == active,
+                });
+            }
+        }
+        Ok(WifiState { radio_enabled, networks: merge_networks(networks) })
+    }
+
+    /// Activates the saved connection for the SSID, or adds a new one when there is none or a new password is given.
+    pub fn connect(&self, ssid: &str, security: WifiSecurity, password: Option<&str>) -> zbus::Result<()> {
+        let device = self.require_wifi_device()?;
+        let manager = self.proxy(MANAGER_PATH, MANAGER)?;
+        let saved: Vec<OwnedObjectPath> = self.saved_connections()?
+            .into_iter()
+            .filter(|(_, saved_ssid)| saved_ssid == ssid.as_bytes())
+            .map(|(path, _)| path)
+            .collect();
+        let no_object = ObjectPath::from_static_str_unchecked("/");
+        if password.is_none() && let Some(connection) = saved.first() {
+            let _: OwnedObjectPath = manager.call("ActivateConnection", &(connection, &device, &no_object))?;
+            return Ok(());
+        }
+        // A new password replaces the saved connection instead of adding a second one next to it
+        for connection in &saved {
+            self.proxy(connection.as_str(), SETTINGS_CONNECTION)?.call::<_, _, ()>("Delete", &())?;
+        }
+        let mut settings: HashMap<&str, HashMap<&str, Value>> = HashMap::new();
+        settings.insert("802-11-wireless", HashMap::from([("ssid", Value::from(ssid.as_bytes().to_vec()))]));
+        if let Some(password) = password {
+            let security_settings = match security {
+                WifiSecurity::Wep => HashMap::from([("key-mgmt", Value::from("none")), ("wep-key0", Value::from(password))]),
+                WifiSecurity::Wpa3Personal => HashMap::from([("key-mgmt", Value::from("sae")), ("psk", Value::from(password))]),
+                _ => HashMap::from([("key-mgmt", Value::from("wpa-psk")), ("psk", Value::from(password))]),
+            };
+            settings.insert("802-11-wireless-security", security_settings);
+        }
+        // NetworkManager fills in everything else from the access point
+        let access_point = self.strongest_access_point(&device, ssid)?;
+        let _: (OwnedObjectPath, OwnedObjectPath) = manager.call(
+            "AddAndActivateConnection",
+            &(settings, &device, access_point.as_ref().map(|path| path.as_ref()).unwrap_or(no_object)),
+        )?;
+        Ok(())
+    }
+
+    fn strongest_access_point(&self, device: &OwnedObjectPath, ssid: &str) -> zbus::Result<Option<OwnedObjectPath>> {
+        let access_points: Vec<OwnedObjectPath> = self.proxy(device.as_str(), WIRELESS)?.call("GetAllAccessPoints", &())?;
+        let mut strongest: Option<(OwnedObjectPath, u32)> = None;
+        for path in access_points {
+            if let Ok((ap_ssid, signal, _)) = self.access_point(&path)
+                && ap_ssid == ssid.as_bytes()
+                && strongest.as_ref().is_none_or(|(_, strongest_signal)| signal > *strongest_signal) {
+                strongest = Some((path, signal));
+            }
+        }
+        Ok(strongest.map(|(path, _)| path))
+    }
+
+    pub fn disconnect(&self) -> zbus::Result<()> {
+        let device = self.require_wifi_device()?;
+        self.proxy(device.as_str(), DEVICE)?.call("Disconnect", &())
+    }
+
+    pub fn forget(&self, ssid: &str) -> zbus::Result<()> {
+        for (path, saved_ssid) in self.saved_connections()? {
+            if saved_ssid == ssid.as_bytes() {
+                self.proxy(path.as_str(), SETTINGS_CONNECTION)?.call::<_, _, ()>("Delete", &())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The connection the taskbar shows, which is the one NetworkManager routes through.
+    pub fn status(&self) -> zbus::Result<WifiStatus> {
+        let primary: OwnedObjectPath = self.proxy(MANAGER_PATH, MANAGER)?.get_property("PrimaryConnection")?;
+        if primary.as_str() == "/" {
+            return Ok(WifiStatus::Disconnected);
+        }
+        let connection_type: String = self.proxy(primary.as_str(), ACTIVE_CONNECTION)?.get_property("Type")?;
+        match connection_type.as_str() {
+            "802-3-ethernet" => Ok(WifiStatus::Ethernet),
+            "802-11-wireless" => {
+                let Some(device) = self.wifi_device()? else {
+                    return Ok(WifiStatus::Disconnected);
+                };
+                let active: OwnedObjectPath = self.proxy(device.as_str(), WIRELESS)?.get_property("ActiveAccessPoint")?;
+                if active.as_str() == "/" {
+                    return Ok(WifiStatus::Disconnected);
+                }
+                let (ssid, signal, _) = self.access_point(&active)?;
+                Ok(WifiStatus::Connected(String::from_utf8_lossy(&ssid).to_string(), signal))
+            }
+            _ => Ok(WifiStatus::Disconnected),
+        }
+    }
+
+    pub fn set_radio(&self, enabled: bool) -> zbus::Result<()> {
+        self.proxy(MANAGER_PATH, MANAGER)?.set_property("WirelessEnabled", enabled)?;
+        Ok(())
+    }
+}
+
+/// Reads an `ay` value, such as an SSID in connection settings.
+fn bytes(value: &Value) -> Vec<u8> {
+    match value {
+        Value::Array(array) => array.iter().filter_map(|byte| match byte {
+            Value::U8(byte) => Some(*byte),
+            _ => None,
+        }).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Picks the security type from an access point's flags. Networks offering both WPA2 and WPA3 are joined with WPA2,
+/// which works with every driver.
+fn security(flags: u32, wpa_flags: u32, rsn_flags: u32) -> WifiSecurity {
+    let key_management = wpa_flags | rsn_flags;
+    if key_management & KEY_MGMT_802_1X != 0 {
+        WifiSecurity::Enterprise
+    } else if key_management & KEY_MGMT_PSK != 0 {
+        WifiSecurity::WpaPersonal
+    } else if key_management & KEY_MGMT_SAE != 0 {
+        WifiSecurity::Wpa3Personal
+    } else if flags & AP_FLAGS_PRIVACY != 0 {
+        WifiSecurity::Wep
+    } else {
+        WifiSecurity::Open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_bus::TestBus;
+    use std::sync::{Arc, Mutex};
+    use zbus::interface;
+
+    const WIRELESS_DEVICE: &str = "/org/freedesktop/NetworkManager/Devices/2";
+    const HOME: &str = "/org/freedesktop/NetworkManager/AccessPoint/1";
+    const HOME_WEAK: &str = "/org/freedesktop/NetworkManager/AccessPoint/2";
+    const CAFE: &str = "/org/freedesktop/NetworkManager/AccessPoint/3";
+    const HOME_SAVED: &str = "/org/freedesktop/NetworkManager/Settings/1";
+    const WIRED_SAVED: &str = "/org/freedesktop/NetworkManager/Settings/2";
+
+    fn path(path: &str) -> OwnedObjectPath {
+        OwnedObjectPath::try_from(path).unwrap()
+    }
+
+    fn text(value: &Value) -> String {
+        match value {
+            Value::Str(text) => text.to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// What the stub service holds and the calls it got.
+    struct State {
+        wireless_enabled: bool,
+        primary_connection: &'static str,
+        saved: Vec<&'static str>,
+        calls: Vec<String>,
+    }
+    type Shared = Arc<Mutex<State>>;
+
+    struct StubManager(Shared);
+    #[interface(name = "org.freedesktop.NetworkManager")]
+    impl StubManager {
+        fn get_devices(&self) -> Vec<OwnedObjectPath> {
+            vec![path("/org/freedesktop/NetworkManager/Devices/1"), path(WIRELESS_DEVICE)]
+        }
+        fn activate_connection(&self, connection: OwnedObjectPath, device: OwnedObjectPath, specific_object: OwnedObjectPath) -> OwnedObjectPath {
+            self.0.lock().unwrap().calls.push(format!("Activate {} {} {}", connection.as_str(), device.as_str(), specific_object.as_str()));
+            path("/org/freedesktop/NetworkManager/ActiveConnection/9")
+        }
+        fn add_and_activate_connection(&self, settings: ConnectionSettings, device: OwnedObjectPath, specific_object: OwnedObjectPath) -> (OwnedObjectPath, OwnedObjectPath) {
+            let ssid = String::from_utf8(bytes(&settings["802-11-wireless"]["ssid"])).unwrap();
+            let security = match settings.get("802-11-wireless-security") {
+                Some(security) => {
+                    let key = security.get("psk").or(security.get("wep-key0")).unwrap();
+                    format!("{} {}", text(&security["key-mgmt"]), text(key))
+                }
+                None => "open".to_string(),
+            };
+            self.0.lock().unwrap().calls.push(format!("AddAndActivate {} {} {} {}", ssid, security, device.as_str(), specific_object.as_str()));
+            (path("/org/freedesktop/NetworkManager/Settings/9"), path("/org/freedesktop/NetworkManager/ActiveConnection/9"))
+        }
+        #[zbus(property)]
+        fn wireless_enabled(&self) -> bool {
+            self.0.lock().unwrap().wireless_enabled
+        }
+        #[zbus(property)]
+        fn set_wireless_enabled(&mut self, enabled: bool) {
+            self.0.lock().unwrap().wireless_enabled = enabled;
+        }
+        #[zbus(property)]
+        fn primary_connection(&self) -> OwnedObjectPath {
+            path(self.0.lock().unwrap().primary_connection)
+        }
+    }
+
+    struct StubDevice {
+        device_type: u32,
+        state: Shared,
+    }
+    #[interface(name = "org.freedesktop.NetworkManager.Device")]
+    impl StubDevice {
+        fn disconnect(&self) {
+            self.state.lock().unwrap().calls.push("Disconnect".to_string());
+        }
+        #[zbus(property)]
+        fn device_type(&self) -> u32 {
+            self.device_type
+        }
+    }
+
+    struct StubWireless;
+    #[interface(name = "org.freedesktop.NetworkManager.Device.Wireless")]
+    impl StubWireless {
+        fn request_scan(&self, _options: HashMap<String, OwnedValue>) -> zbus::fdo::Result<()> {
+            Err(zbus::fdo::Error::Failed("Scanning not allowed immediately following previous scan".to_string()))
+        }
+        fn get_all_access_points(&self) -> Vec<OwnedObjectPath> {
+            // The last one is gone by the time it is read
+            [HOME, HOME_WEAK, CAFE, "/org/freedesktop/NetworkManager/AccessPoint/4", "/org/freedesktop/NetworkManager/AccessPoint/5", "/org/freedesktop/NetworkManager/AccessPoint/6"]
+                .into_iter()
+                .map(path)
+                .collect()
+        }
+        #[zbus(property)]
+        fn active_access_point(&self) -> OwnedObjectPath {
+            path(HOME_WEAK)
+        }
+    }
+
+    struct StubAccessPoint {
+        ssid: &'static str,
+        strength: u8,
+        rsn_flags: u32,
+    }
+    #[interface(name = "org.freedesktop.NetworkManager.AccessPoint")]
+    impl StubAccessPoint {
+        #[zbus(property)]
+        fn ssid(&self) -> Vec<u8> {
+            self.ssid.as_bytes().to_vec()
+        }
+        #[zbus(property)]
+        fn strength(&self) -> u8 {
+            self.strength
+        }
+        #[zbus(property)]
+        fn flags(&self) -> u32 {
+            if self.rsn_flags == 0 { 0 } else { AP_FLAGS_PRIVACY }
+        }
+        #[zbus(property)]
+        fn wpa_flags(&self) -> u32 {
+            0
+        }
+        #[zbus(property)]
+        fn rsn_flags(&self) -> u32 {
+            self.rsn_flags
+        }
+    }
+
+    struct StubSettings(Shared);
+    #[interface(name = "org.freedesktop.NetworkManager.Settings")]
+    impl StubSettings {
+        fn list_connections(&self) -> Vec<OwnedObjectPath> {
+            self.0.lock().unwrap().saved.iter().map(|saved| path(saved)).collect()
+        }
+    }
+
+    struct StubSavedConnection {
+        path: &'static str,
+        ssid: Option<&'static str>,
+        state: Shared,
+    }
+    #[interface(name = "org.freedesktop.NetworkManager.Settings.Connection")]
+    impl StubSavedConnection {
+        fn get_settings(&self) -> HashMap<String, HashMap<String, OwnedValue>> {
+            let mut settings = HashMap::from([("connection".to_string(), HashMap::from([("id".to_string(), OwnedValue::from(42u32))]))]);
+            if let Some(ssid) = self.ssid {
+                let ssid = Value::from(ssid.as_bytes().to_vec()).try_into().unwrap();
+                settings.insert("802-11-wireless".to_string(), HashMap::from([("ssid".to_string(), ssid)]));
+            }
+            settings
+        }
+        fn delete(&self) {
+            let mut state = self.state.lock().unwrap();
+            state.calls.push(format!("Delete {}", self.path));
+            state.saved.retain(|saved| *saved != self.path);
+        }
+    }
+
+    struct StubActiveConnection(&'static str);
+    #[interface(name = "org.freedesktop.NetworkManager.Connection.Active")]
+    impl StubActiveConnection {
+        #[zbus(property, name = "Type")]
+        fn connection_type(&self) -> String {
+            self.0.to_string()
+        }
+    }
+
+    /// Serves a stub NetworkManager with a wired and a wireless device on a private bus.
+    fn start() -> (TestBus, Connection, Shared) {
+        let bus = TestBus::start();
+        let state = Arc::new(Mutex::new(State {
+            wireless_enabled: true,
+            primary_connection: "/",
+            saved: vec![HOME_SAVED, WIRED_SAVED],
+            calls: Vec::new(),
+        }));
+        let service = bus.connect();
+        let objects = service.object_server();
+        objects.at(MANAGER_PATH, StubManager(state.clone())).unwrap();
+        objects.at("/org/freedesktop/NetworkManager/Devices/1", StubDevice { device_type: 1, state: state.clone() }).unwrap();
+        objects.at(WIRELESS_DEVICE, StubDevice { device_type: DEVICE_TYPE_WIFI, state: state.clone() }).unwrap();
+        objects.at(WIRELESS_DEVICE, StubWireless).unwrap();
+        let access_points = [
+            (HOME, "Home", 80, KEY_MGMT_PSK),
+            (HOME_WEAK, "Home", 40, KEY_MGMT_PSK),
+            (CAFE, "Cafe", 60, 0),
+            ("/org/freedesktop/NetworkManager/AccessPoint/4", "Office", 50, KEY_MGMT_802_1X),
+            ("/org/freedesktop/NetworkManager/AccessPoint/5", "", 90, KEY_MGMT_PSK),
+        ];
+        for (access_point, ssid, strength, rsn_flags) in access_points {
+            objects.at(access_point, StubAccessPoint { ssid, strength, rsn_flags }).unwrap();
+        }
+        objects.at(SETTINGS_PATH, StubSettings(state.clone())).unwrap();
+        objects.at(HOME_SAVED, StubSavedConnection { path: HOME_SAVED, ssid: Some("Home"), state: state.clone() }).unwrap();
+        objects.at(WIRED_SAVED, StubSavedConnection { path: WIRED_SAVED, ssid: None, state: state.clone() }).unwrap();
+        objects.at("/org/freedesktop/NetworkManager/ActiveConnection/1", StubActiveConnection("802-11-wireless")).unwrap();
+        objects.at("/org/freedesktop/NetworkManager/ActiveConnection/2", StubActiveConnection("802-3-ethernet")).unwrap();
+        drop(objects);
+        service.request_name(SERVICE).unwrap();
+        (bus, service, state)
+    }
+
+    fn network(ssid: &str, signal: u32, security: WifiSecurity, known: bool, connected: bool) -> WifiNetwork {
+        WifiNetwork { ssid: ssid.to_string(), signal, security, known, connected }
+    }
+
+    #[test]
+    fn scans_the_wireless_device() {
+        let (bus, _service, state) = start();
+        let network_manager = NetworkManager::with_connection(bus.connect());
+        let scanned = network_manager.scan().unwrap();
+        assert!(scanned.radio_enabled);
+        assert_eq!(scanned.networks, [
+            network("Home", 80, WifiSecurity::WpaPersonal, true, true),
+            network("Cafe", 60, WifiSecurity::Open, false, false),
+            network("Office", 50, WifiSecurity::Enterprise, false, false),
+        ]);
+
+        network_manager.set_radio(false).unwrap();
+        assert!(!state.lock().unwrap().wireless_enabled);
+        assert!(!network_manager.scan().unwrap().radio_enabled);
+    }
+
+    #[test]
+    fn connects_with_saved_and_new_connections() {
+        let (bus, _service, state) = start();
+        let network_manager = NetworkManager::with_connection(bus.connect());
+        network_manager.connect("Home", WifiSecurity::WpaPersonal, None).unwrap();
+        network_manager.connect("Cafe", WifiSecurity::Open, None).unwrap();
+        network_manager.connect("Office", WifiSecurity::Wpa3Personal, Some("secret")).unwrap();
+        // A new password replaces the saved connection, and the strongest access point is asked for
+        network_manager.connect("Home", WifiSecurity::WpaPersonal, Some("new password")).unwrap();
+        network_manager.connect("Home", WifiSecurity::Wep, Some("wep key")).unwrap();
+        network_manager.disconnect().unwrap();
+        assert_eq!(state.lock().unwrap().calls, [
+            format!("Activate {} {} /", HOME_SAVED, WIRELESS_DEVICE),
+            format!("AddAndActivate Cafe open {} {}", WIRELESS_DEVICE, CAFE),
+            format!("AddAndActivate Office sae secret {} /org/freedesktop/NetworkManager/AccessPoint/4", WIRELESS_DEVICE),
+            format!("Delete {}", HOME_SAVED),
+            format!("AddAndActivate Home wpa-psk new password {} {}", WIRELESS_DEVICE, HOME),
+            format!("AddAndActivate Home none wep key {} {}", WIRELESS_DEVICE, HOME),
+            "Disconnect".to_string(),
+        ]);
+        assert_eq!(state.lock().unwrap().saved, [WIRED_SAVED]);
+    }
+
+    #[test]
+    fn forgets_saved_networks() {
+        let (bus, _service, state) = start();
+        let network_manager = NetworkManager::with_connection(bus.connect());
+        network_manager.forget("Cafe").unwrap();
+        assert!(state.lock().unwrap().calls.is_empty());
+        network_manager.forget("Home").unwrap();
+        assert_eq!(state.lock().unwrap().calls, [format!("Delete {}", HOME_SAVED)]);
+        assert!(!network_manager.scan().unwrap().networks[0].known);
+    }
+
+    #[test]
+    fn reports_the_primary_connection() {
+        let (bus, _service, state) = start();
+        let network_manager = NetworkManager::with_connection(bus.connect());
+        assert!(matches!(network_manager.status(), Ok(WifiStatus::Disconnected)));
+        state.lock().unwrap().primary_connection = "/org/freedesktop/NetworkManager/ActiveConnection/2";
+        assert!(matches!(network_manager.status(), Ok(WifiStatus::Ethernet)));
+        state.lock().unwrap().primary_connection = "/org/freedesktop/NetworkManager/ActiveConnection/1";
+        match network_manager.status() {
+            Ok(WifiStatus::Connected(ssid, signal)) => assert_eq!((ssid.as_str(), signal), ("Home", 40)),
+            _ => panic!("Expected the Wi-Fi connection"),
+        }
+    }
+
+    #[test]
+    fn maps_access_point_flags_to_security() {
+        let cases = [
+            ((0, 0, 0), WifiSecurity::Open),
+            ((AP_FLAGS_PRIVACY, 0, 0), WifiSecurity::Wep),
+            ((AP_FLAGS_PRIVACY, KEY_MGMT_PSK, 0), WifiSecurity::WpaPersonal),
+            ((AP_FLAGS_PRIVACY, 0, KEY_MGMT_PSK), WifiSecurity::WpaPersonal),
+            ((AP_FLAGS_PRIVACY, 0, KEY_MGMT_SAE), WifiSecurity::Wpa3Personal),
+            // Transition mode networks are joined with WPA2
+            ((AP_FLAGS_PRIVACY, 0, KEY_MGMT_PSK | KEY_MGMT_SAE), WifiSecurity::WpaPersonal),
+            ((AP_FLAGS_PRIVACY, 0, KEY_MGMT_802_1X), WifiSecurity::Enterprise),
+            ((AP_FLAGS_PRIVACY, KEY_MGMT_PSK, KEY_MGMT_802_1X), WifiSecurity::Enterprise),
+        ];
+        for ((flags, wpa_flags, rsn_flags), expected) in cases {
+            assert_eq!(security(flags, wpa_flags, rsn_flags), expected, "{:#x} {:#x} {:#x}", flags, wpa_flags, rsn_flags);
+        }
+    }
+
+    #[test]
+    fn reads_byte_arrays() {
+        assert_eq!(bytes(&Value::from(b"Home".to_vec())), b"Home");
+        assert_eq!(bytes(&Value::from("Home")), b"");
+    }
+}
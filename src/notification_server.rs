@@ -0,0 +1,368 @@
+use crate::icon_theme::{load_icon, system_icon_lookup};
+use crate::mpris::percent_decode;
+use crate::notifications::{Notification, NotificationEvent, NotificationReplies, NotificationReply, Urgency, DEFAULT_TIMEOUT};
+use chrono::Local;
+use iced::futures::channel::mpsc;
+use iced::widget::image::Handle;
+use std::collections::HashMap;
+use std::time::Duration;
+use zbus::blocking::{connection, Connection};
+use zbus::interface;
+use zbus::object_server::SignalEmitter;
+use zbus::zvariant::OwnedValue;
+
+const NAME: &str = "org.freedesktop.Notifications";
+const PATH: &str = "/org/freedesktop/Notifications";
+
+/// The freedesktop.org notification service, which `notify-send` and most toolkits send through.
+pub struct NotificationServer {
+    connection: Connection,
+    replies: std::sync::mpsc::Receiver<NotificationReply>,
+}
+impl NotificationServer {
+    /// Owns the notification service name on the session bus, failing when another notification daemon
+    /// already does.
+    pub fn open(events: mpsc::UnboundedSender<NotificationEvent>) -> Result<Self, String> {
+        Self::serve(connection::Builder::session(), events)
+    }
+
+    /// Owns the service name on the bus `builder` connects to.
+    fn serve(builder: zbus::Result<connection::Builder<'static>>, events: mpsc::UnboundedSender<NotificationEvent>) -> Result<Self, String> {
+        let (replies_sender, replies) = std::sync::mpsc::channel();
+        let service = Notifications { events: events.clone(), next_id: 1 };
+        // Neither side gives the name up, a daemon that quietly took it over would leave the shell without notifications
+        let connection = builder
+            .and_then(|builder| builder.serve_at(PATH, service))
+            .and_then(|builder| builder.name(NAME))
+            .and_then(|builder| builder.allow_name_replacements(false).replace_existing_names(false).build());
+        match connection {
+            Ok(connection) => {
+                let _ = events.unbounded_send(NotificationEvent::Started(NotificationReplies(replies_sender)));
+                Ok(Self { connection, replies })
+            }
+            Err(e) => Err(format!("Could not own {}, is another notification daemon running? {}", NAME, e)),
+        }
+    }
+
+    /// Passes on what became of notifications to the applications that sent them, until the shell stops replying.
+    pub fn run(self) -> Result<(), String> {
+        while let Ok(reply) = self.replies.recv() {
+            let emitter = SignalEmitter::new(self.connection.inner(), PATH).map_err(|e| e.to_string())?;
+            let result = match reply {
+                NotificationReply::ActionInvoked(id, action) => zbus::block_on(Notifications::action_invoked(&emitter, id, &action)),
+                NotificationReply::Closed(id, reason) => zbus::block_on(Notifications::notification_closed(&emitter, id, reason as u32)),
+            };
+            if let Err(e) = result {
+                eprintln!("Error replying to notification: {}", e);
+            }
+        }
+        Ok(())
+    }
+}
+
+struct Notifications {
+    events: mpsc::UnboundedSender<NotificationEvent>,
+    next_id: u32,
+}
+
+#[interface(name = "org.freedesktop.Notifications")]
+impl Notifications {
+    fn get_capabilities(&self) -> Vec<&str> {
+        // Bodies are shown as plain text, so neither markup nor hyperlinks are offered
+        vec!["actions", "body", "icon-static", "persistence"]
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &mut self,
+        app_name: String,
+        replaces_id: u32,
+        app_icon: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        hints: HashMap<String, OwnedValue>,
+        expire_timeout: i32,
+    ) -> u32 {
+        let id = if replaces_id != 0 {
+            replaces_id
+        } else {
+            let id = self.next_id;
+            self.next_id = self.next_id.checked_add(1).unwrap_or(1);
+            id
+        };
+        let urgency = match hints.get("urgency").and_then(|value| value.downcast_ref::<u8>().ok()) {
+            Some(0) => Urgency::Low,
+            Some(2) => Urgency::Critical,
+            _ => Urgency::Normal,
+        };
+        let timeout = match expire_timeout {
+            0 => None,
+            milliseconds if milliseconds > 0 => Some(Duration::from_millis(milliseconds as u64)),
+            _ if urgency == Urgency::Critical => None,
+            _ => Some(DEFAULT_TIMEOUT),
+        };
+        // Older applications send the image under the names of earlier versions of the specification
+        let image = ["image-data", "image_data"].into_iter()
+            .find_map(|name| hints.get(name).and_then(image_data))
+            .or_else(|| ["image-path", "image_path"].into_iter().find_map(|name| hints.get(name).and_then(|value| icon(value.downcast_ref::<&str>().ok()?))))
+            .or_else(|| hints.get("icon_data").and_then(image_data));
+        let notification = Notification {
+            id,
+            app_name,
+            desktop_entry: hints.get("desktop-entry").and_then(|value| value.downcast_ref::<&str>().ok()).map(str::to_string),
+            app_icon: icon(&app_icon),
+            image,
+            summary,
+            body,
+            actions: actions.chunks_exact(2).map(|action| (action[0].clone(), action[1].clone())).collect(),
+            urgency,
+            timeout,
+            received: Local::now(),
+        };
+        let _ = self.events.unbounded_send(NotificationEvent::Notify(Box::new(notification)));
+        id
+    }
+
+    fn close_notification(&self, id: u32) {
+        let _ = self.events.unbounded_send(NotificationEvent::Close(id));
+    }
+
+    #[zbus(out_args("name", "vendor", "version", "spec_version"))]
+    fn get_server_information(&self) -> (String, String, String, String) {
+        ("Frostwin".to_string(), "Frostwin".to_string(), env!("CARGO_PKG_VERSION").to_string(), "1.2".to_string())
+    }
+
+    #[zbus(signal)]
+    async fn notification_closed(emitter: &SignalEmitter<'_>, id: u32, reason: u32) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn action_invoked(emitter: &SignalEmitter<'_>, id: u32, action_key: &str) -> zbus::Result<()>;
+}
+
+/// An icon given as a name from the icon theme, a path or a `file://` URI.
+fn icon(icon: &str) -> Option<Handle> {
+    if icon.is_empty() {
+        return None;
+    }
+    if let Some(path) = icon.strip_prefix("file://") {
+        return Some(Handle::from_path(percent_decode(path)));
+    }
+    system_icon_lookup().find_icon(icon, 48, 1).and_then(|path| load_icon(&path, 48))
+}
+
+/// Raw pixels sent along with the notification, as width, height, row stride, whether there is an alpha channel,
+/// bits per sample, channels and the data.
+fn image_data(value: &OwnedValue) -> Option<Handle> {
+    let (width, height, rowstride, has_alpha, bits_per_sample, channels, data) =
+        <(i32, i32, i32, bool, i32, i32, Vec<u8>)>::try_from(value.try_clone().ok()?).ok()?;
+    let pixels = rgba(width, height, rowstride, has_alpha, bits_per_sample, channels, &data)?;
+    Some(Handle::from_rgba(width as u32, height as u32, pixels))
+}
+
+/// Unpacks rows padded out to the row stride into tightly packed RGBA.
+fn rgba(width: i32, height: i32, rowstride: i32, has_alpha: bool, bits_per_sample: i32, channels: i32, data: &[u8]) -> Option<Vec<u8>> {
+    let expected_channels = if has_alpha { 4 } else { 3 };
+    if width <= 0 || height <= 0 || bits_per_sample != 8 || channels != expected_channels || rowstride < width * channels {
+        return None;
+    }
+    let (width, height, rowstride, channels) = (width as usize, height as usize, rowstride as usize, channels as usize);
+    // The last row is allowed to stop at its last pixel
+    if data.len() < rowstride * (height - 1) + width * channels {
+        return None;
+    }
+    let mut pixels = Vec::with_capacity(width * height * 4);
+    for row in data.chunks(rowstride).take(height) {
+        for pixel in row[..width * channels].chunks_exact(channels) {
+            pixels.extend_from_slice(&pixel[..3]);
+            pixels.push(if has_alpha { pixel[3] } else { 255 });
+        }
+    }
+    Some(pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notifications::CloseReason;
+    use crate::test_bus::{wait_for, TestBus};
+    use zbus::blocking::Proxy;
+    use zbus::zvariant::Value;
+
+    struct Running {
+        bus: TestBus,
+        events: mpsc::UnboundedReceiver<NotificationEvent>,
+        replies: NotificationReplies,
+        client: Proxy<'static>,
+        signals: std::sync::mpsc::Receiver<(String, u32, String)>,
+    }
+    impl Running {
+        fn event(&mut self) -> NotificationEvent {
+            wait_for("a notification event", || self.events.try_recv().ok())
+        }
+        fn notification(&mut self) -> Notification {
+            match self.event() {
+                NotificationEvent::Notify(notification) => *notification,
+                event => panic!("Expected a notification, got {:?}", event),
+            }
+        }
+        fn signal(&self) -> (String, u32, String) {
+            self.signals.recv_timeout(Duration::from_secs(5)).expect("No signal from the server")
+        }
+        fn notify(&self, replaces_id: u32, summary: &str, actions: &[&str], hints: HashMap<&str, Value>, expire_timeout: i32) -> u32 {
+            self.client.call("Notify", &("Test", replaces_id, "", summary, "Body", actions, hints, expire_timeout)).unwrap()
+        }
+    }
+
+    /// Serves notifications on a private bus, with a client following the server's signals.
+    fn start() -> Running {
+        let bus = TestBus::start();
+        let (sender, mut events) = mpsc::unbounded();
+        let server = NotificationServer::serve(connection::Builder::address(bus.address.as_str()), sender).unwrap();
+        let replies = match wait_for("the server to start", || events.try_recv().ok()) {
+            NotificationEvent::Started(replies) => replies,
+            event => panic!("Expected the server to start, got {:?}", event),
+        };
+        std::thread::spawn(move || server.run());
+        let client = Proxy::new(&bus.connect(), NAME, PATH, NAME).unwrap();
+        let (signal_sender, signals) = std::sync::mpsc::channel();
+        let signal_client = client.clone();
+        let subscribed = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let signals = signal_client.receive_all_signals().unwrap();
+            let _ = subscribed.0.send(());
+            for message in signals {
+                let member = message.header().member().map(|member| member.to_string()).unwrap_or_default();
+                let body = message.body();
+                let (id, detail) = match member.as_str() {
+                    "NotificationClosed" => body.deserialize::<(u32, u32)>().map(|(id, reason)| (id, reason.to_string())),
+                    _ => body.deserialize::<(u32, String)>(),
+                }.unwrap();
+                if signal_sender.send((member, id, detail)).is_err() {
+                    return;
+                }
+            }
+        });
+        subscribed.1.recv_timeout(Duration::from_secs(5)).unwrap();
+        Running { bus, events, replies, client, signals }
+    }
+
+    #[test]
+    fn numbers_and_replaces_notifications() {
+        let mut server = start();
+        let first = server.notify(0, "First", &["default", "Open", "reply", "Reply"], HashMap::new(), -1);
+        let second = server.notify(0, "Second", &[], HashMap::new(), -1);
+        assert_eq!((first, second), (1, 2));
+        let notification = server.notification();
+        assert_eq!((notification.id, notification.app_name.as_str(), notification.summary.as_str(), notification.body.as_str()), (1, "Test", "First", "Body"));
+        assert_eq!(notification.actions, [("default".to_string(), "Open".to_string()), ("reply".to_string(), "Reply".to_string())]);
+        assert!(notification.has_default_action());
+        assert_eq!(notification.buttons().collect::<Vec<_>>(), [&("reply".to_string(), "Reply".to_string())]);
+        assert_eq!(server.notification().id, 2);
+
+        // Replacing keeps the number the application already has
+        assert_eq!(server.notify(first, "First again", &[], HashMap::new(), -1), first);
+        let replaced = server.notification();
+        assert_eq!((replaced.id, replaced.summary.as_str()), (1, "First again"));
+        // Even one the server never gave out
+        assert_eq!(server.notify(40, "Unknown", &[], HashMap::new(), -1), 40);
+        assert_eq!(server.notification().id, 40);
+        assert_eq!(server.notify(0, "Third", &[], HashMap::new(), -1), 3);
+    }
+
+    #[test]
+    fn works_out_expiry() {
+        let mut server = start();
+        let urgency = |level: u8| HashMap::from([("urgency", Value::from(level))]);
+        let cases = [
+            (HashMap::new(), -1, Urgency::Normal, Some(DEFAULT_TIMEOUT)),
+            (HashMap::new(), 0, Urgency::Normal, None),
+            (HashMap::new(), 2500, Urgency::Normal, Some(Duration::from_millis(2500))),
+            (urgency(0), -1, Urgency::Low, Some(DEFAULT_TIMEOUT)),
+            // Critical notifications stay up unless the application says otherwise
+            (urgency(2), -1, Urgency::Critical, None),
+            (urgency(2), 1000, Urgency::Critical, Some(Duration::from_secs(1))),
+            (urgency(7), -1, Urgency::Normal, Some(DEFAULT_TIMEOUT)),
+        ];
+        for (hints, expire_timeout, urgency, timeout) in cases {
+            server.notify(0, "Expiring", &[], hints, expire_timeout);
+            let notification = server.notification();
+            assert_eq!((notification.urgency, notification.timeout), (urgency, timeout), "{}", expire_timeout);
+        }
+
+        // The shell takes expired toasts down and says so
+        let id = server.notify(0, "Expiring", &[], HashMap::new(), 10);
+        server.notification();
+        server.replies.send(NotificationReply::Closed(id, CloseReason::Expired));
+        assert_eq!(server.signal(), ("NotificationClosed".to_string(), id, "1".to_string()));
+    }
+
+    #[test]
+    fn closes_notifications_and_signals_why() {
+        let mut server = start();
+        let id = server.notify(0, "Closing", &["default", "Open"], HashMap::new(), 0);
+        server.notification();
+        server.client.call::<_, _, ()>("CloseNotification", &(id,)).unwrap();
+        assert!(matches!(server.event(), NotificationEvent::Close(closed) if closed == id));
+        server.replies.send(NotificationReply::Closed(id, CloseReason::ClosedByCall));
+        assert_eq!(server.signal(), ("NotificationClosed".to_string(), id, "3".to_string()));
+
+        server.replies.send(NotificationReply::ActionInvoked(id, "default".to_string()));
+        server.replies.send(NotificationReply::Closed(id, CloseReason::Dismissed));
+        assert_eq!(server.signal(), ("ActionInvoked".to_string(), id, "default".to_string()));
+        assert_eq!(server.signal(), ("NotificationClosed".to_string(), id, "2".to_string()));
+    }
+
+    #[test]
+    fn reads_images_and_describes_itself() {
+        let mut server = start();
+        let image = Value::from((2i32, 1i32, 8i32, true, 8i32, 4i32, vec![1u8, 2, 3, 4, 5, 6, 7, 8]));
+        let hints = HashMap::from([("image-data", image), ("desktop-entry", Value::from("org.example.Test"))]);
+        server.notify(0, "Image", &[], hints, -1);
+        let notification = server.notification();
+        assert_eq!(notification.desktop_entry.as_deref(), Some("org.example.Test"));
+        let Some(Handle::Rgba { width: 2, height: 1, pixels, .. }) = notification.image else {
+            panic!("The image was not read: {:?}", notification.image);
+        };
+        assert_eq!(pixels.as_ref(), [1, 2, 3, 4, 5, 6, 7, 8]);
+
+        let capabilities: Vec<String> = server.client.call("GetCapabilities", &()).unwrap();
+        assert_eq!(capabilities, ["actions", "body", "icon-static", "persistence"]);
+        let (name, _, _, spec_version): (String, String, String, String) = server.client.call("GetServerInformation", &()).unwrap();
+        assert_eq!((name.as_str(), spec_version.as_str()), ("Frostwin", "1.2"));
+
+        // A second daemon on the same bus is turned away
+        let (sender, _events) = mpsc::unbounded();
+        let Err(e) = NotificationServer::serve(connection::Builder::address(server.bus.address.as_str()), sender) else {
+            panic!("A second server took the name");
+        };
+        assert!(e.contains("another notification daemon"), "{}", e);
+    }
+
+    #[test]
+    fn converts_image_data_to_rgba() {
+        // Width, height, rowstride, alpha, bits per sample, channels, data and the pixels expected
+        type Case = (i32, i32, i32, bool, i32, i32, Vec<u8>, Option<Vec<u8>>);
+        let cases: [Case; 10] = [
+            // RGBA rows packed tightly
+            (2, 1, 8, true, 8, 4, vec![1, 2, 3, 4, 5, 6, 7, 8], Some(vec![1, 2, 3, 4, 5, 6, 7, 8])),
+            // RGB rows padded out to 8 bytes, the last row stopping at its last pixel
+            (2, 2, 8, false, 8, 3, vec![1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 10, 11, 12], Some(vec![1, 2, 3, 255, 4, 5, 6, 255, 7, 8, 9, 255, 10, 11, 12, 255])),
+            // Or padded like the others
+            (1, 2, 4, false, 8, 3, vec![1, 2, 3, 0, 4, 5, 6, 0], Some(vec![1, 2, 3, 255, 4, 5, 6, 255])),
+            // Too short for the last row
+            (2, 2, 8, false, 8, 3, vec![1, 2, 3, 4, 5, 6, 0, 0, 7, 8, 9, 10, 11], None),
+            // Channels not agreeing with the alpha flag
+            (1, 1, 4, false, 8, 4, vec![1, 2, 3, 4], None),
+            (1, 1, 3, true, 8, 3, vec![1, 2, 3], None),
+            (1, 1, 8, true, 16, 4, vec![0; 8], None),
+            // Rows shorter than their pixels
+            (2, 1, 4, true, 8, 4, vec![0; 8], None),
+            (0, 1, 0, true, 8, 4, vec![], None),
+            (1, -1, 4, true, 8, 4, vec![0; 4], None),
+        ];
+        for (width, height, rowstride, has_alpha, bits_per_sample, channels, data, expected) in cases {
+            assert_eq!(rgba(width, height, rowstride, has_alpha, bits_per_sample, channels, &data), expected, "{}x{} stride {}", width, height, rowstride);
+        }
+    }
+}
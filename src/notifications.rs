@@ -0,0 +1,113 @@
+#[cfg(target_os = "linux")]
+use crate::notification_server::NotificationServer;
+use chrono::{DateTime, Local};
+#[cfg(target_os = "linux")]
+use iced::futures::channel::mpsc;
+#[cfg(target_os = "linux")]
+use iced::futures::{SinkExt, Stream, StreamExt};
+use iced::widget::image::Handle;
+use iced::Subscription;
+use std::time::Duration;
+
+/// How long a toast stays up when the application leaves it to the server.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(6);
+/// Notifications kept for the panel menu, the oldest are dropped past this.
+pub const HISTORY_LIMIT: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Urgency {
+    Low,
+    Normal,
+    /// Shown even with Do Not Disturb on and stays up until dismissed.
+    Critical,
+}
+
+/// A notification sent by an application.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub id: u32,
+    pub app_name: String,
+    /// Name of the application's desktop entry, when it gives one.
+    pub desktop_entry: Option<String>,
+    pub app_icon: Option<Handle>,
+    /// A picture to go with the notification, like a contact's avatar.
+    pub image: Option<Handle>,
+    pub summary: String,
+    pub body: String,
+    /// Action keys with their labels. The "default" action is invoked by clicking the notification.
+    pub actions: Vec<(String, String)>,
+    pub urgency: Urgency,
+    /// `None` for notifications that stay up until dismissed.
+    pub timeout: Option<Duration>,
+    pub received: DateTime<Local>,
+}
+impl Notification {
+    /// Actions shown as buttons, the default one being left to clicking the notification.
+    pub fn buttons(&self) -> impl Iterator<Item = &(String, String)> {
+        self.actions.iter().filter(|(key, _)| key != "default")
+    }
+
+    pub fn has_default_action(&self) -> bool {
+        self.actions.iter().any(|(key, _)| key == "default")
+    }
+}
+
+/// Why a notification went away, as the numbers the specification gives them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CloseReason {
+    Expired = 1,
+    Dismissed = 2,
+    ClosedByCall = 3,
+}
+
+#[derive(Debug, Clone)]
+pub enum NotificationEvent {
+    /// The server is running, replies to applications go through the sender.
+    Started(NotificationReplies),
+    Notify(Box<Notification>),
+    /// The application withdrew the notification.
+    Close(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotificationReply {
+    ActionInvoked(u32, String),
+    Closed(u32, CloseReason),
+}
+
+/// Tells applications what happened to their notifications.
+#[derive(Debug, Clone)]
+pub struct NotificationReplies(pub std::sync::mpsc::Sender<NotificationReply>);
+impl NotificationReplies {
+    pub fn send(&self, reply: NotificationReply) {
+        if self.0.send(reply).is_err() {
+            eprintln!("Error replying to notification: the notification server has stopped");
+        }
+    }
+}
+
+/// Receives notifications from applications for as long as it is subscribed to. Windows shows applications'
+/// notifications itself, so there is nothing to serve there.
+pub fn serve() -> Subscription<NotificationEvent> {
+    #[cfg(windows)]
+    return Subscription::none();
+    #[cfg(target_os = "linux")]
+    return Subscription::run(notification_events);
+}
+
+#[cfg(target_os = "linux")]
+fn notification_events() -> impl Stream<Item = NotificationEvent> {
+    iced::stream::channel(10, async |mut output: mpsc::Sender<NotificationEvent>| {
+        let (sender, mut receiver) = mpsc::unbounded::<NotificationEvent>();
+        std::thread::spawn(move || {
+            if let Err(e) = NotificationServer::open(sender).and_then(|server| server.run()) {
+                eprintln!("Error serving notifications: {}", e);
+            }
+        });
+        while let Some(event) = receiver.next().await {
+            if output.send(event).await.is_err() {
+                return;
+            }
+        }
+    })
+}
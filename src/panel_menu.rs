@@ -2,47 +2,182 @@ use std::collections::BTreeMap;
 use std::ops::RangeInclusive;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use iced::{window, Alignment, Element, Length, Pixels, Point, Size, Task};
+use std::time::Duration;
+use iced::{window, Alignment, Color, Element, Length, Pixels, Point, Size, Task};
 use iced::advanced::image::Handle;
-use iced::widget::{row, text, column, slider, button, space, image, container};
+use iced::widget::{row, text, column, slider, button, space, image, container, progress_bar, scrollable, text_input, toggler, Column};
 use iced::widget::text::Wrapping;
 use crate::Message;
-use crate::raw_icons::{battery_icon, sound_icon, wifi_icon};
-use crate::styles::{my_slider, transparent_button, window_style};
-use crate::sys_util::WifiStatus;
+use crate::raw_icons::{battery_icon, microphone_icon, sound_icon, wifi_icon};
+use crate::styles::{colored_button, my_slider, selected_button, transparent_button, window_style};
+use crate::sys_util::{SystemStatus, WifiStatus};
+use crate::audio_devices::{self, AudioDevice, DeviceDirection};
+use crate::bluetooth::{self, BluetoothDevice, BluetoothState};
+use crate::brightness::{self, Display};
+use crate::do_not_disturb::{self, DoNotDisturbChange, DoNotDisturbReason, DoNotDisturbSettings, QuietHours};
+use crate::media::{self, MediaCommand, NowPlaying};
+use crate::notifications::Notification;
+use crate::mixer::{self, AudioStream};
+use crate::shell_settings::ShellSettings;
+use crate::taskbar::ProcessIcons;
+use crate::wifi::{self, WifiNetwork, WifiState};
 
 #[derive(Debug, Clone)]
 pub enum PanelMessage {
     Init,
     Resize(Option<Size>),
+    WifiRefresh,
+    WifiScanned(Result<WifiState, String>),
+    WifiSelect(String),
+    WifiPasswordInput(String),
+    WifiConnect,
+    WifiDisconnect,
+    WifiForget,
+    WifiRadio(bool),
+    WifiDone(Result<(), String>),
+    DisplaysLoaded(Vec<Display>),
+    BrightnessChange(usize, f32),
+    /// The slider was let go, so the brightness is sent to the display.
+    BrightnessRelease(usize),
+    BrightnessSet(Result<(), String>),
+    ToggleMixer,
+    StreamsChanged,
+    StreamVolume(String, f32),
+    StreamMute(String),
+    DevicesRefresh,
+    ToggleDevices,
+    SetDefaultDevice(AudioDevice),
+    InputVolume(f32),
+    InputMute,
+    InputLevel(f32),
+    /// The seek bar was dragged to a position in seconds.
+    SeekChange(f32),
+    SeekRelease,
+    ToggleBluetooth,
+    BluetoothRefresh,
+    BluetoothLoaded(Result<BluetoothState, String>),
+    BluetoothSelect(String),
+    BluetoothPower(bool),
+    /// Starts or stops looking for devices to pair with.
+    BluetoothScan,
+    BluetoothConnect,
+    BluetoothDisconnect,
+    BluetoothPair,
+    BluetoothForget,
+    BluetoothDone(Result<(), String>),
+    ToggleDoNotDisturb,
+    QuietStartInput(String),
+    QuietEndInput(String),
+    QuietDay(usize),
+    AddQuietHours,
+    AllowedAppInput(String),
+    AddAllowedApp,
+    ToggleNotifications,
 }
 pub struct PanelMenu {
-    pub id: window::Id
+    pub id: window::Id,
+    wifi: WifiState,
+    /// Network whose actions are shown.
+    selected_network: Option<String>,
+    password: String,
+    /// Set while a connect, disconnect or forget is running.
+    wifi_busy: bool,
+    wifi_error: Option<String>,
+    displays: Vec<Display>,
+    /// Whether the per-application volume mixer is expanded.
+    pub mixer_open: bool,
+    streams: Vec<AudioStream>,
+    /// Whether the output and input device lists are expanded.
+    devices_open: bool,
+    outputs: Vec<AudioDevice>,
+    inputs: Vec<AudioDevice>,
+    input_volume: f32,
+    input_muted: bool,
+    // Peak level of the default microphone from the level meter
+    input_level: f32,
+    // Position the seek bar is held at while dragging, in seconds
+    seeking: Option<f32>,
+    /// Whether the Bluetooth device list is expanded.
+    pub bluetooth_open: bool,
+    bluetooth: BluetoothState,
+    /// Device whose actions are shown.
+    selected_bluetooth: Option<String>,
+    /// Whether devices to pair with are being looked for.
+    pub bluetooth_scanning: bool,
+    /// Set while a pair, connect, disconnect or forget is running.
+    bluetooth_busy: bool,
+    bluetooth_error: Option<String>,
+    /// Whether the Do Not Disturb settings are expanded.
+    do_not_disturb_open: bool,
+    // The quiet hours and allowed application being added
+    quiet_start: String,
+    quiet_end: String,
+    quiet_days: [bool; 7],
+    allowed_app: String,
+    do_not_disturb_error: Option<String>,
+    /// Whether the notification history is expanded.
+    notifications_open: bool,
 }
 impl PanelMenu {
     pub fn new() -> (Self,Task<window::Id>) {
-        let mut settings = window::Settings::default();
-        settings.decorations = false;
-        settings.resizable = false;
-        settings.min_size = None;
-        settings.max_size = None;
-        settings.icon = None;
-        settings.transparent = true;
-        settings.closeable = false;
-        settings.minimizable = false;
-        settings.level = window::Level::AlwaysOnTop;
-        settings.position = window::Position::Specific(Point::new(1118.0,37.0));
-        settings.size = Size::new(400.0,600.0);
+        let settings = window::Settings {
+            decorations: false,
+            resizable: false,
+            min_size: None,
+            max_size: None,
+            icon: None,
+            transparent: true,
+            closeable: false,
+            minimizable: false,
+            level: window::Level::AlwaysOnTop,
+            position: window::Position::Specific(Point::new(1118.0,37.0)),
+            size: Size::new(400.0,600.0),
+            ..window::Settings::default()
+        };
         let (id,open_task) = window::open(settings);
         (Self {
-            id
+            id,
+            wifi: WifiState::default(),
+            selected_network: None,
+            password: String::new(),
+            wifi_busy: false,
+            wifi_error: None,
+            displays: Vec::new(),
+            mixer_open: false,
+            streams: Vec::new(),
+            devices_open: false,
+            outputs: Vec::new(),
+            inputs: Vec::new(),
+            input_volume: 0.0,
+            input_muted: false,
+            input_level: 0.0,
+            seeking: None,
+            bluetooth_open: false,
+            bluetooth: BluetoothState::default(),
+            selected_bluetooth: None,
+            bluetooth_scanning: false,
+            bluetooth_busy: false,
+            bluetooth_error: None,
+            do_not_disturb_open: false,
+            quiet_start: "22:00".to_string(),
+            quiet_end: "07:00".to_string(),
+            quiet_days: [true; 7],
+            allowed_app: String::new(),
+            do_not_disturb_error: None,
+            notifications_open: false,
         },
          open_task)
     }
     pub fn update(&mut self, message: PanelMessage) -> Task<Message> {
         match message {
             PanelMessage::Init => {
-                window::monitor_size(self.id).map(|size|Message::PanelMenu(PanelMessage::Resize(size)))
+                Task::batch([
+                    window::monitor_size(self.id).map(|size|Message::PanelMenu(PanelMessage::Resize(size))),
+                    self.update(PanelMessage::WifiRefresh),
+                    self.update(PanelMessage::DevicesRefresh),
+                    self.update(PanelMessage::BluetoothRefresh),
+                    brightness::in_background(brightness::displays).map(|displays| Message::PanelMenu(PanelMessage::DisplaysLoaded(displays.unwrap_or_default()))),
+                ])
             }
             PanelMessage::Resize(resize) => {
                 if let Some(size) = resize {
@@ -54,9 +189,818 @@ impl PanelMenu {
                     Task::none()
                 }
             }
+            PanelMessage::WifiRefresh => {
+                wifi::in_background(wifi::scan).map(|result| Message::PanelMenu(PanelMessage::WifiScanned(result)))
+            }
+            PanelMessage::WifiScanned(result) => {
+                match result {
+                    Ok(state) => {
+                        self.wifi = state;
+                    }
+                    Err(e) => {
+                        eprintln!("Error scanning Wi-Fi networks: {}", e);
+                        self.wifi = WifiState::default();
+                    }
+                }
+                Task::none()
+            }
+            PanelMessage::WifiSelect(ssid) => {
+                if self.selected_network.as_ref() == Some(&ssid) {
+                    self.selected_network = None;
+                } else {
+                    self.selected_network = Some(ssid);
+                }
+                self.password.clear();
+                self.wifi_error = None;
+                Task::none()
+            }
+            PanelMessage::WifiPasswordInput(password) => {
+                self.password = password;
+                Task::none()
+            }
+            PanelMessage::WifiConnect => {
+                let Some(network) = self.selected().cloned() else {
+                    return Task::none();
+                };
+                let password = std::mem::take(&mut self.password);
+                self.wifi_action(move || wifi::connect(&network, Some(password.as_str())))
+            }
+            PanelMessage::WifiDisconnect => {
+                self.wifi_action(wifi::disconnect)
+            }
+            PanelMessage::WifiForget => {
+                let Some(ssid) = self.selected_network.clone() else {
+                    return Task::none();
+                };
+                self.wifi_action(move || wifi::forget(&ssid))
+            }
+            PanelMessage::WifiRadio(enabled) => {
+                self.wifi.radio_enabled = enabled;
+                self.wifi_action(move || wifi::set_radio(enabled))
+            }
+            PanelMessage::WifiDone(result) => {
+                self.wifi_busy = false;
+                if let Err(e) = result {
+                    eprintln!("Error changing Wi-Fi connection: {}", e);
+                    self.wifi_error = Some(e);
+                }
+                self.update(PanelMessage::WifiRefresh)
+            }
+            PanelMessage::DisplaysLoaded(displays) => {
+                self.displays = displays;
+                Task::none()
+            }
+            PanelMessage::BrightnessChange(index, value) => {
+                if let Some(display) = self.displays.get_mut(index) {
+                    display.brightness = value;
+                }
+                Task::none()
+            }
+            PanelMessage::BrightnessRelease(index) => {
+                let Some(display) = self.displays.get(index) else {
+                    return Task::none();
+                };
+                let (control, value) = (display.control.clone(), display.brightness);
+                brightness::in_background(move || brightness::set_brightness(&control, value))
+                    .map(|result| Message::PanelMenu(PanelMessage::BrightnessSet(result.unwrap_or(Ok(())))))
+            }
+            PanelMessage::BrightnessSet(result) => {
+                if let Err(e) = result {
+                    eprintln!("Error setting display brightness: {}", e);
+                }
+                Task::none()
+            }
+            PanelMessage::ToggleMixer => {
+                self.mixer_open = !self.mixer_open;
+                if self.mixer_open {
+                    self.update(PanelMessage::StreamsChanged)
+                } else {
+                    Task::none()
+                }
+            }
+            PanelMessage::StreamsChanged => {
+                self.streams = match mixer::streams() {
+                    Ok(streams) => streams,
+                    Err(e) => {
+                        eprintln!("Error listing audio streams: {}", e);
+                        Vec::new()
+                    }
+                };
+                Task::none()
+            }
+            PanelMessage::StreamVolume(id, volume) => {
+                if let Some(stream) = self.streams.iter_mut().find(|stream| stream.id == id) {
+                    match mixer::set_volume(stream, volume) {
+                        Err(e) => {
+                            eprintln!("Error setting application volume: {}", e);
+                        }
+                        _ => {
+                            stream.volume = volume;
+                        }
+                    }
+                }
+                Task::none()
+            }
+            PanelMessage::StreamMute(id) => {
+                if let Some(stream) = self.streams.iter_mut().find(|stream| stream.id == id) {
+                    match mixer::set_mute(stream, !stream.muted) {
+                        Err(e) => {
+                            eprintln!("Error muting application: {}", e);
+                        }
+                        _ => {
+                            stream.muted = !stream.muted;
+                        }
+                    }
+                }
+                Task::none()
+            }
+            PanelMessage::DevicesRefresh => {
+                self.outputs = audio_devices::devices(DeviceDirection::Output).unwrap_or_else(|e| {
+                    eprintln!("Error listing audio outputs: {}", e);
+                    Vec::new()
+                });
+                self.inputs = audio_devices::devices(DeviceDirection::Input).unwrap_or_else(|e| {
+                    eprintln!("Error listing audio inputs: {}", e);
+                    Vec::new()
+                });
+                if self.microphone().is_some() {
+                    (self.input_volume, self.input_muted) = match audio_devices::input_state() {
+                        Ok(state) => state,
+                        Err(e) => {
+                            eprintln!("Error getting microphone volume: {}", e);
+                            (0.0, false)
+                        }
+                    };
+                }
+                Task::none()
+            }
+            PanelMessage::ToggleDevices => {
+                self.devices_open = !self.devices_open;
+                if self.devices_open {
+                    self.update(PanelMessage::DevicesRefresh)
+                } else {
+                    Task::none()
+                }
+            }
+            PanelMessage::SetDefaultDevice(device) => {
+                if let Err(e) = audio_devices::set_default(&device) {
+                    eprintln!("Error changing the default audio device: {}", e);
+                }
+                self.input_level = 0.0;
+                self.update(PanelMessage::DevicesRefresh)
+            }
+            PanelMessage::InputVolume(volume) => {
+                match audio_devices::set_input_state(volume, self.input_muted) {
+                    Err(e) => {
+                        eprintln!("Error setting microphone volume: {}", e);
+                    }
+                    _ => {
+                        self.input_volume = volume;
+                    }
+                }
+                Task::none()
+            }
+            PanelMessage::InputMute => {
+                match audio_devices::set_input_state(self.input_volume, !self.input_muted) {
+                    Err(e) => {
+                        eprintln!("Error muting microphone: {}", e);
+                    }
+                    _ => {
+                        self.input_muted = !self.input_muted;
+                    }
+                }
+                Task::none()
+            }
+            PanelMessage::InputLevel(level) => {
+                self.input_level = level;
+                Task::none()
+            }
+            PanelMessage::SeekChange(seconds) => {
+                self.seeking = Some(seconds);
+                Task::none()
+            }
+            PanelMessage::SeekRelease => {
+                match self.seeking.take() {
+                    Some(seconds) => Task::done(Message::MediaControl(MediaCommand::SetPosition(Duration::from_secs_f32(seconds)))),
+                    None => Task::none(),
+                }
+            }
+            PanelMessage::ToggleBluetooth => {
+                self.bluetooth_open = !self.bluetooth_open;
+                self.bluetooth_scanning = false;
+                self.selected_bluetooth = None;
+                self.update(PanelMessage::BluetoothRefresh)
+            }
+            PanelMessage::BluetoothRefresh => {
+                bluetooth::in_background(bluetooth::state).map(|result| Message::PanelMenu(PanelMessage::BluetoothLoaded(result)))
+            }
+            PanelMessage::BluetoothLoaded(result) => {
+                match result {
+                    Ok(state) => {
+                        self.bluetooth = state;
+                    }
+                    Err(e) => {
+                        eprintln!("Error listing Bluetooth devices: {}", e);
+                        self.bluetooth = BluetoothState::default();
+                    }
+                }
+                if !self.bluetooth.powered {
+                    self.bluetooth_scanning = false;
+                }
+                Task::none()
+            }
+            PanelMessage::BluetoothSelect(id) => {
+                if self.selected_bluetooth.as_ref() == Some(&id) {
+                    self.selected_bluetooth = None;
+                } else {
+                    self.selected_bluetooth = Some(id);
+                }
+                self.bluetooth_error = None;
+                Task::none()
+            }
+            PanelMessage::BluetoothPower(powered) => {
+                self.bluetooth.powered = powered;
+                self.bluetooth_action(move || bluetooth::set_powered(powered))
+            }
+            PanelMessage::BluetoothScan => {
+                self.bluetooth_scanning = !self.bluetooth_scanning;
+                Task::none()
+            }
+            PanelMessage::BluetoothConnect => {
+                let Some(id) = self.selected_bluetooth.clone() else {
+                    return Task::none();
+                };
+                self.bluetooth_action(move || bluetooth::connect(&id))
+            }
+            PanelMessage::BluetoothDisconnect => {
+                let Some(id) = self.selected_bluetooth.clone() else {
+                    return Task::none();
+                };
+                self.bluetooth_action(move || bluetooth::disconnect(&id))
+            }
+            PanelMessage::BluetoothPair => {
+                let Some(id) = self.selected_bluetooth.clone() else {
+                    return Task::none();
+                };
+                // Discovery slows pairing down and is not needed once the device is found
+                self.bluetooth_scanning = false;
+                self.bluetooth_action(move || bluetooth::pair(&id))
+            }
+            PanelMessage::BluetoothForget => {
+                let Some(id) = self.selected_bluetooth.take() else {
+                    return Task::none();
+                };
+                self.bluetooth_action(move || bluetooth::forget(&id))
+            }
+            PanelMessage::BluetoothDone(result) => {
+                self.bluetooth_busy = false;
+                if let Err(e) = result {
+                    eprintln!("Error changing Bluetooth connection: {}", e);
+                    self.bluetooth_error = Some(e);
+                }
+                self.update(PanelMessage::BluetoothRefresh)
+            }
+            PanelMessage::ToggleDoNotDisturb => {
+                self.do_not_disturb_open = !self.do_not_disturb_open;
+                self.do_not_disturb_error = None;
+                Task::none()
+            }
+            PanelMessage::QuietStartInput(start) => {
+                self.quiet_start = start;
+                Task::none()
+            }
+            PanelMessage::QuietEndInput(end) => {
+                self.quiet_end = end;
+                Task::none()
+            }
+            PanelMessage::QuietDay(day) => {
+                self.quiet_days[day] = !self.quiet_days[day];
+                Task::none()
+            }
+            PanelMessage::AddQuietHours => {
+                let (Some(start), Some(end)) = (do_not_disturb::parse_minutes(&self.quiet_start), do_not_disturb::parse_minutes(&self.quiet_end)) else {
+                    self.do_not_disturb_error = Some("Times need to be written like 22:00".to_string());
+                    return Task::none();
+                };
+                if start == end || !self.quiet_days.contains(&true) {
+                    self.do_not_disturb_error = Some("Quiet hours need a length and at least one day".to_string());
+                    return Task::none();
+                }
+                self.do_not_disturb_error = None;
+                Task::done(Message::DoNotDisturb(DoNotDisturbChange::AddSchedule(QuietHours { start, end, days: self.quiet_days })))
+            }
+            PanelMessage::AllowedAppInput(app) => {
+                self.allowed_app = app;
+                Task::none()
+            }
+            PanelMessage::AddAllowedApp => {
+                let app = std::mem::take(&mut self.allowed_app).trim().to_string();
+                if app.is_empty() {
+                    return Task::none();
+                }
+                Task::done(Message::DoNotDisturb(DoNotDisturbChange::AllowApp(app)))
+            }
+            PanelMessage::ToggleNotifications => {
+                self.notifications_open = !self.notifications_open;
+                Task::none()
+            }
+        }
+    }
+    /// The default microphone, which the controls and level are shown for.
+    pub fn microphone(&self) -> Option<&AudioDevice> {
+        self.inputs.iter().find(|device| device.is_default)
+    }
+    fn selected(&self) -> Option<&WifiNetwork> {
+        self.wifi.networks.iter().find(|network| Some(&network.ssid) == self.selected_network.as_ref())
+    }
+    fn wifi_action(&mut self, action: impl FnOnce() -> Result<(), String> + Send + 'static) -> Task<Message> {
+        self.wifi_busy = true;
+        self.wifi_error = None;
+        wifi::in_background(action).map(|result| Message::PanelMenu(PanelMessage::WifiDone(result)))
+    }
+    fn bluetooth_action(&mut self, action: impl FnOnce() -> Result<(), String> + Send + 'static) -> Task<Message> {
+        self.bluetooth_busy = true;
+        self.bluetooth_error = None;
+        bluetooth::in_background(action).map(|result| Message::PanelMenu(PanelMessage::BluetoothDone(result)))
+    }
+    fn mixer_view(&self, app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, process_icons: &ProcessIcons, text_height: f32, spacing: f32) -> Element<'_, Message> {
+        let toggle = button(text!("{} Applications", if self.mixer_open { "▾" } else { "▸" }).size(text_height * 0.45))
+            .style(transparent_button)
+            .on_press(Message::PanelMenu(PanelMessage::ToggleMixer));
+        if !self.mixer_open {
+            return toggle.into();
+        }
+        let mut streams: Column<Message> = Column::new().spacing(spacing);
+        if self.streams.is_empty() {
+            streams = streams.push(text!("No applications are playing audio").size(text_height * 0.4));
+        }
+        for stream in &self.streams {
+            let icon: Element<Message> = match process_icons.get(stream.pid, &stream.executable) {
+                Some(icon) => image(icon.clone()).width(Length::Fixed(text_height)).height(Length::Fixed(text_height)).into(),
+                None => space().width(Length::Fixed(text_height)).into(),
+            };
+            let id = stream.id.clone();
+            streams = streams.push(
+                row![
+                    icon,
+                    column![
+                        text!("{}", stream.name).size(text_height * 0.4),
+                        slider(
+                            RangeInclusive::new(0.0, 1.0),
+                            stream.volume,
+                            move |value| Message::PanelMenu(PanelMessage::StreamVolume(id.clone(), value))
+                        ).width(Length::Fill).step(0.01)
+                        .style(my_slider),
+                    ].width(Length::Fill),
+                    button(
+                        image(sound_icon(app_image_cache.clone(), stream.volume, stream.muted)).width(Length::Fill).height(Length::Fill),
+                    ).width(Length::Fixed(text_height))
+                    .height(Length::Fixed(text_height))
+                    .style(transparent_button)
+                    .padding(0.0)
+                    .on_press(Message::PanelMenu(PanelMessage::StreamMute(stream.id.clone()))),
+                ].align_y(Alignment::Center).spacing(spacing)
+            );
+        }
+        column![toggle, streams].spacing(spacing).into()
+    }
+    fn media_view(&self, now_playing: Option<&NowPlaying>, taskbar_media: bool, base_size: f32, text_height: f32, spacing: f32) -> Element<'_, Message> {
+        let Some(now_playing) = now_playing else {
+            return space().height(Length::Fixed(0.0)).into();
+        };
+        let state = &now_playing.state;
+        let media_button = |label: &'static str, command: MediaCommand, enabled: bool| {
+            button(text!("{}", label).size(text_height * 0.5))
+                .style(transparent_button)
+                .on_press_maybe(enabled.then_some(Message::MediaControl(command)))
+        };
+        let art: Element<Message> = match &state.art {
+            Some(art) => image(art.clone()).width(Length::Fixed(72.0 * base_size)).height(Length::Fixed(72.0 * base_size)).into(),
+            None => space().width(Length::Fixed(0.0)).into(),
+        };
+        let position = now_playing.position();
+        let seek_bar: Element<Message> = match state.length {
+            Some(length) if state.can_seek => {
+                slider(
+                    RangeInclusive::new(0.0, length.as_secs_f32()),
+                    self.seeking.unwrap_or(position.as_secs_f32()),
+                    |seconds| Message::PanelMenu(PanelMessage::SeekChange(seconds))
+                ).width(Length::Fill)
+                .on_release(Message::PanelMenu(PanelMessage::SeekRelease))
+                .style(my_slider)
+                .into()
+            }
+            Some(_) => progress_bar(RangeInclusive::new(0.0, 1.0), now_playing.progress()).girth(Length::Fixed(4.0 * base_size)).into(),
+            None => space().width(Length::Fill).into(),
+        };
+        let shown_position = self.seeking.map(Duration::from_secs_f32).unwrap_or(position);
+        column![
+            row![
+                art,
+                column![
+                    text!("{}", state.display_title()).size(text_height * 0.5).wrapping(Wrapping::WordOrGlyph),
+                    text!("{}", state.artist).size(text_height * 0.4),
+                    text!("{}", state.album).size(text_height * 0.35),
+                    row![
+                        media_button("⏮", MediaCommand::Previous, state.can_go_previous),
+                        media_button(if state.playing { "⏸" } else { "▶" }, MediaCommand::PlayPause, true),
+                        media_button("⏭", MediaCommand::Next, state.can_go_next),
+                        space().width(Length::Fill),
+                        text!("Taskbar").size(text_height * 0.35),
+                        toggler(taskbar_media)
+                            .on_toggle(Message::TaskbarMedia)
+                            .size(text_height * 0.5),
+                    ].align_y(Alignment::Center).spacing(spacing),
+                ].width(Length::Fill),
+            ].align_y(Alignment::Center).spacing(spacing),
+            row![
+                text!("{}", media::format_time(shown_position)).size(text_height * 0.35),
+                seek_bar,
+                text!("{}", state.length.map(media::format_time).unwrap_or_default()).size(text_height * 0.35),
+            ].align_y(Alignment::Center).spacing(spacing),
+        ].spacing(spacing).into()
+    }
+    fn microphone_view(&self, app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, base_size: f32, text_height: f32, spacing: f32) -> Element<'_, Message> {
+        if self.microphone().is_none() {
+            return space().height(Length::Fixed(0.0)).into();
+        }
+        column![
+            row![
+                button(
+                    image(microphone_icon(app_image_cache, self.input_muted)).width(Length::Fill).height(Length::Fill),
+                ).width(Length::Fixed(36.0 * base_size))
+                .height(Length::Fixed(36.0 * base_size))
+                .style(transparent_button)
+                .padding(0.0)
+                .on_press(Message::PanelMenu(PanelMessage::InputMute)),
+                slider(
+                    RangeInclusive::new(0.0, 1.0),
+                    self.input_volume,
+                    |value| Message::PanelMenu(PanelMessage::InputVolume(value))
+                ).width(Length::Fill).step(0.01)
+                .height(Pixels(36.0 * base_size))
+                .style(my_slider),
+                text!("{}%", (self.input_volume * 100.0).round()).width(Length::Fixed(text_height * 2.0)),
+            ].align_y(Alignment::Center).spacing(spacing),
+            row![
+                space().width(Length::Fixed(36.0 * base_size)),
+                progress_bar(RangeInclusive::new(0.0, 1.0), if self.input_muted { 0.0 } else { self.input_level })
+                    .girth(Length::Fixed(4.0 * base_size)),
+                space().width(Length::Fixed(text_height * 2.0)),
+            ].spacing(spacing),
+        ].into()
+    }
+    fn devices_view(&self, text_height: f32, spacing: f32) -> Element<'_, Message> {
+        let toggle = button(text!("{} Devices", if self.devices_open { "▾" } else { "▸" }).size(text_height * 0.45))
+            .style(transparent_button)
+            .on_press(Message::PanelMenu(PanelMessage::ToggleDevices));
+        if !self.devices_open {
+            return toggle.into();
+        }
+        let mut devices: Column<Message> = Column::new().spacing(spacing);
+        for (title, list) in [("Output", &self.outputs), ("Input", &self.inputs)] {
+            devices = devices.push(text!("{}", title).size(text_height * 0.45));
+            if list.is_empty() {
+                devices = devices.push(text!("No devices found").size(text_height * 0.4));
+            }
+            for device in list {
+                devices = devices.push(
+                    button(text!("{}", device.name).size(text_height * 0.4).wrapping(Wrapping::WordOrGlyph))
+                        .width(Length::Fill)
+                        .style(if device.is_default { selected_button } else { transparent_button })
+                        .on_press_maybe((!device.is_default).then(|| Message::PanelMenu(PanelMessage::SetDefaultDevice(device.clone()))))
+                );
+            }
+        }
+        column![toggle, devices].spacing(spacing).into()
+    }
+    fn brightness_view(&self, text_height: f32, spacing: f32) -> Element<'_, Message> {
+        let mut sliders: Column<Message> = Column::new().spacing(spacing);
+        for (index, display) in self.displays.iter().enumerate() {
+            sliders = sliders.push(
+                column![
+                    text!("{}", display.name).size(text_height * 0.4),
+                    row![
+                        slider(
+                            RangeInclusive::new(0.0, 1.0),
+                            display.brightness,
+                            move |value| Message::PanelMenu(PanelMessage::BrightnessChange(index, value))
+                        ).width(Length::Fill).step(0.01)
+                        .on_release(Message::PanelMenu(PanelMessage::BrightnessRelease(index)))
+                        .style(my_slider),
+                        text!("{}%", (display.brightness * 100.0).round()).width(Length::Fixed(text_height * 2.0)),
+                    ].align_y(Alignment::Center).spacing(spacing),
+                ]
+            );
+        }
+        sliders.into()
+    }
+    fn wifi_view(&self, text_height: f32, spacing: f32) -> Element<'_, Message> {
+        let header = row![
+            text!("Wi-Fi").size(text_height * 0.6).width(Length::Fill),
+            button(text!("Refresh").size(text_height * 0.45))
+                .style(transparent_button)
+                .on_press(Message::PanelMenu(PanelMessage::WifiRefresh)),
+            toggler(self.wifi.radio_enabled)
+                .on_toggle(|enabled| Message::PanelMenu(PanelMessage::WifiRadio(enabled)))
+                .size(text_height * 0.6),
+        ].align_y(Alignment::Center).spacing(spacing);
+        let mut networks: Column<Message> = Column::new().spacing(spacing);
+        if !self.wifi.radio_enabled {
+            networks = networks.push(text!("Wi-Fi is turned off").size(text_height * 0.45));
+        } else if self.wifi.networks.is_empty() {
+            networks = networks.push(text!("No networks found").size(text_height * 0.45));
+        }
+        for network in self.wifi.networks.iter().filter(|_| self.wifi.radio_enabled) {
+            let selected = self.selected_network.as_ref() == Some(&network.ssid);
+            let mut details = network.security.label().to_string();
+            if network.connected {
+                details = format!("Connected, {}", details);
+            } else if network.known {
+                details = format!("Saved, {}", details);
+            }
+            networks = networks.push(
+                button(
+                    row![
+                        column![
+                            text!("{}", network.ssid).size(text_height * 0.5).wrapping(Wrapping::WordOrGlyph),
+                            text!("{}", details).size(text_height * 0.35),
+                        ].width(Length::Fill),
+                        text!("{}%", network.signal).size(text_height * 0.45),
+                    ].align_y(Alignment::Center).spacing(spacing)
+                ).width(Length::Fill)
+                .style(if selected || network.connected { selected_button } else { transparent_button })
+                .on_press(Message::PanelMenu(PanelMessage::WifiSelect(network.ssid.clone())))
+            );
+            if selected {
+                networks = networks.push(self.network_actions(network, text_height, spacing));
+            }
+        }
+        let mut section = column![header].spacing(spacing);
+        if let Some(error) = &self.wifi_error {
+            section = section.push(text!("{}", error).size(text_height * 0.4).color(Color::from_rgb(0.9, 0.4, 0.4)));
+        }
+        section.push(scrollable(networks).height(Length::Fill)).into()
+    }
+    fn network_actions<'a>(&self, network: &'a WifiNetwork, text_height: f32, spacing: f32) -> Element<'a, Message> {
+        let action_button = |label: &'a str, message: PanelMessage| {
+            button(text!("{}", label).size(text_height * 0.45))
+                .style(|theme, status| colored_button(theme, status, Color::from_rgb(0.2, 0.2, 0.7)))
+                .on_press_maybe((!self.wifi_busy).then_some(Message::PanelMenu(message)))
+        };
+        let mut actions = row![].spacing(spacing).align_y(Alignment::Center);
+        if network.connected {
+            actions = actions.push(space().width(Length::Fill)).push(action_button("Disconnect", PanelMessage::WifiDisconnect));
+        } else {
+            if !network.known && network.security.needs_password() {
+                actions = actions.push(
+                    text_input("Password", &self.password)
+                        .secure(true)
+                        .on_input(|password| Message::PanelMenu(PanelMessage::WifiPasswordInput(password)))
+                        .on_submit(Message::PanelMenu(PanelMessage::WifiConnect))
+                        .size(text_height * 0.45)
+                        .width(Length::Fill)
+                );
+            } else {
+                actions = actions.push(space().width(Length::Fill));
+            }
+            actions = actions.push(action_button(if self.wifi_busy { "Connecting..." } else { "Connect" }, PanelMessage::WifiConnect));
+        }
+        if network.known {
+            actions = actions.push(
+                button(text!("Forget").size(text_height * 0.45))
+                    .style(transparent_button)
+                    .on_press_maybe((!self.wifi_busy).then_some(Message::PanelMenu(PanelMessage::WifiForget)))
+            );
+        }
+        actions.into()
+    }
+    fn do_not_disturb_view(&self, settings: &DoNotDisturbSettings, reason: Option<DoNotDisturbReason>, text_height: f32, spacing: f32) -> Element<'_, Message> {
+        let header = row![
+            button(text!("{} Do Not Disturb", if self.do_not_disturb_open { "▾" } else { "▸" }).size(text_height * 0.45))
+                .style(transparent_button)
+                .on_press(Message::PanelMenu(PanelMessage::ToggleDoNotDisturb)),
+            text!("{}", reason.map(DoNotDisturbReason::label).unwrap_or_default()).size(text_height * 0.35).width(Length::Fill),
+            toggler(settings.enabled)
+                .on_toggle(|enabled| Message::DoNotDisturb(DoNotDisturbChange::Enabled(enabled)))
+                .size(text_height * 0.5),
+        ].align_y(Alignment::Center).spacing(spacing);
+        if !self.do_not_disturb_open {
+            return header.into();
+        }
+        let remove_button = |change: DoNotDisturbChange| {
+            button(text!("Remove").size(text_height * 0.4))
+                .style(transparent_button)
+                .on_press(Message::DoNotDisturb(change))
+        };
+        let mut section: Column<Message> = column![
+            header,
+            row![
+                text!("While a window is fullscreen").size(text_height * 0.4).width(Length::Fill),
+                toggler(settings.when_full_screen)
+                    .on_toggle(|enabled| Message::DoNotDisturb(DoNotDisturbChange::WhenFullScreen(enabled)))
+                    .size(text_height * 0.5),
+            ].align_y(Alignment::Center).spacing(spacing),
+            text!("Quiet hours").size(text_height * 0.45),
+        ].spacing(spacing);
+        for (index, schedule) in settings.schedules.iter().enumerate() {
+            section = section.push(
+                row![
+                    text!("{}", schedule.label()).size(text_height * 0.4).width(Length::Fill),
+                    remove_button(DoNotDisturbChange::RemoveSchedule(index)),
+                ].align_y(Alignment::Center).spacing(spacing)
+            );
+        }
+        let mut days = row![].spacing(spacing * 0.5);
+        for (day, name) in ["M", "T", "W", "T", "F", "S", "S"].into_iter().enumerate() {
+            days = days.push(
+                button(text!("{}", name).size(text_height * 0.35))
+                    .style(if self.quiet_days[day] { selected_button } else { transparent_button })
+                    .on_press(Message::PanelMenu(PanelMessage::QuietDay(day)))
+            );
+        }
+        section = section.push(
+            row![
+                text_input("22:00", &self.quiet_start)
+                    .on_input(|start| Message::PanelMenu(PanelMessage::QuietStartInput(start)))
+                    .size(text_height * 0.4)
+                    .width(Length::Fixed(text_height * 2.2)),
+                text!("-").size(text_height * 0.4),
+                text_input("07:00", &self.quiet_end)
+                    .on_input(|end| Message::PanelMenu(PanelMessage::QuietEndInput(end)))
+                    .on_submit(Message::PanelMenu(PanelMessage::AddQuietHours))
+                    .size(text_height * 0.4)
+                    .width(Length::Fixed(text_height * 2.2)),
+                days,
+                space().width(Length::Fill),
+                button(text!("Add").size(text_height * 0.4))
+                    .style(transparent_button)
+                    .on_press(Message::PanelMenu(PanelMessage::AddQuietHours)),
+            ].align_y(Alignment::Center).spacing(spacing)
+        );
+        section = section.push(text!("Allowed applications").size(text_height * 0.45));
+        for (index, app) in settings.allowed_apps.iter().enumerate() {
+            section = section.push(
+                row![
+                    text!("{}", app).size(text_height * 0.4).width(Length::Fill),
+                    remove_button(DoNotDisturbChange::RemoveAllowedApp(index)),
+                ].align_y(Alignment::Center).spacing(spacing)
+            );
+        }
+        section = section.push(
+            row![
+                text_input("Application name", &self.allowed_app)
+                    .on_input(|app| Message::PanelMenu(PanelMessage::AllowedAppInput(app)))
+                    .on_submit(Message::PanelMenu(PanelMessage::AddAllowedApp))
+                    .size(text_height * 0.4)
+                    .width(Length::Fill),
+                button(text!("Add").size(text_height * 0.4))
+                    .style(transparent_button)
+                    .on_press(Message::PanelMenu(PanelMessage::AddAllowedApp)),
+            ].align_y(Alignment::Center).spacing(spacing)
+        );
+        if let Some(error) = &self.do_not_disturb_error {
+            section = section.push(text!("{}", error).size(text_height * 0.4).color(Color::from_rgb(0.9, 0.4, 0.4)));
+        }
+        section.into()
+    }
+    fn notifications_view<'a>(&self, history: &'a [Notification], text_height: f32, spacing: f32) -> Element<'a, Message> {
+        let mut header = row![
+            button(text!("{} Notifications ({})", if self.notifications_open { "▾" } else { "▸" }, history.len()).size(text_height * 0.45))
+                .style(transparent_button)
+                .on_press(Message::PanelMenu(PanelMessage::ToggleNotifications)),
+            space().width(Length::Fill),
+        ].align_y(Alignment::Center).spacing(spacing);
+        if !history.is_empty() {
+            header = header.push(
+                button(text!("Clear all").size(text_height * 0.4))
+                    .style(transparent_button)
+                    .on_press(Message::NotificationsClear)
+            );
+        }
+        if !self.notifications_open {
+            return header.into();
+        }
+        let mut notifications: Column<Message> = Column::new().spacing(spacing);
+        if history.is_empty() {
+            notifications = notifications.push(text!("No notifications").size(text_height * 0.4));
+        }
+        for notification in history {
+            let app_icon: Element<Message> = match &notification.app_icon {
+                Some(icon) => image(icon.clone()).width(Length::Fixed(text_height * 0.5)).height(Length::Fixed(text_height * 0.5)).into(),
+                None => space().width(Length::Fixed(0.0)).into(),
+            };
+            notifications = notifications.push(
+                row![
+                    column![
+                        row![
+                            app_icon,
+                            text!("{}  {}", notification.app_name, notification.received.format("%H:%M")).size(text_height * 0.35),
+                        ].align_y(Alignment::Center).spacing(spacing),
+                        text!("{}", notification.summary).size(text_height * 0.45).wrapping(Wrapping::WordOrGlyph),
+                        text!("{}", notification.body).size(text_height * 0.35).wrapping(Wrapping::WordOrGlyph),
+                    ].width(Length::Fill),
+                    button(text!("✕").size(text_height * 0.35))
+                        .style(transparent_button)
+                        .on_press(Message::NotificationForget(notification.id)),
+                ].spacing(spacing)
+            );
+        }
+        column![header, notifications].spacing(spacing).into()
+    }
+    fn bluetooth_view(&self, text_height: f32, spacing: f32) -> Element<'_, Message> {
+        if !self.bluetooth.available {
+            return space().height(Length::Fixed(0.0)).into();
+        }
+        let mut header = row![
+            button(text!("{} Bluetooth", if self.bluetooth_open { "▾" } else { "▸" }).size(text_height * 0.45))
+                .style(transparent_button)
+                .on_press(Message::PanelMenu(PanelMessage::ToggleBluetooth)),
+            space().width(Length::Fill),
+        ].align_y(Alignment::Center).spacing(spacing);
+        if self.bluetooth_open && self.bluetooth.powered {
+            header = header.push(
+                button(text!("{}", if self.bluetooth_scanning { "Stop" } else { "Add device" }).size(text_height * 0.45))
+                    .style(if self.bluetooth_scanning { selected_button } else { transparent_button })
+                    .on_press(Message::PanelMenu(PanelMessage::BluetoothScan))
+            );
+        }
+        header = header.push(
+            toggler(self.bluetooth.powered)
+                .on_toggle(|powered| Message::PanelMenu(PanelMessage::BluetoothPower(powered)))
+                .size(text_height * 0.5)
+        );
+        if !self.bluetooth_open {
+            return header.into();
+        }
+        let mut devices: Column<Message> = Column::new().spacing(spacing);
+        if !self.bluetooth.powered {
+            devices = devices.push(text!("Bluetooth is turned off").size(text_height * 0.4));
+        } else if self.bluetooth.devices.iter().all(|device| !device.paired) && !self.bluetooth_scanning {
+            devices = devices.push(text!("No paired devices").size(text_height * 0.4));
+        } else if self.bluetooth_scanning {
+            devices = devices.push(text!("Looking for devices...").size(text_height * 0.4));
+        }
+        // Devices that are not paired only stay listed while looking for new ones
+        let shown = self.bluetooth.devices.iter().filter(|device| self.bluetooth.powered && (device.paired || self.bluetooth_scanning));
+        for device in shown {
+            let selected = self.selected_bluetooth.as_ref() == Some(&device.id);
+            let mut details = if device.connected {
+                "Connected".to_string()
+            } else if device.paired {
+                "Paired".to_string()
+            } else {
+                "Not paired".to_string()
+            };
+            if let Some(battery) = device.battery {
+                details = format!("{}, battery {}%", details, battery);
+            }
+            devices = devices.push(
+                button(
+                    column![
+                        text!("{}", device.name).size(text_height * 0.45).wrapping(Wrapping::WordOrGlyph),
+                        text!("{}", details).size(text_height * 0.35),
+                    ]
+                ).width(Length::Fill)
+                .style(if selected || device.connected { selected_button } else { transparent_button })
+                .on_press(Message::PanelMenu(PanelMessage::BluetoothSelect(device.id.clone())))
+            );
+            if selected {
+                devices = devices.push(self.bluetooth_actions(device, text_height, spacing));
+            }
+        }
+        let mut section = column![header, devices].spacing(spacing);
+        if let Some(error) = &self.bluetooth_error {
+            section = section.push(text!("{}", error).size(text_height * 0.4).color(Color::from_rgb(0.9, 0.4, 0.4)));
+        }
+        section.into()
+    }
+    fn bluetooth_actions<'a>(&self, device: &'a BluetoothDevice, text_height: f32, spacing: f32) -> Element<'a, Message> {
+        let action_button = |label: &'a str, message: PanelMessage| {
+            button(text!("{}", label).size(text_height * 0.45))
+                .style(|theme, status| colored_button(theme, status, Color::from_rgb(0.2, 0.2, 0.7)))
+                .on_press_maybe((!self.bluetooth_busy).then_some(Message::PanelMenu(message)))
+        };
+        let mut actions = row![space().width(Length::Fill)].spacing(spacing).align_y(Alignment::Center);
+        if !device.paired {
+            actions = actions.push(action_button(if self.bluetooth_busy { "Pairing..." } else { "Pair" }, PanelMessage::BluetoothPair));
+        } else if device.connected {
+            actions = actions.push(action_button("Disconnect", PanelMessage::BluetoothDisconnect));
+        } else {
+            actions = actions.push(action_button(if self.bluetooth_busy { "Connecting..." } else { "Connect" }, PanelMessage::BluetoothConnect));
+        }
+        if device.paired {
+            actions = actions.push(
+                button(text!("Forget").size(text_height * 0.45))
+                    .style(transparent_button)
+                    .on_press_maybe((!self.bluetooth_busy).then_some(Message::PanelMenu(PanelMessage::BluetoothForget)))
+            );
         }
+        actions.into()
     }
-    pub fn view(&self, app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>,base_size: f32,battery: Option<(f32,bool)>,wifi_status: WifiStatus, system_volume: f32, volume_muted: bool) -> Element<'_, Message> {
+    pub fn view<'a>(&'a self, app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>,process_icons: ProcessIcons,base_size: f32,status: SystemStatus,shell_settings: &ShellSettings, notification_history: &'a [Notification]) -> Element<'a, Message> {
+        let SystemStatus { battery, wifi_status, system_volume, volume_muted, now_playing, do_not_disturb, .. } = status;
+        let (taskbar_media, do_not_disturb_settings) = (shell_settings.taskbar_media, &shell_settings.do_not_disturb);
         let spacing = base_size * 2.0;
         let text_height = 30.0 * base_size;
         let battery_icon: Element<Message> = if let Some((battery_level,charging)) = battery {
@@ -83,13 +1027,21 @@ impl PanelMenu {
                         slider(
                             RangeInclusive::new(0.0, 1.0),
                             system_volume,
-                            |value|
-                            Message::VolumeChange(value)
+                            Message::VolumeChange
                         ).width(Length::Fill).step(0.01)
                         .height(Pixels(36.0 * base_size))
-                        .style(move |theme,status| my_slider(theme,status)),
+                        .style(my_slider),
                         text!("{}%", (system_volume * 100.0).round()).width(Length::Fixed(text_height * 2.0)),
                     ].align_y(Alignment::Center).spacing(spacing),
+                    self.microphone_view(app_image_cache.clone(), base_size, text_height, spacing),
+                    self.media_view(now_playing, taskbar_media, base_size, text_height, spacing),
+                    self.mixer_view(app_image_cache.clone(), &process_icons, text_height, spacing),
+                    self.devices_view(text_height, spacing),
+                    self.brightness_view(text_height, spacing),
+                    self.do_not_disturb_view(do_not_disturb_settings, do_not_disturb, text_height, spacing),
+                    self.notifications_view(notification_history, text_height, spacing),
+                    self.bluetooth_view(text_height, spacing),
+                    self.wifi_view(text_height, spacing),
                 ].width(Length::FillPortion(4)).height(Length::Fill).spacing(spacing),
                 column![
                     battery_icon,
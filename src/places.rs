@@ -0,0 +1,286 @@
+#[cfg(windows)]
+use crate::shell_link::ShellLink;
+use std::path::{Path, PathBuf};
+#[cfg(windows)]
+use std::time::SystemTime;
+
+const MAX_RECENT: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaceKind {
+    Folder,
+    Bookmark,
+    Drive,
+    Recent,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Place {
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: PlaceKind,
+}
+impl Place {
+    fn new(name: String, path: PathBuf, kind: PlaceKind) -> Self {
+        Self { name, path, kind }
+    }
+}
+
+/// Locations for the Places pane of the start menu, read again each time the pane is shown.
+#[derive(Debug, Clone, Default)]
+pub struct Places {
+    pub folders: Vec<Place>,
+    pub bookmarks: Vec<Place>,
+    pub drives: Vec<Place>,
+    pub recent: Vec<Place>,
+}
+impl Places {
+    pub fn load(bookmarks: &[PathBuf]) -> Self {
+        Self {
+            folders: user_folders(),
+            bookmarks: bookmarks.iter()
+                .map(|path| Place::new(display_name(path), path.clone(), PlaceKind::Bookmark))
+                .collect(),
+            drives: mounted_drives(),
+            recent: recent_documents(MAX_RECENT),
+        }
+    }
+
+    /// Every place in the order the pane lists them.
+    pub fn all(&self) -> impl Iterator<Item = &Place> {
+        self.folders.iter()
+            .chain(self.bookmarks.iter())
+            .chain(self.drives.iter())
+            .chain(self.recent.iter())
+    }
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string())
+}
+
+fn user_folders() -> Vec<Place> {
+    let folders = [
+        ("Home", dirs::home_dir()),
+        ("Desktop", dirs::desktop_dir()),
+        ("Documents", dirs::document_dir()),
+        ("Downloads", dirs::download_dir()),
+        ("Pictures", dirs::picture_dir()),
+        ("Music", dirs::audio_dir()),
+        ("Videos", dirs::video_dir()),
+    ];
+    let mut places: Vec<Place> = Vec::new();
+    for (name, folder) in folders {
+        // Unset XDG folders fall back to the home folder, which is already listed
+        if let Some(folder) = folder && folder.is_dir() && !places.iter().any(|place| place.path == folder) {
+            places.push(Place::new(name.to_string(), folder, PlaceKind::Folder));
+        }
+    }
+    places
+}
+
+#[cfg(windows)]
+fn mounted_drives() -> Vec<Place> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Storage::FileSystem::{GetDriveTypeW, GetLogicalDrives, GetVolumeInformationW};
+    // GetDriveTypeW results, see winbase.h
+    const DRIVE_NO_ROOT_DIR: u32 = 1;
+    const DRIVE_REMOVABLE: u32 = 2;
+    const DRIVE_FIXED: u32 = 3;
+    const DRIVE_REMOTE: u32 = 4;
+    const DRIVE_CDROM: u32 = 5;
+
+    let mut drives: Vec<Place> = Vec::new();
+    let mask = unsafe { GetLogicalDrives() };
+    for index in 0..26u32 {
+        if mask & (1 << index) == 0 {
+            continue;
+        }
+        let letter = (b'A' + index as u8) as char;
+        let root = format!("{}:\\", letter);
+        let wide_root: Vec<u16> = root.encode_utf16().chain(std::iter::once(0)).collect();
+        let drive_type = unsafe { GetDriveTypeW(PCWSTR(wide_root.as_ptr())) };
+        if drive_type == DRIVE_NO_ROOT_DIR {
+            continue;
+        }
+        // Reading the label of a network share can stall until it times out, so only local volumes are asked
+        let mut label = String::new();
+        if drive_type == DRIVE_FIXED || drive_type == DRIVE_REMOVABLE {
+            let mut buffer = [0u16; 261];
+            if unsafe { GetVolumeInformationW(PCWSTR(wide_root.as_ptr()), Some(&mut buffer), None, None, None, None) }.is_ok() {
+                let len = buffer.iter().position(|c| *c == 0).unwrap_or(buffer.len());
+                label = String::from_utf16_lossy(&buffer[..len]);
+            }
+        }
+        if label.is_empty() {
+            label = match drive_type {
+                DRIVE_REMOVABLE => "Removable Disk",
+                DRIVE_REMOTE => "Network Drive",
+                DRIVE_CDROM => "CD Drive",
+                _ => "Local Disk",
+            }.to_string();
+        }
+        drives.push(Place::new(format!("{} ({}:)", label, letter), PathBuf::from(root), PlaceKind::Drive));
+    }
+    drives
+}
+
+/// The root file system and whatever is mounted where desktops put removable media and extra disks.
+#[cfg(target_os = "linux")]
+fn mounted_drives() -> Vec<Place> {
+    let mut drives: Vec<Place> = vec![Place::new("File System".to_string(), PathBuf::from("/"), PlaceKind::Drive)];
+    let mounts = match std::fs::read_to_string("/proc/self/mounts") {
+        Ok(mounts) => mounts,
+        Err(e) => {
+            eprintln!("Error reading mounts: {}", e);
+            return drives;
+        }
+    };
+    for line in mounts.lines() {
+        let mut fields = line.split_whitespace();
+        let (Some(device), Some(mount_point)) = (fields.next(), fields.next()) else {
+            continue;
+        };
+        let mount_point = PathBuf::from(unescape_mount_field(mount_point));
+        let is_media = ["/media", "/run/media", "/mnt"].iter().any(|parent| mount_point.starts_with(parent) && mount_point != Path::new(parent));
+        if !is_media || !(device.starts_with("/dev/") || device.contains(':')) {
+            continue;
+        }
+        if drives.iter().any(|drive| drive.path == mount_point) {
+            continue;
+        }
+        drives.push(Place::new(display_name(&mount_point), mount_point, PlaceKind::Drive));
+    }
+    drives
+}
+
+/// Mount tables escape spaces, tabs, newlines and backslashes as three digit octal codes.
+#[cfg(target_os = "linux")]
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut unescaped: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'\\' && let Some(Ok(code)) = field.get(index + 1..index + 4).map(|digits| u8::from_str_radix(digits, 8)) {
+            unescaped.push(code);
+            index += 4;
+        } else {
+            unescaped.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8_lossy(&unescaped).to_string()
+}
+
+/// Documents the shell recorded in the user's Recent folder, newest first.
+#[cfg(windows)]
+fn recent_documents(limit: usize) -> Vec<Place> {
+    let Some(recent_dir) = dirs::data_dir().map(|data_dir| data_dir.join(r"Microsoft\Windows\Recent")) else {
+        return Vec::new();
+    };
+    let entries = match std::fs::read_dir(&recent_dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Error reading recent documents: {}", e);
+            return Vec::new();
+        }
+    };
+    let mut links: Vec<(SystemTime, PathBuf)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "lnk"))
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|metadata| metadata.modified()).ok()?;
+            Some((modified, path))
+        })
+        .collect();
+    links.sort_by(|a, b| b.0.cmp(&a.0));
+    let mut places: Vec<Place> = Vec::new();
+    for (_, link_path) in links {
+        let Ok(link) = ShellLink::from_path(&link_path) else {
+            continue;
+        };
+        // Folders are recorded next to documents, the pane only lists files that are still there
+        let Some(target) = link.resolved_target(&link_path).filter(|target| target.is_file()) else {
+            continue;
+        };
+        if places.iter().any(|place| place.path == target) {
+            continue;
+        }
+        places.push(Place::new(display_name(&target), target, PlaceKind::Recent));
+        if places.len() == limit {
+            break;
+        }
+    }
+    places
+}
+
+/// Documents from the freedesktop.org recently used list, newest first.
+#[cfg(target_os = "linux")]
+fn recent_documents(limit: usize) -> Vec<Place> {
+    let Some(xbel_file) = dirs::data_dir().map(|data_dir| data_dir.join("recently-used.xbel")) else {
+        return Vec::new();
+    };
+    match std::fs::read_to_string(&xbel_file) {
+        Ok(xbel) => parse_recently_used(&xbel, limit),
+        Err(e) => {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                eprintln!("Error reading recent documents: {}", e);
+            }
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn parse_recently_used(xbel: &str, limit: usize) -> Vec<Place> {
+    let document = match roxmltree::Document::parse(xbel) {
+        Ok(document) => document,
+        Err(e) => {
+            eprintln!("Error parsing recently used list: {}", e);
+            return Vec::new();
+        }
+    };
+    // Timestamps are ISO 8601 in UTC, which sort correctly as plain text
+    let mut bookmarks: Vec<(&str, PathBuf)> = document.descendants()
+        .filter(|node| node.has_tag_name("bookmark"))
+        .filter_map(|node| {
+            let path = file_uri_to_path(node.attribute("href")?)?;
+            let modified = node.attribute("modified").or(node.attribute("visited")).unwrap_or_default();
+            Some((modified, path))
+        })
+        .filter(|(_, path)| path.is_file())
+        .collect();
+    bookmarks.sort_by(|a, b| b.0.cmp(a.0));
+    bookmarks.into_iter()
+        .take(limit)
+        .map(|(_, path)| Place::new(display_name(&path), path, PlaceKind::Recent))
+        .collect()
+}
+
+/// Turns a local `file://` URI into a path, undoing its percent encoding.
+#[cfg(target_os = "linux")]
+fn file_uri_to_path(uri: &str) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStringExt;
+    let encoded = uri.strip_prefix("file://")?;
+    // Only local files can be opened, a host part other than localhost names another machine
+    let encoded = encoded.strip_prefix("localhost").unwrap_or(encoded);
+    if !encoded.starts_with('/') {
+        return None;
+    }
+    let bytes = encoded.as_bytes();
+    let mut decoded: Vec<u8> = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' && let Some(Ok(byte)) = encoded.get(index + 1..index + 3).map(|digits| u8::from_str_radix(digits, 16)) {
+            decoded.push(byte);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    Some(PathBuf::from(std::ffi::OsString::from_vec(decoded)))
+}
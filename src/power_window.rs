@@ -9,7 +9,7 @@ use std::time::Instant;
 use dirs::data_dir;
 use iced::advanced::image::Handle;
 use crate::raw_icons::start_icon;
-use crate::sys_util::{lock, logoff, windows_power};
+use crate::sys_util::{lock, logoff, shutdown};
 
 #[derive(Debug, Clone)]
 pub enum PowerOptions {
@@ -34,19 +34,21 @@ pub struct PowerWindow {
 }
 impl PowerWindow {
     pub fn new(target: PowerOptions) -> (Self,Task<window::Id>) {
-        let mut settings = window::Settings::default();
-        settings.decorations = false;
-        settings.resizable = false;
-        settings.min_size = None;
-        settings.max_size = None;
-        settings.icon = None;
-        settings.transparent = true;
-        settings.closeable = false;
-        settings.minimizable = false;
-        settings.fullscreen = false;
-        settings.position = window::Position::Centered;
-        settings.size = Size::new(500.0,300.0);
-        settings.level = window::Level::AlwaysOnTop;
+        let settings = window::Settings {
+            decorations: false,
+            resizable: false,
+            min_size: None,
+            max_size: None,
+            icon: None,
+            transparent: true,
+            closeable: false,
+            minimizable: false,
+            fullscreen: false,
+            position: window::Position::Centered,
+            size: Size::new(500.0,300.0),
+            level: window::Level::AlwaysOnTop,
+            ..window::Settings::default()
+        };
         let (id,open_task) = window::open(settings);
         (Self {
             id,
@@ -63,7 +65,7 @@ impl PowerWindow {
             PowerMenuMessage::Execute(option) => {
                 match option {
                     PowerOptions::Shutdown => {
-                        let result = windows_power(false);
+                        let result = shutdown(false);
                         match result {
                             Ok(_) => {
                                 iced::exit()
@@ -75,7 +77,7 @@ impl PowerWindow {
                         }
                     }
                     PowerOptions::LogOff => {
-                        let result: windows::core::Result<()> = logoff();
+                        let result = logoff();
                         match result {
                             Ok(_) => {
                                 iced::exit()
@@ -99,7 +101,7 @@ impl PowerWindow {
                         }
                     }
                     PowerOptions::Reboot => {
-                        let result = windows_power(true);
+                        let result = shutdown(true);
                         match result {
                             Ok(_) => {
                                 iced::exit()
@@ -0,0 +1,131 @@
+use iced::futures::channel::mpsc;
+use iced::futures::{SinkExt, Stream, StreamExt};
+use iced::Subscription;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::path::PathBuf;
+
+/// A change below one of the watched program folders.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgramsChange {
+    Added(PathBuf),
+    Removed(PathBuf),
+    Modified(PathBuf),
+    /// The watcher lost events, so the whole tree has to be read again.
+    Rescan,
+}
+
+/// Follows the folders start menu entries come from, see `StartCatalog::watched_dirs`.
+pub fn watch() -> Subscription<ProgramsChange> {
+    Subscription::run(watch_programs)
+}
+
+fn watch_programs() -> impl Stream<Item = ProgramsChange> {
+    iced::stream::channel(100, async |mut output: mpsc::Sender<ProgramsChange>| {
+        let (sender, mut receiver) = mpsc::unbounded::<notify::Result<Event>>();
+        let mut watcher = match notify::recommended_watcher(move |event: notify::Result<Event>| {
+            let _ = sender.unbounded_send(event);
+        }) {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                eprintln!("Error creating programs watcher: {}", e);
+                return;
+            }
+        };
+        for dir in crate::start_menu::StartCatalog::watched_dirs() {
+            // Missing XDG folders are common and simply not watched
+            if dir.is_dir() && let Err(e) = watcher.watch(&dir, RecursiveMode::Recursive) {
+                eprintln!("Error watching {:?}: {}", dir, e);
+            }
+        }
+        while let Some(event) = receiver.next().await {
+            match event {
+                Ok(event) => {
+                    for change in changes_from_event(event) {
+                        if output.send(change).await.is_err() {
+                            return;
+                        }
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error watching programs: {}", e);
+                }
+            }
+        }
+    })
+}
+
+fn changes_from_event(event: Event) -> Vec<ProgramsChange> {
+    if event.need_rescan() {
+        return vec![ProgramsChange::Rescan];
+    }
+    let paths = event.paths;
+    match event.kind {
+        EventKind::Create(_) => paths.into_iter().map(ProgramsChange::Added).collect(),
+        EventKind::Remove(_) => paths.into_iter().map(ProgramsChange::Removed).collect(),
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => paths.into_iter().map(ProgramsChange::Removed).collect(),
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => paths.into_iter().map(ProgramsChange::Added).collect(),
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if paths.len() == 2 => {
+            let mut paths = paths.into_iter();
+            match (paths.next(), paths.next()) {
+                (Some(from), Some(to)) => vec![ProgramsChange::Removed(from), ProgramsChange::Added(to)],
+                _ => Vec::new(),
+            }
+        }
+        // Backends that cannot tell the two sides of a rename apart report each path on its own
+        EventKind::Modify(ModifyKind::Name(_)) => paths.into_iter()
+            .map(|path| if path.exists() { ProgramsChange::Added(path) } else { ProgramsChange::Removed(path) })
+            .collect(),
+        EventKind::Modify(_) => paths.into_iter().map(ProgramsChange::Modified).collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, DataChange, Flag, RemoveKind};
+    use std::path::Path;
+
+    fn event(kind: EventKind, paths: &[&Path]) -> Event {
+        paths.iter().fold(Event::new(kind), |event, path| event.add_path(path.to_path_buf()))
+    }
+
+    #[test]
+    fn turns_events_into_changes() {
+        let a = Path::new("/programs/a.desktop");
+        let b = Path::new("/programs/b.desktop");
+        assert_eq!(changes_from_event(event(EventKind::Create(CreateKind::File), &[a])), vec![ProgramsChange::Added(a.to_path_buf())]);
+        assert_eq!(changes_from_event(event(EventKind::Remove(RemoveKind::Any), &[a])), vec![ProgramsChange::Removed(a.to_path_buf())]);
+        assert_eq!(
+            changes_from_event(event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), &[a])),
+            vec![ProgramsChange::Modified(a.to_path_buf())],
+        );
+        assert_eq!(
+            changes_from_event(event(EventKind::Modify(ModifyKind::Name(RenameMode::Both)), &[a, b])),
+            vec![ProgramsChange::Removed(a.to_path_buf()), ProgramsChange::Added(b.to_path_buf())],
+        );
+        assert_eq!(
+            changes_from_event(event(EventKind::Modify(ModifyKind::Name(RenameMode::From)), &[a])),
+            vec![ProgramsChange::Removed(a.to_path_buf())],
+        );
+        assert_eq!(
+            changes_from_event(event(EventKind::Modify(ModifyKind::Name(RenameMode::To)), &[b])),
+            vec![ProgramsChange::Added(b.to_path_buf())],
+        );
+        assert!(changes_from_event(event(EventKind::Access(AccessKind::Any), &[a])).is_empty());
+        assert_eq!(changes_from_event(Event::new(EventKind::Other).set_flag(Flag::Rescan)), vec![ProgramsChange::Rescan]);
+    }
+
+    #[test]
+    fn checks_the_disk_when_the_rename_side_is_unknown() {
+        let dir = tempfile::tempdir().unwrap();
+        let present = dir.path().join("present.desktop");
+        let gone = dir.path().join("gone.desktop");
+        std::fs::write(&present, "").unwrap();
+        assert_eq!(
+            changes_from_event(event(EventKind::Modify(ModifyKind::Name(RenameMode::Any)), &[&gone, &present])),
+            vec![ProgramsChange::Removed(gone), ProgramsChange::Added(present)],
+        );
+    }
+}
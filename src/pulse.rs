@@ -0,0 +1,364 @@
+use crate::audio_devices::{AudioDevice, DeviceDirection};
+use crate::mixer::AudioStream;
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Command, Stdio};
+
+// PA_VOLUME_NORM, the volume of a stream at 100%
+const VOLUME_NORM: f64 = 65536.0;
+// Client name of the level meter's recording stream, which is not counted as the microphone being in use
+const METER_CLIENT: &str = "frostwin-level-meter";
+// Samples per second the level meter records, peaks are reported for every twentieth of a second
+const METER_RATE: usize = 8000;
+
+/// Runs `pactl`, which talks to PulseAudio or PipeWire's PulseAudio server, and returns what it printed.
+pub fn pactl(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("pactl").args(args).output().map_err(|e| format!("Error running pactl: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("pactl {} failed: {}", args.join(" "), String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Lists objects of one kind, like `sink-inputs`, in pactl's JSON format.
+pub fn list(kind: &str) -> Result<Vec<Value>, String> {
+    let output = pactl(&["--format=json", "list", kind])?;
+    match serde_json::from_str::<Value>(&output) {
+        Ok(Value::Array(objects)) => Ok(objects),
+        Ok(_) => Ok(Vec::new()),
+        Err(e) => Err(format!("Error reading pactl output: {}", e)),
+    }
+}
+
+/// The average volume over all channels, 1.0 being 100%.
+pub fn volume(object: &Value) -> f32 {
+    let Some(channels) = object["volume"].as_object() else {
+        return 0.0;
+    };
+    let values: Vec<f64> = channels.values().filter_map(|channel| channel["value"].as_f64()).collect();
+    if values.is_empty() {
+        return 0.0;
+    }
+    (values.iter().sum::<f64>() / values.len() as f64 / VOLUME_NORM) as f32
+}
+
+/// The volume as a pactl argument, which sets every channel to it.
+pub fn volume_argument(volume: f32) -> String {
+    format!("{}", (volume.max(0.0) as f64 * VOLUME_NORM).round() as u32)
+}
+
+fn property<'a>(object: &'a Value, name: &str) -> Option<&'a str> {
+    object["properties"][name].as_str().filter(|value| !value.is_empty())
+}
+
+/// Streams currently playing to any sink.
+pub fn sink_inputs() -> Result<Vec<AudioStream>, String> {
+    Ok(list("sink-inputs")?.iter().filter_map(sink_input).collect())
+}
+
+pub fn sink_input(object: &Value) -> Option<AudioStream> {
+    let index = object["index"].as_u64()?;
+    let executable = property(object, "application.process.binary").unwrap_or_default().to_string();
+    let name = property(object, "application.name")
+        .or_else(|| property(object, "media.name"))
+        .map(str::to_string)
+        .unwrap_or_else(|| executable.clone());
+    Some(AudioStream {
+        id: index.to_string(),
+        pid: property(object, "application.process.id").and_then(|pid| pid.parse().ok()),
+        name,
+        executable,
+        volume: volume(object),
+        muted: object["mute"].as_bool().unwrap_or(false),
+    })
+}
+
+pub fn set_sink_input_volume(index: &str, volume: f32) -> Result<(), String> {
+    pactl(&["set-sink-input-volume", index, &volume_argument(volume)]).map(|_| ())
+}
+
+pub fn set_sink_input_mute(index: &str, muted: bool) -> Result<(), String> {
+    pactl(&["set-sink-input-mute", index, if muted { "1" } else { "0" }]).map(|_| ())
+}
+
+fn server_info() -> Result<Value, String> {
+    serde_json::from_str(&pactl(&["--format=json", "info"])?).map_err(|e| format!("Error reading pactl output: {}", e))
+}
+
+/// Name of the default sink or source.
+fn default_name(direction: DeviceDirection) -> Result<String, String> {
+    let key = match direction {
+        DeviceDirection::Output => "default_sink_name",
+        DeviceDirection::Input => "default_source_name",
+    };
+    Ok(server_info()?[key].as_str().unwrap_or_default().to_string())
+}
+
+/// Monitor sources record what a sink plays rather than a microphone.
+fn is_monitor(source: &Value) -> bool {
+    source["monitor_of_sink"].as_str().is_some_and(|sink| sink != "n/a")
+        || property(source, "device.class") == Some("monitor")
+}
+
+/// Sinks or sources, leaving out monitors.
+pub fn devices(direction: DeviceDirection) -> Result<Vec<AudioDevice>, String> {
+    let default = default_name(direction)?;
+    let objects = match direction {
+        DeviceDirection::Output => list("sinks")?,
+        DeviceDirection::Input => list("sources")?,
+    };
+    Ok(audio_devices(&objects, &default, direction))
+}
+
+fn audio_devices(objects: &[Value], default: &str, direction: DeviceDirection) -> Vec<AudioDevice> {
+    objects.iter().filter(|object| !is_monitor(object)).filter_map(|object| {
+        let id = object["name"].as_str()?.to_string();
+        Some(AudioDevice {
+            name: object["description"].as_str().filter(|name| !name.is_empty()).unwrap_or(&id).to_string(),
+            is_default: id == default,
+            id,
+            direction,
+        })
+    }).collect()
+}
+
+/// Sets the default sink or source and moves the streams already playing or recording to it, which PulseAudio
+/// itself leaves where they are.
+pub fn set_default_device(device: &AudioDevice) -> Result<(), String> {
+    let (set_default, streams, move_stream) = match device.direction {
+        DeviceDirection::Output => ("set-default-sink", "sink-inputs", "move-sink-input"),
+        DeviceDirection::Input => ("set-default-source", "source-outputs", "move-source-output"),
+    };
+    pactl(&[set_default, &device.id])?;
+    for stream in list(streams)? {
+        if let Some(index) = stream["index"].as_u64()
+            && let Err(e) = pactl(&[move_stream, &index.to_string(), &device.id]) {
+            // Streams can end or refuse to move, the rest still follow
+            eprintln!("Error moving audio stream: {}", e);
+        }
+    }
+    Ok(())
+}
+
+/// Volume and mute of the default output, the one the taskbar's volume icon stands for.
+pub fn default_sink_state() -> Result<(f32, bool), String> {
+    let default = default_name(DeviceDirection::Output)?;
+    match list("sinks")?.iter().find(|sink| sink["name"].as_str() == Some(default.as_str())) {
+        Some(sink) => Ok((volume(sink), sink["mute"].as_bool().unwrap_or(false))),
+        None => Err("No default output".to_string()),
+    }
+}
+
+pub fn set_default_sink_state(volume: f32, muted: bool) -> Result<(), String> {
+    pactl(&["set-sink-volume", "@DEFAULT_SINK@", &volume_argument(volume)])?;
+    pactl(&["set-sink-mute", "@DEFAULT_SINK@", if muted { "1" } else { "0" }]).map(|_| ())
+}
+
+pub fn default_source_state() -> Result<(f32, bool), String> {
+    let default = default_name(DeviceDirection::Input)?;
+    match list("sources")?.iter().find(|source| source["name"].as_str() == Some(default.as_str())) {
+        Some(source) => Ok((volume(source), source["mute"].as_bool().unwrap_or(false))),
+        None => Err("No default microphone".to_string()),
+    }
+}
+
+pub fn set_default_source_state(volume: f32, muted: bool) -> Result<(), String> {
+    pactl(&["set-source-volume", "@DEFAULT_SOURCE@", &volume_argument(volume)])?;
+    pactl(&["set-source-mute", "@DEFAULT_SOURCE@", if muted { "1" } else { "0" }]).map(|_| ())
+}
+
+/// Whether any application other than our level meter is recording from a source that is not a monitor.
+pub fn recording_from_source() -> Result<bool, String> {
+    Ok(recording(&list("sources")?, &list("source-outputs")?))
+}
+
+fn recording(sources: &[Value], source_outputs: &[Value]) -> bool {
+    let monitors: Vec<u64> = sources.iter()
+        .filter(|source| is_monitor(source))
+        .filter_map(|source| source["index"].as_u64())
+        .collect();
+    source_outputs.iter().any(|output| {
+        property(output, "application.name") != Some(METER_CLIENT)
+            && output["source"].as_u64().is_none_or(|source| !monitors.contains(&source))
+    })
+}
+
+/// Records from a source with `parec`, calling `on_peak` with the peak level of every twentieth of a second until it
+/// returns false.
+pub fn record_peaks(source: &str, mut on_peak: impl FnMut(f32) -> bool) -> Result<(), String> {
+    let rate = METER_RATE.to_string();
+    let device = format!("--device={}", source);
+    let mut child = Command::new("parec")
+        .args(["--raw", "--format=s16le", "--channels=1", "--rate", &rate, "--latency-msec=50", &device])
+        .args(["--client-name", METER_CLIENT, "--stream-name", "Level meter"])
+        .stdout(Stdio::piped())
+        .stdin(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Error running parec: {}", e))?;
+    let Some(mut stdout) = child.stdout.take() else {
+        return Ok(());
+    };
+    let mut buffer = vec![0u8; METER_RATE / 20 * 2];
+    while stdout.read_exact(&mut buffer).is_ok() && on_peak(peak(&buffer)) {}
+    let _ = child.kill();
+    let _ = child.wait();
+    Ok(())
+}
+
+/// The loudest of a run of signed 16 bit little endian samples, 1.0 being full scale.
+pub fn peak(samples: &[u8]) -> f32 {
+    samples.chunks_exact(2)
+        .map(|sample| (i16::from_le_bytes([sample[0], sample[1]]) as i32).unsigned_abs())
+        .max()
+        .unwrap_or(0) as f32 / 32768.0
+}
+
+/// Follows `pactl subscribe`, calling `on_change` with the kind of every object that changed, like `sink-input`,
+/// until the server goes away or `on_change` returns false.
+pub fn subscribe(mut on_change: impl FnMut(&str) -> bool) -> Result<(), String> {
+    let mut child = Command::new("pactl")
+        .arg("subscribe")
+        .stdout(Stdio::piped())
+        .stdin(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Error running pactl: {}", e))?;
+    let Some(stdout) = child.stdout.take() else {
+        return Ok(());
+    };
+    for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+        if let Some(kind) = changed_kind(&line)
+            && !on_change(kind) {
+            break;
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+    Ok(())
+}
+
+/// The kind of object a `pactl subscribe` line is about. Lines look like: Event 'new' on sink-input #42
+fn changed_kind(line: &str) -> Option<&str> {
+    line.split(" on ").nth(1).and_then(|object| object.split_whitespace().next())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(text: &str) -> Vec<Value> {
+        match serde_json::from_str(text) {
+            Ok(Value::Array(objects)) => objects,
+            _ => panic!("Fixture is not a list"),
+        }
+    }
+
+    #[test]
+    fn reads_sink_inputs() {
+        let streams: Vec<AudioStream> = fixture(include_str!("../tests/fixtures/pactl/sink-inputs.json")).iter().filter_map(sink_input).collect();
+        let stream = |id: &str, pid, name: &str, executable: &str, volume, muted| AudioStream {
+            id: id.to_string(),
+            pid,
+            name: name.to_string(),
+            executable: executable.to_string(),
+            volume,
+            muted,
+        };
+        assert_eq!(streams, [
+            // Channels at different volumes are averaged
+            stream("42", Some(1234), "Firefox", "firefox", 0.75, false),
+            stream("43", None, "Notification sound", "", 1.5, true),
+            stream("44", None, "mpv", "mpv", 0.0, false),
+        ]);
+    }
+
+    #[test]
+    fn converts_volumes() {
+        assert_eq!(volume(&serde_json::json!({"volume": {"mono": {"value": 65536}}})), 1.0);
+        assert_eq!(volume(&serde_json::json!({"volume": {"front-left": {"value": 0}, "front-right": {"value": 32768}}})), 0.25);
+        assert_eq!(volume(&serde_json::json!({"volume": "65536"})), 0.0);
+        assert_eq!(volume(&serde_json::json!({})), 0.0);
+        let cases = [(1.0, "65536"), (0.5, "32768"), (1.5, "98304"), (0.0, "0"), (-0.2, "0")];
+        for (volume, expected) in cases {
+            assert_eq!(volume_argument(volume), expected, "{}", volume);
+        }
+    }
+
+    #[test]
+    fn lists_devices_without_monitors() {
+        let sources = fixture(include_str!("../tests/fixtures/pactl/sources.json"));
+        let device = |id: &str, name: &str, is_default| AudioDevice { id: id.to_string(), name: name.to_string(), is_default, direction: DeviceDirection::Input };
+        assert_eq!(audio_devices(&sources, "alsa_input.pci-0000_00_1f.3.analog-stereo", DeviceDirection::Input), [
+            device("alsa_input.pci-0000_00_1f.3.analog-stereo", "Built-in Audio Analog Stereo", true),
+            // Devices without a description go by their name
+            device("bluez_input.00_11_22_33_44_55", "bluez_input.00_11_22_33_44_55", false),
+        ]);
+        assert!(audio_devices(&sources, "", DeviceDirection::Input).iter().all(|device| !device.is_default));
+    }
+
+    #[test]
+    fn tells_when_a_microphone_is_recorded() {
+        let sources = fixture(include_str!("../tests/fixtures/pactl/sources.json"));
+        let outputs = fixture(include_str!("../tests/fixtures/pactl/source-outputs.json"));
+        assert!(recording(&sources, &outputs));
+        // Neither the level meter nor recording a monitor counts
+        assert!(!recording(&sources, &outputs[..2]));
+        assert!(!recording(&sources, &[]));
+        // Outputs without a source are taken to be recording a microphone
+        assert!(recording(&sources, &[serde_json::json!({"index": 90, "properties": {"application.name": "Recorder"}})]));
+    }
+
+    #[test]
+    fn finds_the_loudest_sample() {
+        assert_eq!(peak(&[]), 0.0);
+        assert_eq!(peak(&[0x00, 0x40, 0x00, 0xE0]), 0.5);
+        assert_eq!(peak(&[0x00, 0x80, 0xFF, 0x7F]), 1.0);
+        // A trailing odd byte is not a sample
+        assert_eq!(peak(&[0x00, 0x20, 0xFF]), 0.25);
+    }
+
+    #[test]
+    fn reads_subscription_events() {
+        assert_eq!(changed_kind("Event 'new' on sink-input #42"), Some("sink-input"));
+        assert_eq!(changed_kind("Event 'change' on source #3"), Some("source"));
+        assert_eq!(changed_kind("Event 'change' on server"), Some("server"));
+        assert_eq!(changed_kind("Connection failure: Connection refused"), None);
+        assert_eq!(changed_kind(""), None);
+    }
+
+    /// A sink of its own for the round trip test, unloaded again with the old default put back.
+    struct NullSink {
+        module: String,
+        previous_default: String,
+    }
+    impl Drop for NullSink {
+        fn drop(&mut self) {
+            let _ = pactl(&["set-default-sink", &self.previous_default]);
+            let _ = pactl(&["unload-module", &self.module]);
+        }
+    }
+
+    /// Talks to the running sound server, so it only runs when asked for with `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn round_trips_through_a_null_sink() {
+        let previous_default = default_name(DeviceDirection::Output).unwrap();
+        let module = pactl(&["load-module", "module-null-sink", "sink_name=frostwin_test", "sink_properties=device.description=Frostwin_Test"])
+            .unwrap().trim().to_string();
+        let _sink = NullSink { module, previous_default };
+
+        let device = devices(DeviceDirection::Output).unwrap().into_iter()
+            .find(|device| device.id == "frostwin_test")
+            .expect("The null sink is listed");
+        assert_eq!(device.name, "Frostwin_Test");
+        set_default_device(&device).unwrap();
+        assert_eq!(default_name(DeviceDirection::Output).unwrap(), "frostwin_test");
+        assert!(devices(DeviceDirection::Output).unwrap().iter().any(|device| device.id == "frostwin_test" && device.is_default));
+
+        for (volume, muted) in [(0.3, true), (0.75, false)] {
+            set_default_sink_state(volume, muted).unwrap();
+            let (read_volume, read_muted) = default_sink_state().unwrap();
+            assert!((read_volume - volume).abs() < 0.01, "Set {} and read {}", volume, read_volume);
+            assert_eq!(read_muted, muted);
+        }
+    }
+}
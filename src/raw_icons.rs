@@ -6,8 +6,8 @@ use dirs::data_dir;
 use iced::widget::image::Handle;
 use crate::sys_util::WifiStatus;
 
-pub const START_CLOSED_ICON: &'static [u8] = include_bytes!("images/icons/startIcon/StartIconClosed.png");
-pub const START_OPEN_ICON: &'static [u8] = include_bytes!("images/icons/startIcon/StartIconOpen.png");
+pub const START_CLOSED_ICON: &[u8] = include_bytes!("images/icons/startIcon/StartIconClosed.png");
+pub const START_OPEN_ICON: &[u8] = include_bytes!("images/icons/startIcon/StartIconOpen.png");
 
 pub fn start_icon(app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, open: bool) -> Handle {
     let error_handle = Handle::from_rgba(1,1,vec![255u8,0u8,0u8,255u8]);
@@ -31,14 +31,14 @@ pub fn start_icon(app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, open: b
     }
 }
 
-pub const WIFI_ICON4_ICON: &'static [u8] = include_bytes!("images/icons/network/WifiIcon4.png");
-pub const WIFI_ICON3_ICON: &'static [u8] = include_bytes!("images/icons/network/WifiIcon3.png");
-pub const WIFI_ICON2_ICON: &'static [u8] = include_bytes!("images/icons/network/WifiIcon2.png");
-pub const WIFI_ICON1_ICON: &'static [u8] = include_bytes!("images/icons/network/WifiIcon1.png");
-pub const WIFI_ICON0_ICON: &'static [u8] = include_bytes!("images/icons/network/WifiIcon0.png");
-pub const WIFI_NONE_ICON: &'static [u8] = include_bytes!("images/icons/network/WifiIconNone.png");
-pub const ETHERNET_ICON: &'static [u8] = include_bytes!("images/icons/network/EthernetIcon.png");
-pub const NO_INTERNET_ICON: &'static [u8] = include_bytes!("images/icons/network/NoInternet.png");
+pub const WIFI_ICON4_ICON: &[u8] = include_bytes!("images/icons/network/WifiIcon4.png");
+pub const WIFI_ICON3_ICON: &[u8] = include_bytes!("images/icons/network/WifiIcon3.png");
+pub const WIFI_ICON2_ICON: &[u8] = include_bytes!("images/icons/network/WifiIcon2.png");
+pub const WIFI_ICON1_ICON: &[u8] = include_bytes!("images/icons/network/WifiIcon1.png");
+pub const WIFI_ICON0_ICON: &[u8] = include_bytes!("images/icons/network/WifiIcon0.png");
+pub const WIFI_NONE_ICON: &[u8] = include_bytes!("images/icons/network/WifiIconNone.png");
+pub const ETHERNET_ICON: &[u8] = include_bytes!("images/icons/network/EthernetIcon.png");
+pub const NO_INTERNET_ICON: &[u8] = include_bytes!("images/icons/network/NoInternet.png");
 
 pub fn wifi_icon(app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, wifi_status: WifiStatus) -> Handle {
     let error_handle = Handle::from_rgba(1,1,vec![255u8,0u8,0u8,255u8]);
@@ -78,26 +78,26 @@ pub fn wifi_icon(app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, wifi_sta
     }
 }
 
-pub const BATTERY100_ICON: &'static [u8] = include_bytes!("images/icons/battery/Battery100.png");
-pub const BATTERY90_ICON: &'static [u8] = include_bytes!("images/icons/battery/Battery90.png");
-pub const BATTERY80_ICON: &'static [u8] = include_bytes!("images/icons/battery/Battery80.png");
-pub const BATTERY70_ICON: &'static [u8] = include_bytes!("images/icons/battery/Battery70.png");
-pub const BATTERY60_ICON: &'static [u8] = include_bytes!("images/icons/battery/Battery60.png");
-pub const BATTERY50_ICON: &'static [u8] = include_bytes!("images/icons/battery/Battery50.png");
-pub const BATTERY40_ICON: &'static [u8] = include_bytes!("images/icons/battery/Battery40.png");
-pub const BATTERY30_ICON: &'static [u8] = include_bytes!("images/icons/battery/Battery30.png");
-pub const BATTERY20_ICON: &'static [u8] = include_bytes!("images/icons/battery/Battery20.png");
-pub const BATTERY10_ICON: &'static [u8] = include_bytes!("images/icons/battery/Battery10.png");
-pub const BATTERY_C100_ICON: &'static [u8] = include_bytes!("images/icons/battery/BatteryC100.png");
-pub const BATTERY_C90_ICON: &'static [u8] = include_bytes!("images/icons/battery/BatteryC90.png");
-pub const BATTERY_C80_ICON: &'static [u8] = include_bytes!("images/icons/battery/BatteryC80.png");
-pub const BATTERY_C70_ICON: &'static [u8] = include_bytes!("images/icons/battery/BatteryC70.png");
-pub const BATTERY_C60_ICON: &'static [u8] = include_bytes!("images/icons/battery/BatteryC60.png");
-pub const BATTERY_C50_ICON: &'static [u8] = include_bytes!("images/icons/battery/BatteryC50.png");
-pub const BATTERY_C40_ICON: &'static [u8] = include_bytes!("images/icons/battery/BatteryC40.png");
-pub const BATTERY_C30_ICON: &'static [u8] = include_bytes!("images/icons/battery/BatteryC30.png");
-pub const BATTERY_C20_ICON: &'static [u8] = include_bytes!("images/icons/battery/BatteryC20.png");
-pub const BATTERY_C10_ICON: &'static [u8] = include_bytes!("images/icons/battery/BatteryC10.png");
+pub const BATTERY100_ICON: &[u8] = include_bytes!("images/icons/battery/Battery100.png");
+pub const BATTERY90_ICON: &[u8] = include_bytes!("images/icons/battery/Battery90.png");
+pub const BATTERY80_ICON: &[u8] = include_bytes!("images/icons/battery/Battery80.png");
+pub const BATTERY70_ICON: &[u8] = include_bytes!("images/icons/battery/Battery70.png");
+pub const BATTERY60_ICON: &[u8] = include_bytes!("images/icons/battery/Battery60.png");
+pub const BATTERY50_ICON: &[u8] = include_bytes!("images/icons/battery/Battery50.png");
+pub const BATTERY40_ICON: &[u8] = include_bytes!("images/icons/battery/Battery40.png");
+pub const BATTERY30_ICON: &[u8] = include_bytes!("images/icons/battery/Battery30.png");
+pub const BATTERY20_ICON: &[u8] = include_bytes!("images/icons/battery/Battery20.png");
+pub const BATTERY10_ICON: &[u8] = include_bytes!("images/icons/battery/Battery10.png");
+pub const BATTERY_C100_ICON: &[u8] = include_bytes!("images/icons/battery/BatteryC100.png");
+pub const BATTERY_C90_ICON: &[u8] = include_bytes!("images/icons/battery/BatteryC90.png");
+pub const BATTERY_C80_ICON: &[u8] = include_bytes!("images/icons/battery/BatteryC80.png");
+pub const BATTERY_C70_ICON: &[u8] = include_bytes!("images/icons/battery/BatteryC70.png");
+pub const BATTERY_C60_ICON: &[u8] = include_bytes!("images/icons/battery/BatteryC60.png");
+pub const BATTERY_C50_ICON: &[u8] = include_bytes!("images/icons/battery/BatteryC50.png");
+pub const BATTERY_C40_ICON: &[u8] = include_bytes!("images/icons/battery/BatteryC40.png");
+pub const BATTERY_C30_ICON: &[u8] = include_bytes!("images/icons/battery/BatteryC30.png");
+pub const BATTERY_C20_ICON: &[u8] = include_bytes!("images/icons/battery/BatteryC20.png");
+pub const BATTERY_C10_ICON: &[u8] = include_bytes!("images/icons/battery/BatteryC10.png");
 
 pub fn battery_icon(app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, charging: bool, battery_level: f32) -> Handle {
     let error_handle = Handle::from_rgba(1,1,vec![255u8,0u8,0u8,255u8]);
@@ -161,12 +161,12 @@ pub fn battery_icon(app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, charg
     }
 }
 
-pub const SOUND0_ICON: &'static [u8] = include_bytes!("images/icons/sound/Sound0.png");
-pub const SOUND1_ICON: &'static [u8] = include_bytes!("images/icons/sound/Sound1.png");
-pub const SOUND2_ICON: &'static [u8] = include_bytes!("images/icons/sound/Sound2.png");
-pub const SOUND3_ICON: &'static [u8] = include_bytes!("images/icons/sound/Sound3.png");
-pub const SOUND4_ICON: &'static [u8] = include_bytes!("images/icons/sound/Sound4.png");
-pub const SOUND_M_ICON: &'static [u8] = include_bytes!("images/icons/sound/SoundM.png");
+pub const SOUND0_ICON: &[u8] = include_bytes!("images/icons/sound/Sound0.png");
+pub const SOUND1_ICON: &[u8] = include_bytes!("images/icons/sound/Sound1.png");
+pub const SOUND2_ICON: &[u8] = include_bytes!("images/icons/sound/Sound2.png");
+pub const SOUND3_ICON: &[u8] = include_bytes!("images/icons/sound/Sound3.png");
+pub const SOUND4_ICON: &[u8] = include_bytes!("images/icons/sound/Sound4.png");
+pub const SOUND_M_ICON: &[u8] = include_bytes!("images/icons/sound/SoundM.png");
 
 pub fn sound_icon(app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, volume: f32, muted: bool) -> Handle {
     let error_handle = Handle::from_rgba(1,1,vec![255u8,0u8,0u8,255u8]);
@@ -200,16 +200,61 @@ pub fn sound_icon(app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, volume:
     }
 }
 
-pub const CANCEL_ICON: &'static [u8] = include_bytes!("images/icons/power/Cancel.png");
-pub const LOCK_ICON: &'static [u8] = include_bytes!("images/icons/power/Lock.png");
-pub const LOGOFF_ICON: &'static [u8] = include_bytes!("images/icons/power/Logoff.png");
-pub const RESTART_ICON: &'static [u8] = include_bytes!("images/icons/power/Restart.png");
-pub const SHUTDOWN_ICON: &'static [u8] = include_bytes!("images/icons/power/Shutdown.png");
+pub const MICROPHONE_ICON: &[u8] = include_bytes!("images/icons/sound/Microphone.png");
+pub const MICROPHONE_M_ICON: &[u8] = include_bytes!("images/icons/sound/MicrophoneM.png");
 
-pub const EMPTY_APP_ICON: &'static [u8] = include_bytes!("images/icons/EmptyApp.png");
-pub const TREE_DOT_ICON: &'static [u8] = include_bytes!("images/icons/TreeDot.png");
-pub const FOLDER_ICON: &'static [u8] = include_bytes!("images/icons/Folder.png");
-pub const SETTINGS_ICON: &'static [u8] = include_bytes!("images/icons/Settings.png");
+pub fn microphone_icon(app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, muted: bool) -> Handle {
+    let error_handle = Handle::from_rgba(1,1,vec![255u8,0u8,0u8,255u8]);
+    match (app_image_cache.lock(),data_dir()) {
+        (Ok(app_image_cache),Some(data_dir)) => {
+            let data_folder = data_dir.join("Frostwin");
+            if muted {
+                app_image_cache.get(&data_folder.join("icons/sound/MicrophoneM.png")).unwrap_or(&error_handle).clone()
+            } else {
+                app_image_cache.get(&data_folder.join("icons/sound/Microphone.png")).unwrap_or(&error_handle).clone()
+            }
+        }
+        (Err(e),_) => {
+            eprintln!("Error accessing app_image_cache: {}", e);
+            error_handle
+        }
+        (_,None) => {
+            eprintln!("Error getting data_dir");
+            error_handle
+        }
+    }
+}
+
+pub const DO_NOT_DISTURB_ICON: &[u8] = include_bytes!("images/icons/DoNotDisturb.png");
+
+pub fn do_not_disturb_icon(app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>) -> Handle {
+    let error_handle = Handle::from_rgba(1,1,vec![255u8,0u8,0u8,255u8]);
+    match (app_image_cache.lock(),data_dir()) {
+        (Ok(app_image_cache),Some(data_dir)) => {
+            let data_folder = data_dir.join("Frostwin");
+            app_image_cache.get(&data_folder.join("icons/DoNotDisturb.png")).unwrap_or(&error_handle).clone()
+        }
+        (Err(e),_) => {
+            eprintln!("Error accessing app_image_cache: {}", e);
+            error_handle
+        }
+        (_,None) => {
+            eprintln!("Error getting data_dir");
+            error_handle
+        }
+    }
+}
+
+pub const CANCEL_ICON: &[u8] = include_bytes!("images/icons/power/Cancel.png");
+pub const LOCK_ICON: &[u8] = include_bytes!("images/icons/power/Lock.png");
+pub const LOGOFF_ICON: &[u8] = include_bytes!("images/icons/power/Logoff.png");
+pub const RESTART_ICON: &[u8] = include_bytes!("images/icons/power/Restart.png");
+pub const SHUTDOWN_ICON: &[u8] = include_bytes!("images/icons/power/Shutdown.png");
+
+pub const EMPTY_APP_ICON: &[u8] = include_bytes!("images/icons/EmptyApp.png");
+pub const TREE_DOT_ICON: &[u8] = include_bytes!("images/icons/TreeDot.png");
+pub const FOLDER_ICON: &[u8] = include_bytes!("images/icons/Folder.png");
+pub const SETTINGS_ICON: &[u8] = include_bytes!("images/icons/Settings.png");
 
 pub fn unpack_missing_icons(path: PathBuf) -> std::io::Result<()> {
     let assets: &[(&str, &[u8])] = &[
@@ -249,6 +294,8 @@ pub fn unpack_missing_icons(path: PathBuf) -> std::io::Result<()> {
         ("icons/sound/Sound3.png", SOUND3_ICON),
         ("icons/sound/Sound4.png", SOUND4_ICON),
         ("icons/sound/SoundM.png", SOUND_M_ICON),
+        ("icons/sound/Microphone.png", MICROPHONE_ICON),
+        ("icons/sound/MicrophoneM.png", MICROPHONE_M_ICON),
         ("icons/power/Shutdown.png", SHUTDOWN_ICON),
         ("icons/power/Restart.png", RESTART_ICON),
         ("icons/power/Lock.png", LOCK_ICON),
@@ -258,6 +305,7 @@ pub fn unpack_missing_icons(path: PathBuf) -> std::io::Result<()> {
         ("icons/TreeDot.png", TREE_DOT_ICON),
         ("icons/EmptyApp.png", EMPTY_APP_ICON),
         ("icons/Settings.png", SETTINGS_ICON),
+        ("icons/DoNotDisturb.png", DO_NOT_DISTURB_ICON),
     ];
 
     for (rel_path, data) in assets {
@@ -0,0 +1,608 @@
+#[cfg(target_os = "linux")]
+use crate::desktop_entry::{find_executable, terminal_command};
+#[cfg(target_os = "linux")]
+use crate::launcher::split_shell_words;
+use crate::launcher::{is_program, is_uri, LaunchTarget, Launched, Launcher};
+use crate::start_settings::StartMenuSettings;
+use crate::styles::{selected_button, transparent_button, window_style};
+use crate::Message;
+use dirs::data_dir;
+use iced::keyboard::key::Named;
+use iced::widget::{button, checkbox, column, container, operation, row, text, text_input, Column};
+use iced::{event, keyboard, window, Alignment, Color, Element, Length, Point, Size, Task};
+use serde_json::{from_str, to_string_pretty};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const HISTORY_LIMIT: usize = 50;
+const MAX_COMPLETIONS: usize = 8;
+const INPUT_ID: &str = "run-dialog-input";
+
+#[derive(Debug, Clone)]
+pub enum RunMessage {
+    Init,
+    Resize(Option<Size>),
+    Input(String),
+    ToggleTerminal(bool),
+    Complete(String),
+    Run,
+    Navigate(window::Id, RunKey),
+    Cancel,
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RunKey {
+    Up,
+    Down,
+    Tab,
+    Escape,
+}
+
+/// Maps key presses to completion navigation, for use with `event::listen_with`.
+pub fn run_key(event: iced::Event, _status: event::Status, window: window::Id) -> Option<Message> {
+    let iced::Event::Keyboard(keyboard::Event::KeyPressed { key: keyboard::Key::Named(named), .. }) = event else {
+        return None;
+    };
+    let key = match named {
+        Named::ArrowUp => RunKey::Up,
+        Named::ArrowDown => RunKey::Down,
+        Named::Tab => RunKey::Tab,
+        Named::Escape => RunKey::Escape,
+        _ => return None,
+    };
+    Some(Message::RunDialog(RunMessage::Navigate(window, key)))
+}
+
+/// Command lines that ran successfully, newest first.
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone, Default)]
+pub struct RunHistory {
+    entries: Vec<String>,
+}
+impl RunHistory {
+    pub fn history_file() -> Option<PathBuf> {
+        data_dir().map(|data_dir| data_dir.join("Frostwin").join("Run_History.json"))
+    }
+
+    pub fn load() -> Self {
+        match Self::history_file() {
+            Some(history_file) => Self::load_from(&history_file),
+            None => Self::default(),
+        }
+    }
+
+    fn load_from(history_file: &Path) -> Self {
+        match std::fs::read_to_string(history_file) {
+            Ok(content) => match from_str::<RunHistory>(&content) {
+                Ok(history) => history,
+                Err(e) => {
+                    eprintln!("Error loading run history: {:?}", e);
+                    Self::default()
+                }
+            },
+            Err(e) => {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    eprintln!("Error opening run history: {:?}", e);
+                }
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        if let Some(history_file) = Self::history_file() {
+            self.save_to(&history_file);
+        }
+    }
+
+    fn save_to(&self, history_file: &Path) {
+        if let Some(parent) = history_file.parent() && let Err(e) = std::fs::create_dir_all(parent) {
+            eprintln!("Error creating the Frostwin data folder: {:?}", e);
+            return;
+        }
+        match to_string_pretty(self) {
+            Ok(history) => {
+                if let Err(e) = std::fs::write(history_file, history) {
+                    eprintln!("Error writing run history: {:?}", e);
+                }
+            }
+            Err(e) => {
+                eprintln!("Error serializing run history: {:?}", e);
+            }
+        }
+    }
+
+    pub fn add(&mut self, command_line: &str) {
+        self.entries.retain(|entry| entry != command_line);
+        self.entries.insert(0, command_line.to_string());
+        self.entries.truncate(HISTORY_LIMIT);
+    }
+}
+
+pub struct RunDialog {
+    pub id: window::Id,
+    input: String,
+    in_terminal: bool,
+    history: RunHistory,
+    executables: Vec<String>,
+    completions: Vec<String>,
+    selected: Option<usize>,
+    error: Option<String>,
+}
+impl RunDialog {
+    pub fn new() -> (Self, Task<window::Id>) {
+        let settings = window::Settings {
+            decorations: false,
+            resizable: false,
+            min_size: None,
+            max_size: None,
+            icon: None,
+            transparent: true,
+            closeable: false,
+            minimizable: false,
+            level: window::Level::AlwaysOnTop,
+            position: window::Position::Specific(Point::new(2.0, 37.0)),
+            size: Size::new(400.0, 250.0),
+            ..window::Settings::default()
+        };
+        let (id, open_task) = window::open(settings);
+        (Self {
+            id,
+            input: String::new(),
+            in_terminal: false,
+            history: RunHistory::default(),
+            executables: Vec::new(),
+            completions: Vec::new(),
+            selected: None,
+            error: None,
+        },
+         open_task)
+    }
+    pub fn update(&mut self, message: RunMessage) -> Task<Message> {
+        match message {
+            RunMessage::Init => {
+                self.history = RunHistory::load();
+                self.executables = path_executables();
+                self.completions = self.completions();
+                Task::batch([
+                    window::monitor_size(self.id).map(|size| Message::RunDialog(RunMessage::Resize(size))),
+                    window::gain_focus(self.id),
+                    operation::focus(INPUT_ID),
+                ])
+            }
+            RunMessage::Resize(resize) => {
+                if let Some(size) = resize {
+                    let base_size = size.width * 0.0005;
+                    let window_margin = 4.0 * base_size;
+                    let w_resize = window::resize(self.id, Size::new(600.0 * base_size, 340.0 * base_size));
+                    let w_move = window::move_to(self.id, Point::new(window_margin, 50.0 * base_size));
+                    Task::batch([w_resize, w_move])
+                } else {
+                    Task::none()
+                }
+            }
+            RunMessage::Input(input) => {
+                self.input = input;
+                self.error = None;
+                self.completions = self.completions();
+                self.selected = None;
+                Task::none()
+            }
+            RunMessage::ToggleTerminal(in_terminal) => {
+                self.in_terminal = in_terminal;
+                Task::none()
+            }
+            RunMessage::Complete(completion) => {
+                self.input = completion;
+                self.error = None;
+                self.completions = self.completions();
+                self.selected = None;
+                Task::batch([
+                    operation::focus(INPUT_ID),
+                    operation::move_cursor_to_end(INPUT_ID),
+                ])
+            }
+            RunMessage::Run => {
+                // Enter on a highlighted completion runs that instead of what was typed so far
+                if let Some(completion) = self.selected.and_then(|index| self.completions.get(index)) {
+                    self.input = completion.clone();
+                }
+                let command_line = self.input.trim().to_string();
+                if command_line.is_empty() {
+                    return Task::none();
+                }
+                match run_command_line(&StartMenuSettings::load().launcher(), &command_line, self.in_terminal) {
+                    Ok(_) => {
+                        self.history.add(&command_line);
+                        self.history.save();
+                        window::close(self.id).map(Message::WindowClosed)
+                    }
+                    Err(e) => {
+                        self.error = Some(e);
+                        Task::none()
+                    }
+                }
+            }
+            RunMessage::Navigate(window, key) => {
+                if window != self.id {
+                    return Task::none();
+                }
+                match key {
+                    RunKey::Escape => window::close(self.id).map(Message::WindowClosed),
+                    RunKey::Up => {
+                        self.selected = match self.selected {
+                            Some(0) | None => None,
+                            Some(index) => Some(index - 1),
+                        };
+                        Task::none()
+                    }
+                    RunKey::Down => {
+                        if !self.completions.is_empty() {
+                            self.selected = Some(self.selected.map_or(0, |index| (index + 1).min(self.completions.len() - 1)));
+                        }
+                        Task::none()
+                    }
+                    RunKey::Tab => {
+                        match self.completions.get(self.selected.unwrap_or(0)).cloned() {
+                            Some(completion) => self.update(RunMessage::Complete(completion)),
+                            None => Task::none(),
+                        }
+                    }
+                }
+            }
+            RunMessage::Cancel => {
+                window::close(self.id).map(Message::WindowClosed)
+            }
+        }
+    }
+
+    /// History entries starting with the input, then completions for the word being typed:
+    /// file names when it looks like a path, otherwise programs from PATH for the first word.
+    fn completions(&self) -> Vec<String> {
+        let input = self.input.trim_start();
+        if input.is_empty() {
+            return self.history.entries.iter().take(MAX_COMPLETIONS).cloned().collect();
+        }
+        let mut completions: Vec<String> = self.history.entries.iter()
+            .filter(|entry| entry.to_lowercase().starts_with(&input.to_lowercase()) && entry.as_str() != input)
+            .cloned()
+            .collect();
+        let word_start = last_word_start(input);
+        let (prefix, word) = input.split_at(word_start);
+        let (quote, word) = match word.strip_prefix('"') {
+            Some(rest) => ("\"", rest),
+            None => ("", word),
+        };
+        let candidates = if looks_like_path(word) {
+            path_completions(word)
+        } else if prefix.trim().is_empty() && !word.is_empty() {
+            self.executables.iter()
+                .filter(|executable| starts_with_ignoring_case(executable, word))
+                .cloned()
+                .collect()
+        } else {
+            Vec::new()
+        };
+        for candidate in candidates {
+            let completion = format!("{}{}{}", prefix, quote, candidate);
+            if completion != input && !completions.contains(&completion) {
+                completions.push(completion);
+            }
+        }
+        completions.truncate(MAX_COMPLETIONS);
+        completions
+    }
+
+    pub fn view(&self, base_size: f32) -> Element<'_, Message> {
+        let text_height = 30.0 * base_size;
+        let spacing = 2.0 * base_size;
+        let mut content: Column<Message> = column![
+            text!("Run").size(text_height * 0.8),
+            text!("Type a program, folder, document or address to open.").size(text_height * 0.45),
+            text_input("Command", &self.input)
+                .id(INPUT_ID)
+                .on_input(|input| Message::RunDialog(RunMessage::Input(input)))
+                .on_submit(Message::RunDialog(RunMessage::Run))
+                .size(text_height * 0.55),
+        ].spacing(spacing * 2.0);
+        if let Some(error) = self.error.as_ref() {
+            content = content.push(text!("{}", error).size(text_height * 0.45).color(Color::from_rgb(0.9, 0.3, 0.3)));
+        }
+        let mut completions: Column<Message> = Column::new();
+        for (index, completion) in self.completions.iter().enumerate() {
+            let is_selected = self.selected == Some(index);
+            completions = completions.push(
+                button(text!("{}", completion).size(text_height * 0.45))
+                    .on_press(Message::RunDialog(RunMessage::Complete(completion.clone())))
+                    .style(move |theme, status| if is_selected { selected_button(theme, status) } else { transparent_button(theme, status) })
+                    .width(Length::Fill)
+            );
+        }
+        content = content.push(completions.height(Length::Fill));
+        content = content.push(
+            row![
+                checkbox(self.in_terminal)
+                    .label("Run in terminal")
+                    .on_toggle(|in_terminal| Message::RunDialog(RunMessage::ToggleTerminal(in_terminal)))
+                    .size(text_height * 0.45)
+                    .text_size(text_height * 0.45)
+                    .width(Length::Fill),
+                button(text!("OK")).on_press(Message::RunDialog(RunMessage::Run)),
+                button(text!("Cancel")).on_press(Message::RunDialog(RunMessage::Cancel)).style(transparent_button),
+            ].spacing(spacing * 2.0).align_y(Alignment::Center)
+        );
+        container(content.padding(spacing * 4.0))
+            .style(window_style)
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    }
+}
+
+fn starts_with_ignoring_case(text: &str, prefix: &str) -> bool {
+    text.to_lowercase().starts_with(&prefix.to_lowercase())
+}
+
+/// Byte index where the last word starts, treating quoted text as part of one word.
+fn last_word_start(input: &str) -> usize {
+    let mut in_quotes = false;
+    let mut start = 0;
+    for (index, c) in input.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c.is_whitespace() && !in_quotes {
+            start = index + c.len_utf8();
+        }
+    }
+    start
+}
+
+fn looks_like_path(word: &str) -> bool {
+    let has_drive = word.len() >= 2 && word.as_bytes()[1] == b':' && word.as_bytes()[0].is_ascii_alphabetic();
+    word.starts_with('~') || word.starts_with('.') || word.contains('/') || word.contains('\\') || has_drive
+}
+
+fn expand_home(text: &str) -> String {
+    match (text.strip_prefix('~'), dirs::home_dir()) {
+        (Some(rest), Some(home)) if rest.is_empty() || rest.starts_with('/') || rest.starts_with('\\') => {
+            format!("{}{}", home.display(), rest)
+        }
+        _ => text.to_string(),
+    }
+}
+
+/// Entries of the folder named by `word` whose names continue its last component.
+fn path_completions(word: &str) -> Vec<String> {
+    let split = word.rfind(['/', '\\']).map_or(0, |index| index + 1);
+    let (dir_text, name_prefix) = word.split_at(split);
+    let dir = if dir_text.is_empty() { ".".to_string() } else { expand_home(dir_text) };
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let separator = if dir_text.contains('\\') { '\\' } else { std::path::MAIN_SEPARATOR };
+    let mut completions: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // Hidden files only show up once their leading dot is typed
+            if !starts_with_ignoring_case(&name, name_prefix) || (name.starts_with('.') && !name_prefix.starts_with('.')) {
+                return None;
+            }
+            let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+            Some(if is_dir { format!("{}{}{}", dir_text, name, separator) } else { format!("{}{}", dir_text, name) })
+        })
+        .collect();
+    alphanumeric_sort::sort_str_slice(&mut completions);
+    completions
+}
+
+/// Names of the programs in PATH, without their extension on Windows like the Run dialog accepts them.
+fn path_executables() -> Vec<String> {
+    match std::env::var_os("PATH") {
+        Some(search_path) => executables_in(std::env::split_paths(&search_path)),
+        None => Vec::new(),
+    }
+}
+
+fn executables_in(dirs: impl Iterator<Item = PathBuf>) -> Vec<String> {
+    #[cfg(windows)]
+    let extensions: Vec<String> = std::env::var("PATHEXT")
+        .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string())
+        .split(';')
+        .map(|extension| extension.trim_start_matches('.').to_lowercase())
+        .collect();
+    let mut executables: Vec<String> = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            #[cfg(windows)]
+            {
+                let is_program = path.extension()
+                    .is_some_and(|extension| extensions.contains(&extension.to_string_lossy().to_lowercase()));
+                if is_program && let Some(stem) = path.file_stem() {
+                    executables.push(stem.to_string_lossy().to_string());
+                }
+            }
+            #[cfg(target_os = "linux")]
+            if is_program(&path) {
+                executables.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    alphanumeric_sort::sort_str_slice(&mut executables);
+    executables.dedup();
+    executables
+}
+
+/// Runs what was typed: addresses, folders and documents open with their default program,
+/// anything else is split into a program and its arguments.
+fn run_command_line(launcher: &Launcher, command_line: &str, in_terminal: bool) -> Result<Launched, String> {
+    let expanded = expand_home(command_line);
+    let whole_path = Path::new(&expanded);
+    // A path typed without quotes may contain spaces, so the whole line is tried as one first
+    if is_uri(command_line) {
+        return launcher.launch(&LaunchTarget::Uri(command_line.to_string()));
+    }
+    if whole_path.exists() && !is_program(whole_path) {
+        return launcher.launch(&LaunchTarget::classify(whole_path.as_os_str()));
+    }
+    #[cfg(windows)]
+    return run_windows_command_line(launcher, command_line, in_terminal);
+    #[cfg(target_os = "linux")]
+    return run_shell_words(launcher, command_line, in_terminal);
+}
+
+/// Splits a command line into words and expands `~` in each of them. Splitting comes first, so a home folder
+/// with spaces in its path stays one word, and like in a shell a quoted `~` stays as it is.
+#[cfg(target_os = "linux")]
+fn command_words(command_line: &str) -> Result<Vec<String>, String> {
+    Ok(split_shell_words(command_line)?.into_iter()
+        .map(|word| if word.quoted { word.text } else { expand_home(&word.text) })
+        .collect())
+}
+
+/// Windows programs parse their own command line, so only the program name is split off,
+/// following the rules CreateProcess uses for it, and the rest is passed on untouched.
+#[cfg(windows)]
+fn run_windows_command_line(launcher: &Launcher, command_line: &str, in_terminal: bool) -> Result<Launched, String> {
+    use std::os::windows::process::CommandExt;
+    if in_terminal {
+        return launcher.spawn(Command::new("cmd").raw_arg(format!("/K {}", command_line)));
+    }
+    let (program, arguments) = split_program(command_line);
+    // Programs registered under App Paths and scripts are only found by the shell, which also has the clearer error
+    launcher.spawn(Command::new(&program).raw_arg(arguments))
+        .or_else(|_| launcher.shell_execute(&program, Some(arguments), None))
+}
+
+/// Splits off the program name: up to the closing quote when it starts with one, else up to the first space.
+#[cfg(windows)]
+fn split_program(command_line: &str) -> (String, &str) {
+    let command_line = command_line.trim_start();
+    if let Some(quoted) = command_line.strip_prefix('"') {
+        return match quoted.split_once('"') {
+            Some((program, rest)) => (program.to_string(), rest.trim_start()),
+            None => (quoted.to_string(), ""),
+        };
+    }
+    match command_line.split_once(char::is_whitespace) {
+        Some((program, rest)) => (program.to_string(), rest.trim_start()),
+        None => (command_line.to_string(), ""),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn run_shell_words(launcher: &Launcher, command_line: &str, in_terminal: bool) -> Result<Launched, String> {
+    let mut words = command_words(command_line)?;
+    if words.is_empty() {
+        return Err("Nothing to run".to_string());
+    }
+    if find_executable(&words[0]).is_none() && !is_program(Path::new(&words[0])) {
+        return Err(format!("Cannot find '{}'. Make sure the name is typed correctly.", words[0]));
+    }
+    if in_terminal {
+        let mut wrapped = terminal_command();
+        wrapped.extend(words);
+        words = wrapped;
+    }
+    launcher.spawn(Command::new(&words[0]).args(&words[1..]))
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::launcher::ShellWord;
+
+    #[test]
+    fn expands_home_in_every_word() {
+        let home = dirs::home_dir().unwrap().display().to_string();
+        assert_eq!(command_words("~/bin/tool").unwrap(), [format!("{}/bin/tool", home)]);
+        assert_eq!(command_words("vim ~/notes ~ a~b ~other/file").unwrap(), [
+            "vim".to_string(),
+            format!("{}/notes", home),
+            home.clone(),
+            "a~b".to_string(),
+            "~other/file".to_string(),
+        ]);
+        assert_eq!(command_words("ls \"my files\"").unwrap(), ["ls", "my files"]);
+        assert!(command_words("ls \"my files").is_err());
+    }
+
+    #[test]
+    fn leaves_quoted_home_alone() {
+        let home = dirs::home_dir().unwrap().display().to_string();
+        assert_eq!(command_words("ls \"~/x\" '~' \\~/x").unwrap(), ["ls", "~/x", "~", "~/x"]);
+        // Quotes further into the word do not stop the expansion
+        assert_eq!(command_words("ls ~/\"my dir\"").unwrap(), ["ls".to_string(), format!("{}/my dir", home)]);
+        let words = split_shell_words("a 'b' c\\ d \"e\"f").unwrap();
+        assert_eq!(words, [
+            ShellWord { text: "a".to_string(), quoted: false },
+            ShellWord { text: "b".to_string(), quoted: true },
+            ShellWord { text: "c d".to_string(), quoted: false },
+            ShellWord { text: "ef".to_string(), quoted: true },
+        ]);
+    }
+
+    #[test]
+    fn keeps_history_newest_first() {
+        let mut history = RunHistory::default();
+        for entry in ["one", "two", "one"] {
+            history.add(entry);
+        }
+        assert_eq!(history.entries, ["one", "two"]);
+        for index in 0..HISTORY_LIMIT {
+            history.add(&index.to_string());
+        }
+        assert_eq!(history.entries.len(), HISTORY_LIMIT);
+        assert_eq!(history.entries[0], (HISTORY_LIMIT - 1).to_string());
+
+        let dir = tempfile::tempdir().unwrap();
+        let history_file = dir.path().join("Frostwin").join("Run_History.json");
+        assert!(RunHistory::load_from(&history_file).entries.is_empty());
+        history.save_to(&history_file);
+        assert_eq!(RunHistory::load_from(&history_file).entries, history.entries);
+        std::fs::write(&history_file, "not json").unwrap();
+        assert!(RunHistory::load_from(&history_file).entries.is_empty());
+    }
+
+    #[test]
+    fn completes_programs_from_path() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        for (name, mode) in [("frobnicate", 0o755), ("frobber", 0o755), ("frobnotes.txt", 0o644)] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, "").unwrap();
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        }
+        let executables = executables_in([dir.path().to_path_buf(), dir.path().join("missing")].into_iter());
+        assert_eq!(executables, ["frobber", "frobnicate"]);
+
+        let (mut dialog, _) = RunDialog::new();
+        dialog.executables = executables;
+        dialog.history.add("frobnicate --all");
+        dialog.input = "Frob".to_string();
+        assert_eq!(dialog.completions(), ["frobnicate --all", "frobber", "frobnicate"]);
+        // Only the first word is a program
+        dialog.input = "ls frob".to_string();
+        assert!(dialog.completions().is_empty());
+    }
+
+    #[test]
+    fn completes_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().display().to_string();
+        std::fs::create_dir(dir.path().join("Documents")).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "").unwrap();
+        std::fs::write(dir.path().join(".hidden"), "").unwrap();
+        assert_eq!(path_completions(&format!("{}/", root)), [format!("{}/Documents/", root), format!("{}/notes.txt", root)]);
+        assert_eq!(path_completions(&format!("{}/NO", root)), [format!("{}/notes.txt", root)]);
+        assert_eq!(path_completions(&format!("{}/.h", root)), [format!("{}/.hidden", root)]);
+        assert!(path_completions(&format!("{}/missing/", root)).is_empty());
+
+        let (mut dialog, _) = RunDialog::new();
+        dialog.input = format!("xdg-open \"{}/Doc", root);
+        assert_eq!(dialog.completions(), [format!("xdg-open \"{}/Documents/", root)]);
+    }
+}
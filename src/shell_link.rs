@@ -0,0 +1,518 @@
+use std::path::{Path, PathBuf};
+
+// MS-SHLLINK: https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-shllink
+const HEADER_SIZE: usize = 0x4C;
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+const HAS_LINK_TARGET_ID_LIST: u32 = 0x0000_0001;
+const HAS_LINK_INFO: u32 = 0x0000_0002;
+const HAS_NAME: u32 = 0x0000_0004;
+const HAS_RELATIVE_PATH: u32 = 0x0000_0008;
+const HAS_WORKING_DIR: u32 = 0x0000_0010;
+const HAS_ARGUMENTS: u32 = 0x0000_0020;
+const HAS_ICON_LOCATION: u32 = 0x0000_0040;
+const IS_UNICODE: u32 = 0x0000_0080;
+const FORCE_NO_LINK_INFO: u32 = 0x0000_0100;
+
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 0x1;
+const COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX: u32 = 0x2;
+
+const ENVIRONMENT_VARIABLE_DATA_BLOCK: u32 = 0xA000_0001;
+const ICON_ENVIRONMENT_DATA_BLOCK: u32 = 0xA000_0007;
+const ENVIRONMENT_DATA_BLOCK_SIZE: usize = 0x314;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShowCommand {
+    Normal,
+    Maximized,
+    Minimized,
+}
+impl ShowCommand {
+    fn from_raw(value: u32) -> Self {
+        match value {
+            3 => ShowCommand::Maximized,
+            7 => ShowCommand::Minimized,
+            // The spec says every other value must be treated as SW_SHOWNORMAL
+            _ => ShowCommand::Normal,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShellLink {
+    pub target: Option<String>,
+    pub id_list_target: Option<String>,
+    pub env_target: Option<String>,
+    pub description: Option<String>,
+    pub relative_path: Option<String>,
+    pub working_dir: Option<String>,
+    pub arguments: Option<String>,
+    pub icon_location: Option<String>,
+    pub icon_env_location: Option<String>,
+    pub icon_index: i32,
+    pub show_command: ShowCommand,
+    pub file_attributes: u32,
+}
+
+impl ShellLink {
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        match std::fs::read(path) {
+            Ok(data) => Self::parse(&data),
+            Err(e) => Err(format!("Error reading shortcut {:?}: {}", path, e)),
+        }
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader::new(data);
+        let header_size = reader.u32()? as usize;
+        if header_size != HEADER_SIZE {
+            return Err(format!("Invalid shell link header size: {:#x}", header_size));
+        }
+        if reader.bytes(16)? != LINK_CLSID {
+            return Err("Invalid shell link CLSID".to_string());
+        }
+        let flags = reader.u32()?;
+        let file_attributes = reader.u32()?;
+        // Creation, access and write times, then the target file size
+        reader.skip(8 * 3 + 4)?;
+        let icon_index = reader.u32()? as i32;
+        let show_command = ShowCommand::from_raw(reader.u32()?);
+        // Hotkey and reserved fields
+        reader.skip(2 + 2 + 4 + 4)?;
+
+        let mut link = ShellLink {
+            target: None,
+            id_list_target: None,
+            env_target: None,
+            description: None,
+            relative_path: None,
+            working_dir: None,
+            arguments: None,
+            icon_location: None,
+            icon_env_location: None,
+            icon_index,
+            show_command,
+            file_attributes,
+        };
+
+        if flags & HAS_LINK_TARGET_ID_LIST != 0 {
+            let id_list_size = reader.u16()? as usize;
+            link.id_list_target = parse_id_list(reader.bytes(id_list_size)?);
+        }
+        if flags & HAS_LINK_INFO != 0 {
+            let link_info_size = reader.peek_u32()? as usize;
+            let link_info = reader.bytes(link_info_size)?;
+            if flags & FORCE_NO_LINK_INFO == 0 {
+                link.target = parse_link_info(link_info)?;
+            }
+        }
+
+        let unicode = flags & IS_UNICODE != 0;
+        if flags & HAS_NAME != 0 {
+            link.description = Some(reader.counted_string(unicode)?);
+        }
+        if flags & HAS_RELATIVE_PATH != 0 {
+            link.relative_path = Some(reader.counted_string(unicode)?);
+        }
+        if flags & HAS_WORKING_DIR != 0 {
+            link.working_dir = Some(reader.counted_string(unicode)?);
+        }
+        if flags & HAS_ARGUMENTS != 0 {
+            link.arguments = Some(reader.counted_string(unicode)?);
+        }
+        if flags & HAS_ICON_LOCATION != 0 {
+            link.icon_location = Some(reader.counted_string(unicode)?);
+        }
+
+        // Extra data blocks are optional, a truncated tail is not fatal
+        while let Ok(block_size) = reader.peek_u32() {
+            let block_size = block_size as usize;
+            if block_size < 8 {
+                break;
+            }
+            let Ok(block) = reader.bytes(block_size) else {
+                break;
+            };
+            let signature = u32::from_le_bytes([block[4], block[5], block[6], block[7]]);
+            match signature {
+                ENVIRONMENT_VARIABLE_DATA_BLOCK => {
+                    link.env_target = parse_environment_block(block);
+                }
+                ICON_ENVIRONMENT_DATA_BLOCK => {
+                    link.icon_env_location = parse_environment_block(block);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(link)
+    }
+
+    /// Best known path of the link target with environment variables expanded.
+    /// `lnk_path` is used to resolve the relative path when nothing absolute is stored.
+    pub fn resolved_target(&self, lnk_path: &Path) -> Option<PathBuf> {
+        if let Some(target) = self.env_target.as_ref().or(self.target.as_ref()).or(self.id_list_target.as_ref()) {
+            return Some(PathBuf::from(expand_env_vars(target)));
+        }
+        if let Some(relative) = self.relative_path.as_ref() && let Some(parent) = lnk_path.parent() {
+            return Some(parent.join(relative.replace('\\', std::path::MAIN_SEPARATOR_STR)));
+        }
+        None
+    }
+
+    pub fn resolved_working_dir(&self) -> Option<PathBuf> {
+        self.working_dir.as_ref()
+            .filter(|dir| !dir.is_empty())
+            .map(|dir| PathBuf::from(expand_env_vars(dir)))
+    }
+
+    /// Icon file and index, falling back to the target itself like the shell does.
+    pub fn resolved_icon(&self, lnk_path: &Path) -> Option<(PathBuf, i32)> {
+        match self.icon_env_location.as_ref().or(self.icon_location.as_ref()) {
+            Some(location) if !location.is_empty() => Some((PathBuf::from(expand_env_vars(location)), self.icon_index)),
+            _ => self.resolved_target(lnk_path).map(|target| (target, 0)),
+        }
+    }
+}
+
+/// Expands `%VAR%` references from the current environment, leaving unknown ones untouched.
+pub fn expand_env_vars(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find('%') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('%') {
+            Some(end) => {
+                let name = &after[..end];
+                match std::env::var(name) {
+                    Ok(value) if !name.is_empty() => output.push_str(&value),
+                    _ => {
+                        output.push('%');
+                        output.push_str(name);
+                        output.push('%');
+                    }
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                output.push('%');
+                rest = after;
+            }
+        }
+    }
+    output.push_str(rest);
+    output
+}
+
+fn parse_link_info(data: &[u8]) -> Result<Option<String>, String> {
+    let mut reader = Reader::new(data);
+    let _size = reader.u32()?;
+    let header_size = reader.u32()? as usize;
+    let flags = reader.u32()?;
+    let _volume_id_offset = reader.u32()?;
+    let local_base_path_offset = reader.u32()? as usize;
+    let network_link_offset = reader.u32()? as usize;
+    let path_suffix_offset = reader.u32()? as usize;
+    let (local_base_path_offset_unicode, path_suffix_offset_unicode) = if header_size >= 0x24 {
+        (reader.u32()? as usize, reader.u32()? as usize)
+    } else {
+        (0, 0)
+    };
+
+    let suffix = if path_suffix_offset_unicode != 0 {
+        utf16_cstr_at(data, path_suffix_offset_unicode)
+    } else {
+        ansi_cstr_at(data, path_suffix_offset)
+    }.unwrap_or_default();
+
+    if flags & VOLUME_ID_AND_LOCAL_BASE_PATH != 0 {
+        let base = if local_base_path_offset_unicode != 0 {
+            utf16_cstr_at(data, local_base_path_offset_unicode)
+        } else {
+            ansi_cstr_at(data, local_base_path_offset)
+        };
+        if let Some(base) = base {
+            return Ok(Some(join_windows_path(&base, &suffix)));
+        }
+    }
+    if flags & COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX != 0 && network_link_offset < data.len() {
+        let network = &data[network_link_offset..];
+        let mut reader = Reader::new(network);
+        let _size = reader.u32()?;
+        let _flags = reader.u32()?;
+        let net_name_offset = reader.u32()? as usize;
+        let _device_name_offset = reader.u32()?;
+        let _provider = reader.u32()?;
+        let net_name = if net_name_offset > 0x14 {
+            let net_name_offset_unicode = reader.u32()? as usize;
+            utf16_cstr_at(network, net_name_offset_unicode)
+        } else {
+            ansi_cstr_at(network, net_name_offset)
+        };
+        if let Some(net_name) = net_name {
+            return Ok(Some(join_windows_path(&net_name, &suffix)));
+        }
+    }
+    Ok(None)
+}
+
+fn join_windows_path(base: &str, suffix: &str) -> String {
+    if suffix.is_empty() {
+        base.to_string()
+    } else if base.ends_with('\\') {
+        format!("{}{}", base, suffix)
+    } else {
+        format!("{}\\{}", base, suffix)
+    }
+}
+
+fn parse_environment_block(block: &[u8]) -> Option<String> {
+    if block.len() < ENVIRONMENT_DATA_BLOCK_SIZE {
+        return None;
+    }
+    let ansi = &block[8..8 + 260];
+    let unicode = &block[8 + 260..8 + 260 + 520];
+    utf16_cstr_at(unicode, 0)
+        .filter(|value| !value.is_empty())
+        .or_else(|| ansi_cstr_at(ansi, 0))
+        .filter(|value| !value.is_empty())
+}
+
+/// Rebuilds a file system path from the shell item IDs, used when a link has no LinkInfo.
+/// Only drive and file entry items are understood, anything else (control panel, libraries) gives `None`.
+fn parse_id_list(data: &[u8]) -> Option<String> {
+    let mut path = String::new();
+    let mut offset = 0;
+    while offset + 2 <= data.len() {
+        let item_size = u16::from_le_bytes([data[offset], data[offset + 1]]) as usize;
+        if item_size == 0 {
+            break;
+        }
+        if item_size < 3 || offset + item_size > data.len() {
+            return None;
+        }
+        let item = &data[offset..offset + item_size];
+        match item[2] & 0x70 {
+            // Root folder (My Computer and friends) carries no path component
+            0x10 => {}
+            // Volume item: "C:\"
+            0x20 => {
+                path = ansi_cstr_at(item, 3)?;
+                if !path.ends_with('\\') {
+                    path.push('\\');
+                }
+            }
+            // File entry item: 8.3 name followed by an extension block with the long name
+            0x30 => {
+                let name = file_entry_name(item)?;
+                if !path.is_empty() && !path.ends_with('\\') {
+                    path.push('\\');
+                }
+                path.push_str(&name);
+            }
+            _ => return None,
+        }
+        offset += item_size;
+    }
+    if path.is_empty() { None } else { Some(path) }
+}
+
+fn file_entry_name(item: &[u8]) -> Option<String> {
+    let short_name_start = 14;
+    let short_name = ansi_cstr_at(item, short_name_start)?;
+    let mut extension_start = short_name_start + short_name.len() + 1;
+    if extension_start % 2 != 0 {
+        extension_start += 1;
+    }
+    // BEEF0004 extension block, the long name follows the fixed fields
+    if let Some(extension) = item.get(extension_start..) && extension.len() >= 8
+        && extension[4..8] == [0x04, 0x00, 0xEF, 0xBE] {
+        let version = u16::from_le_bytes([extension[2], extension[3]]);
+        let long_name_offset = match version {
+            0..=6 => 0x14,
+            7 => 0x1A,
+            _ => 0x2E,
+        };
+        if let Some(long_name) = utf16_cstr_at(extension, long_name_offset) && !long_name.is_empty() {
+            return Some(long_name);
+        }
+    }
+    Some(short_name)
+}
+
+fn ansi_cstr_at(data: &[u8], offset: usize) -> Option<String> {
+    let bytes = data.get(offset..)?;
+    let end = bytes.iter().position(|byte| *byte == 0).unwrap_or(bytes.len());
+    Some(decode_ansi(&bytes[..end]))
+}
+
+fn utf16_cstr_at(data: &[u8], offset: usize) -> Option<String> {
+    let bytes = data.get(offset..)?;
+    let units: Vec<u16> = bytes.chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .take_while(|unit| *unit != 0)
+        .collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
+// Code page strings are treated as Latin-1, which is right for the ASCII paths found in practice
+fn decode_ansi(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| *byte as char).collect()
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        match self.data.get(self.offset..self.offset + count) {
+            Some(bytes) => {
+                self.offset += count;
+                Ok(bytes)
+            }
+            None => Err(format!("Unexpected end of shell link data at offset {:#x}", self.offset)),
+        }
+    }
+    fn skip(&mut self, count: usize) -> Result<(), String> {
+        self.bytes(count).map(|_| ())
+    }
+    fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+    fn u32(&mut self) -> Result<u32, String> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+    fn peek_u32(&self) -> Result<u32, String> {
+        match self.data.get(self.offset..self.offset + 4) {
+            Some(bytes) => Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+            None => Err(format!("Unexpected end of shell link data at offset {:#x}", self.offset)),
+        }
+    }
+    fn counted_string(&mut self, unicode: bool) -> Result<String, String> {
+        let count = self.u16()? as usize;
+        if unicode {
+            let bytes = self.bytes(count * 2)?;
+            let units: Vec<u16> = bytes.chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .collect();
+            Ok(String::from_utf16_lossy(&units))
+        } else {
+            Ok(decode_ansi(self.bytes(count)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOCAL_TARGET: &[u8] = include_bytes!("../tests/fixtures/shell_link/local_target.lnk");
+    const ENV_TARGET: &[u8] = include_bytes!("../tests/fixtures/shell_link/env_target.lnk");
+    const ICON_LOCATION: &[u8] = include_bytes!("../tests/fixtures/shell_link/icon_location.lnk");
+
+    #[test]
+    fn parses_link_info_target_and_strings() {
+        let link = ShellLink::parse(LOCAL_TARGET).unwrap();
+        assert_eq!(link.target.as_deref(), Some(r"C:\Program Files\Editor\editor.exe"));
+        assert_eq!(link.description.as_deref(), Some("Text editor"));
+        assert_eq!(link.relative_path.as_deref(), Some(r"..\..\..\..\Program Files\Editor\editor.exe"));
+        assert_eq!(link.working_dir.as_deref(), Some(r"C:\Program Files\Editor"));
+        assert_eq!(link.arguments.as_deref(), Some("--new-window"));
+        assert_eq!(link.show_command, ShowCommand::Normal);
+        assert_eq!(link.file_attributes, 0x20);
+        assert_eq!(link.resolved_target(Path::new("/links/editor.lnk")), Some(PathBuf::from(r"C:\Program Files\Editor\editor.exe")));
+    }
+
+    #[test]
+    fn prefers_environment_block_over_relative_path() {
+        let link = ShellLink::parse(ENV_TARGET).unwrap();
+        assert_eq!(link.target, None);
+        assert_eq!(link.env_target.as_deref(), Some(r"%ProgramFiles%\Tools\tool.exe"));
+        assert_eq!(link.relative_path.as_deref(), Some(r"..\Tools\tool.exe"));
+        assert_eq!(link.show_command, ShowCommand::Maximized);
+
+        let relative_only = ShellLink { env_target: None, ..link };
+        let target = relative_only.resolved_target(Path::new("/links/tool.lnk")).unwrap();
+        assert_eq!(target, Path::new("/links").join("..").join("Tools").join("tool.exe"));
+    }
+
+    #[test]
+    fn reads_icon_location_and_index() {
+        let link = ShellLink::parse(ICON_LOCATION).unwrap();
+        assert_eq!(link.icon_location.as_deref(), Some(r"C:\Windows\system32\shell32.dll"));
+        assert_eq!(link.icon_env_location.as_deref(), Some(r"%SystemRoot%\system32\imageres.dll"));
+        assert_eq!(link.icon_index, -3);
+        assert_eq!(link.show_command, ShowCommand::Minimized);
+
+        let plain = ShellLink { icon_env_location: None, ..link.clone() };
+        assert_eq!(plain.resolved_icon(Path::new("/links/viewer.lnk")), Some((PathBuf::from(r"C:\Windows\system32\shell32.dll"), -3)));
+        // Without an icon location the target's first icon is used
+        let no_icon = ShellLink { icon_env_location: None, icon_location: None, ..link };
+        assert_eq!(no_icon.resolved_icon(Path::new("/links/viewer.lnk")).map(|(_, index)| index), Some(0));
+    }
+
+    #[test]
+    fn rejects_empty_and_truncated_data() {
+        assert!(ShellLink::parse(&[]).is_err());
+        for fixture in [LOCAL_TARGET, ENV_TARGET, ICON_LOCATION] {
+            // Only the extra data blocks may be cut short, everything before them is required
+            let required = match fixture {
+                LOCAL_TARGET => fixture.len() - 4,
+                _ => fixture.len() - 4 - ENVIRONMENT_DATA_BLOCK_SIZE,
+            };
+            for length in 0..required {
+                assert!(ShellLink::parse(&fixture[..length]).is_err(), "length {}", length);
+            }
+            for length in required..fixture.len() {
+                assert!(ShellLink::parse(&fixture[..length]).is_ok(), "length {}", length);
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_lying_size_fields() {
+        let mut header_size = LOCAL_TARGET.to_vec();
+        header_size[0] = 0x50;
+        assert!(ShellLink::parse(&header_size).is_err());
+
+        // LinkInfo claiming more bytes than the file has
+        let mut link_info_size = LOCAL_TARGET.to_vec();
+        link_info_size[HEADER_SIZE..HEADER_SIZE + 4].copy_from_slice(&0xFFFF_u32.to_le_bytes());
+        assert!(ShellLink::parse(&link_info_size).is_err());
+
+        // LinkInfo too small for its own header
+        let mut link_info_header = LOCAL_TARGET.to_vec();
+        link_info_header[HEADER_SIZE..HEADER_SIZE + 4].copy_from_slice(&8_u32.to_le_bytes());
+        assert!(ShellLink::parse(&link_info_header).is_err());
+
+        // String count running past the end of the data
+        let mut string_count = ENV_TARGET.to_vec();
+        string_count[HEADER_SIZE..HEADER_SIZE + 2].copy_from_slice(&0x7FFF_u16.to_le_bytes());
+        assert!(ShellLink::parse(&string_count).is_err());
+
+        // ID list claiming more bytes than the file has
+        let mut id_list_size = ENV_TARGET.to_vec();
+        id_list_size[0x14] |= HAS_LINK_TARGET_ID_LIST as u8;
+        id_list_size.splice(HEADER_SIZE..HEADER_SIZE, 0xFFFF_u16.to_le_bytes());
+        assert!(ShellLink::parse(&id_list_size).is_err());
+    }
+
+    #[test]
+    fn ignores_lying_offsets_inside_link_info() {
+        // Base path offset past the end of the LinkInfo gives no target rather than a panic
+        let mut base_offset = LOCAL_TARGET.to_vec();
+        base_offset[HEADER_SIZE + 16..HEADER_SIZE + 20].copy_from_slice(&0xFFFF_u32.to_le_bytes());
+        let link = ShellLink::parse(&base_offset).unwrap();
+        assert_eq!(link.target, None);
+    }
+}
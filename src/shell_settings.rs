@@ -0,0 +1,59 @@
+use crate::do_not_disturb::DoNotDisturbSettings;
+use dirs::data_dir;
+use serde_json::{from_str, to_string_pretty};
+use std::path::PathBuf;
+
+/// Settings of the taskbar and panel menu, kept apart from the start menu's.
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone, Default)]
+pub struct ShellSettings {
+    // Shows the playing track with its controls next to the status icons
+    #[serde(default)]
+    pub taskbar_media: bool,
+    #[serde(default)]
+    pub do_not_disturb: DoNotDisturbSettings,
+}
+
+impl ShellSettings {
+    pub fn settings_file() -> Option<PathBuf> {
+        data_dir().map(|data_dir| data_dir.join("Frostwin").join("Shell_Settings.json"))
+    }
+
+    pub fn load() -> ShellSettings {
+        let Some(settings_file) = Self::settings_file() else {
+            return Self::default();
+        };
+        match std::fs::read_to_string(&settings_file) {
+            Ok(content) => {
+                match from_str::<ShellSettings>(&content) {
+                    Ok(settings) => settings,
+                    Err(e) => {
+                        eprintln!("Error loading shell settings: {:?}", e);
+                        Self::default()
+                    }
+                }
+            }
+            // Written the first time something is changed
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Self::default(),
+            Err(e) => {
+                eprintln!("Error opening shell settings: {:?}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self) {
+        let Some(settings_file) = Self::settings_file() else {
+            return;
+        };
+        match to_string_pretty(self) {
+            Ok(settings) => {
+                if let Err(e) = std::fs::write(&settings_file, settings) {
+                    eprintln!("Error writing shell settings: {:?}", e);
+                };
+            }
+            Err(e) => {
+                eprintln!("Error serializing shell settings: {:?}", e);
+            }
+        };
+    }
+}
@@ -0,0 +1,331 @@
+#[cfg(windows)]
+use crate::appx::is_family_installed;
+#[cfg(target_os = "linux")]
+use crate::desktop_entry::application_dirs;
+use crate::start_settings::{StartMenuSettings, Tile, TileGroup, TileSize};
+use dirs::data_dir;
+use serde_json::{from_str, to_string_pretty};
+use std::path::{Path, PathBuf};
+
+const LAYOUT_VERSION: u32 = 1;
+
+/// A tile in a portable layout. The path is either `{Folder}/relative/path` for entries below a known folder,
+/// or an absolute path or URI kept as it was.
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone, PartialEq)]
+pub struct LayoutTile {
+    pub path: String,
+    #[serde(default)]
+    pub size: TileSize,
+}
+
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone, PartialEq)]
+pub struct LayoutGroup {
+    pub name: String,
+    #[serde(default)]
+    pub collapsed: bool,
+    #[serde(default)]
+    pub tiles: Vec<LayoutTile>,
+}
+
+/// Start menu tiles, groups and hidden entries in a form that can be copied between users and machines.
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone, PartialEq)]
+pub struct StartLayout {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub groups: Vec<LayoutGroup>,
+    #[serde(default)]
+    pub hidden: Vec<String>,
+}
+impl StartLayout {
+    pub fn from_settings(settings: &StartMenuSettings) -> Self {
+        Self {
+            version: LAYOUT_VERSION,
+            groups: settings.groups.iter()
+                .map(|group| LayoutGroup {
+                    name: group.name.clone(),
+                    collapsed: group.collapsed,
+                    tiles: group.tiles.iter()
+                        .map(|tile| LayoutTile { path: to_portable(&tile.path), size: tile.size })
+                        .collect(),
+                })
+                .collect(),
+            hidden: settings.hidden.iter().map(|path| to_portable(path)).collect(),
+        }
+    }
+
+    pub fn parse(content: &str) -> Result<Self, String> {
+        from_str::<StartLayout>(content).map_err(|e| format!("{:?}", e))
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        to_string_pretty(self).map_err(|e| format!("{:?}", e))
+    }
+
+    /// Replaces the tiles, groups and hidden entries of `settings` with this layout.
+    /// Entries that do not exist for this user are left out and returned.
+    pub fn apply_to(&self, settings: &mut StartMenuSettings) -> Vec<String> {
+        let mut unresolved: Vec<String> = Vec::new();
+        settings.groups = self.groups.iter()
+            .map(|group| {
+                let mut tile_group = TileGroup::new(group.name.clone());
+                tile_group.collapsed = group.collapsed;
+                for tile in group.tiles.iter() {
+                    match from_portable(&tile.path) {
+                        Some(path) => tile_group.tiles.push(Tile { path, size: tile.size }),
+                        None => unresolved.push(tile.path.clone()),
+                    }
+                }
+                tile_group
+            })
+            .collect();
+        settings.hidden.clear();
+        for hidden in self.hidden.iter() {
+            match from_portable(hidden) {
+                Some(path) => settings.hide(path),
+                None => unresolved.push(hidden.clone()),
+            }
+        }
+        unresolved
+    }
+
+    /// Reads the start layout of a Windows `LayoutModification.xml`. Tiles are ordered by their row and column,
+    /// since Frostwin packs them itself. Tiles this shell cannot show, like secondary tiles, are returned
+    /// next to the layout.
+    pub fn from_layout_modification(xml: &str) -> Result<(Self, Vec<String>), String> {
+        let document = roxmltree::Document::parse(xml).map_err(|e| format!("{}", e))?;
+        let mut layout = Self { version: LAYOUT_VERSION, groups: Vec::new(), hidden: Vec::new() };
+        let mut unsupported: Vec<String> = Vec::new();
+        for group_node in document.descendants().filter(|node| node.is_element() && node.tag_name().name() == "Group") {
+            let mut tiles: Vec<((u32, u32), LayoutTile)> = Vec::new();
+            // Tiles inside a tile folder are listed with the group, the folder's own position is used for them
+            for tile_node in group_node.descendants().filter(|node| node.is_element()) {
+                let position_node = match tile_node.parent_element() {
+                    Some(parent) if parent.tag_name().name() == "Folder" => parent,
+                    _ => tile_node,
+                };
+                let row = position_node.attribute("Row").and_then(|row| row.parse().ok()).unwrap_or(u32::MAX);
+                let column = position_node.attribute("Column").and_then(|column| column.parse().ok()).unwrap_or(u32::MAX);
+                let size = tile_size(tile_node.attribute("Size").unwrap_or_default());
+                let path = match tile_node.tag_name().name() {
+                    "Tile" => tile_node.attribute("AppUserModelID").map(|aumid| format!("shell:AppsFolder\\{}", aumid)),
+                    "DesktopApplicationTile" => {
+                        if let Some(link_path) = tile_node.attribute("DesktopApplicationLinkPath") {
+                            Some(to_portable(Path::new(&expand_environment(link_path))))
+                        } else if let Some(id) = tile_node.attribute("DesktopApplicationID") {
+                            match known_folder_path(id) {
+                                Some(path) => Some(to_portable(&path)),
+                                None => Some(format!("shell:AppsFolder\\{}", id)),
+                            }
+                        } else {
+                            None
+                        }
+                    }
+                    "SecondaryTile" => {
+                        unsupported.push(tile_node.attribute("DisplayName").or(tile_node.attribute("TileID")).unwrap_or("Secondary tile").to_string());
+                        continue;
+                    }
+                    _ => continue,
+                };
+                match path {
+                    Some(path) => tiles.push(((row, column), LayoutTile { path, size })),
+                    None => unsupported.push(format!("{} without a target", tile_node.tag_name().name())),
+                }
+            }
+            tiles.sort_by_key(|(position, _)| *position);
+            layout.groups.push(LayoutGroup {
+                name: group_node.attribute("Name").unwrap_or_default().to_string(),
+                collapsed: false,
+                tiles: tiles.into_iter().map(|(_, tile)| tile).collect(),
+            });
+        }
+        Ok((layout, unsupported))
+    }
+}
+
+/// Layout sizes are written as width x height in cells.
+fn tile_size(size: &str) -> TileSize {
+    match size {
+        "1x1" => TileSize::Small,
+        "4x2" => TileSize::Wide,
+        "4x4" => TileSize::Large,
+        _ => TileSize::Medium,
+    }
+}
+
+/// Folders portable paths are relative to. A name may cover several folders, like the user's and the shared
+/// Programs folder, which are tried in order on import.
+fn known_folders() -> Vec<(&'static str, Vec<PathBuf>)> {
+    let mut folders: Vec<(&'static str, Vec<PathBuf>)> = Vec::new();
+    #[cfg(windows)]
+    {
+        let mut programs: Vec<PathBuf> = Vec::new();
+        if let Some(data_dir) = data_dir() {
+            programs.push(data_dir.join(r"Microsoft\Windows\Start Menu\Programs"));
+        }
+        let program_data = std::env::var_os("ProgramData").map(PathBuf::from).unwrap_or_else(|| PathBuf::from(r"C:\ProgramData"));
+        programs.push(program_data.join(r"Microsoft\Windows\Start Menu\Programs"));
+        folders.push(("Programs", programs));
+    }
+    #[cfg(target_os = "linux")]
+    folders.push(("Applications", application_dirs()));
+    let user_folders = [
+        ("Desktop", dirs::desktop_dir()),
+        ("Documents", dirs::document_dir()),
+        ("Downloads", dirs::download_dir()),
+        ("AppData", data_dir()),
+        ("Home", dirs::home_dir()),
+    ];
+    for (name, folder) in user_folders {
+        if let Some(folder) = folder {
+            folders.push((name, vec![folder]));
+        }
+    }
+    folders
+}
+
+/// Writes a path relative to the deepest known folder containing it.
+pub fn to_portable(path: &Path) -> String {
+    let mut best: Option<(&str, usize, PathBuf)> = None;
+    for (name, dirs) in known_folders() {
+        for dir in dirs {
+            if let Ok(relative) = path.strip_prefix(&dir) {
+                let depth = dir.components().count();
+                if best.as_ref().is_none_or(|(_, best_depth, _)| depth > *best_depth) {
+                    best = Some((name, depth, relative.to_path_buf()));
+                }
+            }
+        }
+    }
+    match best {
+        Some((name, _, relative)) => {
+            let relative: Vec<String> = relative.components()
+                .map(|component| component.as_os_str().to_string_lossy().to_string())
+                .collect();
+            format!("{{{}}}/{}", name, relative.join("/"))
+        }
+        None => path.to_string_lossy().to_string(),
+    }
+}
+
+/// Resolves a portable path for the current user, or `None` when it names nothing that exists here.
+pub fn from_portable(portable: &str) -> Option<PathBuf> {
+    if let Some(rest) = portable.strip_prefix('{') {
+        let (name, relative) = rest.split_once('}')?;
+        let relative: PathBuf = relative.split(['/', '\\']).filter(|part| !part.is_empty()).collect();
+        let (_, dirs) = known_folders().into_iter().find(|(folder, _)| folder.eq_ignore_ascii_case(name))?;
+        return dirs.into_iter().map(|dir| dir.join(&relative)).find(|path| path.exists());
+    }
+    // A single letter before the colon is a drive, not a scheme
+    if portable.split_once(':').is_some_and(|(scheme, _)| scheme.len() > 1) {
+        return is_uri_available(portable).then(|| PathBuf::from(portable));
+    }
+    let path = PathBuf::from(portable);
+    path.exists().then_some(path)
+}
+
+/// Store apps are only there when their package is registered for this user. Other addresses are assumed to work.
+fn is_uri_available(uri: &str) -> bool {
+    match uri.strip_prefix("shell:AppsFolder\\") {
+        // Desktop application IDs have no package part and cannot be looked up
+        #[cfg(windows)]
+        Some(aumid) => aumid.split_once('!').is_some_and(|(family, _)| is_family_installed(family)),
+        #[cfg(not(windows))]
+        Some(_) => false,
+        None => true,
+    }
+}
+
+/// Expands `%NAME%` variables the way layout files use them, leaving unknown ones in place.
+fn expand_environment(text: &str) -> String {
+    let mut expanded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('%') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('%') {
+            Some(end) => {
+                let name = &after[..end];
+                match std::env::var(name) {
+                    Ok(value) if !name.is_empty() => expanded.push_str(&value),
+                    _ => {
+                        expanded.push('%');
+                        expanded.push_str(name);
+                        expanded.push('%');
+                    }
+                }
+                rest = &after[end + 1..];
+            }
+            None => {
+                expanded.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Desktop application IDs may be a known folder GUID followed by a path below it, like `{GUID}\cmd.exe`.
+fn known_folder_path(id: &str) -> Option<PathBuf> {
+    let (guid, relative) = id.strip_prefix('{')?.split_once('}')?;
+    let variable = match guid.to_uppercase().as_str() {
+        "1AC14E77-02E7-4E5D-B744-2EB1AE5198B7" => "%SystemRoot%\\System32",
+        "F38BF404-1D43-42F2-9305-67DE0B28FC23" => "%SystemRoot%",
+        "6D809377-6AF0-444B-8957-A3773F02200E" | "905E63B6-C1BF-494E-B29C-65B732D3D21A" => "%ProgramFiles%",
+        "7C5A40EF-A0FB-4BFC-874A-C0F2E0B9FA8E" => "%ProgramFiles(x86)%",
+        _ => return None,
+    };
+    let relative = relative.trim_start_matches(['\\', '/']);
+    Some(PathBuf::from(expand_environment(variable)).join(relative))
+}
+
+/// Handles `--export-start-layout <file>` and `--import-start-layout <file>`, returning the exit code
+/// when one of them was given. Imports take a layout exported by Frostwin or a Windows `LayoutModification.xml`.
+pub fn handle_arguments(args: &[String]) -> Option<i32> {
+    let (option, file) = (args.first()?, args.get(1));
+    if option != "--export-start-layout" && option != "--import-start-layout" {
+        return None;
+    }
+    let Some(file) = file.map(PathBuf::from) else {
+        eprintln!("Error: {} needs a file name", option);
+        return Some(2);
+    };
+    let result = if option == "--export-start-layout" {
+        export_layout(&file)
+    } else {
+        import_layout(&file)
+    };
+    match result {
+        Ok(unresolved) => {
+            for entry in unresolved.iter() {
+                eprintln!("Could not resolve {}", entry);
+            }
+            Some(if unresolved.is_empty() { 0 } else { 1 })
+        }
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            Some(2)
+        }
+    }
+}
+
+fn export_layout(file: &Path) -> Result<Vec<String>, String> {
+    let layout = StartLayout::from_settings(&StartMenuSettings::load());
+    std::fs::write(file, layout.to_json()?).map_err(|e| format!("Error writing {}: {}", file.display(), e))?;
+    Ok(Vec::new())
+}
+
+fn import_layout(file: &Path) -> Result<Vec<String>, String> {
+    let content = std::fs::read_to_string(file).map_err(|e| format!("Error reading {}: {}", file.display(), e))?;
+    let is_xml = file.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("xml")) || content.trim_start().starts_with('<');
+    let (layout, mut unresolved) = if is_xml {
+        StartLayout::from_layout_modification(&content)?
+    } else {
+        (StartLayout::parse(&content)?, Vec::new())
+    };
+    let mut settings = StartMenuSettings::load();
+    unresolved.extend(layout.apply_to(&mut settings));
+    settings.save();
+    Ok(unresolved)
+}
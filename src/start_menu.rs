@@ -1,47 +1,140 @@
+#[cfg(windows)]
+use crate::appx::{installed_applications, AppxApplication};
+use crate::item_actions::{show_in_folder, Uninstaller};
+#[cfg(windows)]
+use crate::item_actions::{find_registered_uninstaller, run_as_administrator};
+#[cfg(target_os = "linux")]
+use crate::item_actions::{find_package_uninstaller, run_with_pkexec};
+use crate::calculator;
+use crate::entry_editor::{EntryEditor, EntryField};
+use crate::places::{Place, PlaceKind, Places};
 use crate::power_window::PowerOptions;
-use crate::styles::{colored_button, context_menu_button, transparent_button, window_style};
+use crate::shell_link::ShellLink;
+#[cfg(target_os = "linux")]
+use crate::icon_theme::{load_icon, system_icon_lookup};
+#[cfg(target_os = "linux")]
+use crate::desktop_entry::{application_dirs, current_desktops, current_locale, desktop_file_id, desktop_files_below, expand_exec, find_executable, group_by_category, load_application, load_applications, DesktopEntry};
+use crate::styles::{colored_button, context_menu_button, selected_button, transparent_button, window_style};
+#[cfg(windows)]
 use crate::windows_icons::get_lnk_icon;
+use crate::programs_watcher::ProgramsChange;
+use crate::launch_history::{now_timestamp, LaunchHistory};
+use crate::launcher::{settings_app, LaunchTarget};
+use crate::start_settings::{pack_tiles, CustomEntry, StartMenuSettings, TileGroup, TileSize, TILE_COLUMNS};
 use crate::Message;
 use dirs::data_dir;
 use iced::advanced::text::Wrapping;
+use iced::futures::channel::oneshot;
 use iced::widget::image::Handle;
-use iced::widget::{button, column, container, image, row, rule, scrollable, space, text, Button, Column, Grid, Text};
-use iced::{window, Alignment, Color, ContentFit, Element, Length, Padding, Point, Size, Task};
+use iced::widget::{button, column, container, image, mouse_area, pin, row, rule, scrollable, space, text, text_input, tooltip, Column, Stack, Text};
+use iced::keyboard::key::Named;
+use iced::{event, keyboard, window, Alignment, Color, ContentFit, Element, Length, Padding, Point, Size, Task};
 use iced_aw::context_menu::ContextMenu;
-use serde_json::{from_str, to_string_pretty};
 use std::collections::BTreeMap;
 use std::ffi::OsString;
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Clone)]
 pub enum StartMessage {
-    Init(Arc<Mutex<BTreeMap<PathBuf,Handle>>>),
+    Init(Arc<StartCatalog>),
+    CatalogUpdated(Arc<StartCatalog>),
     Resize(Option<Size>),
     ItemMessage(StartItemMessage),
     SwitchToTab(StartMenuTab),
     PinToTiles(PathBuf),
     UnpinFromTiles(PathBuf),
+    ResizeTile(PathBuf, TileSize),
+    TilePressed(usize, usize),
+    GroupPressed(usize),
+    DragHover(DropTarget),
+    DragReleased(DropTarget),
+    ToggleGroup(usize),
+    StartRenameGroup(usize),
+    RenameGroupInput(String),
+    RenameGroup,
+    AddGroup,
+    RemoveGroup(usize),
+    Search(String),
+    LaunchTopResult,
+    ClearHistory,
+    ConfirmUninstall,
+    CancelUninstall,
+    DismissError,
+    Navigate(window::Id, NavigationKey),
+    OpenPlace(PathBuf),
+    CopyResult(String),
+    AddBookmark(PathBuf),
+    RemoveBookmark(PathBuf),
+    NewEntry,
+    EditEntry(PathBuf),
+    EntryInput(EntryField, String),
+    SaveEntry,
+    DeleteEntry(u32),
+    CancelEdit,
 }
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NavigationKey {
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Escape,
+}
+/// Keyboard focus inside the menu, drawn like a hovered entry.
+#[derive(Debug, Clone, PartialEq)]
+enum Selection {
+    Tile(usize, usize),
+    Item(PathBuf),
+}
+
+/// Maps key presses to start menu navigation, for use with `event::listen_with`.
+/// Keys the search box used are left to it, apart from Escape.
+pub fn navigation_key(event: iced::Event, status: event::Status, window: window::Id) -> Option<Message> {
+    let iced::Event::Keyboard(keyboard::Event::KeyPressed { key: keyboard::Key::Named(named), .. }) = event else {
+        return None;
+    };
+    let key = match named {
+        Named::ArrowUp => NavigationKey::Up,
+        Named::ArrowDown => NavigationKey::Down,
+        Named::ArrowLeft => NavigationKey::Left,
+        Named::ArrowRight => NavigationKey::Right,
+        Named::Enter => NavigationKey::Enter,
+        Named::Escape => NavigationKey::Escape,
+        _ => return None,
+    };
+    if status == event::Status::Captured && key != NavigationKey::Escape {
+        return None;
+    }
+    Some(Message::StartMenu(StartMessage::Navigate(window, key)))
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DropTarget {
+    Tile(usize, usize),
+    Group(usize),
+}
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DragSource {
+    Tile(usize, usize),
+    Group(usize),
+}
+/// A tile's cells as `(x, y, width, height)`.
+type TileRect = (u32, u32, u32, u32);
+struct DragState {
+    source: DragSource,
+    hover: Option<DropTarget>,
+}
+
 #[derive(Debug, Clone)]
 pub enum StartMenuTab {
     Tiles,
     Applications,
+    Places,
 }
 
-#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone)]
-struct StartMenuSettings {
-    tiles: Vec<PathBuf>,
-}
-impl StartMenuSettings {
-    fn new() -> StartMenuSettings {
-        StartMenuSettings {
-            tiles: Default::default(),
-        }
-    }
-}
-
+// Linux lists desktop entries instead, the Programs folder reader still builds there for its tests
+#[cfg_attr(not(windows), allow(dead_code))]
 fn get_dir_contents(path: PathBuf, target: &mut BTreeMap<String, StartItem>) -> std::io::Result<()> {
     for entry in std::fs::read_dir(path)? {
         match entry {
@@ -60,7 +153,7 @@ fn get_dir_contents(path: PathBuf, target: &mut BTreeMap<String, StartItem>) ->
 
                 match StartItem::new(path) {
                     Ok(start_item) => {
-                        target.insert(start_item.name.clone(), start_item);
+                        merge_entry(target, start_item);
                     }
                     Err(e) => {
                         eprintln!("Error getting start item: {}", e);
@@ -76,27 +169,396 @@ fn get_dir_contents(path: PathBuf, target: &mut BTreeMap<String, StartItem>) ->
     Ok(())
 }
 
+#[cfg(target_os = "linux")]
+fn get_desktop_entries(target: &mut BTreeMap<String, StartItem>) {
+    let locale = current_locale();
+    let applications = load_applications(&application_dirs(), locale.as_deref(), &current_desktops());
+    for (category, entries) in group_by_category(applications) {
+        let mut folder_content: BTreeMap<String, StartItem> = BTreeMap::new();
+        for entry in entries {
+            merge_entry(&mut folder_content, StartItem::from_desktop_entry(entry));
+        }
+        target.insert(category.clone(), StartItem::folder(category.clone(), PathBuf::from(category), folder_content));
+    }
+}
+
+/// Store apps have no shortcut in the Programs folders, so they are listed from their package manifests.
+/// A shortcut with the same name wins, as desktop installers of the same app usually create one.
+#[cfg(windows)]
+fn get_packaged_apps(target: &mut BTreeMap<String, StartItem>) {
+    for application in installed_applications(48, 100) {
+        let item = StartItem::from_package(application);
+        if !target.contains_key(&item.name) {
+            target.insert(item.name.clone(), item);
+        }
+    }
+}
+
+/// Adds `item` to a folder's items and returns the key it ended up under, or `None` when it was folded into an existing entry.
+/// The user and system Programs folders are read into the same tree, so folders with the same name are combined
+/// and a program reached through several shortcuts is listed once. Different entries sharing a name are all kept,
+/// the later ones labelled with what they start.
+fn merge_entry(content: &mut BTreeMap<String, StartItem>, mut item: StartItem) -> Option<String> {
+    let name = item.name.clone();
+    let label = item.disambiguation();
+    let mut attempt = 0;
+    loop {
+        let key = match (attempt, label.as_ref()) {
+            (0, _) => name.clone(),
+            (1, Some(label)) => format!("{} ({})", name, label),
+            (_, Some(label)) => format!("{} ({}, {})", name, label, attempt),
+            (_, None) => format!("{} ({})", name, attempt + 1),
+        };
+        match content.get_mut(&key) {
+            None => {
+                item.name = key.clone();
+                content.insert(key.clone(), item);
+                return Some(key);
+            }
+            Some(existing) if existing.content.is_some() && item.content.is_some() => {
+                existing.merge_folder(item);
+                return Some(key);
+            }
+            Some(existing) if existing.content.is_none() && item.content.is_none() && existing.same_target(&item) => {
+                existing.duplicates.push(item.path);
+                existing.duplicates.extend(item.duplicates);
+                return None;
+            }
+            Some(_) => {
+                attempt += 1;
+            }
+        }
+    }
+}
+
+/// Removes the entry at `path` from a folder's items, keeping its sorted keys in step.
+/// An entry combined from several sources stays as long as one of the others is left.
+fn remove_entry(content: &mut BTreeMap<String, StartItem>, sorted: &mut Vec<String>, path: &PathBuf) -> bool {
+    let key = content.iter()
+        .find(|(_, item)| item.path == *path || item.duplicates.contains(path))
+        .map(|(key, _)| key.clone());
+    if let Some(key) = key {
+        if let Some(item) = content.get_mut(&key) && !item.duplicates.is_empty() {
+            if item.path == *path {
+                item.path = item.duplicates.remove(0);
+            } else {
+                item.duplicates.retain(|duplicate| duplicate != path);
+            }
+            item.remove_below(path);
+            return true;
+        }
+        content.remove(&key);
+        sorted.retain(|sorted_key| *sorted_key != key);
+        return true;
+    }
+    content.values_mut().any(|item| item.remove(path))
+}
+
+/// Takes the whole entry at `path` out of a folder's items, together with every file it stands for,
+/// and returns it with the names of the folders it was in.
+fn take_entry(content: &mut BTreeMap<String, StartItem>, sorted: &mut Vec<String>, path: &PathBuf) -> Option<(StartItem, Vec<String>)> {
+    let key = content.iter()
+        .find(|(_, item)| item.content.is_none() && (item.path == *path || item.duplicates.contains(path)))
+        .map(|(key, _)| key.clone());
+    if let Some(key) = key {
+        sorted.retain(|sorted_key| *sorted_key != key);
+        return content.remove(&key).map(|item| (item, Vec::new()));
+    }
+    for (key, folder) in content.iter_mut() {
+        let Some(folder_content) = folder.content.as_mut() else {
+            continue;
+        };
+        let mut folder_sorted = folder.sorted.take().unwrap_or_default();
+        let taken = take_entry(folder_content, &mut folder_sorted, path);
+        folder.sorted = Some(folder_sorted);
+        if let Some((item, mut folders)) = taken {
+            folders.insert(0, key.clone());
+            return Some((item, folders));
+        }
+    }
+    None
+}
+
+/// Inserts `item` below `folders`, creating the folders that do not exist yet.
+fn insert_entry(content: &mut BTreeMap<String, StartItem>, sorted: &mut Vec<String>, parent: &Path, folders: &[String], item: StartItem) {
+    match folders.split_first() {
+        None => {
+            if let Some(key) = merge_entry(content, item) && !sorted.contains(&key) {
+                sorted.push(key);
+                alphanumeric_sort::sort_str_slice(sorted);
+            }
+        }
+        Some((name, rest)) => {
+            let key = name.clone();
+            if !sorted.contains(&key) {
+                sorted.push(key.clone());
+                alphanumeric_sort::sort_str_slice(sorted);
+            }
+            let folder_path = parent.join(name);
+            let folder = content.entry(key)
+                .or_insert_with(|| StartItem::folder(name.clone(), folder_path.clone(), BTreeMap::new()));
+            if let Some(folder_content) = folder.content.as_mut() {
+                let mut folder_sorted = folder.sorted.take().unwrap_or_default();
+                insert_entry(folder_content, &mut folder_sorted, &folder_path, rest, item);
+                folder.sorted = Some(folder_sorted);
+            }
+        }
+    }
+}
+
+/// Programs tree shared by every opening of the start menu.
+/// It is read once in the background when the shell starts and kept current from file system changes,
+/// so opening the menu never has to touch the disk.
+#[derive(Debug, Clone, Default)]
+pub struct StartCatalog {
+    content: BTreeMap<String, StartItem>,
+    sorted: Vec<String>,
+}
+impl StartCatalog {
+    fn find_item(&self, path: &PathBuf) -> Option<&StartItem> {
+        self.content.values().find_map(|item| item.find(path))
+    }
+
+    /// Folders start menu entries are read from, in precedence order.
+    pub fn watched_dirs() -> Vec<PathBuf> {
+        let mut folders: Vec<PathBuf> = Vec::new();
+        #[cfg(windows)]
+        {
+            if let Some(data_dir) = data_dir() {
+                folders.push(data_dir.join(r"Microsoft\Windows\Start Menu\Programs"));
+            }
+            folders.push(PathBuf::from(r"C:\ProgramData\Microsoft\Windows\Start Menu\Programs"));
+        }
+        #[cfg(target_os = "linux")]
+        folders.extend(application_dirs());
+        folders
+    }
+
+    /// Reads every entry and extracts its icon. This blocks on the disk, so the shell uses `load_in_background`.
+    pub fn load(app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>) -> Self {
+        let mut content: BTreeMap<String, StartItem> = BTreeMap::new();
+        #[cfg(windows)]
+        {
+            for dir in Self::watched_dirs() {
+                if let Err(e) = get_dir_contents(dir.clone(), &mut content) {
+                    eprintln!("Error loading start programs from {:?}: {:?}", dir, e);
+                };
+            }
+            get_packaged_apps(&mut content);
+        }
+        #[cfg(target_os = "linux")]
+        get_desktop_entries(&mut content);
+        for (_, entry) in content.iter_mut() {
+            entry.prep(app_image_cache.clone());
+        }
+        let mut sorted: Vec<String> = content.keys().cloned().collect();
+        alphanumeric_sort::sort_str_slice(&mut sorted);
+        Self { content, sorted }
+    }
+
+    pub fn load_in_background(app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>) -> Task<Self> {
+        in_background(move || Self::load(app_image_cache))
+    }
+
+    /// Applies file system changes to a copy of the catalog on a thread of its own, as reading entries and extracting
+    /// icons blocks on the disk. Gives the updated catalog, or `None` when nothing in it changed.
+    /// A `ProgramsChange::Rescan` among the changes reads the whole catalog again.
+    pub fn apply_in_background(catalog: Arc<Self>, changes: Vec<ProgramsChange>, app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>) -> Task<Option<Self>> {
+        in_background(move || {
+            if changes.contains(&ProgramsChange::Rescan) {
+                return Some(Self::load(app_image_cache));
+            }
+            let mut catalog = Arc::unwrap_or_clone(catalog);
+            let mut changed = false;
+            for change in changes.iter() {
+                changed |= catalog.apply(change, app_image_cache.clone());
+            }
+            changed.then_some(catalog)
+        })
+    }
+
+    /// Applies one file system change to the tree, returning whether anything in it changed.
+    /// `ProgramsChange::Rescan` is left to `apply_in_background`, which reloads the whole catalog.
+    fn apply(&mut self, change: &ProgramsChange, app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>) -> bool {
+        let path = match change {
+            ProgramsChange::Added(path) | ProgramsChange::Modified(path) | ProgramsChange::Removed(path) => path,
+            ProgramsChange::Rescan => return false,
+        };
+        if let ProgramsChange::Modified(path) = change && path.is_dir() {
+            return false;
+        }
+        let Some(root) = Self::watched_dirs().into_iter().find(|root| path.starts_with(root)) else {
+            return false;
+        };
+        match app_image_cache.lock() {
+            Ok(mut app_image_lock) => {
+                // The icon may have changed with the entry, so it is extracted again
+                app_image_lock.remove(path);
+            }
+            Err(e) => {
+                eprintln!("Error accessing app_image_cache: {}", e);
+            }
+        }
+        #[cfg(windows)]
+        return self.apply_programs_path(&root, path, app_image_cache);
+        #[cfg(target_os = "linux")]
+        return self.apply_applications_path(&root, &application_dirs(), path, app_image_cache);
+    }
+
+    #[cfg_attr(not(windows), allow(dead_code))]
+    fn apply_programs_path(&mut self, root: &Path, path: &PathBuf, app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>) -> bool {
+        if path.extension().is_some_and(|extension| extension == "ini") {
+            return false;
+        }
+        // Added and modified entries are read again from scratch, removed ones no longer exist
+        let mut changed = remove_entry(&mut self.content, &mut self.sorted, path);
+        if path.exists() {
+            let Ok(relative) = path.strip_prefix(root) else {
+                return changed;
+            };
+            let folders: Vec<String> = relative.parent()
+                .map(|parent| parent.iter().map(|part| part.to_string_lossy().to_string()).collect())
+                .unwrap_or_default();
+            match StartItem::new(path.clone()) {
+                Ok(mut item) => {
+                    item.prep(app_image_cache);
+                    insert_entry(&mut self.content, &mut self.sorted, root, &folders, item);
+                    changed = true;
+                }
+                Err(e) => {
+                    eprintln!("Error getting start item: {}", e);
+                }
+            }
+        }
+        changed
+    }
+
+    /// Desktop files shadow each other by ID across the XDG folders, so a change is applied
+    /// by looking up whichever file now wins for the affected IDs.
+    #[cfg(target_os = "linux")]
+    fn apply_applications_path(&mut self, root: &Path, dirs: &[PathBuf], path: &PathBuf, app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>) -> bool {
+        let mut affected: Vec<PathBuf> = Vec::new();
+        if path.extension().is_some_and(|extension| extension == "desktop") {
+            affected.push(path.clone());
+        } else {
+            // A whole folder came or went, so every entry below it is looked at again
+            affected.extend(desktop_files_below(path));
+            let mut leaves: Vec<&StartItem> = Vec::new();
+            for item in self.content.values() {
+                item.collect_leaves(&mut leaves);
+            }
+            affected.extend(leaves.into_iter()
+                .flat_map(|item| std::iter::once(&item.path).chain(item.duplicates.iter()))
+                .filter(|item_path| item_path.starts_with(path))
+                .cloned());
+        }
+        let locale = current_locale();
+        let desktops = current_desktops();
+        let mut changed = false;
+        let mut done: Vec<PathBuf> = Vec::new();
+        while let Some(file) = affected.pop() {
+            if done.contains(&file) {
+                continue;
+            }
+            done.push(file.clone());
+            // Entries folded into the one being replaced may live in any of the folders
+            let Some(relative) = std::iter::once(root).chain(dirs.iter().map(PathBuf::as_path))
+                .find_map(|dir| file.strip_prefix(dir).ok()) else {
+                continue;
+            };
+            let Some(id) = desktop_file_id(relative) else {
+                continue;
+            };
+            let mut leaves: Vec<&StartItem> = Vec::new();
+            for item in self.content.values() {
+                item.collect_leaves(&mut leaves);
+            }
+            let shown: Vec<(PathBuf, Vec<PathBuf>)> = leaves.into_iter()
+                .filter(|item| matches!(item.target.as_ref(), Some(StartTarget::DesktopEntry(entry)) if entry.id == id))
+                .map(|item| (item.path.clone(), item.duplicates.clone()))
+                .collect();
+            for (shown_path, duplicates) in shown {
+                for path in std::iter::once(&shown_path).chain(duplicates.iter()) {
+                    changed |= remove_entry(&mut self.content, &mut self.sorted, path);
+                }
+                // Entries folded into this one are listed again on their own
+                affected.extend(duplicates);
+            }
+            // The file may only have been folded into an entry from another desktop file
+            changed |= remove_entry(&mut self.content, &mut self.sorted, &file);
+            if let Some(entry) = load_application(relative, dirs, locale.as_deref(), &desktops) {
+                let category = entry.main_category().to_string();
+                let mut item = StartItem::from_desktop_entry(entry);
+                item.prep(app_image_cache.clone());
+                insert_entry(&mut self.content, &mut self.sorted, Path::new(""), &[category], item);
+                changed = true;
+            }
+        }
+        // Category folders only exist for the entries in them
+        let empty: Vec<String> = self.content.iter()
+            .filter(|(_, item)| item.content.as_ref().is_some_and(|content| content.is_empty()))
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in empty {
+            self.content.remove(&key);
+            self.sorted.retain(|sorted_key| *sorted_key != key);
+        }
+        changed
+    }
+}
+
+/// Runs catalog work on a thread of its own, giving the default value should the thread die.
+fn in_background<T: Default + Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Task<T> {
+    Task::perform(async move {
+        let (sender, receiver) = oneshot::channel();
+        std::thread::spawn(move || {
+            #[cfg(windows)]
+            unsafe {
+                // Icon extraction falls back to the shell, which wants COM on this thread
+                let _ = windows::Win32::System::Com::CoInitializeEx(None, windows::Win32::System::Com::COINIT_APARTMENTTHREADED);
+            }
+            let _ = sender.send(work());
+        });
+        receiver.await.unwrap_or_default()
+    }, |result| result)
+}
+
 pub struct StartMenu {
     pub id: window::Id,
     content: BTreeMap<String, StartItem>,
     sorted: Vec<String>,
     tab: StartMenuTab,
     settings: StartMenuSettings,
+    drag: Option<DragState>,
+    renaming: Option<(usize, String)>,
+    history: LaunchHistory,
+    search: String,
+    error: Option<String>,
+    pending_uninstall: Option<Uninstaller>,
+    selection: Option<Selection>,
+    places: Places,
+    /// What the settings button opens, if the system has a settings application.
+    settings_app: Option<OsString>,
+    /// Catalog as last received, before hidden, renamed and custom entries were applied.
+    catalog: Arc<StartCatalog>,
+    editor: Option<EntryEditor>,
 }
 impl StartMenu {
     pub fn new() -> (Self,Task<window::Id>) {
-        let mut settings = window::Settings::default();
-        settings.decorations = false;
-        settings.resizable = false;
-        settings.min_size = None;
-        settings.max_size = None;
-        settings.icon = None;
-        settings.transparent = true;
-        settings.closeable = false;
-        settings.minimizable = false;
-        settings.level = window::Level::AlwaysOnTop;
-        settings.position = window::Position::Specific(Point::new(2.0,37.0));
-        settings.size = Size::new(400.0,600.0);
+        let settings = window::Settings {
+            decorations: false,
+            resizable: false,
+            min_size: None,
+            max_size: None,
+            icon: None,
+            transparent: true,
+            closeable: false,
+            minimizable: false,
+            level: window::Level::AlwaysOnTop,
+            position: window::Position::Specific(Point::new(2.0,37.0)),
+            size: Size::new(400.0,600.0),
+            ..window::Settings::default()
+        };
         let (id,open_task) = window::open(settings);
         (Self {
             id,
@@ -104,82 +566,39 @@ impl StartMenu {
             sorted: Vec::new(),
             tab: StartMenuTab::Tiles,
             settings: StartMenuSettings::new(),
+            drag: None,
+            renaming: None,
+            history: LaunchHistory::default(),
+            search: String::new(),
+            error: None,
+            pending_uninstall: None,
+            selection: None,
+            places: Places::default(),
+            settings_app: None,
+            catalog: Arc::default(),
+            editor: None,
         },
          open_task)
     }
     pub fn update(&mut self, message: StartMessage) -> Task<Message> {
         match message {
-            StartMessage::Init(app_image_cache) => {
-                let mut content: BTreeMap<String, StartItem> = BTreeMap::new();
-                let system_programs_path = PathBuf::from(r"C:\ProgramData\Microsoft\Windows\Start Menu\Programs\");
-                match data_dir() {
-                    Some(data_dir) => {
-                        let user_programs_path = data_dir.join(r"Microsoft\Windows\Start Menu\Programs\");
-                        let start_settings_file = data_dir.join(r"Frostwin\Start_Settings.json");
-                        match std::fs::read_to_string(start_settings_file.clone()) {
-                            Ok(content) => {
-                                match from_str::<StartMenuSettings>(&content) {
-                                    Ok(settings) => {
-                                        self.settings = settings;
-                                    }
-                                    Err(e) => {
-                                        println!("Error loading start menu settings: {:?}", e);
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                println!("Error opening start menu settings: {:?}", e);
-                                match e.kind() {
-                                    std::io::ErrorKind::NotFound => {
-                                        // Ensure the folder exists before creating the file
-                                        if let Some(parent) = start_settings_file.parent() {
-                                            let _ = std::fs::create_dir_all(parent);
-                                        }
-                                        let _ = std::fs::write(&start_settings_file, "{}");
-                                    }
-
-                                    std::io::ErrorKind::PermissionDenied => {
-                                        eprintln!("Permission denied. Attempting to fix file attributes...");
-                                        if let Ok(metadata) = std::fs::metadata(&start_settings_file) {
-                                            let mut perms = metadata.permissions();
-                                            if perms.readonly() {
-                                                perms.set_readonly(false);
-                                                // If we can fix permissions, try to write a fresh file
-                                                if std::fs::set_permissions(&start_settings_file, perms).is_ok() {
-                                                    let _ = std::fs::write(&start_settings_file, "{}");
-                                                }
-                                            }
-                                        }
-                                    }
-
-                                    _ => eprintln!("Critical I/O error: {}", e),
-                                }
-                            }
-                        }
-                        match get_dir_contents(user_programs_path, &mut content) {
-                            Err(e) => {
-                                eprintln!("Error loading user start programs: {:?}", e);
-                            }
-                            _ => {}
-                        };
-                        match get_dir_contents(system_programs_path, &mut content) {
-                            Err(e) => {
-                                eprintln!("Error loading system start programs: {:?}", e);
-                            }
-                            _ => {}
-                        };
-                    }
-                    None => {}
-                }
-
-                self.content = content;
-                let mut keys: Vec<String> = self.content.keys().cloned().collect();
-                alphanumeric_sort::sort_str_slice(&mut keys);
-                self.sorted = keys;
-                for (_,entry) in self.content.iter_mut() {
-                    entry.prep(app_image_cache.clone())
+            StartMessage::Init(catalog) => {
+                self.settings = StartMenuSettings::load();
+                self.settings_app = settings_app();
+                self.set_catalog(catalog);
+                self.history = LaunchHistory::load();
+                if self.history.prune() {
+                    self.history.save();
                 }
-                window::monitor_size(self.id).map(|size|Message::StartMenu(StartMessage::Resize(size)))
+                Task::batch([
+                    window::monitor_size(self.id).map(|size|Message::StartMenu(StartMessage::Resize(size))),
+                    // Opening from the Meta key should take the keyboard right away
+                    window::gain_focus(self.id),
+                ])
+            }
+            StartMessage::CatalogUpdated(catalog) => {
+                self.set_catalog(catalog);
+                Task::none()
             }
             StartMessage::Resize(resize) => {
                 if let Some(size) = resize {
@@ -202,79 +621,858 @@ impl StartMenu {
                                 eprintln!("Error: Directory key '{}' not found in content.", sub_dir);
                             }
                         }
-                        Task::none()
+                        Task::none()
+                    }
+
+                    StartItemMessage::Launch(path) => {
+                        let target = match self.find_item(&PathBuf::from(&path)).and_then(|item| item.launch_target()) {
+                            Some(target) => target,
+                            None => LaunchTarget::classify(&path),
+                        };
+                        match self.settings.launcher().launch(&target) {
+                            Ok(_) => {
+                                self.history.record(&path.to_string_lossy(), now_timestamp());
+                                self.history.save();
+                                Task::done(Message::WindowClose(self.id))
+                            }
+                            Err(e) => {
+                                self.error = Some(format!("Failed to launch {}: {}", path.to_string_lossy(), e));
+                                Task::none()
+                            }
+                        }
+                    }
+                    StartItemMessage::RunAsAdmin(path) => {
+                        let result = match self.find_item(&path) {
+                            Some(item) => item.run_as_admin(),
+                            None => Err(format!("{} is no longer in the start menu", path.display())),
+                        };
+                        match result {
+                            Ok(_) => Task::done(Message::WindowClose(self.id)),
+                            Err(e) => {
+                                self.error = Some(e);
+                                Task::none()
+                            }
+                        }
+                    }
+                    StartItemMessage::OpenFileLocation(path) => {
+                        match show_in_folder(&path) {
+                            Ok(_) => Task::done(Message::WindowClose(self.id)),
+                            Err(e) => {
+                                self.error = Some(format!("Failed to open the location of {}: {}", path.display(), e));
+                                Task::none()
+                            }
+                        }
+                    }
+                    StartItemMessage::OpenTargetFolder(path) => {
+                        let result = match self.find_item(&path).and_then(|item| item.target_path()) {
+                            Some(target) => show_in_folder(&target)
+                                .map_err(|e| format!("Failed to open the folder of {}: {}", target.display(), e)),
+                            None => Err(format!("The target of {} could not be resolved", path.display())),
+                        };
+                        match result {
+                            Ok(_) => Task::done(Message::WindowClose(self.id)),
+                            Err(e) => {
+                                self.error = Some(e);
+                                Task::none()
+                            }
+                        }
+                    }
+                    StartItemMessage::CopyPath(path) => {
+                        iced::clipboard::write(path.to_string_lossy().to_string())
+                    }
+                    StartItemMessage::Hide(path) => {
+                        // Every file a combined entry stands for is hidden, or the next one would take its place
+                        let paths: Vec<PathBuf> = match self.find_item(&path) {
+                            Some(item) => std::iter::once(item.path.clone()).chain(item.duplicates.iter().cloned()).collect(),
+                            None => vec![path],
+                        };
+                        for path in paths {
+                            remove_entry(&mut self.content, &mut self.sorted, &path);
+                            self.settings.hide(path);
+                        }
+                        self.settings.save();
+                        Task::none()
+                    }
+                    StartItemMessage::Uninstall(path) => {
+                        match self.find_item(&path).map(|item| item.uninstaller()) {
+                            Some(Ok(uninstaller)) => {
+                                self.pending_uninstall = Some(uninstaller);
+                            }
+                            Some(Err(e)) => {
+                                self.error = Some(e);
+                            }
+                            None => {
+                                self.error = Some(format!("{} is no longer in the start menu", path.display()));
+                            }
+                        }
+                        Task::none()
+                    }
+                }
+            },
+            StartMessage::ConfirmUninstall => {
+                if let Some(uninstaller) = self.pending_uninstall.take() {
+                    match uninstaller.run() {
+                        Ok(_) => {
+                            return Task::done(Message::WindowClose(self.id));
+                        }
+                        Err(e) => {
+                            self.error = Some(e);
+                        }
+                    }
+                }
+                Task::none()
+            }
+            StartMessage::CancelUninstall => {
+                self.pending_uninstall = None;
+                Task::none()
+            }
+            StartMessage::DismissError => {
+                self.error = None;
+                Task::none()
+            }
+            StartMessage::Navigate(window, key) => {
+                if window != self.id {
+                    return Task::none();
+                }
+                if self.editor.is_some() {
+                    if key == NavigationKey::Escape {
+                        self.editor = None;
+                    }
+                    return Task::none();
+                }
+                match key {
+                    NavigationKey::Escape => {
+                        if self.search.is_empty() {
+                            Task::done(Message::WindowClose(self.id))
+                        } else {
+                            self.search.clear();
+                            self.selection = None;
+                            Task::none()
+                        }
+                    }
+                    NavigationKey::Enter => self.activate_selection(),
+                    _ => {
+                        if !self.search.is_empty() {
+                            self.move_in_results(key);
+                            Task::none()
+                        } else {
+                            match self.tab {
+                                StartMenuTab::Tiles => {
+                                    self.move_in_tiles(key);
+                                    Task::none()
+                                }
+                                StartMenuTab::Applications => self.move_in_tree(key),
+                                StartMenuTab::Places => {
+                                    self.move_in_places(key);
+                                    Task::none()
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            StartMessage::SwitchToTab(tab) => {
+                if let StartMenuTab::Places = tab {
+                    // Drives and recent documents change while the menu is closed, so they are read when shown
+                    self.places = Places::load(&self.settings.bookmarks);
+                }
+                self.tab = tab;
+                self.selection = None;
+                Task::none()
+            },
+            StartMessage::OpenPlace(path) => {
+                match self.settings.launcher().launch(&LaunchTarget::classify(path.as_os_str())) {
+                    Ok(_) => Task::done(Message::WindowClose(self.id)),
+                    Err(e) => {
+                        self.error = Some(format!("Failed to open {}: {}", path.display(), e));
+                        Task::none()
+                    }
+                }
+            }
+            StartMessage::CopyResult(result) => {
+                iced::clipboard::write(result)
+            }
+            StartMessage::AddBookmark(path) => {
+                self.settings.add_bookmark(path);
+                self.settings.save();
+                self.places = Places::load(&self.settings.bookmarks);
+                Task::none()
+            }
+            StartMessage::RemoveBookmark(path) => {
+                self.settings.remove_bookmark(&path);
+                self.settings.save();
+                self.places = Places::load(&self.settings.bookmarks);
+                Task::none()
+            }
+            StartMessage::NewEntry => {
+                self.editor = Some(EntryEditor::new_entry());
+                Task::none()
+            }
+            StartMessage::EditEntry(path) => {
+                let Some(item) = self.find_item(&path) else {
+                    return Task::none();
+                };
+                let editor = match item.target.as_ref() {
+                    Some(StartTarget::Custom(entry)) => EntryEditor::for_custom(entry),
+                    _ => {
+                        // The override may be kept under any of the files a combined entry stands for
+                        let name_override = std::iter::once(&item.path)
+                            .chain(item.duplicates.iter())
+                            .find_map(|path| self.settings.renamed.get(path));
+                        let original_name = self.catalog.find_item(&item.path).map(|original| original.name.clone()).unwrap_or_else(|| item.name.clone());
+                        EntryEditor::for_entry(item.path.clone(), original_name, name_override)
+                    }
+                };
+                self.editor = Some(editor);
+                Task::none()
+            }
+            StartMessage::EntryInput(field, value) => {
+                if let Some(editor) = self.editor.as_mut() {
+                    editor.set(field, value);
+                }
+                Task::none()
+            }
+            StartMessage::SaveEntry => {
+                let Some(editor) = self.editor.take() else {
+                    return Task::none();
+                };
+                if editor.custom_id.is_some() {
+                    match editor.custom_entry() {
+                        Ok(entry) => self.settings.save_custom_entry(entry),
+                        Err(e) => {
+                            self.error = Some(e);
+                            self.editor = Some(editor);
+                            return Task::none();
+                        }
+                    }
+                } else if let Some(path) = editor.path {
+                    let duplicates = self.find_item(&path).map(|item| item.duplicates.clone()).unwrap_or_default();
+                    for duplicate in duplicates.iter() {
+                        self.settings.renamed.remove(duplicate);
+                    }
+                    self.settings.rename(path, &editor.name);
+                }
+                self.settings.save();
+                self.error = None;
+                self.set_catalog(self.catalog.clone());
+                Task::none()
+            }
+            StartMessage::DeleteEntry(id) => {
+                self.settings.remove_custom_entry(id);
+                self.settings.save();
+                self.editor = None;
+                self.set_catalog(self.catalog.clone());
+                Task::none()
+            }
+            StartMessage::CancelEdit => {
+                self.editor = None;
+                Task::none()
+            }
+            StartMessage::Search(search) => {
+                self.search = search;
+                self.selection = None;
+                Task::none()
+            }
+            StartMessage::LaunchTopResult => {
+                let results: Vec<PathBuf> = self.search_results().iter().map(|item| item.path.clone()).collect();
+                // A calculation is the top result until an entry below it is picked
+                if !matches!(self.selection.as_ref(), Some(Selection::Item(path)) if results.contains(path))
+                    && let Some(calculation) = calculator::evaluate(&self.search) {
+                    return self.update(StartMessage::CopyResult(calculation.result));
+                }
+                let selected = match self.selection.as_ref() {
+                    Some(Selection::Item(path)) if results.contains(path) => Some(path.clone()),
+                    _ => results.first().cloned(),
+                };
+                match selected {
+                    Some(path) => {
+                        self.update(StartMessage::ItemMessage(StartItemMessage::Launch(path.into_os_string())))
+                    }
+                    None => Task::none()
+                }
+            }
+            StartMessage::ClearHistory => {
+                self.history.clear();
+                self.history.save();
+                Task::none()
+            }
+            StartMessage::PinToTiles(path) => {
+                self.settings.pin(path);
+                self.settings.save();
+                Task::none()
+            }
+            StartMessage::UnpinFromTiles(path) => {
+                self.settings.unpin(&path);
+                self.settings.save();
+                Task::none()
+            }
+            StartMessage::ResizeTile(path, size) => {
+                for group in self.settings.groups.iter_mut() {
+                    for tile in group.tiles.iter_mut() {
+                        if tile.path == path {
+                            tile.size = size;
+                        }
+                    }
+                }
+                self.settings.save();
+                Task::none()
+            }
+            StartMessage::TilePressed(group, tile) => {
+                self.drag = Some(DragState { source: DragSource::Tile(group, tile), hover: Some(DropTarget::Tile(group, tile)) });
+                Task::none()
+            }
+            StartMessage::GroupPressed(group) => {
+                self.drag = Some(DragState { source: DragSource::Group(group), hover: Some(DropTarget::Group(group)) });
+                Task::none()
+            }
+            StartMessage::DragHover(target) => {
+                if let Some(drag) = self.drag.as_mut() {
+                    drag.hover = Some(target);
+                }
+                Task::none()
+            }
+            StartMessage::DragReleased(target) => {
+                let Some(drag) = self.drag.take() else {
+                    return Task::none();
+                };
+                match (drag.source, target) {
+                    // Pressing and releasing on the same spot is a click
+                    (DragSource::Tile(group, tile), DropTarget::Tile(to_group, to_tile)) if group == to_group && tile == to_tile => {
+                        if let Some(tile) = self.settings.groups.get(group).and_then(|group| group.tiles.get(tile)) {
+                            let path = tile.path.clone().into_os_string();
+                            return self.update(StartMessage::ItemMessage(StartItemMessage::Launch(path)));
+                        }
+                    }
+                    (DragSource::Group(group), DropTarget::Group(to_group)) if group == to_group => {
+                        return self.update(StartMessage::ToggleGroup(group));
+                    }
+                    (DragSource::Tile(group, tile), DropTarget::Tile(to_group, to_tile)) => {
+                        self.settings.move_tile((group, tile), to_group, Some(to_tile));
+                        self.settings.save();
+                    }
+                    (DragSource::Tile(group, tile), DropTarget::Group(to_group)) => {
+                        self.settings.move_tile((group, tile), to_group, None);
+                        self.settings.save();
+                    }
+                    (DragSource::Group(group), DropTarget::Group(to_group) | DropTarget::Tile(to_group, _)) => {
+                        self.settings.move_group(group, to_group);
+                        self.settings.save();
+                    }
+                }
+                Task::none()
+            }
+            StartMessage::ToggleGroup(group) => {
+                if let Some(group) = self.settings.groups.get_mut(group) {
+                    group.collapsed = !group.collapsed;
+                    self.settings.save();
+                }
+                Task::none()
+            }
+            StartMessage::StartRenameGroup(group) => {
+                if let Some(tile_group) = self.settings.groups.get(group) {
+                    self.renaming = Some((group, tile_group.name.clone()));
+                }
+                Task::none()
+            }
+            StartMessage::RenameGroupInput(name) => {
+                if let Some((_, current)) = self.renaming.as_mut() {
+                    *current = name;
+                }
+                Task::none()
+            }
+            StartMessage::RenameGroup => {
+                if let Some((group, name)) = self.renaming.take() && let Some(group) = self.settings.groups.get_mut(group) {
+                    group.name = name;
+                    self.settings.save();
+                }
+                Task::none()
+            }
+            StartMessage::AddGroup => {
+                self.settings.groups.push(TileGroup::new("New group".to_string()));
+                self.settings.save();
+                self.update(StartMessage::StartRenameGroup(self.settings.groups.len() - 1))
+            }
+            StartMessage::RemoveGroup(group) => {
+                if group < self.settings.groups.len() {
+                    // Tiles of a removed group fall back into the neighbouring group instead of being unpinned
+                    let removed = self.settings.groups.remove(group);
+                    if let Some(neighbour) = self.settings.groups.get_mut(group.saturating_sub(1)) {
+                        neighbour.tiles.extend(removed.tiles);
+                    }
+                    self.renaming = None;
+                    self.settings.save();
+                }
+                Task::none()
+            }
+        }
+    }
+    fn activate_selection(&mut self) -> Task<Message> {
+        if !self.search.is_empty() {
+            return self.update(StartMessage::LaunchTopResult);
+        }
+        if let StartMenuTab::Places = self.tab {
+            return match self.selection.clone() {
+                Some(Selection::Item(path)) => self.update(StartMessage::OpenPlace(path)),
+                _ => Task::none(),
+            };
+        }
+        match self.selection.clone() {
+            Some(Selection::Tile(group, tile)) => {
+                match self.settings.groups.get(group).and_then(|group| group.tiles.get(tile)) {
+                    Some(tile) => {
+                        let path = tile.path.clone().into_os_string();
+                        self.update(StartMessage::ItemMessage(StartItemMessage::Launch(path)))
+                    }
+                    None => Task::none(),
+                }
+            }
+            Some(Selection::Item(path)) => {
+                let row = self.visible_rows().into_iter()
+                    .find(|(item, _)| item.path == path)
+                    .map(|(item, toggle_path)| (item.content.is_some(), toggle_path));
+                match row {
+                    Some((true, toggle_path)) => self.update(StartMessage::ItemMessage(StartItemMessage::Toggle(toggle_path))),
+                    Some((false, _)) => self.update(StartMessage::ItemMessage(StartItemMessage::Launch(path.into_os_string()))),
+                    None => Task::none(),
+                }
+            }
+            None => Task::none(),
+        }
+    }
+    fn move_in_results(&mut self, key: NavigationKey) {
+        let results: Vec<PathBuf> = self.search_results().iter().map(|item| item.path.clone()).collect();
+        self.move_in_list(&results, key);
+    }
+    fn move_in_places(&mut self, key: NavigationKey) {
+        let places: Vec<PathBuf> = self.places.all().map(|place| place.path.clone()).collect();
+        self.move_in_list(&places, key);
+    }
+    /// Up and Down step through a flat list of entries, starting at the top.
+    fn move_in_list(&mut self, results: &[PathBuf], key: NavigationKey) {
+        let current = match self.selection.as_ref() {
+            Some(Selection::Item(path)) => results.iter().position(|result| result == path),
+            _ => None,
+        };
+        let next = match (key, current) {
+            (NavigationKey::Up, Some(index)) => Some(index.saturating_sub(1)),
+            (NavigationKey::Down, Some(index)) => Some((index + 1).min(results.len().saturating_sub(1))),
+            (NavigationKey::Up | NavigationKey::Down, None) => Some(0),
+            _ => current,
+        };
+        if let Some(path) = next.and_then(|index| results.get(index)) {
+            self.selection = Some(Selection::Item(path.clone()));
+        }
+    }
+    /// Tiles of the expanded groups with their cell rectangles `(x, y, width, height)`,
+    /// `y` counting down through every group including a row for each group header.
+    fn visible_tiles(&self) -> Vec<((usize, usize), TileRect)> {
+        let mut tiles: Vec<((usize, usize), TileRect)> = Vec::new();
+        let mut offset = 0;
+        for (group_index, group) in self.settings.groups.iter().enumerate() {
+            offset += 1;
+            if group.collapsed {
+                continue;
+            }
+            let (positions, rows) = pack_tiles(&group.tiles, TILE_COLUMNS);
+            for (tile_index, (tile, (column, row))) in group.tiles.iter().zip(positions).enumerate() {
+                let (width, height) = tile.size.cells();
+                tiles.push(((group_index, tile_index), (column, offset + row, width.min(TILE_COLUMNS), height)));
+            }
+            offset += rows;
+        }
+        tiles
+    }
+    /// Left and Right step through the tiles in order, Up and Down go to the closest tile in the next row.
+    fn move_in_tiles(&mut self, key: NavigationKey) {
+        let tiles = self.visible_tiles();
+        let current = match self.selection.as_ref() {
+            Some(Selection::Tile(group, tile)) => tiles.iter().position(|(index, _)| *index == (*group, *tile)),
+            _ => None,
+        };
+        let Some(current) = current else {
+            if let Some((index, _)) = tiles.first() {
+                self.selection = Some(Selection::Tile(index.0, index.1));
+            }
+            return;
+        };
+        let (x, y, width, height) = tiles[current].1;
+        let center = x * 2 + width;
+        let next = match key {
+            NavigationKey::Left => Some(current.saturating_sub(1)),
+            NavigationKey::Right => Some((current + 1).min(tiles.len() - 1)),
+            NavigationKey::Up => tiles.iter().enumerate()
+                .filter(|(_, (_, (_, other_y, _, other_height)))| other_y + other_height <= y)
+                .max_by_key(|(_, (_, (other_x, other_y, other_width, other_height)))| {
+                    (other_y + other_height, std::cmp::Reverse((other_x * 2 + other_width).abs_diff(center)))
+                })
+                .map(|(index, _)| index),
+            NavigationKey::Down => tiles.iter().enumerate()
+                .filter(|(_, (_, (_, other_y, _, _)))| *other_y >= y + height)
+                .min_by_key(|(_, (_, (other_x, other_y, other_width, _)))| {
+                    (*other_y, (other_x * 2 + other_width).abs_diff(center))
+                })
+                .map(|(index, _)| index),
+            _ => None,
+        };
+        if let Some((index, _)) = next.and_then(|next| tiles.get(next)) {
+            self.selection = Some(Selection::Tile(index.0, index.1));
+        }
+    }
+    /// Rows of the Applications tab as drawn, each with the path `StartItemMessage::Toggle` expects.
+    fn visible_rows(&self) -> Vec<(&StartItem, Vec<String>)> {
+        let mut rows: Vec<(&StartItem, Vec<String>)> = Vec::new();
+        for key in self.sorted.iter() {
+            if let Some(item) = self.content.get(key) {
+                item.collect_rows(vec![key.clone()], &mut rows);
+            }
+        }
+        rows
+    }
+    /// Up and Down move through the visible rows. Right expands a folder or enters an expanded one,
+    /// Left collapses a folder or goes up to the folder holding the entry.
+    fn move_in_tree(&mut self, key: NavigationKey) -> Task<Message> {
+        let rows = self.visible_rows();
+        let current = match self.selection.as_ref() {
+            Some(Selection::Item(path)) => rows.iter().position(|(item, _)| item.path == *path),
+            _ => None,
+        };
+        let Some(current) = current else {
+            if let Some((item, _)) = rows.first() {
+                self.selection = Some(Selection::Item(item.path.clone()));
+            }
+            return Task::none();
+        };
+        let (item, toggle_path) = &rows[current];
+        let is_folder = item.content.is_some();
+        let mut toggle: Option<Vec<String>> = None;
+        let next = match key {
+            NavigationKey::Up => Some(current.saturating_sub(1)),
+            NavigationKey::Down => Some((current + 1).min(rows.len() - 1)),
+            NavigationKey::Right if is_folder && !item.open => {
+                toggle = Some(toggle_path.clone());
+                None
+            }
+            NavigationKey::Right if is_folder => Some((current + 1).min(rows.len() - 1)),
+            NavigationKey::Left if is_folder && item.open => {
+                toggle = Some(toggle_path.clone());
+                None
+            }
+            // The closest row above with a shorter path is the folder holding this one
+            NavigationKey::Left => rows[..current].iter().rposition(|(_, path)| path.len() < toggle_path.len()),
+            _ => None,
+        };
+        let next_path = next.and_then(|index| rows.get(index)).map(|(item, _)| item.path.clone());
+        if let Some(path) = next_path {
+            self.selection = Some(Selection::Item(path));
+        }
+        match toggle {
+            Some(toggle_path) => self.update(StartMessage::ItemMessage(StartItemMessage::Toggle(toggle_path))),
+            None => Task::none(),
+        }
+    }
+    /// Takes over the shared programs tree without hidden entries, keeping open folders open.
+    fn set_catalog(&mut self, catalog: Arc<StartCatalog>) {
+        let mut open_folders: Vec<PathBuf> = Vec::new();
+        for item in self.content.values() {
+            item.collect_open_folders(&mut open_folders);
+        }
+        // The shared catalog stays untouched, hidden, renamed and custom entries are applied to a copy of the tree
+        let mut content = catalog.content.clone();
+        let mut sorted = catalog.sorted.clone();
+        self.catalog = catalog;
+        // Folders only custom entries are in are put where the user's own Programs folders would be
+        let parent = StartCatalog::watched_dirs().into_iter().next().unwrap_or_default();
+        for entry in self.settings.custom_entries.iter() {
+            insert_entry(&mut content, &mut sorted, &parent, &entry.folders(), StartItem::from_custom(entry.clone()));
+        }
+        for path in self.settings.hidden.iter() {
+            remove_entry(&mut content, &mut sorted, path);
+        }
+        for (path, name) in self.settings.renamed.iter() {
+            if let Some((mut item, folders)) = take_entry(&mut content, &mut sorted, path) {
+                item.name = name.clone();
+                insert_entry(&mut content, &mut sorted, &parent, &folders, item);
+            }
+        }
+        for item in content.values_mut() {
+            item.restore_open_folders(&open_folders);
+        }
+        self.content = content;
+        self.sorted = sorted;
+    }
+    /// Standard folders, bookmarks, drives and recent documents, each opened with the default handler.
+    fn places_view(&self, app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, base_size: f32) -> Element<'_, Message> {
+        let text_height = 30.0 * base_size;
+        let text_half_height = 15.0 * base_size;
+        let spacing = 2.0 * base_size;
+        let (folder, document) = match (app_image_cache.lock(), data_dir()) {
+            (Ok(app_image_lock), Some(data_dir)) => {
+                let data_folder = data_dir.join("Frostwin");
+                let error_handle = Handle::from_rgba(1,1,vec![255u8,0u8,0u8,255u8]);
+                (
+                    app_image_lock.get(&data_folder.join("icons/Folder.png")).unwrap_or(&error_handle).clone(),
+                    app_image_lock.get(&data_folder.join("icons/EmptyApp.png")).unwrap_or(&error_handle).clone(),
+                )
+            }
+            _ => {
+                let error_handle = Handle::from_rgba(1,1,vec![255u8,0u8,0u8,255u8]);
+                (error_handle.clone(), error_handle)
+            }
+        };
+        let selected = match self.selection.as_ref() {
+            Some(Selection::Item(path)) => Some(path),
+            _ => None,
+        };
+        let sections: [(&str, &Vec<Place>); 4] = [
+            ("Folders", &self.places.folders),
+            ("Bookmarks", &self.places.bookmarks),
+            ("Drives", &self.places.drives),
+            ("Recent documents", &self.places.recent),
+        ];
+        let mut places: Column<Message> = Column::new().spacing(spacing);
+        for (title, entries) in sections {
+            if entries.is_empty() {
+                continue;
+            }
+            places = places.push(text!("{}", title).size(text_height * 0.7));
+            for place in entries.iter() {
+                let is_selected = selected == Some(&place.path);
+                let icon = match place.kind {
+                    PlaceKind::Recent => document.clone(),
+                    _ => folder.clone(),
+                };
+                let entry = button(
+                    row![
+                        image(icon).height(text_half_height).width(text_half_height),
+                        space().width(Length::Fixed(spacing)),
+                        text!("{}", place.name).size(text_half_height).height(Length::Fixed(text_half_height)).align_y(Alignment::Center),
+                    ].align_y(Alignment::Center)
+                ).on_press(Message::StartMenu(StartMessage::OpenPlace(place.path.clone())))
+                    .style(move |theme, status| if is_selected { selected_button(theme, status) } else { transparent_button(theme, status) })
+                    .width(Length::Fill);
+                let place_path = place.path.clone();
+                let kind = place.kind;
+                places = places.push(ContextMenu::new(
+                    tooltip(
+                        entry,
+                        container(column![
+                            text!("{}", place.path.display()).size(text_half_height),
+                        ]).style(container::rounded_box),
+                        tooltip::Position::FollowCursor
+                    ),
+                    move || {
+                        let mut menu: Column<Message> = Column::new();
+                        menu = menu.push(button(text!("Open file location")).style(context_menu_button)
+                            .on_press(Message::StartMenu(StartMessage::ItemMessage(StartItemMessage::OpenFileLocation(place_path.clone())))));
+                        menu = menu.push(button(text!("Copy path")).style(context_menu_button)
+                            .on_press(Message::StartMenu(StartMessage::ItemMessage(StartItemMessage::CopyPath(place_path.clone())))));
+                        match kind {
+                            PlaceKind::Bookmark => {
+                                menu = menu.push(button(text!("Remove from Places")).style(context_menu_button)
+                                    .on_press(Message::StartMenu(StartMessage::RemoveBookmark(place_path.clone()))));
+                            }
+                            PlaceKind::Recent => {
+                                menu = menu.push(button(text!("Add to Places")).style(context_menu_button)
+                                    .on_press(Message::StartMenu(StartMessage::AddBookmark(place_path.clone()))));
+                            }
+                            _ => {}
+                        }
+                        container(menu).style(container::bordered_box).into()
+                    }
+                ));
+            }
+        }
+        places.into()
+    }
+    /// Errors from item actions and the uninstall confirmation, shown above the menu content.
+    fn notice_view(&self, text_height: f32, spacing: f32) -> Element<'_, Message> {
+        let mut notice: Column<Message> = Column::new().spacing(spacing);
+        if let Some(uninstaller) = self.pending_uninstall.as_ref() {
+            notice = notice.push(
+                container(column![
+                    text!("Uninstall {}?", uninstaller.display_name()).size(text_height * 0.5),
+                    text!("{}", uninstaller.command_text()).size(text_height * 0.4).wrapping(Wrapping::WordOrGlyph),
+                    row![
+                        space().width(Length::Fill),
+                        button(text!("Uninstall").size(text_height * 0.5))
+                            .style(|theme, status| colored_button(theme, status, Color::from_rgb(0.7, 0.2, 0.2)))
+                            .on_press(Message::StartMenu(StartMessage::ConfirmUninstall)),
+                        button(text!("Cancel").size(text_height * 0.5))
+                            .style(transparent_button)
+                            .on_press(Message::StartMenu(StartMessage::CancelUninstall)),
+                    ].spacing(spacing),
+                ].spacing(spacing))
+                .padding(spacing)
+                .width(Length::Fill)
+                .style(container::bordered_box)
+            );
+        }
+        if let Some(error) = self.error.as_ref() {
+            notice = notice.push(
+                container(row![
+                    text!("{}", error).size(text_height * 0.5).color(Color::from_rgb(0.9, 0.3, 0.3)).wrapping(Wrapping::WordOrGlyph).width(Length::Fill),
+                    button(text!("Dismiss").size(text_height * 0.5))
+                        .style(transparent_button)
+                        .on_press(Message::StartMenu(StartMessage::DismissError)),
+                ].align_y(Alignment::Center).spacing(spacing))
+                .padding(spacing)
+                .width(Length::Fill)
+                .style(container::bordered_box)
+            );
+        }
+        notice.into()
+    }
+    fn find_item(&self, path: &PathBuf) -> Option<&StartItem> {
+        self.content.values().find_map(|item| item.find(path))
+    }
+    /// Launchable entries whose name contains the search text, best frecency first.
+    fn search_results(&self) -> Vec<&StartItem> {
+        let query = self.search.to_lowercase();
+        let mut leaves: Vec<&StartItem> = Vec::new();
+        for item in self.content.values() {
+            item.collect_leaves(&mut leaves);
+        }
+        let now = now_timestamp();
+        let mut results: Vec<(&StartItem, f64)> = leaves.into_iter()
+            .filter(|item| item.name.to_lowercase().contains(&query))
+            .map(|item| (item, self.history.frecency(&item.path.to_string_lossy(), now)))
+            .collect();
+        results.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then_with(|| b.0.name.to_lowercase().starts_with(&query).cmp(&a.0.name.to_lowercase().starts_with(&query)))
+                .then_with(|| alphanumeric_sort::compare_str(&a.0.name, &b.0.name))
+        });
+        results.into_iter().map(|(item, _)| item).collect()
+    }
+    fn tile_groups_view(&self, app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, empty_app: Handle, base_size: f32) -> Column<'_, Message> {
+        let text_height = 30.0 * base_size;
+        let spacing = 2.0 * base_size;
+        let cell = 68.0 * base_size;
+        let hover = self.drag.as_ref().and_then(|drag| drag.hover.filter(|hover| match (drag.source, hover) {
+            (DragSource::Tile(group, tile), DropTarget::Tile(to_group, to_tile)) => (group, tile) != (*to_group, *to_tile),
+            (DragSource::Group(group), DropTarget::Group(to_group)) => group != *to_group,
+            _ => true,
+        }));
+        let mut groups_column: Column<Message> = Column::new().spacing(spacing * 4.0);
+        for (group_index, group) in self.settings.groups.iter().enumerate() {
+            let header: Element<'_, Message> = match self.renaming.as_ref() {
+                Some((renaming, name)) if *renaming == group_index => {
+                    text_input("Group name", name)
+                        .on_input(|name| Message::StartMenu(StartMessage::RenameGroupInput(name)))
+                        .on_submit(Message::StartMenu(StartMessage::RenameGroup))
+                        .size(text_height * 0.6)
+                        .into()
+                }
+                _ => {
+                    let highlighted = hover == Some(DropTarget::Group(group_index));
+                    let title = container(
+                        text!("{} {}", if group.collapsed { "▸" } else { "▾" }, group.name).size(text_height * 0.6)
+                    ).width(Length::Fill)
+                        .style(move |theme| if highlighted { container::bordered_box(theme) } else { container::Style::default() });
+                    ContextMenu::new(
+                        mouse_area(title)
+                            .on_press(Message::StartMenu(StartMessage::GroupPressed(group_index)))
+                            .on_release(Message::StartMenu(StartMessage::DragReleased(DropTarget::Group(group_index))))
+                            .on_enter(Message::StartMenu(StartMessage::DragHover(DropTarget::Group(group_index)))),
+                        move || {
+                            container(
+                                column![
+                                    button(text!("Rename group")).style(context_menu_button).on_press(Message::StartMenu(StartMessage::StartRenameGroup(group_index))),
+                                    button(text!("Remove group")).style(context_menu_button).on_press(Message::StartMenu(StartMessage::RemoveGroup(group_index))),
+                                    button(text!("New group")).style(context_menu_button).on_press(Message::StartMenu(StartMessage::AddGroup)),
+                                ]
+                            ).style(container::bordered_box).into()
+                        }
+                    ).into()
+                }
+            };
+            groups_column = groups_column.push(header);
+            if group.collapsed {
+                continue;
+            }
+            let (positions, rows) = pack_tiles(&group.tiles, TILE_COLUMNS);
+            let mut tiles_stack: Stack<Message> = Stack::new()
+                .width(Length::Fixed(TILE_COLUMNS as f32 * cell))
+                .height(Length::Fixed(rows.max(1) as f32 * cell));
+            for (tile_index, (tile, (column, row))) in group.tiles.iter().zip(positions).enumerate() {
+                let path = &tile.path;
+                let (width, height) = tile.size.cells();
+                let icon_size = match tile.size {
+                    TileSize::Small => cell * 0.6,
+                    TileSize::Medium | TileSize::Wide => text_height * 2.0,
+                    TileSize::Large => text_height * 4.0,
+                };
+                let custom_icon = self.find_item(path).and_then(|item| item.custom_icon());
+                let icon: Element<'_,Message> = match app_image_cache.lock() {
+                    Ok(app_image_lock) => {
+                        if let Some(app_image) = custom_icon.as_ref().or(app_image_lock.get(path)) {
+                            image(app_image).height(Length::Fixed(icon_size)).width(Length::Fixed(icon_size)).content_fit(ContentFit::Fill).into()
+                        } else {
+                            image(empty_app.clone()).height(Length::Fixed(icon_size)).width(Length::Fixed(icon_size)).into()
+                        }
                     }
-
-                    StartItemMessage::Launch(path) => {
-                        match Command::new("explorer").args([&path]).spawn() {
-                            Ok(_) => {
-                                Task::done(Message::WindowClose(self.id))
-                            }
-                            Err(e) => {
-                                eprintln!("Failed to launch explorer for path {}: {}", path.to_string_lossy(), e);
-                                Task::none()
-                            }
-                        }
+                    Err(e) => {
+                        eprintln!("Error getting app_image lock: {:?}", e);
+                        image(empty_app.clone()).height(Length::Fixed(icon_size)).width(Length::Fixed(icon_size)).into()
                     }
-                }
-            },
-            StartMessage::SwitchToTab(tab) => {
-                self.tab = tab;
-                Task::none()
-            },
-            StartMessage::PinToTiles(path) => {
-                match data_dir() {
-                    Some(data_dir) => {
-                        let start_settings_file = data_dir.join(r"Frostwin\Start_Settings.json");
-                        self.settings.tiles.push(path);
-                        match to_string_pretty(&self.settings) {
-                            Ok(settings) => {
-                                match std::fs::write(&start_settings_file, settings) {
-                                    Err(e) => {
-                                        eprintln!("Error writing start menu settings: {:?}", e);
-                                    }
-                                    _ => {}
-                                };
-                            }
-                            Err(e) => {
-                                eprintln!("Error serializing start menu settings: {:?}", e);
-                            }
-                        };
+                };
+                let name = if let Some(item) = self.find_item(path) {
+                    item.name.clone()
+                } else if let Some(app_name) = path.file_stem() {
+                    if let Some(app_name_str) = app_name.to_str() {
+                        app_name_str.to_string()
+                    } else {
+                        eprintln!("Error: failed to get App name into str");
+                        "".to_string()
                     }
-                    None => {}
+                } else {
+                    eprintln!("Error: failed to get App name from OsStr");
+                    "".to_string()
+                };
+                let mut tile_content: Column<Message> = column![icon].align_x(Alignment::Center).spacing(0.0);
+                if tile.size != TileSize::Small {
+                    tile_content = tile_content.push(
+                        text!("{}",name).size(text_height * 0.5).align_x(Alignment::Center).wrapping(Wrapping::WordOrGlyph)
+                    );
                 }
-                Task::none()
-            }
-            StartMessage::UnpinFromTiles(path) => {
-                match data_dir() {
-                    Some(data_dir) => {
-                        let start_settings_file = data_dir.join(r"Frostwin\Start_Settings.json");
-                        let mut new_tiles_list: Vec<PathBuf> = Vec::new();
-                        for old_path in self.settings.tiles.iter() {
-                            if *old_path != path {
-                                new_tiles_list.push(old_path.clone());
+                let highlighted = hover == Some(DropTarget::Tile(group_index, tile_index))
+                    || self.selection == Some(Selection::Tile(group_index, tile_index));
+                let tile_body = container(tile_content)
+                    .width(Length::Fixed(width as f32 * cell - spacing))
+                    .height(Length::Fixed(height as f32 * cell - spacing))
+                    .padding(spacing)
+                    .align_x(Alignment::Center)
+                    .align_y(Alignment::Center)
+                    .style(move |theme| if highlighted { container::bordered_box(theme) } else { container::rounded_box(theme) });
+                let tile_area = mouse_area(tile_body)
+                    .on_press(Message::StartMenu(StartMessage::TilePressed(group_index, tile_index)))
+                    .on_release(Message::StartMenu(StartMessage::DragReleased(DropTarget::Tile(group_index, tile_index))))
+                    .on_enter(Message::StartMenu(StartMessage::DragHover(DropTarget::Tile(group_index, tile_index))));
+                let context_menu = ContextMenu::new(
+                    tile_area,
+                    || {
+                        let mut menu: Column<Message> = column![
+                            button(text!("Unpin")).style(context_menu_button).on_press(Message::StartMenu(StartMessage::UnpinFromTiles(path.clone()))),
+                        ];
+                        for size in TileSize::ALL {
+                            if size != tile.size {
+                                menu = menu.push(
+                                    button(text!("Resize to {}", size.label())).style(context_menu_button).on_press(Message::StartMenu(StartMessage::ResizeTile(path.clone(), size)))
+                                );
                             }
                         }
-                        self.settings.tiles = new_tiles_list;
-                        match to_string_pretty(&self.settings) {
-                            Ok(settings) => {
-                                match std::fs::write(&start_settings_file, settings) {
-                                    Err(e) => {
-                                        eprintln!("Error writing start menu settings: {:?}", e);
-                                    }
-                                    _ => {}
-                                };
-                            }
-                            Err(e) => {
-                                eprintln!("Error serializing start menu settings: {:?}", e);
-                            }
-                        };
+                        container(menu).style(container::bordered_box).into()
                     }
-                    None => {}
-                }
-                Task::none()
+                );
+                tiles_stack = tiles_stack.push(
+                    pin(context_menu).x(column as f32 * cell).y(row as f32 * cell)
+                );
             }
+            groups_column = groups_column.push(tiles_stack);
         }
+        groups_column.push(
+            button(text!("+ New group").size(text_height * 0.5)).style(transparent_button).on_press(Message::StartMenu(StartMessage::AddGroup))
+        )
     }
     pub fn view(&self, app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>,base_size: f32) -> Element<'_, Message> {
         let text_height = 30.0 * base_size;
@@ -322,75 +1520,83 @@ impl StartMenu {
                 )
             }
         };
-        match self.tab {
-            StartMenuTab::Tiles => {
-                header = text!("Tiles").size(text_height * 1.3);
-                let mut tiles_grid: Grid<Message> = Grid::new();
-                for path in self.settings.tiles.iter() {
-                    match app_image_cache.lock() {
-                        Ok(app_image_lock) => {
-                            let icon: Element<'_,Message> = if app_image_lock.contains_key(path) {
-                                if let Some(app_image) = app_image_lock.get(path) {
-                                    image(app_image).height(Length::Fixed(text_height * 2.0)).width(Length::Fixed(text_height * 2.0)).content_fit(ContentFit::Fill).into()
-                                } else {
-                                    eprintln!("Error getting app_image");
-                                    image(empty_app.clone()).into()
-                                }
-                            } else {
-                                image(empty_app.clone()).into()
-                            };
-                            let name = if let Some(app_name) = path.file_stem() {
-                                if let Some(app_name_str) = app_name.to_str() {
-                                    app_name_str.to_string()
-                                } else {
-                                    eprintln!("Error: failed to get App name into str");
-                                    "".to_string()
-                                }
-                            } else {
-                                eprintln!("Error: failed to get App name from OsStr");
-                                "".to_string()
-                            };
-                            let tile_button: Button<Message> = Button::new(
-                                column![
-                                    icon,
-                                    text!("{}",name).size(text_height * 0.5).align_x(Alignment::Center).wrapping(Wrapping::WordOrGlyph)
-                                ].align_x(Alignment::Center)
-                                    .width(Length::Fill)
-                                    .height(Length::Fill)
-                                    .spacing(0.0)
-                                    .padding(spacing)
-                            ).style(transparent_button)
-                            .on_press(Message::StartMenu(StartMessage::ItemMessage(StartItemMessage::Launch(path.clone().into_os_string()))));
-                            let context_menu = ContextMenu::new(
-                                tile_button,
+        let selected_item = match self.selection.as_ref() {
+            Some(Selection::Item(path)) => Some(path),
+            _ => None,
+        };
+        if let Some(editor) = self.editor.as_ref() {
+            header = text!("{}", if editor.path.is_some() { "Edit entry" } else { "New entry" }).size(text_height * 1.3);
+            tab_content = tab_content.push(editor.view(text_height, spacing));
+        } else if !self.search.is_empty() {
+            header = text!("Search").size(text_height * 1.3);
+            if let Some(calculation) = calculator::evaluate(&self.search) {
+                let is_selected = !matches!(self.selection.as_ref(), Some(Selection::Item(_)));
+                tab_content = tab_content.push(
+                    button(
+                        row![
+                            text!("= {}", calculation.result).size(text_height * 0.7).width(Length::Fill),
+                            text!("Enter to copy").size(text_height * 0.4),
+                        ].align_y(Alignment::Center)
+                    ).on_press(Message::StartMenu(StartMessage::CopyResult(calculation.result.clone())))
+                        .style(move |theme, status| if is_selected { selected_button(theme, status) } else { transparent_button(theme, status) })
+                        .width(Length::Fill)
+                );
+            }
+            for item in self.search_results() {
+                tab_content = tab_content.push(item.view(app_image_cache.clone(), base_size, Vec::new(), selected_item));
+            }
+        } else {
+            match self.tab {
+                StartMenuTab::Tiles => {
+                    header = text!("Tiles").size(text_height * 1.3);
+                    tab_content = tab_content.push(self.tile_groups_view(app_image_cache.clone(), empty_app.clone(), base_size));
+                    if !self.history.is_empty() {
+                        let now = now_timestamp();
+                        let sections = [
+                            ("Recently used", self.history.recent(5)),
+                            ("Most used", self.history.most_used(5, now)),
+                        ];
+                        for (title, targets) in sections {
+                            tab_content = tab_content.push(rule::horizontal(spacing));
+                            tab_content = tab_content.push(ContextMenu::new(
+                                text!("{}", title).size(text_height * 0.7),
                                 || {
                                     container(
                                         column![
-                                            button(text!("Unpin")).style(context_menu_button).on_press(Message::StartMenu(StartMessage::UnpinFromTiles(path.clone()))),
+                                            button(text!("Clear history")).style(context_menu_button).on_press(Message::StartMenu(StartMessage::ClearHistory)),
                                         ]
                                     ).style(container::bordered_box).into()
                                 }
-                            );
-                            tiles_grid = tiles_grid.push(context_menu);
-                        }
-                        Err(e) => {
-                            eprintln!("Error getting app_image lock: {:?}", e);
+                            ));
+                            for target in targets {
+                                if let Some(item) = self.find_item(&PathBuf::from(target)) {
+                                    tab_content = tab_content.push(item.view(app_image_cache.clone(), base_size, Vec::new(), selected_item));
+                                }
+                            }
                         }
                     }
+                },
+                StartMenuTab::Places => {
+                    header = text!("Places").size(text_height * 1.3);
+                    tab_content = tab_content.push(self.places_view(app_image_cache.clone(), base_size));
                 }
-                tab_content = tab_content.push(tiles_grid.spacing(spacing));
-            },
-            StartMenuTab::Applications => {
-                header = text!("Applications").size(text_height * 1.3);
-                for key in self.sorted.iter() {
-                    let mut path: Vec<String> = Vec::new();
-                    path.push(key.clone());
-                    if let Some(item) = self.content.get(key) {
-                        tab_content = tab_content.push(item.view(app_image_cache.clone(),base_size.clone(), path))
+                StartMenuTab::Applications => {
+                    header = text!("Applications").size(text_height * 1.3);
+                    tab_content = tab_content.push(
+                        button(text!("New entry...").size(text_height * 0.5))
+                            .style(transparent_button)
+                            .on_press(Message::StartMenu(StartMessage::NewEntry))
+                    );
+                    for key in self.sorted.iter() {
+                        let mut path: Vec<String> = Vec::new();
+                        path.push(key.clone());
+                        if let Some(item) = self.content.get(key) {
+                            tab_content = tab_content.push(item.view(app_image_cache.clone(),base_size, path, selected_item))
+                        }
                     }
                 }
-            }
-        };
+            };
+        }
         container(
             column![
                 row![
@@ -401,6 +1607,12 @@ impl StartMenu {
                         button("Applications").on_press(Message::StartMenu(StartMessage::SwitchToTab(StartMenuTab::Applications)))
                         .style(transparent_button)
                         .width(Length::Fill),
+                        button("Places").on_press(Message::StartMenu(StartMessage::SwitchToTab(StartMenuTab::Places)))
+                        .style(transparent_button)
+                        .width(Length::Fill),
+                        button("Run...").on_press(Message::OpenRunDialog)
+                        .style(transparent_button)
+                        .width(Length::Fill),
                     ].width(Length::FillPortion(3))
                     .height(Length::Fill),
                     rule::vertical(spacing),
@@ -409,6 +1621,12 @@ impl StartMenu {
                         .align_x(Alignment::Center)
                         .width(Length::Fill)
                         .height(Length::FillPortion(1)),
+                        text_input("Search", &self.search)
+                        .on_input(|search| Message::StartMenu(StartMessage::Search(search)))
+                        .on_submit(Message::StartMenu(StartMessage::LaunchTopResult))
+                        .size(text_height * 0.5)
+                        .width(Length::Fill),
+                        self.notice_view(text_height, spacing),
                         rule::horizontal(spacing),
                         scrollable(
                             tab_content.width(Length::Fill)
@@ -429,7 +1647,7 @@ impl StartMenu {
                     .width(Length::Fixed(text_height))
                     .padding(0.0)
                     .style(transparent_button)
-                    .on_press(Message::StartMenu(StartMessage::ItemMessage(StartItemMessage::Launch("ms-settings:home".into())))),
+                    .on_press_maybe(self.settings_app.clone().map(|target| Message::StartMenu(StartMessage::ItemMessage(StartItemMessage::Launch(target))))),
                     space().width(Length::Fill),
                     button(
                         image(lock_button)
@@ -467,41 +1685,72 @@ impl StartMenu {
 pub enum StartItemMessage {
     Toggle(Vec<String>),
     Launch(OsString),
+    RunAsAdmin(PathBuf),
+    OpenFileLocation(PathBuf),
+    OpenTargetFolder(PathBuf),
+    CopyPath(PathBuf),
+    Hide(PathBuf),
+    Uninstall(PathBuf),
 }
 
+#[derive(Debug, Clone)]
 struct StartItem {
     name: String,
     content: Option<BTreeMap<String,Self>>,
     sorted: Option<Vec<String>>,
     path: PathBuf,
+    target: Option<StartTarget>,
     open: bool,
+    /// Other files this entry stands for, like the same shortcut in the system Programs folder.
+    duplicates: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone)]
+enum StartTarget {
+    // Only Windows Programs folders hold shortcuts
+    #[cfg_attr(not(windows), allow(dead_code))]
+    Shortcut(ShellLink),
+    #[cfg(windows)]
+    Package(AppxApplication),
+    #[cfg(target_os = "linux")]
+    DesktopEntry(DesktopEntry),
+    Custom(CustomEntry),
 }
 
 impl StartItem {
+    #[cfg_attr(not(windows), allow(dead_code))]
     pub fn new(path: PathBuf) -> Result<Self,String> {
         if let Some(file_name) = path.file_name() && let Some(name) = file_name.to_str() {
             let mut name = name.to_string();
             let mut content: Option<BTreeMap<String,Self>> = None;
+            let mut target: Option<StartTarget> = None;
             if path.is_dir() {
                 let mut new_content: BTreeMap<String,Self> = BTreeMap::new();
-                match get_dir_contents(path.clone(),&mut new_content) {
-                    Err(e) => {
-                        return Err(format!("Error getting directory contents: {}", e));
-                    }
-                    _ => {}
+                if let Err(e) = get_dir_contents(path.clone(),&mut new_content) {
+                    return Err(format!("Error getting directory contents: {}", e));
                 };
                 content = Some(new_content);
             } else if path.is_file() && let Some(extension) = path.extension() && extension == "lnk" {
                 if let Some(name_osstr) = path.file_stem() && let Some(name_str) = name_osstr.to_str() {
                     name = name_str.to_string();
                 }
+                match ShellLink::from_path(&path) {
+                    Ok(parsed) => {
+                        target = Some(StartTarget::Shortcut(parsed));
+                    }
+                    Err(e) => {
+                        eprintln!("Error parsing shortcut {:?}: {}", path, e);
+                    }
+                }
             }
             Ok(Self {
                 name,
                 content,
                 sorted: None,
                 path,
+                target,
                 open: false,
+                duplicates: Vec::new(),
             })
         } else {
             Err(format!("Error getting name from path: {:?}", path))
@@ -518,12 +1767,27 @@ impl StartItem {
         } else {
             match app_image_cache.lock() {
                 Ok(mut app_image_lock) => {
+                    #[cfg(windows)]
+                    if let Some(StartTarget::Package(application)) = self.target.as_ref() {
+                        if !app_image_lock.contains_key(&self.path.clone()) && let Some(logo) = application.logo.as_ref() {
+                            app_image_lock.insert(self.path.clone(), Handle::from_path(logo));
+                        }
+                        return;
+                    }
+                    #[cfg(windows)]
                     if !app_image_lock.contains_key(&self.path.clone()) {
                         if let Some((data,width,height)) = get_lnk_icon(self.path.clone()) {
                             let icon_handle = Handle::from_rgba(width, height, data);
                             app_image_lock.insert(self.path.clone(), icon_handle);
                         }
                     }
+                    #[cfg(target_os = "linux")]
+                    if !app_image_lock.contains_key(&self.path.clone())
+                        && let Some(StartTarget::DesktopEntry(entry)) = self.target.as_ref()
+                        && let Some(icon) = entry.icon.as_ref()
+                        && let Some(icon_handle) = system_icon_lookup().find_icon(icon, 48, 1).and_then(|icon_path| load_icon(&icon_path, 48)) {
+                            app_image_lock.insert(self.path.clone(), icon_handle);
+                        }
                 }
                 Err(e) => {
                     eprintln!("Error accessing app_image_cache: {}", e);
@@ -531,27 +1795,326 @@ impl StartItem {
             }
         }
     }
-    pub fn update(&mut self, message: StartItemMessage) {
-        match message {
-            StartItemMessage::Toggle(path) => {
-                let mut path = path.clone();
-                if path.len() == 0 {
-                    self.open = !self.open;
-                } else if path.len() > 0 {
-                    if let Some(content) = self.content.as_mut() {
-                        if let Some(sub_dir) = path.pop() {
-                            if let Some(item) = content.get_mut(&sub_dir) {
-                                item.update(StartItemMessage::Toggle(path));
-                            }
-                        };
+    #[cfg(target_os = "linux")]
+    pub fn from_desktop_entry(entry: DesktopEntry) -> Self {
+        Self {
+            name: entry.name.clone(),
+            content: None,
+            sorted: None,
+            path: entry.path.clone(),
+            target: Some(StartTarget::DesktopEntry(entry)),
+            open: false,
+            duplicates: Vec::new(),
+        }
+    }
+    /// Store apps launch through their `shell:AppsFolder` URI, which also serves as their key for tiles and history.
+    #[cfg(windows)]
+    pub fn from_package(application: AppxApplication) -> Self {
+        Self {
+            name: application.display_name.clone(),
+            content: None,
+            sorted: None,
+            path: PathBuf::from(application.launch_uri()),
+            target: Some(StartTarget::Package(application)),
+            open: false,
+            duplicates: Vec::new(),
+        }
+    }
+    pub fn from_custom(entry: CustomEntry) -> Self {
+        Self {
+            name: entry.name.clone(),
+            content: None,
+            sorted: None,
+            path: entry.path(),
+            target: Some(StartTarget::Custom(entry)),
+            open: false,
+            duplicates: Vec::new(),
+        }
+    }
+    pub fn folder(name: String, path: PathBuf, content: BTreeMap<String,Self>) -> Self {
+        Self {
+            name,
+            content: Some(content),
+            sorted: None,
+            path,
+            target: None,
+            open: false,
+            duplicates: Vec::new(),
+        }
+    }
+    fn find(&self, path: &PathBuf) -> Option<&Self> {
+        match self.content.as_ref() {
+            Some(content) => content.values().find_map(|item| item.find(path)),
+            None => if self.path == *path || self.duplicates.contains(path) { Some(self) } else { None },
+        }
+    }
+    fn remove(&mut self, path: &PathBuf) -> bool {
+        let Some(content) = self.content.as_mut() else {
+            return false;
+        };
+        let mut sorted = self.sorted.take().unwrap_or_default();
+        let removed = remove_entry(content, &mut sorted, path);
+        self.sorted = Some(sorted);
+        removed
+    }
+    /// Takes the children of a folder with the same name from another Programs folder.
+    fn merge_folder(&mut self, other: Self) {
+        self.duplicates.push(other.path);
+        self.duplicates.extend(other.duplicates);
+        self.open |= other.open;
+        if let Some(content) = self.content.as_mut() {
+            for item in other.content.into_iter().flat_map(|content| content.into_values()) {
+                merge_entry(content, item);
+            }
+            if self.sorted.is_some() {
+                let mut keys: Vec<String> = content.keys().cloned().collect();
+                alphanumeric_sort::sort_str_slice(&mut keys);
+                self.sorted = Some(keys);
+            }
+        }
+    }
+    /// Drops the children that came from below `path`, after the folder there went away.
+    fn remove_below(&mut self, path: &Path) {
+        let Some(content) = self.content.as_mut() else {
+            return;
+        };
+        let inside: Vec<PathBuf> = content.values()
+            .flat_map(|item| std::iter::once(&item.path).chain(item.duplicates.iter()))
+            .filter(|item_path| item_path.starts_with(path))
+            .cloned()
+            .collect();
+        let mut sorted = self.sorted.take().unwrap_or_default();
+        for item_path in inside {
+            remove_entry(content, &mut sorted, &item_path);
+        }
+        self.sorted = Some(sorted);
+    }
+    /// Whether two entries start the same thing, so only one of them needs to be listed.
+    fn same_target(&self, other: &Self) -> bool {
+        match (self.target.as_ref(), other.target.as_ref()) {
+            (Some(StartTarget::Shortcut(link)), Some(StartTarget::Shortcut(other_link))) => {
+                let (Some(target), Some(other_target)) = (link.resolved_target(&self.path), other_link.resolved_target(&other.path)) else {
+                    return false;
+                };
+                // Windows paths are not case sensitive
+                target.to_string_lossy().to_lowercase() == other_target.to_string_lossy().to_lowercase()
+                    && link.arguments.as_deref().unwrap_or_default() == other_link.arguments.as_deref().unwrap_or_default()
+            }
+            #[cfg(windows)]
+            (Some(StartTarget::Package(application)), Some(StartTarget::Package(other_application))) => application.aumid == other_application.aumid,
+            #[cfg(target_os = "linux")]
+            (Some(StartTarget::DesktopEntry(entry)), Some(StartTarget::DesktopEntry(other_entry))) => entry.exec.is_some() && entry.exec == other_entry.exec,
+            _ => false,
+        }
+    }
+    /// Tells an entry apart from a different one with the same name.
+    fn disambiguation(&self) -> Option<String> {
+        match self.target.as_ref()? {
+            StartTarget::Shortcut(link) => link.resolved_target(&self.path)?
+                .file_name()
+                .map(|file_name| file_name.to_string_lossy().to_string()),
+            #[cfg(windows)]
+            StartTarget::Package(application) => Some(application.id.clone()),
+            #[cfg(target_os = "linux")]
+            StartTarget::DesktopEntry(entry) => Some(entry.id.clone()),
+            StartTarget::Custom(_) => None,
+        }
+    }
+    /// Program the entry ends up starting, when it can be told without running it.
+    fn target_path(&self) -> Option<PathBuf> {
+        match self.target.as_ref()? {
+            StartTarget::Shortcut(link) => link.resolved_target(&self.path),
+            #[cfg(windows)]
+            StartTarget::Package(_) => None,
+            #[cfg(target_os = "linux")]
+            StartTarget::DesktopEntry(entry) => {
+                let program = match entry.try_exec.clone() {
+                    Some(try_exec) => try_exec,
+                    None => {
+                        let exec = entry.exec.as_ref()?;
+                        expand_exec(exec, &[], entry.icon.as_deref(), &entry.name, &entry.path).ok()?.into_iter().next()?
+                    }
+                };
+                // Launchers in PATH are often symlinks into the real install folder
+                find_executable(&program).map(|found| std::fs::canonicalize(&found).unwrap_or(found))
+            }
+            StartTarget::Custom(entry) => {
+                #[cfg(target_os = "linux")]
+                return find_executable(&entry.command).map(|found| std::fs::canonicalize(&found).unwrap_or(found));
+                #[cfg(windows)]
+                return Some(PathBuf::from(&entry.command)).filter(|program| program.is_file());
+            }
+        }
+    }
+    /// Custom entries start their own command line, everything else is started from its path.
+    fn launch_target(&self) -> Option<LaunchTarget> {
+        match self.target.as_ref()? {
+            StartTarget::Custom(entry) => Some(entry.launch_target()),
+            _ => None,
+        }
+    }
+    fn is_custom(&self) -> bool {
+        matches!(self.target, Some(StartTarget::Custom(_)))
+    }
+    fn custom_icon(&self) -> Option<Handle> {
+        match self.target.as_ref()? {
+            StartTarget::Custom(entry) => entry.icon.as_ref().map(Handle::from_path),
+            _ => None,
+        }
+    }
+    fn is_package(&self) -> bool {
+        #[cfg(windows)]
+        return matches!(self.target, Some(StartTarget::Package(_)));
+        #[cfg(not(windows))]
+        return false;
+    }
+    fn run_as_admin(&self) -> Result<(), String> {
+        match self.target.as_ref() {
+            #[cfg(windows)]
+            Some(StartTarget::Shortcut(_)) => run_as_administrator(&self.path),
+            #[cfg(target_os = "linux")]
+            Some(StartTarget::DesktopEntry(entry)) => {
+                let command = entry.command(&[])?;
+                run_with_pkexec(&command, entry.working_dir.as_deref().map(std::path::Path::new))
+            }
+            _ => Err(format!("{} cannot be run as administrator", self.name)),
+        }
+    }
+    fn uninstaller(&self) -> Result<Uninstaller, String> {
+        match self.target.as_ref() {
+            #[cfg(windows)]
+            Some(StartTarget::Shortcut(link)) => find_registered_uninstaller(&self.name, link.resolved_target(&self.path).as_deref()),
+            #[cfg(windows)]
+            Some(StartTarget::Package(application)) => {
+                let family_name = application.aumid.split('!').next().unwrap_or_default();
+                Ok(Uninstaller::Command {
+                    display_name: application.display_name.clone(),
+                    program: "powershell".to_string(),
+                    args: vec![
+                        "-NoProfile".to_string(),
+                        "-Command".to_string(),
+                        format!("Get-AppxPackage -PackageFamilyName '{}' | Remove-AppxPackage", family_name),
+                    ],
+                })
+            }
+            #[cfg(target_os = "linux")]
+            Some(StartTarget::DesktopEntry(entry)) => find_package_uninstaller(&entry.path, &self.name),
+            _ => Err(format!("No uninstaller is known for {}", self.name)),
+        }
+    }
+    /// Adds this entry and, for an open folder, its children in the order and with the paths `view` uses.
+    fn collect_rows<'a>(&'a self, path: Vec<String>, rows: &mut Vec<(&'a Self, Vec<String>)>) {
+        match (self.content.as_ref(), self.sorted.as_ref()) {
+            (Some(content), Some(keys)) => {
+                if content.is_empty() || keys.is_empty() {
+                    return;
+                }
+                rows.push((self, path.clone()));
+                if self.open {
+                    for key in keys.iter() {
+                        let mut child_path = path.clone();
+                        child_path.insert(0, key.clone());
+                        if let Some(item) = content.get(key) {
+                            item.collect_rows(child_path, rows);
+                        }
+                    }
+                }
+            }
+            (Some(_), None) => {}
+            (None, _) => {
+                if self.path.extension().is_some_and(|extension| extension != "ini") {
+                    rows.push((self, path));
+                }
+            }
+        }
+    }
+    fn collect_open_folders(&self, open_folders: &mut Vec<PathBuf>) {
+        if let Some(content) = self.content.as_ref() {
+            if self.open {
+                open_folders.push(self.path.clone());
+            }
+            for item in content.values() {
+                item.collect_open_folders(open_folders);
+            }
+        }
+    }
+    fn restore_open_folders(&mut self, open_folders: &[PathBuf]) {
+        if let Some(content) = self.content.as_mut() {
+            self.open = open_folders.contains(&self.path);
+            for item in content.values_mut() {
+                item.restore_open_folders(open_folders);
+            }
+        }
+    }
+    fn collect_leaves<'a>(&'a self, leaves: &mut Vec<&'a Self>) {
+        match self.content.as_ref() {
+            Some(content) => {
+                for item in content.values() {
+                    item.collect_leaves(leaves);
+                }
+            }
+            None => leaves.push(self),
+        }
+    }
+    fn tooltip_text(&self) -> Option<String> {
+        let mut lines: Vec<String> = Vec::new();
+        match self.target.as_ref()? {
+            StartTarget::Shortcut(link) => {
+                if let Some(description) = link.description.as_ref() && !description.is_empty() {
+                    lines.push(description.clone());
+                }
+                if let Some(target) = link.resolved_target(&self.path) {
+                    let mut command = target.to_string_lossy().to_string();
+                    if let Some(arguments) = link.arguments.as_ref() && !arguments.is_empty() {
+                        command.push(' ');
+                        command.push_str(arguments);
                     }
+                    lines.push(command);
+                }
+            }
+            #[cfg(windows)]
+            StartTarget::Package(application) => {
+                if let Some(description) = application.description.as_ref() && !description.is_empty() {
+                    lines.push(description.clone());
+                }
+                lines.push(application.aumid.clone());
+            }
+            #[cfg(target_os = "linux")]
+            StartTarget::DesktopEntry(entry) => {
+                if let Some(comment) = entry.comment.as_ref().or(entry.generic_name.as_ref()) {
+                    lines.push(comment.clone());
+                }
+                if let Some(exec) = entry.exec.as_ref() {
+                    lines.push(exec.clone());
                 }
             }
-            _ => {}
+            StartTarget::Custom(entry) => {
+                let mut command = entry.command.clone();
+                if !entry.arguments.is_empty() {
+                    command.push(' ');
+                    command.push_str(&entry.arguments);
+                }
+                lines.push(command);
+            }
+        }
+        if lines.is_empty() { None } else { Some(lines.join("\n")) }
+    }
+    pub fn update(&mut self, message: StartItemMessage) {
+        if let StartItemMessage::Toggle(path) = message {
+            let mut path = path.clone();
+            if path.is_empty() {
+                self.open = !self.open;
+            } else if !path.is_empty()
+                && let Some(content) = self.content.as_mut()
+                    && let Some(sub_dir) = path.pop()
+                        && let Some(item) = content.get_mut(&sub_dir) {
+                            item.update(StartItemMessage::Toggle(path));
+                        };
         }
     }
-    pub fn view(&self, app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, base_size: f32, path: Vec<String>) -> Element<'_, Message> {
+    pub fn view(&self, app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, base_size: f32, path: Vec<String>, selected: Option<&PathBuf>) -> Element<'_, Message> {
         let text_half_height = 15.0 * base_size;
+        let is_selected = selected == Some(&self.path);
         let spacing = 2.0 * base_size;
         let mut head: Column<Message> = Column::new();
         let (
@@ -598,7 +2161,7 @@ impl StartItem {
                             text!("{}",self.name).size(text_half_height).height(Length::Fixed(text_half_height)).align_y(Alignment::Center),
                         ].align_y(Alignment::Center)
                     ).on_press(Message::StartMenu(StartMessage::ItemMessage(StartItemMessage::Toggle(path.clone()))))
-                        .style(transparent_button),
+                        .style(move |theme, status| if is_selected { selected_button(theme, status) } else { transparent_button(theme, status) }),
                 );
                 if self.open {
                     let mut children: Column<Message> = Column::new();
@@ -607,7 +2170,7 @@ impl StartItem {
                         new_path.insert(0,key.clone());
                         if let Some(item) = content.get(key) {
                             children = children.push(
-                                item.view(app_image_cache.clone(), base_size.clone(), new_path)
+                                item.view(app_image_cache.clone(), base_size, new_path, selected)
                             );
                         }
                     };
@@ -621,40 +2184,71 @@ impl StartItem {
             }
             head.spacing(spacing).into()
         } else if let Some(extension) = self.path.extension() && extension != "ini" {
-            let icon: Element<'_,Message> =
-            match app_image_cache.lock() {
-                Ok(app_image_lock) => {
-                    if app_image_lock.contains_key(&self.path) {
-                        if let Some(image) = app_image_lock.get(&self.path) {
-                            iced::widget::image(image).height(text_half_height).width(text_half_height).content_fit(ContentFit::Fill).into()
+            let icon: Element<'_,Message> = if let Some(custom_icon) = self.custom_icon() {
+                image(custom_icon).height(text_half_height).width(text_half_height).content_fit(ContentFit::Fill).into()
+            } else {
+                match app_image_cache.lock() {
+                    Ok(app_image_lock) => {
+                        if app_image_lock.contains_key(&self.path) {
+                            if let Some(image) = app_image_lock.get(&self.path) {
+                                iced::widget::image(image).height(text_half_height).width(text_half_height).content_fit(ContentFit::Fill).into()
+                            } else {
+                                image(empty_app).height(text_half_height).width(text_half_height).into()
+                            }
                         } else {
                             image(empty_app).height(text_half_height).width(text_half_height).into()
                         }
-                    } else {
+                    }
+                    Err(e) => {
+                        eprintln!("Error getting app_image_cache: {}", e);
                         image(empty_app).height(text_half_height).width(text_half_height).into()
                     }
                 }
-                Err(e) => {
-                    eprintln!("Error getting app_image_cache: {}", e);
-                    image(empty_app).height(text_half_height).width(text_half_height).into()
-                }
+            };
+            let item_button = button(
+                row![
+                    image(tree_dot).height(text_half_height).width(text_half_height),
+                    icon,
+                    space().width(Length::Fixed(spacing)),
+                    text!("{}",self.name).size(text_half_height).height(Length::Fixed(text_half_height)).align_y(Alignment::Center)
+                ].align_y(Alignment::Center)
+            ).on_press(Message::StartMenu(StartMessage::ItemMessage(StartItemMessage::Launch(self.path.clone().into_os_string()))))
+                .style(move |theme, status| if is_selected { selected_button(theme, status) } else { transparent_button(theme, status) });
+            let item_element: Element<'_,Message> = if let Some(tooltip_text) = self.tooltip_text() {
+                tooltip(
+                    item_button,
+                    container(column![
+                        text!("{}", tooltip_text).size(text_half_height),
+                    ]).style(container::rounded_box),
+                    tooltip::Position::FollowCursor
+                ).into()
+            } else {
+                item_button.into()
             };
             let context_menu = ContextMenu::new(
-                button(
-                    row![
-                        image(tree_dot).height(text_half_height).width(text_half_height),
-                        icon,
-                        space().width(Length::Fixed(spacing)),
-                        text!("{}",self.name).size(text_half_height).height(Length::Fixed(text_half_height)).align_y(Alignment::Center)
-                    ].align_y(Alignment::Center)
-                ).on_press(Message::StartMenu(StartMessage::ItemMessage(StartItemMessage::Launch(self.path.clone().into_os_string()))))
-                    .style(transparent_button),
+                item_element,
                 || {
-                    container(
-                        column![
-                                button(text!("Pin to Tiles")).style(context_menu_button).on_press(Message::StartMenu(StartMessage::PinToTiles(self.path.clone())))
-                            ]
-                    ).style(container::bordered_box).into()
+                    let item_message = |message: StartItemMessage| Message::StartMenu(StartMessage::ItemMessage(message));
+                    let mut actions: Column<Message> = column![
+                        button(text!("Pin to Tiles")).style(context_menu_button).on_press(Message::StartMenu(StartMessage::PinToTiles(self.path.clone()))),
+                    ];
+                    if self.target.is_some() && !self.is_package() && !self.is_custom() {
+                        actions = actions.push(button(text!("Run as administrator")).style(context_menu_button).on_press(item_message(StartItemMessage::RunAsAdmin(self.path.clone()))));
+                    }
+                    if !self.is_package() && !self.is_custom() {
+                        actions = actions.push(button(text!("Open file location")).style(context_menu_button).on_press(item_message(StartItemMessage::OpenFileLocation(self.path.clone()))));
+                    }
+                    if self.target_path().is_some() {
+                        actions = actions.push(button(text!("Open target folder")).style(context_menu_button).on_press(item_message(StartItemMessage::OpenTargetFolder(self.path.clone()))));
+                    }
+                    actions = actions
+                        .push(button(text!("Edit entry")).style(context_menu_button).on_press(Message::StartMenu(StartMessage::EditEntry(self.path.clone()))))
+                        .push(button(text!("Copy path")).style(context_menu_button).on_press(item_message(StartItemMessage::CopyPath(self.path.clone()))))
+                        .push(button(text!("Hide")).style(context_menu_button).on_press(item_message(StartItemMessage::Hide(self.path.clone()))));
+                    if !self.is_custom() {
+                        actions = actions.push(button(text!("Uninstall")).style(context_menu_button).on_press(item_message(StartItemMessage::Uninstall(self.path.clone()))));
+                    }
+                    container(actions).style(container::bordered_box).into()
                 }
             );
             head = head.push(
@@ -665,4 +2259,311 @@ impl StartItem {
             head.into()
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::start_settings::Tile;
+
+    fn leaves(catalog: &StartCatalog) -> Vec<PathBuf> {
+        let mut leaves: Vec<&StartItem> = Vec::new();
+        for item in catalog.content.values() {
+            item.collect_leaves(&mut leaves);
+        }
+        let mut paths: Vec<PathBuf> = leaves.into_iter().map(|item| item.path.clone()).collect();
+        paths.sort();
+        paths
+    }
+
+    fn entry(name: &str, content: Option<Vec<StartItem>>) -> StartItem {
+        let content: Option<BTreeMap<String, StartItem>> = content.map(|items| items.into_iter().map(|item| (item.name.clone(), item)).collect());
+        StartItem {
+            name: name.to_string(),
+            sorted: content.as_ref().map(|content| content.keys().cloned().collect()),
+            // Only files with an extension are listed
+            path: PathBuf::from(if content.is_some() { name.to_string() } else { format!("{}.desktop", name) }),
+            content,
+            target: None,
+            open: false,
+            duplicates: Vec::new(),
+        }
+    }
+
+    fn menu() -> StartMenu {
+        let (mut menu, _) = StartMenu::new();
+        for item in [entry("Accessories", Some(vec![entry("Calculator", None), entry("Notes", None)])), entry("Browser", None)] {
+            menu.sorted.push(item.name.clone());
+            menu.content.insert(item.name.clone(), item);
+        }
+        let mut group = TileGroup::new("One".to_string());
+        for (path, size) in [("a", TileSize::Wide), ("b", TileSize::Small), ("c", TileSize::Small)] {
+            group.tiles.push(Tile { path: PathBuf::from(path), size });
+        }
+        menu.settings.groups.push(group);
+        let mut group = TileGroup::new("Two".to_string());
+        group.tiles.push(Tile { path: PathBuf::from("d"), size: TileSize::Medium });
+        menu.settings.groups.push(group);
+        menu
+    }
+
+    #[test]
+    fn moves_through_tiles_by_position() {
+        let mut menu = menu();
+        let mut step = |key: NavigationKey| {
+            menu.move_in_tiles(key);
+            menu.selection.clone()
+        };
+        assert_eq!(step(NavigationKey::Down), Some(Selection::Tile(0, 0)));
+        assert_eq!(step(NavigationKey::Right), Some(Selection::Tile(0, 1)));
+        assert_eq!(step(NavigationKey::Right), Some(Selection::Tile(0, 2)));
+        // Nothing is above the first row
+        assert_eq!(step(NavigationKey::Up), Some(Selection::Tile(0, 2)));
+        assert_eq!(step(NavigationKey::Down), Some(Selection::Tile(1, 0)));
+        // The wide tile reaches down to the next group, the small ones beside it do not
+        assert_eq!(step(NavigationKey::Up), Some(Selection::Tile(0, 0)));
+        assert_eq!(step(NavigationKey::Left), Some(Selection::Tile(0, 0)));
+        assert_eq!(step(NavigationKey::Down), Some(Selection::Tile(1, 0)));
+        assert_eq!(step(NavigationKey::Right), Some(Selection::Tile(1, 0)));
+
+        // Tiles of collapsed groups are skipped
+        menu.settings.groups[0].collapsed = true;
+        menu.selection = Some(Selection::Tile(0, 1));
+        menu.move_in_tiles(NavigationKey::Right);
+        assert_eq!(menu.selection, Some(Selection::Tile(1, 0)));
+    }
+
+    #[test]
+    fn moves_through_the_tree() {
+        let mut menu = menu();
+        let mut step = |key: NavigationKey| {
+            let _ = menu.move_in_tree(key);
+            let open = menu.content["Accessories"].open;
+            (menu.selection.clone(), open)
+        };
+        let item = |path: &str| Some(Selection::Item(PathBuf::from(path)));
+        assert_eq!(step(NavigationKey::Down), (item("Accessories"), false));
+        // Right opens a folder and then steps into it
+        assert_eq!(step(NavigationKey::Right), (item("Accessories"), true));
+        assert_eq!(step(NavigationKey::Right), (item("Calculator.desktop"), true));
+        assert_eq!(step(NavigationKey::Down), (item("Notes.desktop"), true));
+        assert_eq!(step(NavigationKey::Down), (item("Browser.desktop"), true));
+        assert_eq!(step(NavigationKey::Down), (item("Browser.desktop"), true));
+        assert_eq!(step(NavigationKey::Up), (item("Notes.desktop"), true));
+        // Left goes up to the folder and then closes it
+        assert_eq!(step(NavigationKey::Left), (item("Accessories"), true));
+        assert_eq!(step(NavigationKey::Left), (item("Accessories"), false));
+        assert_eq!(step(NavigationKey::Down), (item("Browser.desktop"), false));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn applies_application_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user");
+        let system = dir.path().join("system");
+        std::fs::create_dir_all(&user).unwrap();
+        std::fs::create_dir_all(&system).unwrap();
+        let dirs = vec![user.clone(), system.clone()];
+        let cache: Arc<Mutex<BTreeMap<PathBuf, Handle>>> = Arc::default();
+        let write = |path: &Path, name: &str| {
+            std::fs::write(path, format!("[Desktop Entry]\nType=Application\nName={}\nExec=editor\nCategories=Development;\n", name)).unwrap();
+        };
+        let mut catalog = StartCatalog::default();
+
+        let editor = system.join("editor.desktop");
+        write(&editor, "Editor");
+        assert!(catalog.apply_applications_path(&system, &dirs, &editor, cache.clone()));
+        assert_eq!(leaves(&catalog), vec![editor.clone()]);
+        assert_eq!(catalog.sorted, vec!["Programming".to_string()]);
+
+        // A file with the same ID in an earlier folder takes the entry's place, and gives it back when removed
+        let shadow = user.join("editor.desktop");
+        write(&shadow, "My editor");
+        assert!(catalog.apply_applications_path(&user, &dirs, &shadow, cache.clone()));
+        assert_eq!(leaves(&catalog), vec![shadow.clone()]);
+        std::fs::remove_file(&shadow).unwrap();
+        assert!(catalog.apply_applications_path(&user, &dirs, &shadow, cache.clone()));
+        assert_eq!(leaves(&catalog), vec![editor.clone()]);
+
+        // The watcher reports a rename as the old path going and the new one coming
+        let renamed = system.join("renamed.desktop");
+        std::fs::rename(&editor, &renamed).unwrap();
+        catalog.apply_applications_path(&system, &dirs, &editor, cache.clone());
+        catalog.apply_applications_path(&system, &dirs, &renamed, cache.clone());
+        assert_eq!(leaves(&catalog), vec![renamed.clone()]);
+
+        // A whole folder of entries appearing at once
+        let folder = system.join("suite");
+        std::fs::create_dir_all(&folder).unwrap();
+        write(&folder.join("writer.desktop"), "Writer");
+        assert!(catalog.apply_applications_path(&system, &dirs, &folder, cache.clone()));
+        assert_eq!(leaves(&catalog), vec![renamed.clone(), folder.join("writer.desktop")]);
+
+        std::fs::remove_dir_all(&folder).unwrap();
+        std::fs::remove_file(&renamed).unwrap();
+        assert!(catalog.apply_applications_path(&system, &dirs, &folder, cache.clone()));
+        assert!(catalog.apply_applications_path(&system, &dirs, &renamed, cache.clone()));
+        // The emptied category goes with its last entry
+        assert!(catalog.content.is_empty());
+        assert!(catalog.sorted.is_empty());
+    }
+
+    #[test]
+    fn applies_programs_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let cache: Arc<Mutex<BTreeMap<PathBuf, Handle>>> = Arc::default();
+        let mut catalog = StartCatalog::default();
+
+        let shortcut = write(root, "Tools/App.lnk", EDITOR);
+        assert!(catalog.apply_programs_path(root, &shortcut, cache.clone()));
+        assert_eq!(leaves(&catalog), vec![shortcut.clone()]);
+        assert_eq!(catalog.sorted, vec!["Tools".to_string()]);
+
+        let renamed = root.join("App.lnk");
+        std::fs::rename(&shortcut, &renamed).unwrap();
+        assert!(catalog.apply_programs_path(root, &shortcut, cache.clone()));
+        assert!(catalog.apply_programs_path(root, &renamed, cache.clone()));
+        assert_eq!(leaves(&catalog), vec![renamed.clone()]);
+
+        // Folder settings files are never entries
+        let desktop_ini = root.join("desktop.ini");
+        std::fs::write(&desktop_ini, "").unwrap();
+        assert!(!catalog.apply_programs_path(root, &desktop_ini, cache.clone()));
+
+        std::fs::remove_file(&renamed).unwrap();
+        assert!(catalog.apply_programs_path(root, &renamed, cache.clone()));
+        assert!(leaves(&catalog).is_empty());
+    }
+
+    const EDITOR: &[u8] = include_bytes!("../tests/fixtures/shell_link/local_target.lnk");
+    const TOOL: &[u8] = include_bytes!("../tests/fixtures/shell_link/env_target.lnk");
+    // Points next to the shortcut, so the copies in the two trees start different programs
+    const VIEWER: &[u8] = include_bytes!("../tests/fixtures/shell_link/icon_location.lnk");
+
+    fn write(root: &Path, relative: &str, data: &[u8]) -> PathBuf {
+        let path = root.join(relative);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, data).unwrap();
+        path
+    }
+
+    /// Reads a user and a system Programs folder into one tree, like `StartCatalog::load` does.
+    fn programs_trees() -> (tempfile::TempDir, PathBuf, PathBuf, BTreeMap<String, StartItem>) {
+        let temp = tempfile::tempdir().unwrap();
+        let user = temp.path().join("user");
+        let system = temp.path().join("system");
+        write(&user, "Editor.lnk", EDITOR);
+        write(&user, "Tools/Tool.lnk", TOOL);
+        write(&user, "Tools/Viewer.lnk", VIEWER);
+        write(&user, "Tools/Extra/Mine.lnk", TOOL);
+        write(&system, "Editor.lnk", EDITOR);
+        write(&system, "desktop.ini", b"[.ShellClassInfo]");
+        write(&system, "Tools/Tool.lnk", TOOL);
+        write(&system, "Tools/Viewer.lnk", VIEWER);
+        write(&system, "Tools/Extra/Shared.lnk", EDITOR);
+        let mut content: BTreeMap<String, StartItem> = BTreeMap::new();
+        for dir in [&user, &system] {
+            get_dir_contents(dir.clone(), &mut content).unwrap();
+        }
+        (temp, user, system, content)
+    }
+
+    fn child<'a>(content: &'a BTreeMap<String, StartItem>, names: &[&str]) -> &'a StartItem {
+        let (first, rest) = names.split_first().unwrap();
+        let item = content.get(*first).unwrap_or_else(|| panic!("{} missing", first));
+        if rest.is_empty() {
+            item
+        } else {
+            child(item.content.as_ref().unwrap(), rest)
+        }
+    }
+
+    fn keys(content: &BTreeMap<String, StartItem>) -> Vec<&str> {
+        content.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn merges_folders_with_the_same_name_recursively() {
+        let (_temp, user, system, content) = programs_trees();
+        assert_eq!(keys(&content), ["Editor", "Tools"]);
+
+        let tools = child(&content, &["Tools"]);
+        assert_eq!(tools.path, user.join("Tools"));
+        assert_eq!(tools.duplicates, [system.join("Tools")]);
+        assert_eq!(keys(tools.content.as_ref().unwrap()), ["Extra", "Tool", "Viewer", "Viewer (viewer.exe)"]);
+
+        let extra = child(&content, &["Tools", "Extra"]);
+        assert_eq!(extra.duplicates, [system.join("Tools/Extra")]);
+        assert_eq!(keys(extra.content.as_ref().unwrap()), ["Mine", "Shared"]);
+    }
+
+    #[test]
+    fn user_shortcut_wins_over_system_one() {
+        let (_temp, user, system, content) = programs_trees();
+        let editor = child(&content, &["Editor"]);
+        assert_eq!(editor.path, user.join("Editor.lnk"));
+        assert_eq!(editor.duplicates, [system.join("Editor.lnk")]);
+
+        let tool = child(&content, &["Tools", "Tool"]);
+        assert_eq!(tool.path, user.join("Tools/Tool.lnk"));
+        assert_eq!(tool.duplicates, [system.join("Tools/Tool.lnk")]);
+
+        // Same name but a different program, so both are listed
+        assert_eq!(child(&content, &["Tools", "Viewer"]).path, user.join("Tools/Viewer.lnk"));
+        assert_eq!(child(&content, &["Tools", "Viewer (viewer.exe)"]).path, system.join("Tools/Viewer.lnk"));
+    }
+
+    #[test]
+    fn removing_one_source_keeps_the_other() {
+        let (_temp, user, system, mut content) = programs_trees();
+        let mut sorted: Vec<String> = content.keys().cloned().collect();
+
+        assert!(remove_entry(&mut content, &mut sorted, &user.join("Editor.lnk")));
+        let editor = child(&content, &["Editor"]);
+        assert_eq!(editor.path, system.join("Editor.lnk"));
+        assert!(editor.duplicates.is_empty());
+
+        assert!(remove_entry(&mut content, &mut sorted, &system.join("Tools/Tool.lnk")));
+        let tool = child(&content, &["Tools", "Tool"]);
+        assert_eq!(tool.path, user.join("Tools/Tool.lnk"));
+        assert!(tool.duplicates.is_empty());
+
+        // The system folder going away takes only its own children with it
+        assert!(remove_entry(&mut content, &mut sorted, &system.join("Tools")));
+        let tools = child(&content, &["Tools"]);
+        assert!(tools.duplicates.is_empty());
+        assert_eq!(keys(tools.content.as_ref().unwrap()), ["Extra", "Tool", "Viewer"]);
+        assert_eq!(keys(child(&content, &["Tools", "Extra"]).content.as_ref().unwrap()), ["Mine"]);
+
+        // Once the last source is gone the entry goes too
+        assert!(remove_entry(&mut content, &mut sorted, &system.join("Editor.lnk")));
+        assert_eq!(keys(&content), ["Tools"]);
+        assert_eq!(sorted, ["Tools"]);
+    }
+
+
+    #[test]
+    fn taking_an_entry_keeps_every_source() {
+        let (_temp, user, system, mut content) = programs_trees();
+        let mut sorted: Vec<String> = content.keys().cloned().collect();
+
+        let (item, folders) = take_entry(&mut content, &mut sorted, &system.join("Tools/Tool.lnk")).unwrap();
+        assert_eq!(folders, ["Tools"]);
+        assert_eq!(item.path, user.join("Tools/Tool.lnk"));
+        assert_eq!(item.duplicates, [system.join("Tools/Tool.lnk")]);
+        assert!(!child(&content, &["Tools"]).content.as_ref().unwrap().contains_key("Tool"));
+
+        // Renamed entries are put back where they were
+        let mut renamed = item;
+        renamed.name = "Renamed".to_string();
+        insert_entry(&mut content, &mut sorted, &user, &folders, renamed);
+        let tool = child(&content, &["Tools", "Renamed"]);
+        assert_eq!(tool.path, user.join("Tools/Tool.lnk"));
+        assert_eq!(tool.duplicates, [system.join("Tools/Tool.lnk")]);
+
+        // Removing one of its sources afterwards still leaves the other
+        assert!(remove_entry(&mut content, &mut sorted, &user.join("Tools/Tool.lnk")));
+        assert_eq!(child(&content, &["Tools", "Renamed"]).path, system.join("Tools/Tool.lnk"));
+    }
+}
@@ -0,0 +1,477 @@
+use crate::launcher::{LaunchTarget, Launcher};
+use dirs::data_dir;
+use serde_json::{from_str, to_string_pretty};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+pub const SETTINGS_VERSION: u32 = 2;
+pub const TILE_COLUMNS: u32 = 6;
+const DEFAULT_GROUP_NAME: &str = "Pinned";
+
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TileSize {
+    Small,
+    #[default]
+    Medium,
+    Wide,
+    Large,
+}
+impl TileSize {
+    pub const ALL: [TileSize; 4] = [TileSize::Small, TileSize::Medium, TileSize::Wide, TileSize::Large];
+
+    /// Width and height in grid cells.
+    pub fn cells(&self) -> (u32, u32) {
+        match self {
+            TileSize::Small => (1, 1),
+            TileSize::Medium => (2, 2),
+            TileSize::Wide => (4, 2),
+            TileSize::Large => (4, 4),
+        }
+    }
+    pub fn label(&self) -> &'static str {
+        match self {
+            TileSize::Small => "Small",
+            TileSize::Medium => "Medium",
+            TileSize::Wide => "Wide",
+            TileSize::Large => "Large",
+        }
+    }
+}
+
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone, PartialEq)]
+pub struct Tile {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub size: TileSize,
+}
+
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone, PartialEq)]
+pub struct TileGroup {
+    pub name: String,
+    #[serde(default)]
+    pub collapsed: bool,
+    #[serde(default)]
+    pub tiles: Vec<Tile>,
+}
+impl TileGroup {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            collapsed: false,
+            tiles: Vec::new(),
+        }
+    }
+}
+
+/// A start menu entry the user added, for things without a shortcut or desktop file like a script with arguments.
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct CustomEntry {
+    pub id: u32,
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub arguments: String,
+    #[serde(default)]
+    pub working_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub icon: Option<PathBuf>,
+    // Folders the entry is listed in, separated by slashes. Empty for the top level.
+    #[serde(default)]
+    pub folder: String,
+}
+impl CustomEntry {
+    /// Key standing in for a file path in tiles, history and the hidden list.
+    /// The extension is what makes the menu list it like a shortcut.
+    pub fn path(&self) -> PathBuf {
+        PathBuf::from(format!("frostwin:entry/{}.entry", self.id))
+    }
+
+    pub fn folders(&self) -> Vec<String> {
+        self.folder.split(['/', '\\'])
+            .map(|folder| folder.trim())
+            .filter(|folder| !folder.is_empty())
+            .map(|folder| folder.to_string())
+            .collect()
+    }
+
+    pub fn launch_target(&self) -> LaunchTarget {
+        LaunchTarget::CommandLine {
+            program: self.command.clone(),
+            arguments: self.arguments.clone(),
+            working_dir: self.working_dir.clone(),
+        }
+    }
+}
+
+#[derive(serde_derive::Serialize, serde_derive::Deserialize, Debug, Clone)]
+pub struct StartMenuSettings {
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub groups: Vec<TileGroup>,
+    // Entries removed from the menu, keyed by their shortcut, desktop file or app URI
+    #[serde(default)]
+    pub hidden: Vec<PathBuf>,
+    // Extra folders and files listed under Places, after the standard user folders
+    #[serde(default)]
+    pub bookmarks: Vec<PathBuf>,
+    // Started programs get a process group of their own, so they keep running when the shell restarts
+    #[serde(default = "detach_by_default")]
+    pub detach_launched: bool,
+    #[serde(default)]
+    pub custom_entries: Vec<CustomEntry>,
+    // Names shown instead of an entry's own, keyed like hidden so they apply again when a program is reinstalled
+    #[serde(default)]
+    pub renamed: BTreeMap<PathBuf, String>,
+    // Version 1 kept a flat list of pinned shortcuts, only read for migration
+    #[serde(default, skip_serializing)]
+    tiles: Vec<PathBuf>,
+}
+fn detach_by_default() -> bool {
+    true
+}
+
+impl StartMenuSettings {
+    pub fn new() -> StartMenuSettings {
+        StartMenuSettings {
+            version: SETTINGS_VERSION,
+            groups: Default::default(),
+            hidden: Default::default(),
+            bookmarks: Default::default(),
+            detach_launched: true,
+            custom_entries: Default::default(),
+            renamed: Default::default(),
+            tiles: Default::default(),
+        }
+    }
+
+    pub fn launcher(&self) -> Launcher {
+        Launcher::new().detach(self.detach_launched)
+    }
+
+    pub fn settings_file() -> Option<PathBuf> {
+        data_dir().map(|data_dir| data_dir.join("Frostwin").join("Start_Settings.json"))
+    }
+
+    pub fn load() -> StartMenuSettings {
+        let Some(start_settings_file) = Self::settings_file() else {
+            return Self::new();
+        };
+        match std::fs::read_to_string(&start_settings_file) {
+            Ok(content) => {
+                match Self::parse(&content) {
+                    Ok((settings, migrated)) => {
+                        if migrated {
+                            settings.save();
+                        }
+                        settings
+                    }
+                    Err(e) => {
+                        eprintln!("Error loading start menu settings: {}", e);
+                        Self::new()
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("Error opening start menu settings: {:?}", e);
+                match e.kind() {
+                    std::io::ErrorKind::NotFound => {
+                        // Ensure the folder exists before creating the file
+                        if let Some(parent) = start_settings_file.parent() {
+                            let _ = std::fs::create_dir_all(parent);
+                        }
+                        Self::new().save();
+                    }
+
+                    std::io::ErrorKind::PermissionDenied => {
+                        eprintln!("Permission denied. Attempting to fix file attributes...");
+                        if let Ok(metadata) = std::fs::metadata(&start_settings_file) {
+                            let mut perms = metadata.permissions();
+                            if perms.readonly() {
+                                // Only the owner gets write access back, on Unix set_readonly(false) would give it to everyone
+                                #[cfg(unix)]
+                                {
+                                    use std::os::unix::fs::PermissionsExt;
+                                    perms.set_mode(perms.mode() | 0o200);
+                                }
+                                #[cfg(windows)]
+                                #[allow(clippy::permissions_set_readonly_false)]
+                                perms.set_readonly(false);
+                                // If we can fix permissions, try to write a fresh file
+                                if std::fs::set_permissions(&start_settings_file, perms).is_ok() {
+                                    Self::new().save();
+                                }
+                            }
+                        }
+                    }
+
+                    _ => eprintln!("Critical I/O error: {}", e),
+                }
+                Self::new()
+            }
+        }
+    }
+
+    /// Parses the settings json, returning whether an older schema was migrated.
+    pub fn parse(content: &str) -> Result<(StartMenuSettings, bool), String> {
+        let mut settings = from_str::<StartMenuSettings>(content).map_err(|e| format!("{:?}", e))?;
+        let migrated = settings.migrate();
+        Ok((settings, migrated))
+    }
+
+    fn migrate(&mut self) -> bool {
+        if self.version >= SETTINGS_VERSION {
+            return false;
+        }
+        if self.version < 2 && !self.tiles.is_empty() {
+            let mut group = TileGroup::new(DEFAULT_GROUP_NAME.to_string());
+            for path in self.tiles.drain(..) {
+                group.tiles.push(Tile { path, size: TileSize::Medium });
+            }
+            self.groups.insert(0, group);
+        }
+        self.version = SETTINGS_VERSION;
+        true
+    }
+
+    pub fn save(&self) {
+        let Some(start_settings_file) = Self::settings_file() else {
+            return;
+        };
+        match to_string_pretty(self) {
+            Ok(settings) => {
+                if let Err(e) = std::fs::write(&start_settings_file, settings) {
+                    eprintln!("Error writing start menu settings: {:?}", e);
+                };
+            }
+            Err(e) => {
+                eprintln!("Error serializing start menu settings: {:?}", e);
+            }
+        };
+    }
+
+    pub fn is_pinned(&self, path: &PathBuf) -> bool {
+        self.groups.iter().any(|group| group.tiles.iter().any(|tile| tile.path == *path))
+    }
+
+    pub fn pin(&mut self, path: PathBuf) {
+        if self.is_pinned(&path) {
+            return;
+        }
+        if self.groups.is_empty() {
+            self.groups.push(TileGroup::new(DEFAULT_GROUP_NAME.to_string()));
+        }
+        if let Some(group) = self.groups.last_mut() {
+            group.tiles.push(Tile { path, size: TileSize::Medium });
+        }
+    }
+
+    pub fn unpin(&mut self, path: &PathBuf) {
+        for group in self.groups.iter_mut() {
+            group.tiles.retain(|tile| tile.path != *path);
+        }
+    }
+
+    pub fn is_hidden(&self, path: &PathBuf) -> bool {
+        self.hidden.contains(path)
+    }
+
+    pub fn hide(&mut self, path: PathBuf) {
+        self.unpin(&path);
+        if !self.is_hidden(&path) {
+            self.hidden.push(path);
+        }
+    }
+
+    pub fn add_bookmark(&mut self, path: PathBuf) {
+        if !self.bookmarks.contains(&path) {
+            self.bookmarks.push(path);
+        }
+    }
+
+    pub fn remove_bookmark(&mut self, path: &PathBuf) {
+        self.bookmarks.retain(|bookmark| bookmark != path);
+    }
+
+    /// Shows the entry at `path` as `name`, or under its own name again when `name` is empty.
+    pub fn rename(&mut self, path: PathBuf, name: &str) {
+        let name = name.trim();
+        if name.is_empty() {
+            self.renamed.remove(&path);
+        } else {
+            self.renamed.insert(path, name.to_string());
+        }
+    }
+
+    /// Replaces the custom entry with the same ID. New entries come with ID 0 and are given a free one.
+    pub fn save_custom_entry(&mut self, mut entry: CustomEntry) {
+        match self.custom_entries.iter_mut().find(|existing| existing.id == entry.id) {
+            Some(existing) => *existing = entry,
+            None => {
+                entry.id = self.custom_entries.iter().map(|existing| existing.id + 1).max().unwrap_or(1);
+                self.custom_entries.push(entry);
+            }
+        }
+    }
+
+    pub fn remove_custom_entry(&mut self, id: u32) {
+        if let Some(entry) = self.custom_entries.iter().find(|entry| entry.id == id) {
+            let path = entry.path();
+            self.unpin(&path);
+            self.hidden.retain(|hidden| *hidden != path);
+        }
+        self.custom_entries.retain(|entry| entry.id != id);
+    }
+
+    /// Moves a tile before `to_tile` in `to_group`, or to the end of the group when `to_tile` is `None`.
+    pub fn move_tile(&mut self, from: (usize, usize), to_group: usize, to_tile: Option<usize>) {
+        let (from_group, from_tile) = from;
+        if from_group >= self.groups.len() || to_group >= self.groups.len() || from_tile >= self.groups[from_group].tiles.len() {
+            return;
+        }
+        let tile = self.groups[from_group].tiles.remove(from_tile);
+        let target = &mut self.groups[to_group].tiles;
+        let index = match to_tile {
+            // Removing the tile shifted everything after it in the same group
+            Some(index) if from_group == to_group && index > from_tile => index - 1,
+            Some(index) => index,
+            None => target.len(),
+        };
+        target.insert(index.min(target.len()), tile);
+    }
+
+    pub fn move_group(&mut self, from: usize, to: usize) {
+        if from >= self.groups.len() || to >= self.groups.len() || from == to {
+            return;
+        }
+        let group = self.groups.remove(from);
+        self.groups.insert(to, group);
+    }
+}
+
+/// First-fit packing of tiles into a grid `columns` cells wide.
+/// Returns the cell position of every tile and the number of rows used.
+pub fn pack_tiles(tiles: &[Tile], columns: u32) -> (Vec<(u32, u32)>, u32) {
+    let mut occupied: Vec<Vec<bool>> = Vec::new();
+    let mut positions: Vec<(u32, u32)> = Vec::with_capacity(tiles.len());
+    let mut rows = 0;
+    for tile in tiles {
+        let (width, height) = tile.size.cells();
+        let width = width.min(columns);
+        let mut row = 0;
+        let position = loop {
+            let fit = (0..=columns - width).find(|column| {
+                (row..row + height).all(|r| {
+                    (*column..*column + width).all(|c| {
+                        occupied.get(r as usize).map(|cells| !cells[c as usize]).unwrap_or(true)
+                    })
+                })
+            });
+            if let Some(column) = fit {
+                break (column, row);
+            }
+            row += 1;
+        };
+        let (column, row) = position;
+        while occupied.len() < (row + height) as usize {
+            occupied.push(vec![false; columns as usize]);
+        }
+        for r in row..row + height {
+            for c in column..column + width {
+                occupied[r as usize][c as usize] = true;
+            }
+        }
+        rows = rows.max(row + height);
+        positions.push(position);
+    }
+    (positions, rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tile(path: &str, size: TileSize) -> Tile {
+        Tile { path: PathBuf::from(path), size }
+    }
+
+    fn settings(groups: &[(&str, &[&str])]) -> StartMenuSettings {
+        let mut settings = StartMenuSettings::new();
+        for (name, paths) in groups {
+            let mut group = TileGroup::new(name.to_string());
+            group.tiles = paths.iter().map(|path| tile(path, TileSize::Medium)).collect();
+            settings.groups.push(group);
+        }
+        settings
+    }
+
+    fn paths(settings: &StartMenuSettings, group: usize) -> Vec<&str> {
+        settings.groups[group].tiles.iter().map(|tile| tile.path.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn migrates_v1_tiles_into_a_pinned_group() {
+        let (settings, migrated) = StartMenuSettings::parse(r#"{ "tiles": ["a.lnk", "b.lnk"] }"#).unwrap();
+        assert!(migrated);
+        assert_eq!(settings.version, SETTINGS_VERSION);
+        assert_eq!(settings.groups.len(), 1);
+        assert_eq!(settings.groups[0].name, DEFAULT_GROUP_NAME);
+        assert_eq!(settings.groups[0].tiles, vec![tile("a.lnk", TileSize::Medium), tile("b.lnk", TileSize::Medium)]);
+        assert!(settings.tiles.is_empty());
+
+        let saved = to_string_pretty(&settings).unwrap();
+        let (reloaded, migrated) = StartMenuSettings::parse(&saved).unwrap();
+        assert!(!migrated);
+        assert_eq!(reloaded.groups, settings.groups);
+    }
+
+    #[test]
+    fn packs_tiles_first_fit() {
+        let tiles = [
+            tile("wide", TileSize::Wide),
+            tile("medium", TileSize::Medium),
+            tile("small", TileSize::Small),
+            tile("large", TileSize::Large),
+            tile("small2", TileSize::Small),
+        ];
+        let (positions, rows) = pack_tiles(&tiles, TILE_COLUMNS);
+        // The small tiles fill holes left beside bigger ones
+        assert_eq!(positions, vec![(0, 0), (4, 0), (0, 2), (1, 2), (5, 2)]);
+        assert_eq!(rows, 6);
+        assert_eq!(pack_tiles(&[], TILE_COLUMNS), (vec![], 0));
+        // Tiles wider than the grid get clamped instead of never fitting
+        assert_eq!(pack_tiles(&[tile("large", TileSize::Large)], 2), (vec![(0, 0)], 4));
+    }
+
+    #[test]
+    fn moves_tiles_within_and_across_groups() {
+        let mut settings = settings(&[("One", &["a", "b", "c"]), ("Two", &["d"])]);
+        settings.move_tile((0, 0), 0, Some(2));
+        assert_eq!(paths(&settings, 0), vec!["b", "a", "c"]);
+        settings.move_tile((0, 2), 0, Some(0));
+        assert_eq!(paths(&settings, 0), vec!["c", "b", "a"]);
+        settings.move_tile((0, 1), 1, Some(0));
+        assert_eq!(paths(&settings, 0), vec!["c", "a"]);
+        assert_eq!(paths(&settings, 1), vec!["b", "d"]);
+        settings.move_tile((1, 1), 0, None);
+        assert_eq!(paths(&settings, 0), vec!["c", "a", "d"]);
+        assert_eq!(paths(&settings, 1), vec!["b"]);
+        // Out of range sources and targets are ignored
+        settings.move_tile((1, 5), 0, None);
+        settings.move_tile((0, 0), 7, None);
+        assert_eq!(paths(&settings, 0), vec!["c", "a", "d"]);
+        assert_eq!(paths(&settings, 1), vec!["b"]);
+    }
+
+    #[test]
+    fn moves_groups() {
+        let mut settings = settings(&[("One", &[]), ("Two", &[]), ("Three", &[])]);
+        let names = |settings: &StartMenuSettings| settings.groups.iter().map(|group| group.name.clone()).collect::<Vec<_>>();
+        settings.move_group(0, 2);
+        assert_eq!(names(&settings), vec!["Two", "Three", "One"]);
+        settings.move_group(2, 0);
+        assert_eq!(names(&settings), vec!["One", "Two", "Three"]);
+        settings.move_group(1, 3);
+        assert_eq!(names(&settings), vec!["One", "Two", "Three"]);
+    }
+}
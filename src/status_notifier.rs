@@ -0,0 +1,619 @@
+use crate::icon_theme::{load_icon, system_icon_lookup, ICON_EXTENSIONS};
+use crate::tray::{TrayCommand, TrayItem, TrayMenuItem, TrayStatus};
+use iced::widget::image::Handle;
+use std::collections::HashMap;
+use std::path::Path;
+use zbus::blocking::fdo::DBusProxy;
+use zbus::blocking::{proxy, Connection, MessageIterator, Proxy};
+use zbus::fdo::RequestNameFlags;
+use zbus::message::{Header, Type};
+use zbus::names::BusName;
+use zbus::object_server::SignalEmitter;
+use zbus::proxy::CacheProperties;
+use zbus::zvariant::{ObjectPath, OwnedValue, Value};
+use zbus::{interface, MatchRule};
+
+const WATCHER_NAME: &str = "org.kde.StatusNotifierWatcher";
+const WATCHER_PATH: &str = "/StatusNotifierWatcher";
+const WATCHER: &str = "org.kde.StatusNotifierWatcher";
+const ITEM: &str = "org.kde.StatusNotifierItem";
+// Where items registered by bus name alone keep their object
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const MENU: &str = "com.canonical.dbusmenu";
+const PROPERTIES: &str = "org.freedesktop.DBus.Properties";
+
+const RULES: [&str; 3] = [
+    "type='signal',sender='org.freedesktop.DBus',interface='org.freedesktop.DBus',member='NameOwnerChanged'",
+    "type='signal',interface='org.kde.StatusNotifierWatcher'",
+    "type='signal',interface='org.kde.StatusNotifierItem'",
+];
+
+/// What GetLayout answers with: the layout revision, then the root entry's id, properties and children.
+type Layout = (u32, (i32, HashMap<String, OwnedValue>, Vec<OwnedValue>));
+
+/// The system tray through the StatusNotifierItem protocol that KDE, GNOME's AppIndicator extension and
+/// most tray applications on Linux speak, with item menus exported as DBusMenu.
+pub struct StatusNotifier {
+    connection: Connection,
+}
+impl StatusNotifier {
+    pub fn open() -> Result<Self, String> {
+        match Connection::session() {
+            Ok(connection) => Ok(Self::with_connection(connection)),
+            Err(e) => Err(format!("Could not reach the session bus: {}", e)),
+        }
+    }
+
+    pub fn with_connection(connection: Connection) -> Self {
+        Self { connection }
+    }
+
+    fn proxy(&self, destination: &str, path: &str, interface: &'static str) -> zbus::Result<Proxy<'static>> {
+        // Items signal their changes without the properties, so a cache would never be filled
+        proxy::Builder::new(&self.connection)
+            .destination(destination.to_string())?
+            .path(path.to_string())?
+            .interface(interface)?
+            .cache_properties(CacheProperties::No)
+            .build()
+    }
+
+    /// Reads an item registered as "bus name/object path".
+    fn item(&self, id: &str) -> zbus::Result<TrayItem> {
+        let (destination, path) = split_id(id);
+        let properties: HashMap<String, OwnedValue> = self.proxy(destination, path, PROPERTIES)?.call("GetAll", &(ITEM,))?;
+        let text = |name: &str| properties.get(name).and_then(|value| value.downcast_ref::<&str>().ok()).map(str::to_string).unwrap_or_default();
+        let theme_path = text("IconThemePath");
+        let (tooltip_title, tooltip_body) = properties.get("ToolTip")
+            .and_then(|tooltip| <(String, Vec<(i32, i32, Vec<u8>)>, String, String)>::try_from(tooltip.try_clone().ok()?).ok())
+            .map(|(_, _, title, body)| (title, body))
+            .unwrap_or_default();
+        let title = match text("Title") {
+            title if title.is_empty() => text("Id"),
+            title => title,
+        };
+        Ok(TrayItem {
+            id: id.to_string(),
+            tooltip_title: if tooltip_title.is_empty() { title.clone() } else { tooltip_title },
+            tooltip_body,
+            title,
+            icon: icon(&properties, "IconName", "IconPixmap", &theme_path),
+            attention_icon: icon(&properties, "AttentionIconName", "AttentionIconPixmap", &theme_path),
+            status: match text("Status").as_str() {
+                "Passive" => TrayStatus::Passive,
+                "NeedsAttention" => TrayStatus::NeedsAttention,
+                _ => TrayStatus::Active,
+            },
+            menu: properties.get("Menu")
+                .and_then(|menu| menu.downcast_ref::<ObjectPath>().ok())
+                .map(|menu| menu.to_string())
+                .filter(|menu| menu != "/"),
+            item_is_menu: properties.get("ItemIsMenu").and_then(|value| value.downcast_ref::<bool>().ok()).unwrap_or(false),
+        })
+    }
+
+    pub fn command(&self, id: &str, command: TrayCommand) -> zbus::Result<()> {
+        let (destination, path) = split_id(id);
+        let item = self.proxy(destination, path, ITEM)?;
+        // The taskbar does not know where on screen the icon is, items that place a window by it get the corner
+        match command {
+            TrayCommand::Activate => item.call("Activate", &(0i32, 0i32)),
+            TrayCommand::SecondaryActivate => item.call("SecondaryActivate", &(0i32, 0i32)),
+            TrayCommand::ContextMenu => item.call("ContextMenu", &(0i32, 0i32)),
+            TrayCommand::Scroll(delta) => item.call("Scroll", &(delta, "vertical")),
+        }
+    }
+
+    /// Reads the whole menu, submenus included.
+    pub fn menu(&self, id: &str, menu: &str) -> zbus::Result<Vec<TrayMenuItem>> {
+        let (destination, _) = split_id(id);
+        let menu = self.proxy(destination, menu, MENU)?;
+        // Lets applications that fill their menu on demand do so, older ones do not have it
+        let _ = menu.call::<_, _, bool>("AboutToShow", &(0i32,));
+        let (_, (_, _, children)): Layout = menu.call("GetLayout", &(0i32, -1i32, Vec::<&str>::new()))?;
+        Ok(children.iter().filter_map(|child| menu_item(child)).collect())
+    }
+
+    pub fn menu_clicked(&self, id: &str, menu: &str, entry: i32) -> zbus::Result<()> {
+        let (destination, _) = split_id(id);
+        self.proxy(destination, menu, MENU)?.call("Event", &(entry, "clicked", Value::from(0i32), 0u32))
+    }
+
+    /// Becomes the watcher items register with, unless another one already runs, like the one in a KDE session.
+    /// Returns whether it did.
+    fn serve_watcher(&self) -> zbus::Result<bool> {
+        self.connection.object_server().at(WATCHER_PATH, Watcher::default())?;
+        match self.connection.request_name_with_flags(WATCHER_NAME, RequestNameFlags::DoNotQueue.into()) {
+            Ok(_) => Ok(true),
+            Err(zbus::Error::NameTaken) => {
+                self.connection.object_server().remove::<Watcher, _>(WATCHER_PATH)?;
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Registers as the host showing the items, which is how items learn there is a tray to appear in.
+    fn register_host(&self) -> zbus::Result<()> {
+        let host = format!("org.kde.StatusNotifierHost-{}", std::process::id());
+        self.connection.request_name_with_flags(host.as_str(), RequestNameFlags::DoNotQueue.into())?;
+        self.proxy(WATCHER_NAME, WATCHER_PATH, WATCHER)?.call("RegisterStatusNotifierHost", &(host,))
+    }
+
+    /// Brings the items in line with those registered with the watcher, keeping the ones already read.
+    fn sync(&self, items: &mut Vec<(String, TrayItem)>) -> zbus::Result<()> {
+        let registered: Vec<String> = self.proxy(WATCHER_NAME, WATCHER_PATH, WATCHER)?.get_property("RegisteredStatusNotifierItems")?;
+        items.retain(|(_, item)| registered.contains(&item.id));
+        for id in registered {
+            if items.iter().any(|(_, item)| item.id == id) {
+                continue;
+            }
+            match self.owner(&id).and_then(|owner| Ok((owner, self.item(&id)?))) {
+                Ok(item) => items.push(item),
+                Err(e) => {
+                    eprintln!("Error reading tray item {}: {}", id, e);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The unique name an item's signals come from.
+    fn owner(&self, id: &str) -> zbus::Result<String> {
+        let (destination, _) = split_id(id);
+        if destination.starts_with(':') {
+            return Ok(destination.to_string());
+        }
+        Ok(DBusProxy::new(&self.connection)?.get_name_owner(BusName::try_from(destination)?)?.to_string())
+    }
+
+    /// Calls `report` with the items in the order they registered, then again after every change, until it
+    /// returns false.
+    pub fn watch(self, mut report: impl FnMut(Vec<TrayItem>) -> bool) -> Result<(), String> {
+        let dbus = DBusProxy::new(&self.connection).map_err(|e| e.to_string())?;
+        for rule in RULES {
+            let rule = MatchRule::try_from(rule).map_err(|e| e.to_string())?;
+            dbus.add_match_rule(rule).map_err(|e| e.to_string())?;
+        }
+        let messages = MessageIterator::from(&self.connection);
+        let watching = self.serve_watcher().map_err(|e| e.to_string())?;
+        self.register_host().map_err(|e| e.to_string())?;
+        // Items by the unique name of their owner
+        let mut items: Vec<(String, TrayItem)> = Vec::new();
+        self.sync(&mut items).map_err(|e| e.to_string())?;
+        if !report(items.iter().map(|(_, item)| item.clone()).collect()) {
+            return Ok(());
+        }
+        for message in messages {
+            let message = message.map_err(|e| e.to_string())?;
+            if message.message_type() != Type::Signal {
+                continue;
+            }
+            let header = message.header();
+            let sender = header.sender().map(|sender| sender.to_string()).unwrap_or_default();
+            match header.member().map(|member| member.as_str()) {
+                Some("NameOwnerChanged") => {
+                    let Ok((name, _, new_owner)) = message.body().deserialize::<(String, String, String)>() else {
+                        continue;
+                    };
+                    // Either way the watcher's signals bring the items up to date
+                    let result = if new_owner.is_empty() && watching {
+                        self.unregister_items(&name)
+                    } else if name == WATCHER_NAME && !new_owner.is_empty() && !watching {
+                        // Another watcher took over after the last one quit, it needs telling about the host again
+                        self.register_host()
+                    } else {
+                        Ok(())
+                    };
+                    if let Err(e) = result {
+                        eprintln!("Error following tray items: {}", e);
+                    }
+                    continue;
+                }
+                Some("StatusNotifierItemRegistered") | Some("StatusNotifierItemUnregistered") => {
+                    if let Err(e) = self.sync(&mut items) {
+                        eprintln!("Error listing tray items: {}", e);
+                    }
+                }
+                Some("NewTitle") | Some("NewIcon") | Some("NewAttentionIcon") | Some("NewOverlayIcon") | Some("NewToolTip") | Some("NewStatus") | Some("NewMenu") => {
+                    for (owner, item) in items.iter_mut() {
+                        if *owner != sender {
+                            continue;
+                        }
+                        match self.item(&item.id) {
+                            Ok(updated) => *item = updated,
+                            Err(e) => {
+                                eprintln!("Error reading tray item {}: {}", item.id, e);
+                            }
+                        }
+                    }
+                }
+                _ => continue,
+            }
+            if !report(items.iter().map(|(_, item)| item.clone()).collect()) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Drops the items of an application that left the bus from the watcher.
+    fn unregister_items(&self, name: &str) -> zbus::Result<()> {
+        let watcher = self.connection.object_server().interface::<_, Watcher>(WATCHER_PATH)?;
+        let removed = watcher.get_mut().remove_owner(name);
+        for id in removed {
+            zbus::block_on(Watcher::status_notifier_item_unregistered(watcher.signal_emitter(), &id))?;
+        }
+        Ok(())
+    }
+}
+
+/// The registry items announce themselves to and hosts like the taskbar read them from.
+#[derive(Default)]
+struct Watcher {
+    items: Vec<String>,
+    hosts: Vec<String>,
+}
+impl Watcher {
+    /// Forgets the items and hosts of a name that left the bus, returning the items.
+    fn remove_owner(&mut self, name: &str) -> Vec<String> {
+        self.hosts.retain(|host| host != name);
+        let (removed, kept) = self.items.drain(..).partition(|id| split_id(id).0 == name);
+        self.items = kept;
+        removed
+    }
+}
+
+#[interface(name = "org.kde.StatusNotifierWatcher")]
+impl Watcher {
+    /// Items pass the bus name their object is on, or just the object path for the name they call from.
+    async fn register_status_notifier_item(
+        &mut self,
+        service: &str,
+        #[zbus(header)] header: Header<'_>,
+        #[zbus(signal_emitter)] emitter: SignalEmitter<'_>,
+    ) -> zbus::fdo::Result<()> {
+        let Some(sender) = header.sender() else {
+            return Err(zbus::fdo::Error::InvalidArgs("The registration has no sender".to_string()));
+        };
+        let id = if service.starts_with('/') {
+            format!("{}{}", sender, service)
+        } else {
+            format!("{}{}", service, ITEM_PATH)
+        };
+        if !self.items.contains(&id) {
+            self.items.push(id.clone());
+            Self::status_notifier_item_registered(&emitter, &id).await?;
+        }
+        Ok(())
+    }
+
+    async fn register_status_notifier_host(&mut self, service: &str, #[zbus(signal_emitter)] emitter: SignalEmitter<'_>) -> zbus::fdo::Result<()> {
+        if !self.hosts.iter().any(|host| host == service) {
+            self.hosts.push(service.to_string());
+        }
+        Self::status_notifier_host_registered(&emitter).await?;
+        Ok(())
+    }
+
+    #[zbus(property)]
+    fn registered_status_notifier_items(&self) -> Vec<String> {
+        self.items.clone()
+    }
+
+    #[zbus(property)]
+    fn is_status_notifier_host_registered(&self) -> bool {
+        !self.hosts.is_empty()
+    }
+
+    #[zbus(property)]
+    fn protocol_version(&self) -> i32 {
+        0
+    }
+
+    #[zbus(signal)]
+    async fn status_notifier_item_registered(emitter: &SignalEmitter<'_>, service: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn status_notifier_item_unregistered(emitter: &SignalEmitter<'_>, service: &str) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn status_notifier_host_registered(emitter: &SignalEmitter<'_>) -> zbus::Result<()>;
+}
+
+/// Splits "bus name/object path" into the two.
+fn split_id(id: &str) -> (&str, &str) {
+    match id.find('/') {
+        Some(index) => id.split_at(index),
+        None => (id, ITEM_PATH),
+    }
+}
+
+/// The icon named in the theme, or else the largest of the pixmaps.
+fn icon(properties: &HashMap<String, OwnedValue>, name: &str, pixmap: &str, theme_path: &str) -> Option<Handle> {
+    let name = properties.get(name).and_then(|value| value.downcast_ref::<&str>().ok()).unwrap_or_default();
+    if !name.is_empty() {
+        // Applications shipping their own icons point at them with the theme path
+        let bundled = ICON_EXTENSIONS.iter()
+            .map(|extension| Path::new(theme_path).join(format!("{}.{}", name, extension)))
+            .find(|path| !theme_path.is_empty() && path.is_file());
+        if let Some(handle) = bundled.or_else(|| system_icon_lookup().find_icon(name, 48, 1)).and_then(|path| load_icon(&path, 48)) {
+            return Some(handle);
+        }
+    }
+    let pixmaps = <Vec<(i32, i32, Vec<u8>)>>::try_from(properties.get(pixmap)?.try_clone().ok()?).ok()?;
+    let (width, height, data) = pixmaps.into_iter().max_by_key(|(width, _, _)| *width)?;
+    if width <= 0 || height <= 0 || data.len() != width as usize * height as usize * 4 {
+        return None;
+    }
+    // Pixels are ARGB in network byte order
+    let pixels: Vec<u8> = data.chunks_exact(4).flat_map(|pixel| [pixel[1], pixel[2], pixel[3], pixel[0]]).collect();
+    Some(Handle::from_rgba(width as u32, height as u32, pixels))
+}
+
+/// Reads an entry of a DBusMenu layout, with its children, leaving out hidden ones.
+fn menu_item(value: &Value) -> Option<TrayMenuItem> {
+    // Children come wrapped in variants
+    let value = match value {
+        Value::Value(inner) => inner,
+        value => value,
+    };
+    let Value::Structure(structure) = value else {
+        return None;
+    };
+    let [Value::I32(id), Value::Dict(properties), Value::Array(children)] = structure.fields() else {
+        return None;
+    };
+    let property = |name: &str| properties.iter()
+        .find(|(key, _)| matches!(key, Value::Str(key) if key.as_str() == name))
+        .map(|(_, value)| match value {
+            Value::Value(inner) => &**inner,
+            value => value,
+        });
+    let text = |name: &str| match property(name) {
+        Some(Value::Str(text)) => Some(text.as_str()),
+        _ => None,
+    };
+    let flag = |name: &str| match property(name) {
+        Some(Value::Bool(flag)) => *flag,
+        _ => true,
+    };
+    if !flag("visible") {
+        return None;
+    }
+    let checked = match (text("toggle-type"), property("toggle-state")) {
+        (Some("checkmark") | Some("radio"), Some(Value::I32(state))) => Some(*state == 1),
+        (Some("checkmark") | Some("radio"), _) => Some(false),
+        _ => None,
+    };
+    Some(TrayMenuItem {
+        id: *id,
+        label: label(text("label").unwrap_or_default()),
+        enabled: flag("enabled"),
+        separator: text("type") == Some("separator"),
+        checked,
+        children: children.inner().iter().filter_map(menu_item).collect(),
+    })
+}
+
+/// Drops the underscores marking access keys, a doubled one stands for an underscore.
+fn label(text: &str) -> String {
+    let mut label = String::with_capacity(text.len());
+    let mut characters = text.chars().peekable();
+    while let Some(character) = characters.next() {
+        if character == '_' {
+            if characters.peek() == Some(&'_') {
+                characters.next();
+                label.push('_');
+            }
+        } else {
+            label.push(character);
+        }
+    }
+    label
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_bus::{wait_for, TestBus};
+    use std::sync::{mpsc, Arc, Mutex};
+    use std::time::Duration;
+    use zbus::zvariant::Structure;
+
+    /// Icon name, icon pixmaps, title and body.
+    type ToolTip = (String, Vec<(i32, i32, Vec<u8>)>, String, String);
+
+    /// A tray item recording the calls it gets.
+    struct StubItem {
+        title: &'static str,
+        status: &'static str,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+    #[interface(name = "org.kde.StatusNotifierItem")]
+    impl StubItem {
+        fn activate(&self, x: i32, y: i32) {
+            self.calls.lock().unwrap().push(format!("Activate {} {}", x, y));
+        }
+        fn secondary_activate(&self, x: i32, y: i32) {
+            self.calls.lock().unwrap().push(format!("SecondaryActivate {} {}", x, y));
+        }
+        fn scroll(&self, delta: i32, orientation: &str) {
+            self.calls.lock().unwrap().push(format!("Scroll {} {}", delta, orientation));
+        }
+        #[zbus(property)]
+        fn id(&self) -> String {
+            "stub".to_string()
+        }
+        #[zbus(property)]
+        fn title(&self) -> String {
+            self.title.to_string()
+        }
+        #[zbus(property)]
+        fn status(&self) -> String {
+            self.status.to_string()
+        }
+        #[zbus(property)]
+        fn icon_pixmap(&self) -> Vec<(i32, i32, Vec<u8>)> {
+            vec![(1, 1, vec![255, 1, 2, 3]), (2, 1, vec![255, 10, 20, 30, 128, 40, 50, 60])]
+        }
+        #[zbus(property)]
+        fn attention_icon_pixmap(&self) -> Vec<(i32, i32, Vec<u8>)> {
+            // Not width by height by four bytes, so there is no attention icon
+            vec![(2, 2, vec![255, 200, 0, 0])]
+        }
+        #[zbus(property)]
+        fn tool_tip(&self) -> ToolTip {
+            (String::new(), Vec::new(), "Stub tip".to_string(), "Body text".to_string())
+        }
+        #[zbus(property)]
+        fn menu(&self) -> ObjectPath<'static> {
+            ObjectPath::from_static_str_unchecked("/MenuBar")
+        }
+        #[zbus(signal)]
+        async fn new_status(emitter: &SignalEmitter<'_>, status: &str) -> zbus::Result<()>;
+    }
+
+    struct StubMenu {
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+    fn entry(id: i32, properties: Vec<(&str, Value<'static>)>, children: Vec<Value<'static>>) -> Value<'static> {
+        let properties: HashMap<String, Value<'static>> = properties.into_iter().map(|(key, value)| (key.to_string(), value)).collect();
+        Value::from(Structure::from((id, properties, children)))
+    }
+    #[interface(name = "com.canonical.dbusmenu")]
+    impl StubMenu {
+        fn about_to_show(&self, id: i32) -> bool {
+            self.calls.lock().unwrap().push(format!("AboutToShow {}", id));
+            false
+        }
+        fn get_layout(&self, parent: i32, depth: i32, _properties: Vec<String>) -> Layout {
+            self.calls.lock().unwrap().push(format!("GetLayout {} {}", parent, depth));
+            let children = vec![
+                entry(1, vec![("label", Value::from("_Open"))], vec![]),
+                entry(2, vec![("type", Value::from("separator"))], vec![]),
+                entry(3, vec![("label", Value::from("Show __hidden")), ("toggle-type", Value::from("checkmark")), ("toggle-state", Value::from(1i32))], vec![]),
+                entry(4, vec![("label", Value::from("Gone")), ("visible", Value::from(false))], vec![]),
+                entry(5, vec![("label", Value::from("More")), ("children-display", Value::from("submenu"))], vec![
+                    entry(6, vec![("label", Value::from("Inner")), ("enabled", Value::from(false)), ("toggle-type", Value::from("radio"))], vec![]),
+                ]),
+            ];
+            (1, (0, HashMap::new(), children.into_iter().map(|child| OwnedValue::try_from(child).unwrap()).collect()))
+        }
+        fn event(&self, id: i32, event_id: &str, _data: Value<'_>, _timestamp: u32) {
+            self.calls.lock().unwrap().push(format!("Event {} {}", id, event_id));
+        }
+    }
+
+    fn serve_item(bus: &TestBus, title: &'static str, calls: &Arc<Mutex<Vec<String>>>) -> Connection {
+        let connection = bus.connect();
+        connection.object_server().at(ITEM_PATH, StubItem { title, status: "Active", calls: calls.clone() }).unwrap();
+        connection.object_server().at("/MenuBar", StubMenu { calls: calls.clone() }).unwrap();
+        connection
+    }
+
+    fn register(connection: &Connection, service: &str) {
+        Proxy::new(connection, WATCHER_NAME, WATCHER_PATH, WATCHER).unwrap()
+            .call::<_, _, ()>("RegisterStatusNotifierItem", &(service,)).unwrap();
+    }
+
+    #[test]
+    fn hosts_items_registered_by_name_and_by_path() {
+        let bus = TestBus::start();
+        let (sender, reports) = mpsc::channel::<Vec<TrayItem>>();
+        let host = StatusNotifier::with_connection(bus.connect());
+        std::thread::spawn(move || host.watch(|items| sender.send(items).is_ok()));
+        assert!(reports.recv_timeout(Duration::from_secs(5)).unwrap().is_empty());
+        let latest = |count: usize| wait_for("the tray items", || reports.recv_timeout(Duration::from_millis(100)).ok().filter(|items| items.len() == count));
+
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let named = serve_item(&bus, "Named", &calls);
+        named.request_name("org.example.Stub").unwrap();
+        register(&named, "org.example.Stub");
+        latest(1);
+        let by_path = serve_item(&bus, "By path", &calls);
+        register(&by_path, ITEM_PATH);
+        let items = latest(2);
+
+        let by_path_id = format!("{}{}", by_path.unique_name().unwrap(), ITEM_PATH);
+        let client = bus.connect();
+        let registered: Vec<String> = Proxy::new(&client, WATCHER_NAME, WATCHER_PATH, WATCHER).unwrap()
+            .get_property("RegisteredStatusNotifierItems").unwrap();
+        assert_eq!(registered, ["org.example.Stub/StatusNotifierItem".to_string(), by_path_id.clone()]);
+        let host_registered: bool = Proxy::new(&client, WATCHER_NAME, WATCHER_PATH, WATCHER).unwrap()
+            .get_property("IsStatusNotifierHostRegistered").unwrap();
+        assert!(host_registered);
+
+        assert_eq!(items.iter().map(|item| item.id.as_str()).collect::<Vec<&str>>(), ["org.example.Stub/StatusNotifierItem", by_path_id.as_str()]);
+        let item = &items[0];
+        assert_eq!(item.title, "Named");
+        assert_eq!((item.tooltip_title.as_str(), item.tooltip_body.as_str()), ("Stub tip", "Body text"));
+        assert_eq!(item.status, TrayStatus::Active);
+        assert_eq!(item.menu.as_deref(), Some("/MenuBar"));
+        assert!(!item.item_is_menu);
+        // The largest pixmap, turned from ARGB into RGBA
+        let Some(Handle::Rgba { width: 2, height: 1, pixels, .. }) = item.icon.clone() else {
+            panic!("The icon was not read from the pixmaps: {:?}", item.icon);
+        };
+        assert_eq!(pixels.as_ref(), [10, 20, 30, 255, 40, 50, 60, 128]);
+        assert!(item.attention_icon.is_none());
+        assert_eq!(items[1].title, "By path");
+
+        // Signals from an item bring its properties up to date
+        let interface = named.object_server().interface::<_, StubItem>(ITEM_PATH).unwrap();
+        interface.get_mut().status = "NeedsAttention";
+        zbus::block_on(StubItem::new_status(interface.signal_emitter(), "NeedsAttention")).unwrap();
+        wait_for("the new status", || reports.recv_timeout(Duration::from_millis(100)).ok()
+            .filter(|items| items.first().is_some_and(|item| item.status == TrayStatus::NeedsAttention)));
+
+        // An item leaving the bus leaves the tray
+        drop(by_path);
+        let items = latest(1);
+        assert_eq!(items[0].id, "org.example.Stub/StatusNotifierItem");
+        let registered: Vec<String> = Proxy::new(&client, WATCHER_NAME, WATCHER_PATH, WATCHER).unwrap()
+            .get_property("RegisteredStatusNotifierItems").unwrap();
+        assert_eq!(registered, ["org.example.Stub/StatusNotifierItem"]);
+    }
+
+    #[test]
+    fn reads_menus_and_sends_commands() {
+        let bus = TestBus::start();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let item = serve_item(&bus, "Item", &calls);
+        let id = format!("{}{}", item.unique_name().unwrap(), ITEM_PATH);
+        let host = StatusNotifier::with_connection(bus.connect());
+
+        let menu = host.menu(&id, "/MenuBar").unwrap();
+        let plain = |id: i32, label: &str| TrayMenuItem { id, label: label.to_string(), enabled: true, separator: false, checked: None, children: Vec::new() };
+        assert_eq!(menu, [
+            plain(1, "Open"),
+            TrayMenuItem { separator: true, ..plain(2, "") },
+            TrayMenuItem { checked: Some(true), ..plain(3, "Show _hidden") },
+            TrayMenuItem {
+                children: vec![TrayMenuItem { enabled: false, checked: Some(false), ..plain(6, "Inner") }],
+                ..plain(5, "More")
+            },
+        ]);
+        host.menu_clicked(&id, "/MenuBar", 3).unwrap();
+        host.command(&id, TrayCommand::Activate).unwrap();
+        host.command(&id, TrayCommand::SecondaryActivate).unwrap();
+        host.command(&id, TrayCommand::Scroll(-120)).unwrap();
+        assert_eq!(*calls.lock().unwrap(), [
+            "AboutToShow 0",
+            "GetLayout 0 -1",
+            "Event 3 clicked",
+            "Activate 0 0",
+            "SecondaryActivate 0 0",
+            "Scroll -120 vertical",
+        ]);
+    }
+
+    #[test]
+    fn splits_item_ids() {
+        assert_eq!(split_id(":1.42/StatusNotifierItem"), (":1.42", "/StatusNotifierItem"));
+        assert_eq!(split_id("org.example.Stub/org/ayatana/NotificationItem/stub"), ("org.example.Stub", "/org/ayatana/NotificationItem/stub"));
+        assert_eq!(split_id("org.example.Stub"), ("org.example.Stub", ITEM_PATH));
+        assert_eq!(label("_File"), "File");
+        assert_eq!(label("Save __as"), "Save _as");
+    }
+}
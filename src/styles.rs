@@ -37,6 +37,10 @@ pub fn transparent_button(_theme: &Theme, status: button::Status) -> button::Sty
         },
     }
 }
+// Keyboard selection looks the same as hovering with the mouse
+pub fn selected_button(theme: &Theme, _status: button::Status) -> button::Style {
+    transparent_button(theme, button::Status::Hovered)
+}
 pub fn colored_button(_theme: &Theme, status: button::Status, color: Color) -> button::Style {
     let base = button::Style {
         background: Some(Background::Color(color)),
@@ -124,4 +128,14 @@ pub fn window_style(theme: &Theme) -> container::Style {
         },
         snap: true,
     }
-}
\ No newline at end of file
+}
+// A window that needs attention, like a critical notification
+pub fn urgent_window_style(theme: &Theme) -> container::Style {
+    container::Style {
+        border: Border {
+            color: Color::from_rgb(0.9, 0.4, 0.4),
+            ..window_style(theme).border
+        },
+        ..window_style(theme)
+    }
+}
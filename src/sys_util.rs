@@ -1,15 +1,46 @@
 use battery::*;
-use windows::core::Result;
-use windows::Win32::Foundation::{ERROR_BUFFER_OVERFLOW, ERROR_SUCCESS, HANDLE, LUID};
+use crate::do_not_disturb::DoNotDisturbReason;
+use crate::media::NowPlaying;
+#[cfg(windows)]
+use crate::audio_devices::{AudioDevice, DeviceDirection};
+#[cfg(windows)]
+use crate::bluetooth::{sort_devices, BluetoothDevice, BluetoothState};
+#[cfg(windows)]
+use crate::mixer::AudioStream;
+#[cfg(windows)]
+use crate::wifi::{merge_networks, WifiNetwork, WifiSecurity, WifiState};
+#[cfg(windows)]
+use std::ffi::c_void;
+#[cfg(windows)]
+use windows::core::{Interface, Result, BOOL, GUID, HRESULT, HSTRING, PCWSTR, PWSTR};
+#[cfg(windows)]
+use windows::Devices::Radios::{Radio, RadioAccessStatus, RadioKind, RadioState};
+#[cfg(windows)]
+use windows::Win32::Devices::Bluetooth::*;
+#[cfg(windows)]
+use windows::Win32::Devices::FunctionDiscovery::PKEY_Device_FriendlyName;
+#[cfg(windows)]
+use windows::Win32::Foundation::{CloseHandle, E_INVALIDARG, ERROR_BUFFER_OVERFLOW, ERROR_NOT_FOUND, ERROR_SUCCESS, HANDLE, LUID, PROPERTYKEY, S_OK, WIN32_ERROR};
+#[cfg(windows)]
 use windows::Win32::Media::Audio::{Endpoints::*, *};
+#[cfg(windows)]
 use windows::Win32::NetworkManagement::IpHelper::{GetAdaptersAddresses, GAA_FLAG_SKIP_ANYCAST, GAA_FLAG_SKIP_DNS_SERVER, GAA_FLAG_SKIP_MULTICAST, IF_TYPE_ETHERNET_CSMACD, IP_ADAPTER_ADDRESSES_LH};
+#[cfg(windows)]
 use windows::Win32::NetworkManagement::Ndis::*;
+#[cfg(windows)]
 use windows::Win32::NetworkManagement::WiFi::*;
+#[cfg(windows)]
 use windows::Win32::Networking::WinSock::AF_UNSPEC;
+#[cfg(windows)]
 use windows::Win32::System::Com::*;
+#[cfg(windows)]
+use windows::Win32::System::Com::StructuredStorage::{PropVariantClear, PropVariantToStringAlloc, PROPVARIANT};
+#[cfg(windows)]
 use windows::Win32::Security::{AdjustTokenPrivileges, LookupPrivilegeValueW, SE_PRIVILEGE_ENABLED, TOKEN_ADJUST_PRIVILEGES, TOKEN_PRIVILEGES, TOKEN_QUERY};
+#[cfg(windows)]
 use windows::Win32::System::Shutdown::{ExitWindowsEx, InitiateSystemShutdownExA, EWX_LOGOFF, SHTDN_REASON_FLAG_PLANNED, SHTDN_REASON_MINOR_NONE};
-use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+#[cfg(windows)]
+use windows::Win32::System::Threading::{GetCurrentProcess, GetCurrentProcessId, OpenProcess, OpenProcessToken, QueryFullProcessImageNameW, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION};
 
 pub fn get_battery_info() -> battery::Result<(f32, bool)> {
     let manager = Manager::new()?;
@@ -24,7 +55,7 @@ pub fn get_battery_info() -> battery::Result<(f32, bool)> {
     Ok((0.0, false))
 }
 
-#[derive(Debug,Clone)]
+#[derive(Debug,Clone,PartialEq)]
 pub enum WifiStatus {
     Disconnected,
     Connected(
@@ -34,6 +65,18 @@ pub enum WifiStatus {
     Ethernet,
 }
 
+/// What the taskbar and panel menu show about the machine.
+pub struct SystemStatus<'a> {
+    pub battery: Option<(f32, bool)>,
+    pub wifi_status: WifiStatus,
+    pub system_volume: f32,
+    pub volume_muted: bool,
+    pub microphone_in_use: bool,
+    pub now_playing: Option<&'a NowPlaying>,
+    pub do_not_disturb: Option<DoNotDisturbReason>,
+}
+
+#[cfg(windows)]
 pub fn get_wifi_status() -> WifiStatus {
     let wifi_status = unsafe {
         let mut handle = HANDLE::default();
@@ -105,6 +148,7 @@ pub fn get_wifi_status() -> WifiStatus {
         wifi_status
     }
 }
+#[cfg(windows)]
 fn get_ethernet_status() -> bool {
     unsafe {
         let mut dw_size = 15000; // Recommended initial buffer size (15KB)
@@ -147,6 +191,252 @@ fn get_ethernet_status() -> bool {
         false
     }
 }
+
+/// An open WLAN client handle and the first wireless interface, closed again when dropped.
+#[cfg(windows)]
+struct WlanSession {
+    handle: HANDLE,
+    interface: GUID,
+}
+#[cfg(windows)]
+impl WlanSession {
+    fn open() -> std::result::Result<Self, String> {
+        unsafe {
+            let mut handle = HANDLE::default();
+            let mut version = 0;
+            wlan_result(WlanOpenHandle(2, None, &mut version, &mut handle))?;
+            let mut list: *mut WLAN_INTERFACE_INFO_LIST = std::ptr::null_mut();
+            if let Err(e) = wlan_result(WlanEnumInterfaces(handle, None, &mut list)) {
+                WlanCloseHandle(handle, None);
+                return Err(e);
+            }
+            let interface = if (*list).dwNumberOfItems > 0 {
+                Some((*list).InterfaceInfo[0].InterfaceGuid)
+            } else {
+                None
+            };
+            WlanFreeMemory(list as *mut _);
+            match interface {
+                Some(interface) => Ok(Self { handle, interface }),
+                None => {
+                    WlanCloseHandle(handle, None);
+                    Err("No Wi-Fi adapter found".to_string())
+                }
+            }
+        }
+    }
+
+    /// Visible networks, one entry per network and profile as the WLAN service reports them.
+    fn available_networks(&self) -> std::result::Result<Vec<(WifiNetwork, Option<String>)>, String> {
+        unsafe {
+            let mut list: *mut WLAN_AVAILABLE_NETWORK_LIST = std::ptr::null_mut();
+            wlan_result(WlanGetAvailableNetworkList(self.handle, &self.interface, 0, None, &mut list))?;
+            let entries = std::slice::from_raw_parts((*list).Network.as_ptr(), (*list).dwNumberOfItems as usize);
+            let networks = entries.iter().map(|entry| {
+                let ssid = String::from_utf8_lossy(&entry.dot11Ssid.ucSSID[..entry.dot11Ssid.uSSIDLength as usize]).to_string();
+                let known = entry.dwFlags & WLAN_AVAILABLE_NETWORK_HAS_PROFILE != 0;
+                let profile = if known {
+                    let length = entry.strProfileName.iter().position(|c| *c == 0).unwrap_or(entry.strProfileName.len());
+                    Some(String::from_utf16_lossy(&entry.strProfileName[..length]))
+                } else {
+                    None
+                };
+                (WifiNetwork {
+                    ssid,
+                    signal: entry.wlanSignalQuality,
+                    security: wlan_security(entry.dot11DefaultAuthAlgorithm, entry.bSecurityEnabled.as_bool()),
+                    known,
+                    connected: entry.dwFlags & WLAN_AVAILABLE_NETWORK_CONNECTED != 0,
+                }, profile)
+            }).collect();
+            WlanFreeMemory(list as *mut _);
+            Ok(networks)
+        }
+    }
+
+    /// Profile names saved for an SSID.
+    fn profiles_for(&self, ssid: &str) -> std::result::Result<Vec<String>, String> {
+        let mut profiles: Vec<String> = self.available_networks()?
+            .into_iter()
+            .filter(|(network, _)| network.ssid == ssid)
+            .filter_map(|(_, profile)| profile)
+            .collect();
+        profiles.sort();
+        profiles.dedup();
+        Ok(profiles)
+    }
+
+    fn radio_state(&self) -> std::result::Result<Vec<WLAN_PHY_RADIO_STATE>, String> {
+        unsafe {
+            let mut data_size = 0;
+            let mut data = std::ptr::null_mut();
+            wlan_result(WlanQueryInterface(self.handle, &self.interface, wlan_intf_opcode_radio_state, None, &mut data_size, &mut data, None))?;
+            let radio_state = &*(data as *const WLAN_RADIO_STATE);
+            let phys = radio_state.PhyRadioState[..radio_state.dwNumberOfPhys as usize].to_vec();
+            WlanFreeMemory(data);
+            Ok(phys)
+        }
+    }
+}
+#[cfg(windows)]
+impl Drop for WlanSession {
+    fn drop(&mut self) {
+        unsafe {
+            WlanCloseHandle(self.handle, None);
+        }
+    }
+}
+
+#[cfg(windows)]
+fn wlan_result(code: u32) -> std::result::Result<(), String> {
+    if code == ERROR_SUCCESS.0 {
+        Ok(())
+    } else {
+        Err(windows::core::Error::from_hresult(WIN32_ERROR(code).to_hresult()).message())
+    }
+}
+
+#[cfg(windows)]
+fn wlan_security(algorithm: DOT11_AUTH_ALGORITHM, security_enabled: bool) -> WifiSecurity {
+    match algorithm {
+        DOT11_AUTH_ALGO_WPA_PSK | DOT11_AUTH_ALGO_RSNA_PSK => WifiSecurity::WpaPersonal,
+        DOT11_AUTH_ALGO_WPA3_SAE => WifiSecurity::Wpa3Personal,
+        DOT11_AUTH_ALGO_WPA | DOT11_AUTH_ALGO_RSNA | DOT11_AUTH_ALGO_WPA3 | DOT11_AUTH_ALGO_WPA3_ENT => WifiSecurity::Enterprise,
+        DOT11_AUTH_ALGO_80211_SHARED_KEY => WifiSecurity::Wep,
+        _ if security_enabled && algorithm == DOT11_AUTH_ALGO_80211_OPEN => WifiSecurity::Wep,
+        _ => WifiSecurity::Open,
+    }
+}
+
+#[cfg(windows)]
+pub fn scan_wifi_networks() -> std::result::Result<WifiState, String> {
+    let session = WlanSession::open()?;
+    // The scan finishes in the background, the list below holds the results of the previous one until then
+    unsafe {
+        WlanScan(session.handle, &session.interface, None, None, None);
+    }
+    let radio_enabled = session.radio_state()?.iter().any(|phy| phy.dot11SoftwareRadioState == dot11_radio_state_on && phy.dot11HardwareRadioState == dot11_radio_state_on);
+    let networks = session.available_networks()?.into_iter().map(|(network, _)| network).collect();
+    Ok(WifiState { radio_enabled, networks: merge_networks(networks) })
+}
+
+/// Connects with the saved profile, or saves a new one for networks without a profile or when a password is given.
+#[cfg(windows)]
+pub fn connect_wifi_network(ssid: &str, security: WifiSecurity, password: Option<&str>) -> std::result::Result<(), String> {
+    let session = WlanSession::open()?;
+    let profile = match (session.profiles_for(ssid)?.into_iter().next(), password) {
+        (Some(profile), None) => profile,
+        (profile, password) => {
+            let name = profile.unwrap_or_else(|| ssid.to_string());
+            let profile_xml = HSTRING::from(wlan_profile_xml(&name, ssid, security, password)?);
+            let mut reason = 0;
+            unsafe {
+                // Overwrites a saved profile of the same name, which is how a new password is stored
+                wlan_result(WlanSetProfile(session.handle, &session.interface, 0, &profile_xml, PCWSTR::null(), true, None, &mut reason))?;
+            }
+            name
+        }
+    };
+    let profile = HSTRING::from(profile);
+    let parameters = WLAN_CONNECTION_PARAMETERS {
+        wlanConnectionMode: wlan_connection_mode_profile,
+        strProfile: PCWSTR(profile.as_ptr()),
+        pDot11Ssid: std::ptr::null_mut(),
+        pDesiredBssidList: std::ptr::null_mut(),
+        dot11BssType: dot11_BSS_type_any,
+        dwFlags: 0,
+    };
+    unsafe {
+        wlan_result(WlanConnect(session.handle, &session.interface, &parameters, None))
+    }
+}
+
+#[cfg(windows)]
+pub fn disconnect_wifi() -> std::result::Result<(), String> {
+    let session = WlanSession::open()?;
+    unsafe {
+        wlan_result(WlanDisconnect(session.handle, &session.interface, None))
+    }
+}
+
+#[cfg(windows)]
+pub fn forget_wifi_network(ssid: &str) -> std::result::Result<(), String> {
+    let session = WlanSession::open()?;
+    for profile in session.profiles_for(ssid)? {
+        unsafe {
+            wlan_result(WlanDeleteProfile(session.handle, &session.interface, &HSTRING::from(profile), None))?;
+        }
+    }
+    Ok(())
+}
+
+/// Turns the software radio switch of every PHY on the adapter on or off, like the Wi-Fi button in the action center.
+#[cfg(windows)]
+pub fn set_wifi_radio(enabled: bool) -> std::result::Result<(), String> {
+    let session = WlanSession::open()?;
+    for phy in session.radio_state()? {
+        let state = WLAN_PHY_RADIO_STATE {
+            dwPhyIndex: phy.dwPhyIndex,
+            dot11SoftwareRadioState: if enabled { dot11_radio_state_on } else { dot11_radio_state_off },
+            dot11HardwareRadioState: phy.dot11HardwareRadioState,
+        };
+        unsafe {
+            wlan_result(WlanSetInterface(
+                session.handle,
+                &session.interface,
+                wlan_intf_opcode_radio_state,
+                std::mem::size_of::<WLAN_PHY_RADIO_STATE>() as u32,
+                &state as *const _ as *const c_void,
+                None,
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn wlan_profile_xml(name: &str, ssid: &str, security: WifiSecurity, password: Option<&str>) -> std::result::Result<String, String> {
+    let (authentication, encryption, key_type) = match security {
+        WifiSecurity::Open => ("open", "none", None),
+        WifiSecurity::Wep => ("open", "WEP", Some("networkKey")),
+        WifiSecurity::WpaPersonal => ("WPA2PSK", "AES", Some("passPhrase")),
+        WifiSecurity::Wpa3Personal => ("WPA3SAE", "AES", Some("passPhrase")),
+        WifiSecurity::Enterprise => return Err(format!("{} needs to be set up in the Windows network settings", ssid)),
+    };
+    let shared_key = match (key_type, password) {
+        (Some(key_type), Some(password)) => format!(
+            "<sharedKey><keyType>{}</keyType><protected>false</protected><keyMaterial>{}</keyMaterial></sharedKey>",
+            key_type,
+            xml_escape(password)
+        ),
+        (Some(_), None) => return Err(format!("{} needs a password", ssid)),
+        _ => String::new(),
+    };
+    Ok(format!(
+        "<?xml version=\"1.0\"?>\
+        <WLANProfile xmlns=\"http://www.microsoft.com/networking/WLAN/profile/v1\">\
+        <name>{}</name>\
+        <SSIDConfig><SSID><name>{}</name></SSID></SSIDConfig>\
+        <connectionType>ESS</connectionType>\
+        <connectionMode>auto</connectionMode>\
+        <MSM><security>\
+        <authEncryption><authentication>{}</authentication><encryption>{}</encryption><useOneX>false</useOneX></authEncryption>\
+        {}\
+        </security></MSM>\
+        </WLANProfile>",
+        xml_escape(name),
+        xml_escape(ssid),
+        authentication,
+        encryption,
+        shared_key
+    ))
+}
+
+#[cfg(windows)]
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&apos;")
+}
+#[cfg(windows)]
 pub fn get_sound_state() -> Result<(f32, bool)> {
     unsafe {
         // CoInitializeEx can safely be called multiple times; we ignore errors if already initialized
@@ -169,6 +459,7 @@ pub fn get_sound_state() -> Result<(f32, bool)> {
     }
 }
 
+#[cfg(windows)]
 pub fn set_sound_state(level: f32, mute: bool) -> Result<()> {
     unsafe {
         let level = level.clamp(0.0, 1.0);
@@ -186,7 +477,473 @@ pub fn set_sound_state(level: f32, mute: bool) -> Result<()> {
     }
 }
 
-pub fn windows_power(reboot: bool) -> Result<()> {
+#[cfg(windows)]
+fn audio_sessions() -> Result<IAudioSessionEnumerator> {
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        let enumerator: IMMDeviceEnumerator = CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)?;
+        let device = enumerator.GetDefaultAudioEndpoint(eRender, eConsole)?;
+        let manager: IAudioSessionManager2 = device.Activate(CLSCTX_ALL, None)?;
+        manager.GetSessionEnumerator()
+    }
+}
+
+/// Takes a string the audio service allocated, freeing it.
+#[cfg(windows)]
+unsafe fn take_com_string(text: PWSTR) -> String {
+    unsafe {
+        let value = text.to_string().unwrap_or_default();
+        CoTaskMemFree(Some(text.0 as *const c_void));
+        value
+    }
+}
+
+/// File name of a process' executable without its extension.
+#[cfg(windows)]
+fn process_executable(pid: u32) -> String {
+    unsafe {
+        let Ok(process) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) else {
+            return String::new();
+        };
+        let mut buffer = [0u16; 1024];
+        let mut size = buffer.len() as u32;
+        let result = QueryFullProcessImageNameW(process, PROCESS_NAME_WIN32, PWSTR(buffer.as_mut_ptr()), &mut size);
+        let _ = CloseHandle(process);
+        if result.is_err() {
+            return String::new();
+        }
+        let path = String::from_utf16_lossy(&buffer[..size as usize]);
+        std::path::Path::new(&path).file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default()
+    }
+}
+
+/// Audio sessions of the default output that are playing right now.
+#[cfg(windows)]
+pub fn get_audio_sessions() -> Result<Vec<AudioStream>> {
+    unsafe {
+        let sessions = audio_sessions()?;
+        let mut streams = Vec::new();
+        for index in 0..sessions.GetCount()? {
+            let control = sessions.GetSession(index)?;
+            if control.GetState()? != AudioSessionStateActive {
+                continue;
+            }
+            let control: IAudioSessionControl2 = control.cast()?;
+            let volume: ISimpleAudioVolume = control.cast()?;
+            let id = take_com_string(control.GetSessionInstanceIdentifier()?);
+            let display_name = take_com_string(control.GetDisplayName()?);
+            let system_sounds = control.IsSystemSoundsSession() == S_OK;
+            // Sessions shared by several processes have no single PID
+            let pid = control.GetProcessId().ok().filter(|pid| *pid != 0 && !system_sounds);
+            let executable = pid.map(process_executable).unwrap_or_default();
+            // Names starting with @ point into resource files, like the one of the system sounds session
+            let name = if system_sounds {
+                "System sounds".to_string()
+            } else if !display_name.is_empty() && !display_name.starts_with('@') {
+                display_name
+            } else {
+                executable.clone()
+            };
+            streams.push(AudioStream {
+                id,
+                pid,
+                name,
+                executable,
+                volume: volume.GetMasterVolume()?,
+                muted: volume.GetMute()?.as_bool(),
+            });
+        }
+        Ok(streams)
+    }
+}
+
+#[cfg(windows)]
+pub fn set_audio_session(id: &str, level: f32, mute: bool) -> Result<()> {
+    unsafe {
+        let sessions = audio_sessions()?;
+        for index in 0..sessions.GetCount()? {
+            let control: IAudioSessionControl2 = sessions.GetSession(index)?.cast()?;
+            if take_com_string(control.GetSessionInstanceIdentifier()?) != id {
+                continue;
+            }
+            let volume: ISimpleAudioVolume = control.cast()?;
+            volume.SetMasterVolume(level.clamp(0.0, 1.0), std::ptr::null())?;
+            volume.SetMute(mute, std::ptr::null())?;
+        }
+        Ok(())
+    }
+}
+
+/// The undocumented interface behind the Sound control panel, the only way to change the default device.
+/// The interface macro drops attributes, so the lints for its generated code are allowed on the whole module.
+#[allow(dead_code, non_snake_case)]
+#[cfg(windows)]
+mod policy_config {
+    use super::*;
+
+    // Only SetDefaultEndpoint is called, the other methods are declared to get the vtable layout right
+    #[windows::core::interface("f8679f50-850a-41cf-9c72-430f290290c8")]
+    pub unsafe trait IPolicyConfig: windows::core::IUnknown {
+        fn GetMixFormat(&self, device: PCWSTR, format: *mut *mut WAVEFORMATEX) -> HRESULT;
+        fn GetDeviceFormat(&self, device: PCWSTR, default: BOOL, format: *mut *mut WAVEFORMATEX) -> HRESULT;
+        fn ResetDeviceFormat(&self, device: PCWSTR) -> HRESULT;
+        fn SetDeviceFormat(&self, device: PCWSTR, endpoint_format: *const WAVEFORMATEX, mix_format: *const WAVEFORMATEX) -> HRESULT;
+        fn GetProcessingPeriod(&self, device: PCWSTR, default: BOOL, default_period: *mut i64, minimum_period: *mut i64) -> HRESULT;
+        fn SetProcessingPeriod(&self, device: PCWSTR, period: *const i64) -> HRESULT;
+        fn GetShareMode(&self, device: PCWSTR, mode: *mut c_void) -> HRESULT;
+        fn SetShareMode(&self, device: PCWSTR, mode: *const c_void) -> HRESULT;
+        fn GetPropertyValue(&self, device: PCWSTR, key: *const PROPERTYKEY, value: *mut PROPVARIANT) -> HRESULT;
+        fn SetPropertyValue(&self, device: PCWSTR, key: *const PROPERTYKEY, value: *const PROPVARIANT) -> HRESULT;
+        pub fn SetDefaultEndpoint(&self, device: PCWSTR, role: ERole) -> HRESULT;
+        fn SetEndpointVisibility(&self, device: PCWSTR, visible: BOOL) -> HRESULT;
+    }
+
+    // CPolicyConfigClient
+    pub const POLICY_CONFIG_CLIENT: GUID = GUID::from_u128(0x870af99c_171d_4f9e_af0d_e63df40c2bc9);
+}
+#[cfg(windows)]
+use policy_config::{IPolicyConfig, POLICY_CONFIG_CLIENT};
+
+#[cfg(windows)]
+fn device_enumerator() -> Result<IMMDeviceEnumerator> {
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_ALL)
+    }
+}
+
+#[cfg(windows)]
+fn data_flow(direction: DeviceDirection) -> EDataFlow {
+    match direction {
+        DeviceDirection::Output => eRender,
+        DeviceDirection::Input => eCapture,
+    }
+}
+
+#[cfg(windows)]
+fn device_name(device: &IMMDevice) -> Result<String> {
+    unsafe {
+        let mut value = device.OpenPropertyStore(STGM_READ)?.GetValue(&PKEY_Device_FriendlyName)?;
+        let name = PropVariantToStringAlloc(&value).map(|name| take_com_string(name));
+        let _ = PropVariantClear(&mut value);
+        name
+    }
+}
+
+/// Plugged in and enabled devices of one direction.
+#[cfg(windows)]
+pub fn get_audio_devices(direction: DeviceDirection) -> Result<Vec<AudioDevice>> {
+    unsafe {
+        let enumerator = device_enumerator()?;
+        // There is no default while no device of the kind is plugged in
+        let default = match enumerator.GetDefaultAudioEndpoint(data_flow(direction), eConsole) {
+            Ok(device) => take_com_string(device.GetId()?),
+            Err(_) => String::new(),
+        };
+        let collection = enumerator.EnumAudioEndpoints(data_flow(direction), DEVICE_STATE_ACTIVE)?;
+        let mut devices = Vec::new();
+        for index in 0..collection.GetCount()? {
+            let device = collection.Item(index)?;
+            let id = take_com_string(device.GetId()?);
+            devices.push(AudioDevice {
+                name: device_name(&device).unwrap_or_else(|_| id.clone()),
+                is_default: id == default,
+                id,
+                direction,
+            });
+        }
+        Ok(devices)
+    }
+}
+
+/// Makes the device the default for every role, like the Sound control panel does. Applications following the
+/// default device move over on their own.
+#[cfg(windows)]
+pub fn set_default_audio_device(id: &str) -> Result<()> {
+    unsafe {
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        let policy: IPolicyConfig = CoCreateInstance(&POLICY_CONFIG_CLIENT, None, CLSCTX_ALL)?;
+        let id = HSTRING::from(id);
+        for role in [eConsole, eMultimedia, eCommunications] {
+            policy.SetDefaultEndpoint(PCWSTR(id.as_ptr()), role).ok()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(windows)]
+fn microphone_volume() -> Result<IAudioEndpointVolume> {
+    unsafe {
+        let device = device_enumerator()?.GetDefaultAudioEndpoint(eCapture, eConsole)?;
+        device.Activate(CLSCTX_ALL, None)
+    }
+}
+
+#[cfg(windows)]
+pub fn get_microphone_state() -> Result<(f32, bool)> {
+    unsafe {
+        let volume = microphone_volume()?;
+        Ok((volume.GetMasterVolumeLevelScalar()?, volume.GetMute()?.as_bool()))
+    }
+}
+
+#[cfg(windows)]
+pub fn set_microphone_state(level: f32, mute: bool) -> Result<()> {
+    unsafe {
+        let volume = microphone_volume()?;
+        volume.SetMasterVolumeLevelScalar(level.clamp(0.0, 1.0), std::ptr::null())?;
+        volume.SetMute(mute, std::ptr::null())?;
+        Ok(())
+    }
+}
+
+/// Whether another process has an active session on any microphone. Our own level meter is left out.
+#[cfg(windows)]
+pub fn microphone_in_use() -> Result<bool> {
+    unsafe {
+        let own_pid = GetCurrentProcessId();
+        let collection = device_enumerator()?.EnumAudioEndpoints(eCapture, DEVICE_STATE_ACTIVE)?;
+        for index in 0..collection.GetCount()? {
+            let manager: IAudioSessionManager2 = collection.Item(index)?.Activate(CLSCTX_ALL, None)?;
+            let sessions = manager.GetSessionEnumerator()?;
+            for session in 0..sessions.GetCount()? {
+                let control: IAudioSessionControl2 = sessions.GetSession(session)?.cast()?;
+                if control.GetState()? == AudioSessionStateActive && control.GetProcessId().is_ok_and(|pid| pid != own_pid) {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+}
+
+/// Peak meter of a microphone. The endpoint only measures while something records from it, so a capture
+/// stream is kept running for as long as the meter is open.
+#[cfg(windows)]
+pub struct MicrophoneMeter {
+    client: IAudioClient,
+    meter: IAudioMeterInformation,
+}
+#[cfg(windows)]
+impl MicrophoneMeter {
+    pub fn open(id: &str) -> Result<Self> {
+        unsafe {
+            let device = device_enumerator()?.GetDevice(&HSTRING::from(id))?;
+            let meter: IAudioMeterInformation = device.Activate(CLSCTX_ALL, None)?;
+            let client: IAudioClient = device.Activate(CLSCTX_ALL, None)?;
+            let format = client.GetMixFormat()?;
+            // One second of buffer, which is never read, the samples are only there to be measured
+            let initialized = client.Initialize(AUDCLNT_SHAREMODE_SHARED, 0, 10_000_000, 0, format, None);
+            CoTaskMemFree(Some(format as *const c_void));
+            initialized?;
+            client.Start()?;
+            Ok(Self { client, meter })
+        }
+    }
+
+    pub fn peak(&self) -> Result<f32> {
+        unsafe { self.meter.GetPeakValue() }
+    }
+}
+#[cfg(windows)]
+impl Drop for MicrophoneMeter {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = self.client.Stop();
+        }
+    }
+}
+
+/// The first Bluetooth radio, closed again when dropped.
+#[cfg(windows)]
+struct BluetoothRadio {
+    handle: HANDLE,
+}
+#[cfg(windows)]
+impl BluetoothRadio {
+    fn open() -> Result<Self> {
+        unsafe {
+            let params = BLUETOOTH_FIND_RADIO_PARAMS { dwSize: std::mem::size_of::<BLUETOOTH_FIND_RADIO_PARAMS>() as u32 };
+            let mut handle = HANDLE::default();
+            let find = BluetoothFindFirstRadio(&params, &mut handle)?;
+            let _ = BluetoothFindRadioClose(find);
+            Ok(Self { handle })
+        }
+    }
+
+    /// Remembered and connected devices, and with `inquiry` the ones in range as well. Inquiring blocks for about
+    /// five seconds.
+    fn devices(&self, inquiry: bool) -> Result<Vec<BLUETOOTH_DEVICE_INFO>> {
+        let params = BLUETOOTH_DEVICE_SEARCH_PARAMS {
+            dwSize: std::mem::size_of::<BLUETOOTH_DEVICE_SEARCH_PARAMS>() as u32,
+            fReturnAuthenticated: true.into(),
+            fReturnRemembered: true.into(),
+            fReturnUnknown: inquiry.into(),
+            fReturnConnected: true.into(),
+            fIssueInquiry: inquiry.into(),
+            // In steps of 1.28 seconds
+            cTimeoutMultiplier: if inquiry { 4 } else { 0 },
+            hRadio: self.handle,
+        };
+        let new_info = || BLUETOOTH_DEVICE_INFO { dwSize: std::mem::size_of::<BLUETOOTH_DEVICE_INFO>() as u32, ..Default::default() };
+        let mut devices = Vec::new();
+        unsafe {
+            let mut info = new_info();
+            // Fails when there are no devices at all
+            let Ok(find) = BluetoothFindFirstDevice(&params, &mut info) else {
+                return Ok(devices);
+            };
+            loop {
+                devices.push(info);
+                info = new_info();
+                if BluetoothFindNextDevice(find, &mut info).is_err() {
+                    break;
+                }
+            }
+            BluetoothFindDeviceClose(find)?;
+        }
+        Ok(devices)
+    }
+
+    /// A device by the address it is listed with, searching for it when `inquiry` is set.
+    fn device(&self, id: &str, inquiry: bool) -> Result<BLUETOOTH_DEVICE_INFO> {
+        let address = u64::from_str_radix(&id.replace(':', ""), 16).map_err(|_| windows::core::Error::from_hresult(E_INVALIDARG))?;
+        self.devices(inquiry)?
+            .into_iter()
+            .find(|info| unsafe { info.Address.Anonymous.ullLong } == address)
+            .ok_or_else(|| windows::core::Error::from_hresult(ERROR_NOT_FOUND.to_hresult()))
+    }
+}
+#[cfg(windows)]
+impl Drop for BluetoothRadio {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseHandle(self.handle);
+        }
+    }
+}
+
+/// The radio switch behind the Bluetooth toggle in the Windows quick settings. The classic API only finds the
+/// radio while it is switched on.
+#[cfg(windows)]
+fn bluetooth_switch() -> Result<Option<Radio>> {
+    let radios = Radio::GetRadiosAsync()?.join()?;
+    for index in 0..radios.Size()? {
+        let radio = radios.GetAt(index)?;
+        if radio.Kind()? == RadioKind::Bluetooth {
+            return Ok(Some(radio));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(windows)]
+pub fn get_bluetooth_state(inquiry: bool) -> Result<BluetoothState> {
+    let Some(switch) = bluetooth_switch()? else {
+        return Ok(BluetoothState::default());
+    };
+    if switch.State()? != RadioState::On {
+        return Ok(BluetoothState { available: true, powered: false, devices: Vec::new() });
+    }
+    let mut devices: Vec<BluetoothDevice> = BluetoothRadio::open()?.devices(inquiry)?
+        .iter()
+        .map(|info| {
+            let length = info.szName.iter().position(|c| *c == 0).unwrap_or(info.szName.len());
+            let bytes = unsafe { info.Address.Anonymous.rgBytes };
+            // Stored least significant byte first
+            let address = bytes.iter().rev().map(|byte| format!("{:02X}", byte)).collect::<Vec<String>>().join(":");
+            BluetoothDevice {
+                name: if length > 0 { String::from_utf16_lossy(&info.szName[..length]) } else { address.clone() },
+                id: address,
+                paired: info.fAuthenticated.as_bool() || info.fRemembered.as_bool(),
+                connected: info.fConnected.as_bool(),
+                // Only exposed through the GATT battery service, which would need a connection of our own
+                battery: None,
+            }
+        })
+        .collect();
+    sort_devices(&mut devices);
+    Ok(BluetoothState { available: true, powered: true, devices })
+}
+
+#[cfg(windows)]
+pub fn set_bluetooth_power(powered: bool) -> std::result::Result<(), String> {
+    let switch = bluetooth_switch().map_err(|e| e.message())?.ok_or_else(|| "No Bluetooth adapter found".to_string())?;
+    let access = Radio::RequestAccessAsync().and_then(|access| access.join()).map_err(|e| e.message())?;
+    if access != RadioAccessStatus::Allowed {
+        return Err("Windows does not allow switching Bluetooth on and off".to_string());
+    }
+    let status = switch.SetStateAsync(if powered { RadioState::On } else { RadioState::Off })
+        .and_then(|status| status.join())
+        .map_err(|e| e.message())?;
+    if status != RadioAccessStatus::Allowed {
+        return Err("Windows did not switch Bluetooth".to_string());
+    }
+    Ok(())
+}
+
+/// Turns the profiles of a paired device on or off. Windows connects to a device whenever one of its profiles is
+/// turned on and has no other call for connecting. Turning a profile off removes it from the installed ones, so the
+/// usual audio and input profiles are tried as well when turning them back on.
+#[cfg(windows)]
+pub fn set_bluetooth_connected(id: &str, connected: bool) -> Result<()> {
+    // Serial numbers of the headset, hands-free, audio sink, remote control and input device profiles
+    const PROFILES: [u32; 6] = [0x1108, 0x111E, 0x110B, 0x110E, 0x110C, 0x1124];
+    let radio = BluetoothRadio::open()?;
+    let info = radio.device(id, false)?;
+    let mut services = Vec::new();
+    unsafe {
+        let mut count = 0;
+        // The first call only counts them
+        BluetoothEnumerateInstalledServices(Some(radio.handle), &info, &mut count, None);
+        services.resize(count as usize, GUID::zeroed());
+        if count > 0 {
+            WIN32_ERROR(BluetoothEnumerateInstalledServices(Some(radio.handle), &info, &mut count, Some(services.as_mut_ptr()))).ok()?;
+        }
+        services.truncate(count as usize);
+    }
+    if connected {
+        for profile in PROFILES {
+            // Bluetooth base UUID
+            let service = GUID::from_u128(((profile as u128) << 96) | 0x0000_1000_8000_00805f9b34fb);
+            if !services.contains(&service) {
+                services.push(service);
+            }
+        }
+    }
+    let flags = if connected { BLUETOOTH_SERVICE_ENABLE } else { BLUETOOTH_SERVICE_DISABLE };
+    let mut changed = false;
+    let mut last_error = Ok(());
+    for service in &services {
+        // Devices refuse the profiles they do not have, which only matters when none can be changed
+        match WIN32_ERROR(unsafe { BluetoothSetServiceState(Some(radio.handle), &info, service, flags) }).ok() {
+            Ok(()) => changed = true,
+            Err(e) => last_error = Err(e),
+        }
+    }
+    if changed { Ok(()) } else { last_error }
+}
+
+/// Pairs with a device found by an inquiry. Devices that need a PIN or passkey confirmed get the Windows pairing
+/// dialog.
+#[cfg(windows)]
+pub fn pair_bluetooth_device(id: &str) -> Result<()> {
+    let radio = BluetoothRadio::open()?;
+    let mut info = radio.device(id, true)?;
+    unsafe {
+        WIN32_ERROR(BluetoothAuthenticateDeviceEx(None, Some(radio.handle), &mut info, None, MITMProtectionNotRequired)).ok()?;
+    }
+    set_bluetooth_connected(id, true)
+}
+
+#[cfg(windows)]
+pub fn forget_bluetooth_device(id: &str) -> Result<()> {
+    let info = BluetoothRadio::open()?.device(id, false)?;
+    unsafe { WIN32_ERROR(BluetoothRemoveDevice(&info.Address)).ok() }
+}
+
+#[cfg(windows)]
+pub fn shutdown(reboot: bool) -> Result<()> {
     unsafe {
         // --- Step 1: Get the process token ---
         let mut token_handle = HANDLE::default();
@@ -231,13 +988,42 @@ pub fn windows_power(reboot: bool) -> Result<()> {
     Ok(())
 }
 
+#[cfg(windows)]
 pub fn logoff() -> Result<()> {
     unsafe {
         ExitWindowsEx(EWX_LOGOFF, SHTDN_REASON_MINOR_NONE | SHTDN_REASON_FLAG_PLANNED)
     }
 }
+#[cfg(windows)]
 pub fn lock() -> Result<()> {
     unsafe {
         windows::Win32::System::Shutdown::LockWorkStation()
     }
-}
\ No newline at end of file
+}
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::process::Command;
+
+    fn run(program: &str, args: &[&str]) -> Result<(), String> {
+        let status = Command::new(program).args(args).status().map_err(|e| format!("Error running {}: {}", program, e))?;
+        if !status.success() {
+            return Err(format!("{} {} failed: {}", program, args.join(" "), status));
+        }
+        Ok(())
+    }
+
+    pub fn shutdown(reboot: bool) -> Result<(), String> {
+        run("systemctl", &[if reboot { "reboot" } else { "poweroff" }])
+    }
+
+    pub fn logoff() -> Result<(), String> {
+        let session = std::env::var("XDG_SESSION_ID").map_err(|_| "Not running in a logind session".to_string())?;
+        run("loginctl", &["terminate-session", &session])
+    }
+
+    pub fn lock() -> Result<(), String> {
+        run("loginctl", &["lock-session"])
+    }
+}
+#[cfg(target_os = "linux")]
+pub use linux::*;
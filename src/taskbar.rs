@@ -1,21 +1,28 @@
-use crate::styles::{transparent_button, window_style};
-use crate::sys_util::WifiStatus;
+use crate::media::MediaCommand;
+use crate::styles::{selected_button, transparent_button, window_style};
+use crate::sys_util::{SystemStatus, WifiStatus};
+use crate::tray::{TrayCommand, TrayItem, TrayStatus};
 use crate::Message;
 use base64::Engine;
 use chrono::offset::Local;
 use iced::widget::image::Allocation;
-use iced::widget::{button, column, container, image, row, space, text, tooltip, Button, Column, Row};
-use iced::{window, Alignment, Element, Length, Padding, Point, Size, Task};
+use iced::widget::{button, column, container, image, mouse_area, row, space, text, tooltip, Button, Column, Row};
+use iced::widget::text::Wrapping;
+use iced::{mouse, window, Alignment, Element, Length, Padding, Point, Size, Task};
 use std::collections::BTreeMap;
+#[cfg(windows)]
 use std::ffi::c_void;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use iced::advanced::image::Handle;
+#[cfg(windows)]
 use windows::Win32::Foundation::{HWND, RECT};
+#[cfg(windows)]
 use windows::Win32::UI::Shell::{SHAppBarMessage, ABE_TOP, ABM_NEW, ABM_QUERYPOS, ABM_SETPOS, APPBARDATA};
+#[cfg(windows)]
 use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, GetWindowPlacement, SetForegroundWindow, SetWindowPos, ShowWindow, HWND_NOTOPMOST, SM_CXSCREEN, SWP_NOACTIVATE, SWP_NOMOVE, SWP_NOSIZE, SW_RESTORE, SW_SHOWMINIMIZED, WINDOWPLACEMENT};
 use x_win::{get_open_windows, get_window_icon, WindowInfo};
-use crate::raw_icons::{battery_icon, sound_icon, start_icon, wifi_icon};
+use crate::raw_icons::{battery_icon, do_not_disturb_icon, microphone_icon, sound_icon, start_icon, wifi_icon};
 
 #[derive(Debug, Clone)]
 pub enum TaskbarMessage {
@@ -26,24 +33,40 @@ pub enum TaskbarMessage {
     Allocate(u32,(Option<Allocation>,WindowInfo)),
     None
 }
+/// Icons of the open windows, for views that only know a process, like the volume mixer.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessIcons {
+    by_pid: BTreeMap<u32, Handle>,
+    by_executable: BTreeMap<String, Handle>,
+}
+impl ProcessIcons {
+    /// The icon of a window of the process, or else of a window from the same executable,
+    /// as browsers and others play audio from helper processes without windows.
+    pub fn get(&self, pid: Option<u32>, executable: &str) -> Option<&Handle> {
+        pid.and_then(|pid| self.by_pid.get(&pid))
+            .or_else(|| self.by_executable.get(&executable.to_lowercase()))
+    }
+}
 pub struct Taskbar {
     pub id: window::Id,
     tasks: BTreeMap<u32,(Option<Allocation>,WindowInfo)>,
 }
 impl Taskbar {
     pub fn new() -> (Self,Task<window::Id>) {
-        let mut settings = window::Settings::default();
-        settings.decorations = false;
-        settings.resizable = false;
-        settings.min_size = None;
-        settings.max_size = None;
-        settings.icon = None;
-        settings.transparent = true;
-        settings.closeable = false;
-        settings.minimizable = false;
-        settings.level = window::Level::Normal;
-        settings.size = Size::new(0.0,0.0);
-        settings.position = window::Position::Specific(Point::new(0.0,0.0));
+        let settings = window::Settings {
+            decorations: false,
+            resizable: false,
+            min_size: None,
+            max_size: None,
+            icon: None,
+            transparent: true,
+            closeable: false,
+            minimizable: false,
+            level: window::Level::Normal,
+            size: Size::new(0.0,0.0),
+            position: window::Position::Specific(Point::new(0.0,0.0)),
+            ..window::Settings::default()
+        };
         let (id,open_task) = window::open(settings);
         (Self {
             id,
@@ -60,6 +83,7 @@ impl Taskbar {
                             Ok(window_handle) => {
                                 let raw_handle = window_handle.as_raw();
                                 match raw_handle {
+                                    #[cfg(windows)]
                                     window::raw_window_handle::RawWindowHandle::Win32(handle) => {
                                         let win_handle:HWND = HWND{ 0: handle.hwnd.get() as *mut c_void };
                                         let mut abd = APPBARDATA {
@@ -104,6 +128,18 @@ impl Taskbar {
                                             };
                                         }
                                     }
+                                    #[cfg(target_os = "linux")]
+                                    window::raw_window_handle::RawWindowHandle::Xlib(handle) => {
+                                        if let Err(e) = x11_windows::reserve_top_edge(handle.window as u32) {
+                                            eprintln!("Error reserving the taskbar's space: {}", e);
+                                        }
+                                    }
+                                    #[cfg(target_os = "linux")]
+                                    window::raw_window_handle::RawWindowHandle::Xcb(handle) => {
+                                        if let Err(e) = x11_windows::reserve_top_edge(handle.window.get()) {
+                                            eprintln!("Error reserving the taskbar's space: {}", e);
+                                        }
+                                    }
                                     _ => {}
                                 }
                             }
@@ -128,6 +164,7 @@ impl Taskbar {
                 }
             }
             TaskbarMessage::FocusWindow(window_id) => {
+                #[cfg(windows)]
                 unsafe {
                     let win_handle = HWND(window_id as isize as *mut c_void);
 
@@ -146,6 +183,10 @@ impl Taskbar {
                     // 4. Bring to the foreground
                     let _ = SetForegroundWindow(win_handle);
                 }
+                #[cfg(target_os = "linux")]
+                if let Err(e) = x11_windows::activate(window_id) {
+                    eprintln!("Error focusing window: {}", e);
+                }
                 Task::none()
             }
             TaskbarMessage::Tick => {
@@ -156,7 +197,8 @@ impl Taskbar {
                     Ok(user_windows) => {
                         for window in user_windows {
                             if old_windows.contains_key(&window.id) {
-                                self.tasks.insert(window.id,old_windows[&window.id].clone());
+                                // The icon is kept, the title and position can have changed
+                                self.tasks.insert(window.id,(old_windows[&window.id].0.clone(), window));
                             } else {
                                 match get_window_icon(&window.clone()) {
                                     Ok(window_icon) => {
@@ -205,7 +247,22 @@ impl Taskbar {
             _ => Task::none()
         }
     }
-    pub fn view(&self, app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, start_state: bool, panel_state: bool,base_size: f32,battery: Option<(f32,bool)>,wifi_status: WifiStatus,system_volume: f32,volume_muted: bool) -> Element<'_, Message> {
+    pub fn process_icons(&self) -> ProcessIcons {
+        let mut icons = ProcessIcons::default();
+        for (allocation, window) in self.tasks.values() {
+            if let Some(allocation) = allocation {
+                icons.by_pid.insert(window.info.process_id, allocation.handle().clone());
+                icons.by_executable.insert(window.info.exec_name.to_lowercase(), allocation.handle().clone());
+            }
+        }
+        icons
+    }
+    /// Whether a window of another application covers the whole screen, like games and presentations.
+    pub fn full_screen_window(&self) -> bool {
+        self.tasks.values().any(|(_, window)| window.info.exec_name != "frostwin" && window.position.is_full_screen)
+    }
+    pub fn view(&self, app_image_cache: Arc<Mutex<BTreeMap<PathBuf,Handle>>>, start_state: bool, panel_state: bool,base_size: f32,status: SystemStatus,tray_items: &[TrayItem]) -> Element<'_, Message> {
+        let SystemStatus { battery, wifi_status, system_volume, volume_muted, microphone_in_use, now_playing, do_not_disturb } = status;
         let text_half_height = 15.0 * base_size;
         let spacing = 2.0 * base_size;
         let clock: Column<Message> =
@@ -250,6 +307,103 @@ impl Taskbar {
         } else {
             space().width(Length::Fixed(0.0)).into()
         };
+        // The compact media controls, shown when turned on in the panel menu's media card
+        let media_controls: Element<Message> = if let Some(now_playing) = now_playing {
+            let state = &now_playing.state;
+            let media_button = |label: &'static str, command: MediaCommand, enabled: bool| {
+                button(text!("{}", label).size(text_half_height).center())
+                    .style(transparent_button)
+                    .padding(Padding::from([0.0, spacing]))
+                    .on_press_maybe(enabled.then_some(Message::MediaControl(command)))
+            };
+            tooltip(
+                row![
+                    container(text!("{}", state.display_title()).size(text_half_height).wrapping(Wrapping::None))
+                        .width(Length::Fixed(160.0 * base_size))
+                        .clip(true),
+                    media_button("⏮", MediaCommand::Previous, state.can_go_previous),
+                    media_button(if state.playing { "⏸" } else { "▶" }, MediaCommand::PlayPause, true),
+                    media_button("⏭", MediaCommand::Next, state.can_go_next),
+                ].align_y(Alignment::Center),
+                container(column![
+                        text!("{}", state.display_title()),
+                        text!("{}", state.artist),
+                    ]).style(container::rounded_box),
+                tooltip::Position::FollowCursor
+            ).into()
+        } else {
+            space().width(Length::Fixed(0.0)).into()
+        };
+        // Passive items have nothing to say right now, so they stay hidden until they change status
+        let mut tray: Row<Message> = Row::new();
+        for item in tray_items.iter().filter(|item| item.status != TrayStatus::Passive) {
+            let icon: Element<Message> = match item.shown_icon() {
+                Some(icon) => image(icon.clone())
+                    .width(Length::Fixed(24.0 * base_size))
+                    .height(Length::Fixed(24.0 * base_size))
+                    .into(),
+                None => text!("{}", item.title.chars().next().unwrap_or('?'))
+                    .size(text_half_height)
+                    .width(Length::Fixed(24.0 * base_size))
+                    .center()
+                    .into(),
+            };
+            let needs_attention = item.status == TrayStatus::NeedsAttention;
+            let id = item.id.clone();
+            let tray_button = mouse_area(
+                button(icon)
+                    .style(move |theme, status| if needs_attention { selected_button(theme, status) } else { transparent_button(theme, status) })
+                    .padding(spacing)
+                    .on_press(Message::TrayActivate(item.id.clone()))
+            ).on_right_press(Message::OpenTrayMenu(item.id.clone()))
+                .on_middle_press(Message::TrayCommand(item.id.clone(), TrayCommand::SecondaryActivate))
+                .on_scroll(move |delta| {
+                    // Items expect wheel steps of 120, as X11 sends them
+                    let steps = match delta {
+                        mouse::ScrollDelta::Lines { y, .. } => y * 120.0,
+                        mouse::ScrollDelta::Pixels { y, .. } => y,
+                    };
+                    Message::TrayCommand(id.clone(), TrayCommand::Scroll(steps.round() as i32))
+                });
+            let title = if item.tooltip_title.is_empty() { &item.title } else { &item.tooltip_title };
+            tray = tray.push(
+                tooltip(
+                    tray_button,
+                    container(column![
+                            text!("{}", title),
+                            text!("{}", item.tooltip_body),
+                        ]).style(container::rounded_box),
+                    tooltip::Position::FollowCursor
+                )
+            );
+        }
+        let microphone_icon: Element<Message> = if microphone_in_use {
+            tooltip(
+                image(microphone_icon(app_image_cache.clone(), false))
+                    .width(Length::Fixed(24.0 * base_size))
+                    .height(Length::Fixed(24.0 * base_size)),
+                container(column![
+                        text!("Microphone in use"),
+                    ]).style(container::rounded_box),
+                tooltip::Position::FollowCursor
+            ).into()
+        } else {
+            space().width(Length::Fixed(0.0)).into()
+        };
+        let do_not_disturb_icon: Element<Message> = if let Some(reason) = do_not_disturb {
+            tooltip(
+                image(do_not_disturb_icon(app_image_cache.clone()))
+                    .width(Length::Fixed(24.0 * base_size))
+                    .height(Length::Fixed(24.0 * base_size)),
+                container(column![
+                        text!("Do Not Disturb"),
+                        text!("{}", reason.label()),
+                    ]).style(container::rounded_box),
+                tooltip::Position::FollowCursor
+            ).into()
+        } else {
+            space().width(Length::Fixed(0.0)).into()
+        };
         container(
             row![
                 button(
@@ -265,7 +419,11 @@ impl Taskbar {
                     .style(transparent_button),
                 tasks.spacing(spacing),
                 space().width(Length::Fill),
+                media_controls,
+                tray.align_y(Alignment::Center),
                 button(row![
+                    do_not_disturb_icon,
+                    microphone_icon,
                     tooltip(
                         image(sound_icon(app_image_cache.clone(), system_volume, volume_muted))
                             .width(Length::Fixed(24.0 * base_size))
@@ -302,4 +460,51 @@ impl Taskbar {
         ).style(window_style).into()
         
     }
-}
\ No newline at end of file
+}
+
+/// Window manager requests through EWMH, for X11 sessions.
+#[cfg(target_os = "linux")]
+mod x11_windows {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{AtomEnum, ClientMessageEvent, ConnectionExt as _, EventMask, PropMode, Window};
+    use x11rb::rust_connection::RustConnection;
+    use x11rb::wrapper::ConnectionExt as _;
+
+    fn connect() -> Result<(RustConnection, Window, u16), String> {
+        let (connection, screen_num) = x11rb::connect(None).map_err(|e| e.to_string())?;
+        let screen = &connection.setup().roots[screen_num];
+        let (root, width) = (screen.root, screen.width_in_pixels);
+        Ok((connection, root, width))
+    }
+
+    fn atom(connection: &RustConnection, name: &str) -> Result<u32, String> {
+        Ok(connection.intern_atom(false, name.as_bytes()).map_err(|e| e.to_string())?.reply().map_err(|e| e.to_string())?.atom)
+    }
+
+    /// Makes the taskbar a dock and keeps maximized windows clear of it, the way the app bar does on Windows.
+    pub fn reserve_top_edge(window: Window) -> Result<(), String> {
+        let (connection, _, width) = connect()?;
+        let base_size = width as f32 * 0.0005;
+        let height = (50.0 * base_size) as u32;
+        let window_type = atom(&connection, "_NET_WM_WINDOW_TYPE")?;
+        let dock = atom(&connection, "_NET_WM_WINDOW_TYPE_DOCK")?;
+        let strut = atom(&connection, "_NET_WM_STRUT")?;
+        let strut_partial = atom(&connection, "_NET_WM_STRUT_PARTIAL")?;
+        connection.change_property32(PropMode::REPLACE, window, window_type, AtomEnum::ATOM, &[dock]).map_err(|e| e.to_string())?;
+        connection.change_property32(PropMode::REPLACE, window, strut, AtomEnum::CARDINAL, &[0, 0, height, 0]).map_err(|e| e.to_string())?;
+        // Left, right, top and bottom widths, then the start and end of each edge's reserved span
+        connection.change_property32(PropMode::REPLACE, window, strut_partial, AtomEnum::CARDINAL, &[0, 0, height, 0, 0, 0, 0, 0, 0, width as u32 - 1, 0, 0])
+            .map_err(|e| e.to_string())?;
+        connection.flush().map_err(|e| e.to_string())
+    }
+
+    /// Asks the window manager to raise and focus a window, restoring it when minimized.
+    pub fn activate(window: Window) -> Result<(), String> {
+        let (connection, root, _) = connect()?;
+        let active_window = atom(&connection, "_NET_ACTIVE_WINDOW")?;
+        // Source 2 tells the window manager the request comes from a pager, which it should always follow
+        let event = ClientMessageEvent::new(32, window, active_window, [2, 0, 0, 0, 0]);
+        connection.send_event(false, root, EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY, event).map_err(|e| e.to_string())?;
+        connection.flush().map_err(|e| e.to_string())
+    }
+}
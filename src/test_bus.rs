@@ -0,0 +1,47 @@
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use zbus::blocking::{connection, Connection};
+
+/// A D-Bus daemon of its own for a test, so stub services never meet the desktop's buses. It is stopped when dropped.
+pub struct TestBus {
+    daemon: Child,
+    pub address: String,
+}
+impl TestBus {
+    pub fn start() -> Self {
+        let mut daemon = Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(Stdio::piped())
+            // It complains about fd limits when not run as a user's session bus
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("dbus-daemon is needed for the D-Bus tests");
+        let mut address = String::new();
+        BufReader::new(daemon.stdout.take().unwrap()).read_line(&mut address).unwrap();
+        assert!(!address.trim().is_empty(), "dbus-daemon did not start");
+        Self { daemon, address: address.trim().to_string() }
+    }
+
+    pub fn connect(&self) -> Connection {
+        connection::Builder::address(self.address.as_str()).unwrap().build().unwrap()
+    }
+}
+impl Drop for TestBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
+
+/// Polls `check` until it gives a value, failing the test after a few seconds.
+pub fn wait_for<T>(what: &str, mut check: impl FnMut() -> Option<T>) -> T {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    loop {
+        if let Some(value) = check() {
+            return value;
+        }
+        assert!(Instant::now() < deadline, "Timed out waiting for {}", what);
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,184 @@
+use crate::notifications::{Notification, Urgency};
+use crate::styles::{colored_button, transparent_button, urgent_window_style, window_style};
+use crate::Message;
+use iced::widget::text::Wrapping;
+use iced::widget::{button, column, container, image, row, space, text, Row};
+use iced::{window, Alignment, Color, Element, Length, Point, Size, Task};
+use std::time::Instant;
+
+/// Toasts shown at once, older ones go straight to the notification history.
+const TOAST_LIMIT: usize = 4;
+
+/// A notification shown in a window of its own below the taskbar.
+pub struct Toast {
+    pub id: window::Id,
+    pub notification: Notification,
+    // Reset when the application replaces the notification
+    shown: Instant,
+}
+
+/// The toasts on screen, stacked down from the top right corner with the newest first.
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+    // Found out when the first toast opens
+    screen: Option<Size>,
+}
+impl Toasts {
+    /// Shows a notification, in place of the one it replaces if that is still up. Returns the oldest toast's
+    /// notification when there are too many to show.
+    pub fn show(&mut self, notification: Notification) -> (Task<Message>, Option<Notification>) {
+        if let Some(toast) = self.toasts.iter_mut().find(|toast| toast.notification.id == notification.id) {
+            toast.notification = notification;
+            toast.shown = Instant::now();
+            return (Task::none(), None);
+        }
+        let settings = window::Settings {
+            decorations: false,
+            resizable: false,
+            min_size: None,
+            max_size: None,
+            icon: None,
+            transparent: true,
+            closeable: false,
+            minimizable: false,
+            level: window::Level::AlwaysOnTop,
+            size: Size::new(500.0, 150.0),
+            position: match self.screen {
+                Some(screen) => window::Position::Specific(Self::position(screen, 0)),
+                None => window::Position::Specific(Point::new(1018.0, 37.0)),
+            },
+            // Left to the window manager, a new toast would take the focus and close the start and panel menus
+            #[cfg(target_os = "linux")]
+            platform_specific: window::settings::PlatformSpecific { override_redirect: true, ..Default::default() },
+            ..window::Settings::default()
+        };
+        let (id, open_task) = window::open(settings);
+        self.toasts.insert(0, Toast { id, notification, shown: Instant::now() });
+        let mut tasks = vec![open_task.map(Message::WindowOpened)];
+        let mut overflow = None;
+        if self.toasts.len() > TOAST_LIMIT && let Some(oldest) = self.toasts.pop() {
+            tasks.push(window::close(oldest.id).map(Message::WindowClosed));
+            overflow = Some(oldest.notification);
+        }
+        tasks.push(self.restack());
+        (Task::batch(tasks), overflow)
+    }
+
+    /// Takes the notification's toast down, handing the notification back if it was up.
+    pub fn remove(&mut self, notification_id: u32) -> (Task<Message>, Option<Notification>) {
+        let Some(index) = self.toasts.iter().position(|toast| toast.notification.id == notification_id) else {
+            return (Task::none(), None);
+        };
+        let toast = self.toasts.remove(index);
+        (Task::batch([window::close(toast.id).map(Message::WindowClosed), self.restack()]), Some(toast.notification))
+    }
+
+    /// Notifications whose time is up.
+    pub fn expired(&self) -> Vec<u32> {
+        self.toasts.iter()
+            .filter(|toast| toast.notification.timeout.is_some_and(|timeout| toast.shown.elapsed() >= timeout))
+            .map(|toast| toast.notification.id)
+            .collect()
+    }
+
+    pub fn contains(&self, window_id: window::Id) -> bool {
+        self.toasts.iter().any(|toast| toast.id == window_id)
+    }
+
+    /// A toast's window has opened, so the screen it went on can be measured.
+    pub fn opened(&self, window_id: window::Id) -> Task<Message> {
+        if self.screen.is_some() {
+            self.restack()
+        } else {
+            window::monitor_size(window_id).map(Message::ToastScreen)
+        }
+    }
+
+    pub fn set_screen(&mut self, screen: Option<Size>) -> Task<Message> {
+        if screen.is_some() {
+            self.screen = screen;
+        }
+        self.restack()
+    }
+
+    /// The top left corner of the toast at the index, counted down from the taskbar.
+    fn position(screen: Size, index: usize) -> Point {
+        let base_size = screen.width * 0.0005;
+        Point::new(screen.width - 504.0 * base_size, 50.0 * base_size + index as f32 * 158.0 * base_size)
+    }
+
+    /// Sizes the toasts for the screen and closes up the gaps left by toasts that went away.
+    fn restack(&self) -> Task<Message> {
+        let Some(screen) = self.screen else {
+            return Task::none();
+        };
+        let base_size = screen.width * 0.0005;
+        Task::batch(self.toasts.iter().enumerate().flat_map(|(index, toast)| [
+            window::resize(toast.id, Size::new(500.0 * base_size, 150.0 * base_size)),
+            window::move_to(toast.id, Self::position(screen, index)),
+        ]))
+    }
+
+    pub fn view(&self, window_id: window::Id, base_size: f32) -> Option<Element<'_, Message>> {
+        let toast = self.toasts.iter().find(|toast| toast.id == window_id)?;
+        let notification = &toast.notification;
+        let spacing = base_size * 2.0;
+        let text_height = 30.0 * base_size;
+        let app_icon: Element<Message> = match &notification.app_icon {
+            Some(icon) => image(icon.clone()).width(Length::Fixed(text_height * 0.6)).height(Length::Fixed(text_height * 0.6)).into(),
+            None => space().width(Length::Fixed(0.0)).into(),
+        };
+        let picture: Element<Message> = match &notification.image {
+            Some(picture) => image(picture.clone()).width(Length::Fixed(text_height * 2.0)).height(Length::Fixed(text_height * 2.0)).into(),
+            None => space().width(Length::Fixed(0.0)).into(),
+        };
+        // Clicking the notification itself runs its default action, or just dismisses it
+        let on_click = if notification.has_default_action() {
+            Message::NotificationAction(notification.id, "default".to_string())
+        } else {
+            Message::NotificationDismiss(notification.id)
+        };
+        let mut actions: Row<Message> = row![space().width(Length::Fill)].spacing(spacing).align_y(Alignment::Center);
+        for (key, label) in notification.buttons() {
+            actions = actions.push(
+                button(text!("{}", label).size(text_height * 0.4))
+                    .style(|theme, status| colored_button(theme, status, Color::from_rgb(0.2, 0.2, 0.7)))
+                    .on_press(Message::NotificationAction(notification.id, key.clone()))
+            );
+        }
+        let content = column![
+            row![
+                app_icon,
+                text!("{}", notification.app_name).size(text_height * 0.35).width(Length::Fill),
+                text!("{}", notification.received.format("%H:%M")).size(text_height * 0.35),
+                button(text!("✕").size(text_height * 0.35))
+                    .style(transparent_button)
+                    .on_press(Message::NotificationDismiss(notification.id)),
+            ].align_y(Alignment::Center).spacing(spacing),
+            button(
+                row![
+                    picture,
+                    column![
+                        text!("{}", notification.summary).size(text_height * 0.5).wrapping(Wrapping::WordOrGlyph),
+                        text!("{}", notification.body).size(text_height * 0.4).wrapping(Wrapping::WordOrGlyph),
+                    ].width(Length::Fill),
+                ].align_y(Alignment::Center).spacing(spacing)
+            ).width(Length::Fill)
+            .height(Length::Fill)
+            .style(transparent_button)
+            .on_press(on_click),
+            actions,
+        ].spacing(spacing * 0.5);
+        let urgent = notification.urgency == Urgency::Critical;
+        Some(
+            container(content)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .padding(spacing * 2.0)
+                .clip(true)
+                .style(move |theme| if urgent { urgent_window_style(theme) } else { window_style(theme) })
+                .into()
+        )
+    }
+}
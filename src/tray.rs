@@ -0,0 +1,137 @@
+#[cfg(target_os = "linux")]
+use crate::status_notifier::StatusNotifier;
+#[cfg(target_os = "linux")]
+use iced::futures::channel::mpsc;
+use iced::futures::channel::oneshot;
+#[cfg(target_os = "linux")]
+use iced::futures::{SinkExt, Stream, StreamExt};
+use iced::widget::image::Handle;
+use iced::{Subscription, Task};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrayStatus {
+    /// Nothing worth showing right now, the icon is hidden.
+    Passive,
+    Active,
+    /// Shown with its attention icon and highlighted until the application clears it.
+    NeedsAttention,
+}
+
+/// An icon an application put in the system tray.
+#[derive(Debug, Clone)]
+pub struct TrayItem {
+    /// The bus name and object path the item was registered with, like ":1.42/StatusNotifierItem".
+    pub id: String,
+    pub title: String,
+    pub icon: Option<Handle>,
+    pub attention_icon: Option<Handle>,
+    pub tooltip_title: String,
+    pub tooltip_body: String,
+    pub status: TrayStatus,
+    /// Object path of the item's context menu, for items that export one.
+    pub menu: Option<String>,
+    /// The item only has a menu, so clicking it opens that instead of activating it.
+    pub item_is_menu: bool,
+}
+impl TrayItem {
+    /// The icon to show for the item's status.
+    pub fn shown_icon(&self) -> Option<&Handle> {
+        match self.status {
+            TrayStatus::NeedsAttention => self.attention_icon.as_ref().or(self.icon.as_ref()),
+            _ => self.icon.as_ref(),
+        }
+    }
+}
+
+/// An entry of a tray item's context menu.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TrayMenuItem {
+    pub id: i32,
+    pub label: String,
+    pub enabled: bool,
+    pub separator: bool,
+    /// Whether check and radio entries are checked, `None` for plain ones.
+    pub checked: Option<bool>,
+    pub children: Vec<TrayMenuItem>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrayCommand {
+    Activate,
+    /// Middle click, which usually does something less common than activating.
+    SecondaryActivate,
+    /// Asks the item to show a menu of its own, for items that do not export one.
+    ContextMenu,
+    /// Scrolled over the icon by a delta in wheel steps of 120, positive is up.
+    Scroll(i32),
+}
+
+/// Sends a command to a tray item.
+pub fn command(item: &str, command: TrayCommand) -> Task<Result<(), String>> {
+    let item = item.to_string();
+    in_background(move || {
+        #[cfg(windows)]
+        return Err(format!("Could not send {:?} to tray item {}, the tray is not hosted on Windows", command, item));
+        #[cfg(target_os = "linux")]
+        return StatusNotifier::open()?.command(&item, command).map_err(|e| e.to_string());
+    })
+}
+
+/// Reads the entries of a tray item's context menu.
+pub fn menu(item: &str, menu: &str) -> Task<Result<Vec<TrayMenuItem>, String>> {
+    let (item, menu) = (item.to_string(), menu.to_string());
+    in_background(move || {
+        #[cfg(windows)]
+        return Err(format!("Could not read menu {} of tray item {}, the tray is not hosted on Windows", menu, item));
+        #[cfg(target_os = "linux")]
+        return StatusNotifier::open()?.menu(&item, &menu).map_err(|e| e.to_string());
+    })
+}
+
+/// Tells the application an entry of its context menu was clicked.
+pub fn menu_clicked(item: &str, menu: &str, entry: i32) -> Task<Result<(), String>> {
+    let (item, menu) = (item.to_string(), menu.to_string());
+    in_background(move || {
+        #[cfg(windows)]
+        return Err(format!("Could not click entry {} of menu {} of tray item {}, the tray is not hosted on Windows", entry, menu, item));
+        #[cfg(target_os = "linux")]
+        return StatusNotifier::open()?.menu_clicked(&item, &menu, entry).map_err(|e| e.to_string());
+    })
+}
+
+/// Runs a call on a thread of its own, as applications can be slow to answer.
+fn in_background<T: Send + 'static>(work: impl FnOnce() -> Result<T, String> + Send + 'static) -> Task<Result<T, String>> {
+    Task::perform(async move {
+        let (sender, receiver) = oneshot::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(work());
+        });
+        receiver.await.unwrap_or_else(|_| Err("The tray request was cancelled".to_string()))
+    }, |result| result)
+}
+
+/// Fires with every tray item once at the start, then again after any item appears, goes away or changes.
+/// Windows keeps its notification area icons to Explorer's taskbar, so there is nothing to show there yet.
+pub fn watch() -> Subscription<Vec<TrayItem>> {
+    #[cfg(windows)]
+    return Subscription::none();
+    #[cfg(target_os = "linux")]
+    return Subscription::run(tray_changes);
+}
+
+#[cfg(target_os = "linux")]
+fn tray_changes() -> impl Stream<Item = Vec<TrayItem>> {
+    iced::stream::channel(10, async |mut output: mpsc::Sender<Vec<TrayItem>>| {
+        let (sender, mut receiver) = mpsc::unbounded::<Vec<TrayItem>>();
+        std::thread::spawn(move || {
+            if let Err(e) = StatusNotifier::open().and_then(|status_notifier| status_notifier.watch(|items| sender.unbounded_send(items).is_ok())) {
+                eprintln!("Error hosting the system tray: {}", e);
+            }
+        });
+        while let Some(items) = receiver.next().await {
+            if output.send(items).await.is_err() {
+                return;
+            }
+        }
+    })
+}
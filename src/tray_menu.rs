@@ -0,0 +1,188 @@
+use crate::styles::{context_menu_button, window_style};
+use crate::tray::{self, TrayMenuItem};
+use crate::Message;
+use iced::widget::{button, container, row, rule, scrollable, text, Column};
+use iced::{window, Alignment, Color, Element, Length, Point, Size, Task};
+
+/// Height of a menu entry, before scaling to the screen.
+const ENTRY_HEIGHT: f32 = 34.0;
+/// Menus taller than this scroll.
+const MAX_HEIGHT: f32 = 600.0;
+
+#[derive(Debug, Clone)]
+pub enum TrayMenuMessage {
+    Init,
+    Resize(Option<Size>),
+    Loaded(Result<Vec<TrayMenuItem>, String>),
+    OpenSubmenu(i32),
+    Back,
+    Click(i32),
+}
+
+/// The context menu of a tray item, in a window below the taskbar.
+pub struct TrayMenu {
+    pub id: window::Id,
+    // The item and its menu's object path, as reported by the item
+    item: String,
+    menu: String,
+    entries: Vec<TrayMenuItem>,
+    /// Entries whose submenus were opened, outermost first.
+    submenus: Vec<i32>,
+    screen: Option<Size>,
+    error: Option<String>,
+    loading: bool,
+}
+impl TrayMenu {
+    pub fn new(item: &str, menu: &str) -> (Self, Task<window::Id>) {
+        let settings = window::Settings {
+            decorations: false,
+            resizable: false,
+            min_size: None,
+            max_size: None,
+            icon: None,
+            transparent: true,
+            closeable: false,
+            minimizable: false,
+            level: window::Level::AlwaysOnTop,
+            position: window::Position::Specific(Point::new(1218.0, 37.0)),
+            size: Size::new(300.0, ENTRY_HEIGHT * 2.0),
+            ..window::Settings::default()
+        };
+        let (id, open_task) = window::open(settings);
+        (Self {
+            id,
+            item: item.to_string(),
+            menu: menu.to_string(),
+            entries: Vec::new(),
+            submenus: Vec::new(),
+            screen: None,
+            error: None,
+            loading: true,
+        },
+         open_task)
+    }
+
+    pub fn update(&mut self, message: TrayMenuMessage) -> Task<Message> {
+        match message {
+            TrayMenuMessage::Init => {
+                Task::batch([
+                    window::monitor_size(self.id).map(|size| Message::TrayMenu(TrayMenuMessage::Resize(size))),
+                    tray::menu(&self.item, &self.menu).map(|result| Message::TrayMenu(TrayMenuMessage::Loaded(result))),
+                ])
+            }
+            TrayMenuMessage::Resize(size) => {
+                if size.is_some() {
+                    self.screen = size;
+                }
+                self.fit()
+            }
+            TrayMenuMessage::Loaded(result) => {
+                self.loading = false;
+                match result {
+                    Ok(entries) => {
+                        self.entries = entries;
+                        self.error = None;
+                    }
+                    Err(e) => {
+                        eprintln!("Error loading tray menu: {}", e);
+                        self.error = Some(e);
+                    }
+                }
+                self.fit()
+            }
+            TrayMenuMessage::OpenSubmenu(entry) => {
+                self.submenus.push(entry);
+                self.fit()
+            }
+            TrayMenuMessage::Back => {
+                self.submenus.pop();
+                self.fit()
+            }
+            TrayMenuMessage::Click(entry) => {
+                Task::batch([
+                    tray::menu_clicked(&self.item, &self.menu, entry).map(Message::TrayDone),
+                    window::close(self.id).map(Message::WindowClosed),
+                ])
+            }
+        }
+    }
+
+    /// The entries of the innermost open submenu, or of the menu itself.
+    fn shown(&self) -> &[TrayMenuItem] {
+        let mut entries = self.entries.as_slice();
+        for submenu in &self.submenus {
+            match entries.iter().find(|entry| entry.id == *submenu) {
+                Some(entry) => entries = &entry.children,
+                None => return &[],
+            }
+        }
+        entries
+    }
+
+    /// Sizes the window to the shown entries, at the right of the screen under the taskbar.
+    fn fit(&self) -> Task<Message> {
+        let Some(screen) = self.screen else {
+            return Task::none();
+        };
+        let base_size = screen.width * 0.0005;
+        let rows = self.shown().len() + if self.submenus.is_empty() { 0 } else { 1 };
+        let height = (rows.max(1) as f32 * ENTRY_HEIGHT + 12.0).min(MAX_HEIGHT);
+        Task::batch([
+            window::resize(self.id, Size::new(300.0 * base_size, height * base_size)),
+            window::move_to(self.id, Point::new(screen.width - 304.0 * base_size, 50.0 * base_size)),
+        ])
+    }
+
+    pub fn view(&self, base_size: f32) -> Element<'_, Message> {
+        let text_height = 30.0 * base_size;
+        let spacing = 2.0 * base_size;
+        let mut entries: Column<Message> = Column::new();
+        if !self.submenus.is_empty() {
+            entries = entries.push(
+                button(text!("‹ Back").size(text_height * 0.45))
+                    .style(context_menu_button)
+                    .width(Length::Fill)
+                    .on_press(Message::TrayMenu(TrayMenuMessage::Back))
+            );
+        }
+        if self.loading {
+            entries = entries.push(text!("Loading…").size(text_height * 0.45));
+        } else if let Some(error) = self.error.as_ref() {
+            entries = entries.push(text!("{}", error).size(text_height * 0.45).color(Color::from_rgb(0.9, 0.3, 0.3)));
+        }
+        for entry in self.shown() {
+            if entry.separator {
+                entries = entries.push(rule::horizontal(spacing));
+                continue;
+            }
+            let on_press = if entry.children.is_empty() {
+                TrayMenuMessage::Click(entry.id)
+            } else {
+                TrayMenuMessage::OpenSubmenu(entry.id)
+            };
+            let mut label = text!("{}", entry.label).size(text_height * 0.45).width(Length::Fill);
+            if !entry.enabled {
+                label = label.color(Color::from_rgb(0.5, 0.5, 0.5));
+            }
+            entries = entries.push(
+                button(
+                    row![
+                        text!("{}", if entry.checked == Some(true) { "✓" } else { "" })
+                            .size(text_height * 0.45)
+                            .width(Length::Fixed(text_height * 0.6)),
+                        label,
+                        text!("{}", if entry.children.is_empty() { "" } else { "›" }).size(text_height * 0.45),
+                    ].align_y(Alignment::Center)
+                ).style(context_menu_button)
+                    .width(Length::Fill)
+                    .on_press_maybe(entry.enabled.then_some(Message::TrayMenu(on_press)))
+            );
+        }
+        container(scrollable(entries.spacing(spacing)).height(Length::Fill))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding(spacing * 2.0)
+            .style(window_style)
+            .into()
+    }
+}
@@ -0,0 +1,241 @@
+#[cfg(target_os = "linux")]
+use crate::network_manager::NetworkManager;
+#[cfg(target_os = "linux")]
+use crate::sys_util::WifiStatus;
+#[cfg(windows)]
+use crate::sys_util;
+use iced::futures::channel::oneshot;
+#[cfg(target_os = "linux")]
+use iced::futures::channel::mpsc;
+#[cfg(target_os = "linux")]
+use iced::futures::{SinkExt, Stream, StreamExt};
+#[cfg(target_os = "linux")]
+use iced::Subscription;
+use iced::Task;
+#[cfg(target_os = "linux")]
+use std::time::Duration;
+
+// How often the taskbar's connection is read from NetworkManager
+#[cfg(target_os = "linux")]
+const STATUS_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WifiSecurity {
+    Open,
+    Wep,
+    WpaPersonal,
+    Wpa3Personal,
+    /// 802.1X networks, which need more than a password and are left to the system settings.
+    Enterprise,
+}
+impl WifiSecurity {
+    pub fn needs_password(self) -> bool {
+        matches!(self, WifiSecurity::Wep | WifiSecurity::WpaPersonal | WifiSecurity::Wpa3Personal)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WifiSecurity::Open => "Open",
+            WifiSecurity::Wep => "WEP",
+            WifiSecurity::WpaPersonal => "WPA2",
+            WifiSecurity::Wpa3Personal => "WPA3",
+            WifiSecurity::Enterprise => "Enterprise",
+        }
+    }
+}
+
+/// A network seen in the last scan. Access points sharing an SSID are merged into one entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WifiNetwork {
+    pub ssid: String,
+    /// Signal quality from 0 to 100.
+    pub signal: u32,
+    pub security: WifiSecurity,
+    /// Whether the system has a saved profile for the network.
+    pub known: bool,
+    pub connected: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WifiState {
+    pub radio_enabled: bool,
+    pub networks: Vec<WifiNetwork>,
+}
+
+/// Lists the visible networks and whether the radio is on.
+pub fn scan() -> Result<WifiState, String> {
+    #[cfg(windows)]
+    return sys_util::scan_wifi_networks();
+    #[cfg(target_os = "linux")]
+    return NetworkManager::open()?.scan().map_err(|e| e.to_string());
+}
+
+/// Connects to a network, saving it first when it is new or a password is given.
+pub fn connect(network: &WifiNetwork, password: Option<&str>) -> Result<(), String> {
+    if !network.known && network.security == WifiSecurity::Enterprise {
+        return Err(format!("{} needs to be set up in the system network settings", network.ssid));
+    }
+    if !network.known && network.security.needs_password() && password.is_none_or(str::is_empty) {
+        return Err(format!("{} needs a password", network.ssid));
+    }
+    let password = password.filter(|password| !password.is_empty());
+    #[cfg(windows)]
+    return sys_util::connect_wifi_network(&network.ssid, network.security, password);
+    #[cfg(target_os = "linux")]
+    return NetworkManager::open()?.connect(&network.ssid, network.security, password).map_err(|e| e.to_string());
+}
+
+pub fn disconnect() -> Result<(), String> {
+    #[cfg(windows)]
+    return sys_util::disconnect_wifi();
+    #[cfg(target_os = "linux")]
+    return NetworkManager::open()?.disconnect().map_err(|e| e.to_string());
+}
+
+/// Removes the saved profiles for a network.
+pub fn forget(ssid: &str) -> Result<(), String> {
+    #[cfg(windows)]
+    return sys_util::forget_wifi_network(ssid);
+    #[cfg(target_os = "linux")]
+    return NetworkManager::open()?.forget(ssid).map_err(|e| e.to_string());
+}
+
+pub fn set_radio(enabled: bool) -> Result<(), String> {
+    #[cfg(windows)]
+    return sys_util::set_wifi_radio(enabled);
+    #[cfg(target_os = "linux")]
+    return NetworkManager::open()?.set_radio(enabled).map_err(|e| e.to_string());
+}
+
+/// Runs one of the calls above on a thread of its own, as scans and connects can take seconds.
+pub fn in_background<T: Send + 'static>(work: impl FnOnce() -> Result<T, String> + Send + 'static) -> Task<Result<T, String>> {
+    Task::perform(async move {
+        let (sender, receiver) = oneshot::channel();
+        std::thread::spawn(move || {
+            let _ = sender.send(work());
+        });
+        receiver.await.unwrap_or_else(|_| Err("The Wi-Fi request was cancelled".to_string()))
+    }, |result| result)
+}
+
+/// The connection the taskbar shows, read on a thread of its own and passed on when it changes.
+#[cfg(target_os = "linux")]
+pub fn status_updates() -> Subscription<WifiStatus> {
+    Subscription::run(watch_status)
+}
+
+#[cfg(target_os = "linux")]
+fn watch_status() -> impl Stream<Item = WifiStatus> {
+    iced::stream::channel(1, async |mut output: mpsc::Sender<WifiStatus>| {
+        let (sender, mut receiver) = mpsc::unbounded::<WifiStatus>();
+        std::thread::spawn(move || {
+            let mut network_manager = None;
+            let mut last_status = None;
+            let mut last_error = None;
+            loop {
+                if network_manager.is_none() {
+                    match NetworkManager::open() {
+                        Ok(client) => network_manager = Some(client),
+                        Err(e) => last_error = report(e, last_error.take()),
+                    }
+                }
+                if let Some(client) = network_manager.as_ref() {
+                    match client.status() {
+                        Ok(status) => {
+                            last_error = None;
+                            if last_status.as_ref() != Some(&status) {
+                                if sender.unbounded_send(status.clone()).is_err() {
+                                    return;
+                                }
+                                last_status = Some(status);
+                            }
+                        }
+                        // The connection is opened again on the next round, NetworkManager may have restarted
+                        Err(e) => {
+                            network_manager = None;
+                            last_error = report(e.to_string(), last_error.take());
+                        }
+                    }
+                }
+                std::thread::sleep(STATUS_INTERVAL);
+            }
+        });
+        while let Some(status) = receiver.next().await {
+            if output.send(status).await.is_err() {
+                return;
+            }
+        }
+    })
+}
+
+/// Logs an error unless it is the one logged last round, and returns it to compare against next time.
+#[cfg(target_os = "linux")]
+fn report(error: String, last_error: Option<String>) -> Option<String> {
+    if last_error.as_ref() != Some(&error) {
+        eprintln!("Error getting the Wi-Fi status: {}", error);
+    }
+    Some(error)
+}
+
+/// Merges access points with the same SSID and sorts the list, connected network first and then by signal.
+/// Hidden networks have no SSID to show and are left out.
+pub fn merge_networks(access_points: Vec<WifiNetwork>) -> Vec<WifiNetwork> {
+    let mut networks: Vec<WifiNetwork> = Vec::new();
+    for access_point in access_points {
+        if access_point.ssid.is_empty() {
+            continue;
+        }
+        match networks.iter_mut().find(|network| network.ssid == access_point.ssid) {
+            Some(network) => {
+                if access_point.signal > network.signal {
+                    network.signal = access_point.signal;
+                    network.security = access_point.security;
+                }
+                network.known |= access_point.known;
+                network.connected |= access_point.connected;
+            }
+            None => networks.push(access_point),
+        }
+    }
+    networks.sort_by(|a, b| b.connected.cmp(&a.connected).then(b.signal.cmp(&a.signal)).then(a.ssid.cmp(&b.ssid)));
+    networks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn network(ssid: &str, signal: u32, security: WifiSecurity, known: bool, connected: bool) -> WifiNetwork {
+        WifiNetwork { ssid: ssid.to_string(), signal, security, known, connected }
+    }
+
+    #[test]
+    fn merges_access_points_by_ssid() {
+        let merged = merge_networks(vec![
+            network("Cafe", 30, WifiSecurity::Open, false, false),
+            network("Home", 40, WifiSecurity::WpaPersonal, true, true),
+            network("", 99, WifiSecurity::Open, false, false),
+            network("Cafe", 70, WifiSecurity::WpaPersonal, false, false),
+            network("Attic", 70, WifiSecurity::Open, false, false),
+            network("Home", 90, WifiSecurity::Wpa3Personal, false, false),
+        ]);
+        // The strongest access point gives the signal and security, the connected network goes first
+        assert_eq!(merged, [
+            network("Home", 90, WifiSecurity::Wpa3Personal, true, true),
+            network("Attic", 70, WifiSecurity::Open, false, false),
+            network("Cafe", 70, WifiSecurity::WpaPersonal, false, false),
+        ]);
+    }
+
+    #[test]
+    fn asks_for_what_new_networks_need() {
+        let enterprise = network("Office", 50, WifiSecurity::Enterprise, false, false);
+        assert_eq!(connect(&enterprise, Some("secret")), Err("Office needs to be set up in the system network settings".to_string()));
+        let protected = network("Home", 50, WifiSecurity::WpaPersonal, false, false);
+        assert_eq!(connect(&protected, None), Err("Home needs a password".to_string()));
+        assert_eq!(connect(&protected, Some("")), Err("Home needs a password".to_string()));
+        assert!(WifiSecurity::Wep.needs_password());
+        assert!(!WifiSecurity::Open.needs_password());
+        assert!(!WifiSecurity::Enterprise.needs_password());
+    }
+}
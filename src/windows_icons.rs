@@ -1,45 +1,42 @@
 use std::os::windows::ffi::OsStrExt;
 use std::path::PathBuf;
-use std::ptr;
-use windows::core::{Interface, PCWSTR};
+use windows::core::PCWSTR;
 use windows::Win32::Graphics::Gdi::{GetDC, GetDIBits, ReleaseDC, BITMAPINFO, BITMAPINFOHEADER, DIB_RGB_COLORS};
 use windows::Win32::Storage::FileSystem::FILE_FLAGS_AND_ATTRIBUTES;
-use windows::Win32::System::Com::{CoCreateInstance, CoInitializeEx, CLSCTX_ALL, COINIT_APARTMENTTHREADED, STGM};
-use windows::Win32::UI::Shell::{IShellLinkW, SHGetFileInfoW, SHFILEINFOW, SHGFI_ICON, SHGFI_LARGEICON};
-use windows::Win32::UI::WindowsAndMessaging::{DestroyIcon, GetIconInfo, GetSystemMetrics, ICONINFO, SM_CXICON, SM_CYICON};
+use windows::Win32::UI::Shell::{ExtractIconExW, SHGetFileInfoW, SHFILEINFOW, SHGFI_ICON, SHGFI_LARGEICON};
+use windows::Win32::UI::WindowsAndMessaging::{DestroyIcon, GetIconInfo, GetSystemMetrics, HICON, ICONINFO, SM_CXICON, SM_CYICON};
+use crate::shell_link::ShellLink;
 
 pub fn get_lnk_icon(path: PathBuf) -> Option<(Vec<u8>, u32, u32)> {
+    let link = ShellLink::from_path(&path).ok()?;
+    let (icon_path, icon_index) = link.resolved_icon(&path)?;
     unsafe {
-        // 1. Initialize COM for the current thread
-        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        // 1. Prefer the icon the shortcut names explicitly
+        let icon_wide: Vec<u16> = icon_path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+        let mut large_icon = HICON::default();
+        let extracted = ExtractIconExW(PCWSTR(icon_wide.as_ptr()), icon_index, Some(&mut large_icon), None, 1);
 
-        // 2. Create IShellLink instance to resolve the .lnk
-        let shell_link: IShellLinkW = CoCreateInstance(&windows::Win32::UI::Shell::ShellLink, None, CLSCTX_ALL).ok()?;
-        let persist_file: windows::Win32::System::Com::IPersistFile = shell_link.cast().ok()?;
-
-        // Load the .lnk file
-        let path_wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
-        persist_file.Load(PCWSTR(path_wide.as_ptr()), STGM(0)).ok()?;
-
-        // Get target path
-        let mut target_path = [0u16; 260];
-        shell_link.GetPath(&mut target_path, ptr::null_mut(), 0).ok()?;
-
-        // 3. Get Icon Handle (HICON) from the target path
+        // 2. Otherwise ask the shell for the icon of the target itself
         let mut shfi = SHFILEINFOW::default();
-        SHGetFileInfoW(
-            PCWSTR(target_path.as_ptr()),
-            FILE_FLAGS_AND_ATTRIBUTES(0),
-            Some(&mut shfi),
-            size_of::<SHFILEINFOW>() as u32,
-            SHGFI_ICON | SHGFI_LARGEICON,
-        );
+        if extracted == 0 || large_icon.is_invalid() {
+            let target_path = link.resolved_target(&path)?;
+            let target_wide: Vec<u16> = target_path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+            SHGetFileInfoW(
+                PCWSTR(target_wide.as_ptr()),
+                FILE_FLAGS_AND_ATTRIBUTES(0),
+                Some(&mut shfi),
+                size_of::<SHFILEINFOW>() as u32,
+                SHGFI_ICON | SHGFI_LARGEICON,
+            );
+        } else {
+            shfi.hIcon = large_icon;
+        }
 
         if shfi.hIcon.is_invalid() {
             return None;
         }
 
-        // 4. Convert HICON to Raw RGBA pixels
+        // 3. Convert HICON to Raw RGBA pixels
         let mut icon_info = ICONINFO::default();
         GetIconInfo(shfi.hIcon, &mut icon_info).ok()?;
 
@@ -66,7 +63,7 @@ pub fn get_lnk_icon(path: PathBuf) -> Option<(Vec<u8>, u32, u32)> {
         ReleaseDC(None, hdc);
         let _ = DestroyIcon(shfi.hIcon);
 
-        // 5. Swap BGRA (Windows default) to RGBA (iced/most loaders default)
+        // 4. Swap BGRA (Windows default) to RGBA (iced/most loaders default)
         for chunk in buffer.chunks_exact_mut(4) {
             chunk.swap(0, 2);
         }